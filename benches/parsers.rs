@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::io;
+
+use linux_proc::{diskstats::DiskStats, stat::Stat, stat::StatOptions, uptime::Uptime};
+
+/// A `/proc/stat` from a 64 core machine with 1024 interrupt lines.
+fn stat_input() -> String {
+    let mut raw = String::from("cpu  1750142 2235 629364 821246955 201417 0 80523 0 0 0\n");
+    for cpu in 0..64 {
+        raw.push_str(&format!(
+            "cpu{} 27346 34 9833 12832 3147 0 1258 0 0 0\n",
+            cpu
+        ));
+    }
+    raw.push_str("intr 1015182898");
+    for irq in 0..1024 {
+        raw.push_str(&format!(" {}", irq * 37));
+    }
+    raw.push_str(
+        "
+ctxt 2238717321
+btime 1535128607
+processes 2453908
+procs_running 3
+procs_blocked 0
+softirq 4257581 64 299604 69 2986 36581 0 3497229 283111 0 137937
+",
+    );
+    raw
+}
+
+/// A `/proc/diskstats` with 128 devices.
+fn diskstats_input() -> String {
+    let mut raw = String::new();
+    for dev in 0..128 {
+        raw.push_str(&format!(
+            " 259 {} nvme0n{} 446866 32893 8168064 20164 339296 376515 86758441 4343530 0 250860 4704740 0 0 0 0 0 0\n",
+            dev, dev
+        ));
+    }
+    raw
+}
+
+fn parsers(c: &mut Criterion) {
+    let stat = stat_input();
+    c.bench_function("stat", |b| {
+        b.iter(|| Stat::from_reader(io::Cursor::new(black_box(&stat))).unwrap())
+    });
+    let totals_only = StatOptions::new()
+        .per_cpu(false)
+        .interrupts(false)
+        .softirqs(false);
+    c.bench_function("stat totals only", |b| {
+        b.iter(|| Stat::from_reader_with(io::Cursor::new(black_box(&stat)), totals_only).unwrap())
+    });
+    let mut sample = Stat::from_reader(io::Cursor::new(&stat)).unwrap();
+    c.bench_function("stat refresh", |b| {
+        b.iter(|| {
+            sample
+                .refresh_from_reader(io::Cursor::new(black_box(&stat)))
+                .unwrap()
+        })
+    });
+    let diskstats = diskstats_input();
+    c.bench_function("diskstats", |b| {
+        b.iter(|| DiskStats::from_reader(io::Cursor::new(black_box(&diskstats))).unwrap())
+    });
+    let mut stats = DiskStats::from_reader(io::Cursor::new(&diskstats)).unwrap();
+    c.bench_function("diskstats refresh", |b| {
+        b.iter(|| {
+            stats
+                .refresh_from_reader(io::Cursor::new(black_box(&diskstats)))
+                .unwrap()
+        })
+    });
+    c.bench_function("uptime", |b| {
+        b.iter(|| Uptime::from_str(black_box("1640919.14 2328903.47\n")).unwrap())
+    });
+}
+
+criterion_group!(benches, parsers);
+criterion_main!(benches);
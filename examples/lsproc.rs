@@ -5,17 +5,19 @@ extern crate linux_proc;
 
 use quicli::prelude::*;
 
-use linux_proc::diskstats::DiskStat;
+use linux_proc::{
+    diskstats::DiskStats,
+    stat::{Stat, StatOptions},
+    Timestamped,
+};
 
 /// Carriage return
-const CR_CODE: &'static str = "\x1b[G";
+const CR_CODE: &str = "\x1b[G";
 /// Clear to end of line
-const CLEAR_CODE: &'static str = "\x1b[K";
+const CLEAR_CODE: &str = "\x1b[K";
 
 /// Sampling interval length
 const INTERVAL_NANOS: u64 = 400_000_000;
-/// 1_000_000_000 nanoseconds in a second
-const NANOS_IN_SEC: u64 = 1_000_000_000;
 
 #[derive(Debug, StructOpt)]
 struct Cli {
@@ -39,16 +41,30 @@ enum Command {
     /// Present the contents of `/proc/uptime`.
     #[structopt(name = "uptime")]
     Uptime,
+    /// Show cpu, memory, processes, disks and network live, like `top`. Press `q` to quit.
+    ///
+    /// Needs the `tui` feature.
+    #[structopt(name = "watch")]
+    Watch {
+        /// The time between samples, in milliseconds.
+        #[structopt(long = "interval", default_value = "1000")]
+        interval: u64,
+    },
 }
 
 main!(|args: Cli, log_level: verbosity| match args.command {
     Command::Stat => {
-        let mut prev_stat = linux_proc::stat::Stat::from_system()?;
+        // We only need the totals.
+        let options = StatOptions::new()
+            .per_cpu(false)
+            .interrupts(false)
+            .softirqs(false);
+        let mut prev_stat = Stat::parse_with(options)?;
         loop {
             std::thread::sleep(std::time::Duration::from_millis(400));
-            let stat = linux_proc::stat::Stat::from_system()?;
-            let cpu_sum = (stat.cpu_totals.total() - prev_stat.cpu_totals.total()) as f64;
-            let idle = (stat.cpu_totals.idle - prev_stat.cpu_totals.idle) as f64;
+            let stat = Stat::parse_with(options)?;
+            let cpu_sum = (stat.cpu_totals.total() - prev_stat.cpu_totals.total()).get() as f64;
+            let idle = (stat.cpu_totals.idle - prev_stat.cpu_totals.idle).get() as f64;
             print!("{}", CR_CODE);
             print!("cpu: {:3.0}% ", (cpu_sum - idle) * 100.0 / cpu_sum);
             print!("{}", CLEAR_CODE);
@@ -57,17 +73,18 @@ main!(|args: Cli, log_level: verbosity| match args.command {
         }
     }
     Command::DiskStats { device } => {
-        let mut prev_stat = linux_proc::diskstats::DiskStats::from_system()?;
+        let mut prev_stat = Timestamped::read(DiskStats::from_system)?;
         loop {
             std::thread::sleep(std::time::Duration::from_nanos(INTERVAL_NANOS));
-            let curr_stat = linux_proc::diskstats::DiskStats::from_system()?;
-            let reading = time_reading(
-                prev_stat
-                    .get(&device)
-                    .expect(&format!("cannot find device \"{}\"", &device)),
-                curr_stat.get(&device).unwrap(),
-            );
-            let read_ratio = (reading as f64) / (INTERVAL_NANOS as f64);
+            let curr_stat = Timestamped::read(DiskStats::from_system)?;
+            // Measure the real interval, as sleeping can overrun under load.
+            let elapsed = curr_stat.elapsed_since(&prev_stat);
+            let delta = curr_stat.diff(&prev_stat);
+            let reading = delta
+                .get(&device)
+                .unwrap_or_else(|| panic!("cannot find device \"{}\"", &device))
+                .time_reading;
+            let read_ratio = reading.as_secs_f64() / elapsed.as_secs_f64();
 
             print!("{}", CR_CODE);
             print!("read: {:3.3}% ", read_ratio * 100.0);
@@ -78,18 +95,378 @@ main!(|args: Cli, log_level: verbosity| match args.command {
     }
     Command::Uptime => {
         let uptime = linux_proc::uptime::Uptime::from_system()?;
-        println!("system has been up for {:?}", uptime.up);
-        println!("cores have been idle for {:?}", uptime.idle);
+        println!("{}", uptime);
+    }
+    Command::Watch { interval } => {
+        watch::run(std::time::Duration::from_millis(interval))?;
     }
 });
 
-fn time_reading(prev: &DiskStat, current: &DiskStat) -> u64 {
-    let read_time = current.time_reading - prev.time_reading;
-    let read_time = read_time
-        .as_secs()
-        .checked_mul(NANOS_IN_SEC)
-        .expect("overflow")
-        .checked_add(read_time.subsec_nanos().into())
-        .expect("overflow");
-    read_time
+#[cfg(not(feature = "tui"))]
+mod watch {
+    pub fn run(_interval: std::time::Duration) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "lsproc was built without the `tui` feature",
+        ))
+    }
+}
+
+/// The `watch` subcommand, a full-screen view redrawn after every sample.
+#[cfg(feature = "tui")]
+mod watch {
+    use std::{
+        collections::HashMap,
+        io::{self, Read},
+        time::Duration,
+    };
+
+    use linux_proc::{
+        diskstats::DiskStats,
+        loadavg::LoadAvg,
+        meminfo::MemInfo,
+        net::dev::NetDev,
+        process::{self, ProcessCpuTracker, ProcessScan},
+        stat::{CpuUsageOptions, Stat, StatCpu, StatOptions},
+        uptime::Uptime,
+        ByteSize, Timestamped,
+    };
+    use ratatui::{
+        backend::TermionBackend,
+        layout::{Constraint, Layout, Rect},
+        style::{Color, Style},
+        termion::{raw::IntoRawMode, screen::IntoAlternateScreen},
+        text::Line,
+        widgets::{Block, LineGauge, Paragraph, Row, Table},
+        Frame, Terminal,
+    };
+
+    /// How often to check for a key press while waiting for the next sample.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Everything read from `/proc` in one sample.
+    struct Sample {
+        stat: Timestamped<Stat>,
+        diskstats: Timestamped<DiskStats>,
+        net_dev: Timestamped<NetDev>,
+        meminfo: MemInfo,
+        loadavg: LoadAvg,
+        uptime: Uptime,
+    }
+
+    impl Sample {
+        fn read() -> io::Result<Self> {
+            let options = StatOptions::new().interrupts(false).softirqs(false);
+            Ok(Sample {
+                stat: Timestamped::read(|| Stat::parse_with(options))?,
+                diskstats: Timestamped::read(DiskStats::from_system)?,
+                net_dev: Timestamped::read(NetDev::from_system)?,
+                meminfo: MemInfo::from_system()?,
+                loadavg: LoadAvg::from_system()?,
+                uptime: Uptime::from_system()?,
+            })
+        }
+    }
+
+    /// A process row in the table.
+    struct ProcessRow {
+        pid: u32,
+        comm: String,
+        percent: f64,
+        rss: ByteSize,
+    }
+
+    /// What is drawn on the screen: the rates between the last two samples.
+    struct View {
+        header: String,
+        /// The busy proportion of each cpu, from 0 to 1.
+        cpus: Vec<f64>,
+        memory: (ByteSize, ByteSize),
+        swap: (ByteSize, ByteSize),
+        processes: Vec<ProcessRow>,
+        /// Device, read and written bytes per second, and utilization.
+        disks: Vec<(String, f64, f64, f64)>,
+        /// Interface, received and transmitted bytes per second.
+        interfaces: Vec<(String, f64, f64)>,
+    }
+
+    pub fn run(interval: Duration) -> io::Result<()> {
+        let stdout = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+        let mut terminal = Terminal::new(TermionBackend::new(stdout))?;
+        let mut keys = ratatui::termion::async_stdin();
+        let page_size = process::page_size();
+        let mut tracker = ProcessCpuTracker::new();
+
+        let mut earlier = Sample::read()?;
+        update_processes(&mut tracker, &earlier.stat, page_size)?;
+        loop {
+            // Wait for the next sample, quitting on `q`, escape or ctrl-c.
+            let mut waited = Duration::from_secs(0);
+            while waited < interval {
+                std::thread::sleep(POLL_INTERVAL);
+                waited += POLL_INTERVAL;
+                let mut buf = [0; 32];
+                let len = keys.read(&mut buf)?;
+                if buf[..len]
+                    .iter()
+                    .any(|&key| matches!(key, b'q' | 0x1b | 0x03))
+                {
+                    return Ok(());
+                }
+            }
+            let later = Sample::read()?;
+            let processes = update_processes(&mut tracker, &later.stat, page_size)?;
+            let view = View::new(&earlier, &later, processes);
+            terminal.draw(|frame| view.draw(frame))?;
+            earlier = later;
+        }
+    }
+
+    /// Scan every process and calculate its cpu usage since the last scan, busiest first.
+    fn update_processes(
+        tracker: &mut ProcessCpuTracker,
+        stat: &Stat,
+        page_size: u64,
+    ) -> io::Result<Vec<ProcessRow>> {
+        let stats: Vec<_> = ProcessScan::new()
+            .run()?
+            .into_iter()
+            .filter_map(|snapshot| snapshot.stat)
+            .collect();
+        let rss: HashMap<u32, ByteSize> = stats
+            .iter()
+            .map(|stat| (stat.pid, stat.rss.bytes(page_size)))
+            .collect();
+        let mut rows: Vec<_> = tracker
+            .update(stat, stats)
+            .into_iter()
+            .map(|usage| ProcessRow {
+                pid: usage.pid,
+                rss: rss.get(&usage.pid).copied().unwrap_or_default(),
+                comm: usage.comm,
+                percent: usage.percent,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.percent.total_cmp(&a.percent).then(b.rss.cmp(&a.rss)));
+        Ok(rows)
+    }
+
+    /// The proportion of the time between two samples that a cpu wasn't idle.
+    fn busy(earlier: &StatCpu, later: &StatCpu) -> f64 {
+        later
+            .usage_since(earlier, CpuUsageOptions::new().iowait_as_idle(true))
+            .busy()
+    }
+
+    impl View {
+        fn new(earlier: &Sample, later: &Sample, processes: Vec<ProcessRow>) -> Self {
+            let cpus = earlier
+                .stat
+                .cpus
+                .iter()
+                .zip(&later.stat.cpus)
+                .map(|(earlier, later)| busy(earlier, later))
+                .collect();
+
+            let meminfo = &later.meminfo;
+            let total = meminfo.total().unwrap_or_default();
+            let available = meminfo.available().or(meminfo.free()).unwrap_or_default();
+            let memory = (ByteSize(total.0.saturating_sub(available.0)), total);
+            let swap_total = meminfo.size("SwapTotal").unwrap_or_default();
+            let swap_free = meminfo.size("SwapFree").unwrap_or_default();
+            let swap = (
+                ByteSize(swap_total.0.saturating_sub(swap_free.0)),
+                swap_total,
+            );
+
+            let elapsed = later.diskstats.elapsed_since(&earlier.diskstats);
+            let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+            let disks = later
+                .diskstats
+                .diff(&earlier.diskstats)
+                .devices
+                .into_iter()
+                .map(|delta| {
+                    let read = delta.sectors_read.bytes().0 as f64 / secs;
+                    let written = delta.sectors_written.bytes().0 as f64 / secs;
+                    let utilization = delta.utilization(elapsed);
+                    (delta.display_name(), read, written, utilization)
+                })
+                .collect();
+
+            let elapsed = later.net_dev.elapsed_since(&earlier.net_dev);
+            let interfaces = later
+                .net_dev
+                .diff(&earlier.net_dev)
+                .interfaces
+                .into_iter()
+                .map(|delta| {
+                    let rx = delta.rx_bytes_per_sec(elapsed);
+                    let tx = delta.tx_bytes_per_sec(elapsed);
+                    (delta.0.name, rx, tx)
+                })
+                .collect();
+
+            let load = &later.loadavg;
+            let header = format!(
+                "{}  load average: {:.2} {:.2} {:.2}  tasks: {} running, {} total",
+                later.uptime, load.one, load.five, load.fifteen, load.running, load.total
+            );
+            View {
+                header,
+                cpus,
+                memory,
+                swap,
+                processes,
+                disks,
+                interfaces,
+            }
+        }
+
+        fn draw(&self, frame: &mut Frame) {
+            let [header, cpus, memory, processes, devices] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(self.cpus.len().div_ceil(2) as u16 + 2),
+                Constraint::Length(4),
+                Constraint::Min(5),
+                Constraint::Length(self.disks.len().max(self.interfaces.len()) as u16 + 3),
+            ])
+            .areas(frame.area());
+            frame.render_widget(Paragraph::new(self.header.as_str()), header);
+            self.draw_cpus(frame, cpus);
+            self.draw_memory(frame, memory);
+            self.draw_processes(frame, processes);
+            let [disks, interfaces] =
+                Layout::horizontal([Constraint::Percentage(50); 2]).areas(devices);
+            self.draw_disks(frame, disks);
+            self.draw_interfaces(frame, interfaces);
+        }
+
+        /// The cpus in two columns of bars.
+        fn draw_cpus(&self, frame: &mut Frame, area: Rect) {
+            let block = Block::bordered().title("cpu");
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            let columns: [Rect; 2] =
+                Layout::horizontal([Constraint::Percentage(50); 2]).areas(inner);
+            let rows = self.cpus.len().div_ceil(2);
+            for (idx, &busy) in self.cpus.iter().enumerate() {
+                let column = columns[idx / rows.max(1)];
+                let area = Rect {
+                    y: column.y + (idx % rows.max(1)) as u16,
+                    height: 1,
+                    ..column
+                };
+                frame.render_widget(gauge(format!("{:>3}", idx), busy), area);
+            }
+        }
+
+        fn draw_memory(&self, frame: &mut Frame, area: Rect) {
+            let block = Block::bordered().title("memory");
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            let [memory, swap] =
+                Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(inner);
+            for &((used, total), area, name) in
+                &[(self.memory, memory, "mem "), (self.swap, swap, "swap")]
+            {
+                let ratio = if total.0 > 0 {
+                    used.0 as f64 / total.0 as f64
+                } else {
+                    0.0
+                };
+                let label = format!("{} {:>10} / {:>10}", name, used, total);
+                frame.render_widget(gauge(label, ratio), area);
+            }
+        }
+
+        fn draw_processes(&self, frame: &mut Frame, area: Rect) {
+            let rows = self.processes.iter().map(|process| {
+                Row::new(vec![
+                    process.pid.to_string(),
+                    format!("{:.1}", process.percent),
+                    process.rss.to_string(),
+                    process.comm.clone(),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(8),
+                    Constraint::Length(7),
+                    Constraint::Length(11),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(heading(&["PID", "%CPU", "RSS", "COMMAND"]))
+            .block(Block::bordered().title("processes"));
+            frame.render_widget(table, area);
+        }
+
+        fn draw_disks(&self, frame: &mut Frame, area: Rect) {
+            let rows = self.disks.iter().map(|(name, read, written, util)| {
+                Row::new(vec![
+                    name.clone(),
+                    rate(*read),
+                    rate(*written),
+                    format!("{:.1}", util * 100.0),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Fill(1),
+                    Constraint::Length(13),
+                    Constraint::Length(13),
+                    Constraint::Length(6),
+                ],
+            )
+            .header(heading(&["DEVICE", "READ", "WRITE", "%UTIL"]))
+            .block(Block::bordered().title("disks"));
+            frame.render_widget(table, area);
+        }
+
+        fn draw_interfaces(&self, frame: &mut Frame, area: Rect) {
+            let rows = self
+                .interfaces
+                .iter()
+                .map(|(name, rx, tx)| Row::new(vec![name.clone(), rate(*rx), rate(*tx)]));
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Fill(1),
+                    Constraint::Length(13),
+                    Constraint::Length(13),
+                ],
+            )
+            .header(heading(&["INTERFACE", "RECEIVE", "TRANSMIT"]))
+            .block(Block::bordered().title("network"));
+            frame.render_widget(table, area);
+        }
+    }
+
+    /// A one-line bar, green when idle through to red when busy.
+    fn gauge(label: String, ratio: f64) -> LineGauge<'static> {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let color = if ratio > 0.9 {
+            Color::Red
+        } else if ratio > 0.6 {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+        LineGauge::default()
+            .label(Line::from(format!("{} {:5.1}%", label, ratio * 100.0)))
+            .filled_style(Style::new().fg(color))
+            .ratio(ratio)
+    }
+
+    fn heading(names: &[&'static str]) -> Row<'static> {
+        Row::new(names.to_vec()).style(Style::new().fg(Color::Cyan))
+    }
+
+    /// A rate in bytes per second, like `1.50 MiB/s`.
+    fn rate(bytes_per_sec: f64) -> String {
+        format!("{}/s", ByteSize(bytes_per_sec as u64))
+    }
 }
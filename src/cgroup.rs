@@ -0,0 +1,692 @@
+//! Bindings to `/proc/cgroups`, and to the resource usage files of a cgroup in the cgroup
+//! filesystem (usually mounted at `/sys/fs/cgroup`).
+//!
+//! Use `CgroupDir::of_process` to find the cgroup of a process, then read its usage with the
+//! same calls whichever cgroup version the machine uses.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::process::{self, clock_ticks_per_sec, Process};
+use crate::{util, ByteSize, Error};
+
+/// Where the cgroup filesystem is mounted on almost every distribution.
+const MOUNT: &str = "/sys/fs/cgroup";
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The cgroup v1 controllers the kernel supports, from `/proc/cgroups`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CgroupControllers {
+    inner: Vec<CgroupController>,
+}
+
+/// A cgroup controller, from a line of `/proc/cgroups`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CgroupController {
+    /// The controller name, e.g. `memory`.
+    pub name: String,
+    /// The id of the v1 hierarchy the controller is bound to, or 0 if it is unused or bound to
+    /// the v2 hierarchy.
+    pub hierarchy: u32,
+    /// The number of cgroups in the controller's hierarchy.
+    pub num_cgroups: u64,
+    /// Whether the controller is enabled (see the `cgroup_disable` boot option).
+    pub enabled: bool,
+}
+
+impl CgroupControllers {
+    const PATH: &'static str = "/proc/cgroups";
+    /// Parse the contents of `/proc/cgroups`.
+    pub fn from_system() -> io::Result<Self> {
+        CgroupControllers::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/cgroups`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // `#subsys_name	hierarchy	num_cgroups	enabled`
+        reader.parse_line(util::parse_dummy)?;
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(CgroupController::from_str) {
+                Ok(controller) => inner.push(controller),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(CgroupControllers { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CgroupController> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for CgroupControllers {
+    type Target = Vec<CgroupController>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for CgroupControllers {
+    type IntoIter = std::vec::IntoIter<CgroupController>;
+    type Item = CgroupController;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl CgroupController {
+    fn from_str(input: &str) -> Result<CgroupController, Error> {
+        let (input, name) = err_msg!(util::parse_token(input), "subsys_name")?;
+        let (input, hierarchy) = err_msg!(util::parse_u64(input), "hierarchy")?;
+        let (input, num_cgroups) = err_msg!(util::parse_u64(input), "num_cgroups")?;
+        let (_, enabled) = err_msg!(util::parse_u64(input), "enabled")?;
+        Ok(CgroupController {
+            name: name.to_owned(),
+            hierarchy: util::to_u32(hierarchy, "hierarchy")?,
+            num_cgroups,
+            enabled: enabled != 0,
+        })
+    }
+}
+
+/// The version of the cgroup interface.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CgroupVersion {
+    /// A hierarchy per controller, each with its own file names.
+    V1,
+    /// A single unified hierarchy.
+    V2,
+}
+
+/// A cgroup's directory (or, for cgroup v1, directories) in the cgroup filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupDir {
+    layout: Layout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Layout {
+    V2(PathBuf),
+    /// The directory of the cgroup in each controller's hierarchy.
+    V1(HashMap<String, PathBuf>),
+}
+
+impl CgroupDir {
+    /// The cgroup v2 cgroup in directory `dir`, e.g.
+    /// `/sys/fs/cgroup/system.slice/sshd.service`.
+    pub fn v2(dir: impl Into<PathBuf>) -> CgroupDir {
+        CgroupDir {
+            layout: Layout::V2(dir.into()),
+        }
+    }
+
+    /// A cgroup v1 cgroup, given its directory in each controller's hierarchy, e.g.
+    /// `("memory", "/sys/fs/cgroup/memory/docker/[id]")`.
+    pub fn v1(dirs: impl IntoIterator<Item = (String, PathBuf)>) -> CgroupDir {
+        CgroupDir {
+            layout: Layout::V1(dirs.into_iter().collect()),
+        }
+    }
+
+    /// The cgroup of `process`, assuming the cgroup filesystem is mounted at `/sys/fs/cgroup`.
+    pub fn of_process(process: &Process) -> io::Result<CgroupDir> {
+        Ok(CgroupDir::from_membership(&process.cgroups()?))
+    }
+
+    /// The cgroup described by the lines of a `/proc/[pid]/cgroup` file, assuming the cgroup
+    /// filesystem is mounted at `/sys/fs/cgroup`.
+    ///
+    /// If any v1 controllers are in use (including on "hybrid" systems that also mount the v2
+    /// hierarchy), the v1 hierarchies are used.
+    pub fn from_membership(cgroups: &[process::Cgroup]) -> CgroupDir {
+        let relative = |path: &Path| Path::new(MOUNT).join(path.strip_prefix("/").unwrap_or(path));
+        let dirs: HashMap<String, PathBuf> = cgroups
+            .iter()
+            .flat_map(|cgroup| {
+                cgroup
+                    .controllers
+                    .iter()
+                    // Named hierarchies like `name=systemd` have no controller files.
+                    .filter(|controller| !controller.starts_with("name="))
+                    .map(move |controller| {
+                        let dir = Path::new(MOUNT)
+                            .join(controller)
+                            .join(cgroup.path.strip_prefix("/").unwrap_or(&cgroup.path));
+                        (controller.clone(), dir)
+                    })
+            })
+            .collect();
+        if !dirs.is_empty() {
+            return CgroupDir::v1(dirs);
+        }
+        let unified = cgroups
+            .iter()
+            .find(|cgroup| cgroup.hierarchy == 0)
+            .map(|cgroup| relative(&cgroup.path))
+            .unwrap_or_else(|| PathBuf::from(MOUNT));
+        CgroupDir::v2(unified)
+    }
+
+    pub fn version(&self) -> CgroupVersion {
+        match self.layout {
+            Layout::V1(_) => CgroupVersion::V1,
+            Layout::V2(_) => CgroupVersion::V2,
+        }
+    }
+
+    /// The path of file `name` in the hierarchy of `controller` (ignored for cgroup v2).
+    fn file(&self, controller: &str, name: &str) -> io::Result<PathBuf> {
+        match &self.layout {
+            Layout::V2(dir) => Ok(dir.join(name)),
+            Layout::V1(dirs) => match dirs.get(controller) {
+                Some(dir) => Ok(dir.join(name)),
+                None => Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("the cgroup isn't in a {} hierarchy", controller),
+                )),
+            },
+        }
+    }
+
+    /// The cpu time used, and throttling caused by cpu limits.
+    ///
+    /// From `cpu.stat` for cgroup v2, and `cpuacct.usage`, `cpuacct.stat` and `cpu.stat` for
+    /// cgroup v1.
+    pub fn cpu_stat(&self) -> io::Result<CpuStat> {
+        match self.layout {
+            Layout::V2(_) => CpuStat::from_v2_reader(File::open(self.file("cpu", "cpu.stat")?)?),
+            Layout::V1(_) => {
+                let usage = util::read_value(self.file("cpuacct", "cpuacct.usage")?)?;
+                let mut stat = CpuStat::from_v1_readers(
+                    File::open(self.file("cpuacct", "cpuacct.stat")?)?,
+                    File::open(self.file("cpu", "cpu.stat")?)?,
+                    clock_ticks_per_sec(),
+                )?;
+                stat.usage = Duration::from_nanos(usage);
+                Ok(stat)
+            }
+        }
+    }
+
+    /// The memory in use, from `memory.current` (v2) or `memory.usage_in_bytes` (v1).
+    pub fn memory_current(&self) -> io::Result<ByteSize> {
+        let name = match self.layout {
+            Layout::V2(_) => "memory.current",
+            Layout::V1(_) => "memory.usage_in_bytes",
+        };
+        Ok(ByteSize(util::read_value(self.file("memory", name)?)?))
+    }
+
+    /// The breakdown of memory use, from `memory.stat`.
+    pub fn memory_stat(&self) -> io::Result<MemoryStat> {
+        MemoryStat::from_reader(File::open(self.file("memory", "memory.stat")?)?)
+    }
+
+    /// The I/O done on each device, from `io.stat` (v2) or the `blkio.throttle.io_service_bytes`
+    /// and `blkio.throttle.io_serviced` files (v1).
+    pub fn io_stat(&self) -> io::Result<Vec<IoStat>> {
+        match self.layout {
+            Layout::V2(_) => IoStat::from_v2_reader(File::open(self.file("io", "io.stat")?)?),
+            Layout::V1(_) => IoStat::from_v1_readers(
+                File::open(self.file("blkio", "blkio.throttle.io_service_bytes")?)?,
+                File::open(self.file("blkio", "blkio.throttle.io_serviced")?)?,
+            ),
+        }
+    }
+
+    /// The number of processes and threads in the cgroup, from `pids.current`.
+    pub fn pids_current(&self) -> io::Result<u64> {
+        util::read_value(self.file("pids", "pids.current")?)
+    }
+}
+
+/// Parse a file of `key value` lines, like `cpu.stat` and `memory.stat`.
+fn parse_flat_keyed(reader: impl io::Read) -> io::Result<Vec<(String, u64)>> {
+    let mut reader = util::LineParser::new(reader);
+    let mut values = Vec::new();
+    loop {
+        let pair = reader.parse_line(|line| {
+            let (input, key) = err_msg!(util::parse_token(line), "key")?;
+            let (_, value) = err_msg!(util::parse_u64(input), "value")?;
+            Ok::<_, Error>((key.to_owned(), value))
+        });
+        match pair {
+            Ok(pair) => values.push(pair),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(values)
+}
+
+/// The cpu usage of a cgroup.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CpuStat {
+    /// The total cpu time used.
+    pub usage: Duration,
+    /// The cpu time spent in user mode.
+    pub user: Duration,
+    /// The cpu time spent in kernel mode.
+    pub system: Duration,
+    /// The number of enforcement periods that have elapsed, if a cpu limit is set.
+    pub nr_periods: u64,
+    /// The number of periods in which the cgroup hit its limit and was throttled.
+    pub nr_throttled: u64,
+    /// The total time the cgroup was throttled for.
+    pub throttled: Duration,
+}
+
+impl CpuStat {
+    /// Parse a cgroup v2 `cpu.stat` file from `reader`.
+    pub fn from_v2_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut stat = CpuStat::default();
+        for (key, value) in parse_flat_keyed(reader)? {
+            match key.as_str() {
+                "usage_usec" => stat.usage = Duration::from_micros(value),
+                "user_usec" => stat.user = Duration::from_micros(value),
+                "system_usec" => stat.system = Duration::from_micros(value),
+                "nr_periods" => stat.nr_periods = value,
+                "nr_throttled" => stat.nr_throttled = value,
+                "throttled_usec" => stat.throttled = Duration::from_micros(value),
+                _ => (),
+            }
+        }
+        Ok(stat)
+    }
+
+    /// Parse the cgroup v1 `cpuacct.stat` (in clock ticks) and `cpu.stat` files.
+    ///
+    /// `usage`, which comes from a third file, is left at zero. Fails with `InvalidInput` if
+    /// `ticks_per_sec` is 0.
+    pub fn from_v1_readers(
+        cpuacct_stat: impl io::Read,
+        cpu_stat: impl io::Read,
+        ticks_per_sec: u64,
+    ) -> io::Result<Self> {
+        if ticks_per_sec == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the clock tick rate must not be 0",
+            ));
+        }
+        // Checked above, so this is never `None`.
+        let ticks = |value| util::ticks_to_duration(value, ticks_per_sec).unwrap_or_default();
+        let mut stat = CpuStat::default();
+        for (key, value) in parse_flat_keyed(cpuacct_stat)? {
+            match key.as_str() {
+                "user" => stat.user = ticks(value),
+                "system" => stat.system = ticks(value),
+                _ => (),
+            }
+        }
+        for (key, value) in parse_flat_keyed(cpu_stat)? {
+            match key.as_str() {
+                "nr_periods" => stat.nr_periods = value,
+                "nr_throttled" => stat.nr_throttled = value,
+                "throttled_time" => stat.throttled = Duration::from_nanos(value),
+                _ => (),
+            }
+        }
+        Ok(stat)
+    }
+
+    /// The proportion of periods in which the cgroup was throttled, from 0 to 1.
+    pub fn throttled_ratio(&self) -> f64 {
+        if self.nr_periods == 0 {
+            return 0.0;
+        }
+        self.nr_throttled as f64 / self.nr_periods as f64
+    }
+}
+
+/// The memory statistics of a cgroup, from `memory.stat`, in bytes (or counts, for the event
+/// counters).
+///
+/// The keys differ between cgroup versions, so they are kept in a map.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStat {
+    inner: HashMap<String, u64>,
+    /// Keys in the order the kernel listed them.
+    order: Vec<String>,
+}
+
+impl MemoryStat {
+    /// Parse `memory.stat`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut stat = MemoryStat::default();
+        for (key, value) in parse_flat_keyed(reader)? {
+            stat.order.push(key.clone());
+            stat.inner.insert(key, value);
+        }
+        Ok(stat)
+    }
+
+    /// The value of `key`, like `anon` or `pgfault`.
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.inner.get(key).copied()
+    }
+
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Iterate over the values in the order the kernel listed them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.order
+            .iter()
+            .map(move |key| (key.as_str(), self.inner[key]))
+    }
+
+    /// Anonymous memory, like the heap and stacks (`anon` in v2, `rss` in v1).
+    pub fn anon(&self) -> Option<ByteSize> {
+        self.get("anon").or_else(|| self.get("rss")).map(ByteSize)
+    }
+
+    /// The page cache (`file` in v2, `cache` in v1).
+    pub fn file(&self) -> Option<ByteSize> {
+        self.get("file").or_else(|| self.get("cache")).map(ByteSize)
+    }
+}
+
+impl std::ops::Index<&str> for MemoryStat {
+    type Output = u64;
+    /// The value of `key`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no value called `key`.
+    fn index(&self, key: &str) -> &u64 {
+        self.inner
+            .get(key)
+            .unwrap_or_else(|| panic!("no value named {:?}", key))
+    }
+}
+
+/// The I/O done by a cgroup on one block device.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoStat {
+    pub major: u32,
+    pub minor: u32,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    /// The number of read requests.
+    pub read_ios: u64,
+    /// The number of write requests.
+    pub write_ios: u64,
+}
+
+impl IoStat {
+    /// The `/dev` path of the device, from `major:minor`.
+    pub fn device_path(&self) -> io::Result<Option<PathBuf>> {
+        crate::device_name(self.major, self.minor)
+    }
+
+    /// Parse a cgroup v2 `io.stat` file from `reader`, with lines like
+    /// `8:0 rbytes=90112 wbytes=0 rios=3 wios=0 dbytes=0 dios=0`.
+    pub fn from_v2_reader(reader: impl io::Read) -> io::Result<Vec<Self>> {
+        let mut reader = util::LineParser::new(reader);
+        let mut stats = Vec::new();
+        loop {
+            match reader.parse_line(IoStat::from_v2_str) {
+                Ok(stat) => stats.push(stat),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Parse the cgroup v1 `blkio.throttle.io_service_bytes` and `blkio.throttle.io_serviced`
+    /// files, with lines like `8:0 Read 90112`.
+    pub fn from_v1_readers(
+        mut service_bytes: impl io::Read,
+        mut serviced: impl io::Read,
+    ) -> io::Result<Vec<Self>> {
+        let mut stats: Vec<IoStat> = Vec::new();
+        for (reader, bytes) in [
+            (&mut service_bytes as &mut dyn io::Read, true),
+            (&mut serviced as &mut dyn io::Read, false),
+        ] {
+            let mut reader = util::LineParser::new(reader);
+            loop {
+                let line = match reader.parse_line(IoStat::parse_v1_line) {
+                    Ok(line) => line,
+                    Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e),
+                };
+                // The per-device `Total`, the final grand total, and the sync/discard splits.
+                let (major, minor, op, value) = match line {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let idx = match stats
+                    .iter()
+                    .position(|stat| (stat.major, stat.minor) == (major, minor))
+                {
+                    Some(idx) => idx,
+                    None => {
+                        stats.push(IoStat {
+                            major,
+                            minor,
+                            ..IoStat::default()
+                        });
+                        stats.len() - 1
+                    }
+                };
+                let stat = &mut stats[idx];
+                match (op, bytes) {
+                    (V1Op::Read, true) => stat.read_bytes = value,
+                    (V1Op::Write, true) => stat.write_bytes = value,
+                    (V1Op::Read, false) => stat.read_ios = value,
+                    (V1Op::Write, false) => stat.write_ios = value,
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    fn from_v2_str(input: &str) -> Result<IoStat, Error> {
+        let (input, device) = err_msg!(parse_device(input), "device")?;
+        let (major, minor) = device_numbers(device)?;
+        let mut stat = IoStat {
+            major,
+            minor,
+            ..IoStat::default()
+        };
+        for field in input.split_ascii_whitespace() {
+            let idx = err_msg!(field.find('='), "expected key=value")?;
+            let (_, value) = err_msg!(util::parse_u64(&field[idx + 1..]), "value")?;
+            match &field[..idx] {
+                "rbytes" => stat.read_bytes = value,
+                "wbytes" => stat.write_bytes = value,
+                "rios" => stat.read_ios = value,
+                "wios" => stat.write_ios = value,
+                _ => (),
+            }
+        }
+        Ok(stat)
+    }
+
+    fn parse_v1_line(input: &str) -> Result<Option<(u32, u32, V1Op, u64)>, Error> {
+        let (input, (major, minor)) = match parse_device(input) {
+            Some((input, device)) => (input, device_numbers(device)?),
+            None => return Ok(None),
+        };
+        let (input, op) = err_msg!(util::parse_token(input), "operation")?;
+        let op = match op {
+            "Read" => V1Op::Read,
+            "Write" => V1Op::Write,
+            _ => return Ok(None),
+        };
+        let (_, value) = err_msg!(util::parse_u64(input), "value")?;
+        Ok(Some((major, minor, op, value)))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum V1Op {
+    Read,
+    Write,
+}
+
+/// Parse a `major:minor` device number. Check the numbers fit with `device_numbers`.
+fn parse_device(input: &str) -> Option<(&str, (u64, u64))> {
+    let (input, major) = util::parse_u64(input)?;
+    let input = util::expect_bytes(":", input)?;
+    let (input, minor) = util::parse_u64(input)?;
+    Some((input, (major, minor)))
+}
+
+fn device_numbers((major, minor): (u64, u64)) -> Result<(u32, u32), Error> {
+    Ok((util::to_u32(major, "major")?, util::to_u32(minor, "minor")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CgroupControllers, CgroupDir, CgroupVersion, CpuStat, IoStat, MemoryStat};
+    use crate::{process::Process, ByteSize};
+    use std::{io, path::Path, time::Duration};
+
+    #[test]
+    fn proc_cgroups() {
+        let raw = "\
+#subsys_name\thierarchy\tnum_cgroups\tenabled
+cpuset\t0\t71\t1
+cpu\t0\t71\t1
+memory\t4\t16\t1
+rdma\t0\t71\t0
+";
+        let controllers = CgroupControllers::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(controllers.len(), 4);
+        assert_eq!(controllers[2].name, "memory");
+        assert_eq!(controllers[2].hierarchy, 4);
+        assert_eq!(controllers[2].num_cgroups, 16);
+        assert!(!controllers[3].enabled);
+        CgroupControllers::from_system().unwrap();
+    }
+
+    #[test]
+    fn cpu_stat() {
+        let v2 = "\
+usage_usec 2500000
+user_usec 2000000
+system_usec 500000
+nr_periods 100
+nr_throttled 25
+throttled_usec 1500000
+";
+        let stat = CpuStat::from_v2_reader(io::Cursor::new(v2)).unwrap();
+        assert_eq!(stat.usage, Duration::from_millis(2500));
+        assert_eq!(stat.system, Duration::from_millis(500));
+        assert_eq!(stat.throttled, Duration::from_millis(1500));
+        assert_eq!(stat.throttled_ratio(), 0.25);
+
+        let stat = CpuStat::from_v1_readers(
+            io::Cursor::new("user 167183\nsystem 20395\n"),
+            io::Cursor::new("nr_periods 10\nnr_throttled 1\nthrottled_time 5000000\n"),
+            100,
+        )
+        .unwrap();
+        assert_eq!(stat.user, Duration::from_millis(1_671_830));
+        assert_eq!(stat.system, Duration::from_millis(203_950));
+        assert_eq!(stat.nr_throttled, 1);
+        assert_eq!(stat.throttled, Duration::from_millis(5));
+        let err = CpuStat::from_v1_readers(io::Cursor::new(""), io::Cursor::new(""), 0);
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn memory_stat() {
+        let v2 = "anon 1048576\nfile 4096\nkernel_stack 16384\npgfault 1234\n";
+        let stat = MemoryStat::from_reader(io::Cursor::new(v2)).unwrap();
+        assert_eq!(stat.anon(), Some(ByteSize(1_048_576)));
+        assert_eq!(stat.file(), Some(ByteSize(4096)));
+        assert_eq!(stat["pgfault"], 1234);
+        assert_eq!(stat.get("pgfault"), Some(1234));
+        assert_eq!(stat.len(), 4);
+        assert_eq!(stat.iter().nth(2), Some(("kernel_stack", 16384)));
+        let v1 = "cache 8192\nrss 2048\n";
+        let stat = MemoryStat::from_reader(io::Cursor::new(v1)).unwrap();
+        assert_eq!(stat.anon(), Some(ByteSize(2048)));
+        assert_eq!(stat.file(), Some(ByteSize(8192)));
+    }
+
+    #[test]
+    fn io_stat() {
+        let v2 = "\
+8:0 rbytes=90112 wbytes=4096 rios=3 wios=1 dbytes=0 dios=0
+253:1 rbytes=0 wbytes=8192 rios=0 wios=2 dbytes=0 dios=0
+";
+        let stats = IoStat::from_v2_reader(io::Cursor::new(v2)).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[1].major, stats[1].minor), (253, 1));
+        assert_eq!(stats[0].read_bytes, 90112);
+        assert_eq!(stats[1].write_ios, 2);
+
+        let bytes = "\
+8:0 Read 90112
+8:0 Write 4096
+8:0 Sync 94208
+8:0 Async 0
+8:0 Discard 0
+8:0 Total 94208
+Total 94208
+";
+        let serviced = "8:0 Read 3\n8:0 Write 1\n8:0 Total 4\nTotal 4\n";
+        let stats =
+            IoStat::from_v1_readers(io::Cursor::new(bytes), io::Cursor::new(serviced)).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].read_bytes, 90112);
+        assert_eq!(stats[0].write_bytes, 4096);
+        assert_eq!(stats[0].read_ios, 3);
+        assert_eq!(stats[0].write_ios, 1);
+    }
+
+    #[test]
+    fn cgroup_dir() {
+        let raw = "9:name=systemd:/\n4:memory:/docker/abc\n2:cpu,cpuacct:/docker/abc\n0::/\n";
+        let cgroups = crate::process::cgroup::from_reader(io::Cursor::new(raw)).unwrap();
+        let dir = CgroupDir::from_membership(&cgroups);
+        assert_eq!(dir.version(), CgroupVersion::V1);
+        assert_eq!(
+            dir.file("cpuacct", "cpuacct.usage").unwrap(),
+            Path::new("/sys/fs/cgroup/cpuacct/docker/abc/cpuacct.usage")
+        );
+        let err = dir.file("pids", "pids.current").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let cgroups =
+            crate::process::cgroup::from_reader(io::Cursor::new("0::/system.slice/sshd.service\n"))
+                .unwrap();
+        let dir = CgroupDir::from_membership(&cgroups);
+        assert_eq!(dir.version(), CgroupVersion::V2);
+        assert_eq!(
+            dir.file("memory", "memory.current").unwrap(),
+            Path::new("/sys/fs/cgroup/system.slice/sshd.service/memory.current")
+        );
+
+        // The cgroup may not be visible from inside a container, but its files should parse if it
+        // is.
+        let dir = CgroupDir::of_process(&Process::current()).unwrap();
+        match dir.pids_current() {
+            Ok(pids) => assert!(pids > 0),
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Converting the boot-relative times in `/proc` to wall-clock times.
+//!
+//! Process start times (`ProcessStat::starttime`) are in clock ticks since boot. Turning them into
+//! a date needs the boot time and the tick rate, and the obvious sources of each are subtly wrong:
+//! `btime` in `/proc/stat` is truncated to whole seconds, the tick rate is `USER_HZ` rather than
+//! the kernel's `HZ`, and `SystemTime::now()` minus the uptime is off by however long passed
+//! between the two reads. `BootClock` gets these right in one place.
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    process::{clock_ticks_per_sec, ProcessStat},
+    stat::{Stat, StatOptions},
+    uptime::Uptime,
+    util,
+};
+
+/// Converts times since boot, in clock ticks, to wall-clock times.
+///
+/// ```no_run
+/// use linux_proc::{clock::BootClock, process::Process};
+///
+/// let clock = BootClock::from_system()?;
+/// let stat = Process::current().stat()?;
+/// println!("started at {:?}", clock.process_start(&stat));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BootClock {
+    boot_time: SystemTime,
+    ticks_per_sec: u64,
+}
+
+impl BootClock {
+    /// Read the boot time from `/proc/stat` and `/proc/uptime`, and the tick rate from
+    /// `sysconf(_SC_CLK_TCK)`.
+    ///
+    /// `btime` is only accurate to the second, so the boot time is refined using the uptime, which
+    /// is accurate to 10ms.
+    pub fn from_system() -> io::Result<Self> {
+        let options = StatOptions::new()
+            .per_cpu(false)
+            .interrupts(false)
+            .softirqs(false);
+        let stat = Stat::parse_with(options)?;
+        let uptime = Uptime::from_system()?;
+        BootClock::from_readings(&stat, &uptime, SystemTime::now(), clock_ticks_per_sec())
+    }
+
+    /// A clock for a system that booted at `boot_time`, with `ticks_per_sec` clock ticks per
+    /// second.
+    ///
+    /// Fails with `InvalidInput` if `ticks_per_sec` is 0.
+    pub fn new(boot_time: SystemTime, ticks_per_sec: u64) -> io::Result<Self> {
+        if ticks_per_sec == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the clock tick rate must not be 0",
+            ));
+        }
+        Ok(BootClock {
+            boot_time,
+            ticks_per_sec,
+        })
+    }
+
+    /// A clock from `btime` alone, accurate to the second.
+    ///
+    /// Fails with `InvalidData` if `btime` is too large to be a `SystemTime`.
+    pub fn from_stat(stat: &Stat) -> io::Result<Self> {
+        BootClock::new(btime(stat)?, clock_ticks_per_sec())
+    }
+
+    /// Combine existing readings: `btime` from `stat`, and `uptime` read at `now`.
+    ///
+    /// `btime` is truncated to the second, so the true boot time is in the second after it.
+    /// `now - uptime` is more precise, but only if the readings were taken together: this uses it
+    /// when it falls in that second, and `btime` otherwise.
+    ///
+    /// Fails with `InvalidInput` if `ticks_per_sec` is 0, and `InvalidData` if `btime` is too
+    /// large to be a `SystemTime`.
+    pub fn from_readings(
+        stat: &Stat,
+        uptime: &Uptime,
+        now: SystemTime,
+        ticks_per_sec: u64,
+    ) -> io::Result<Self> {
+        let btime = btime(stat)?;
+        let within_second = |derived: SystemTime| {
+            derived >= btime
+                && btime
+                    .checked_add(Duration::from_secs(1))
+                    .is_none_or(|end| derived < end)
+        };
+        let boot_time = match now.checked_sub(uptime.up) {
+            Some(derived) if within_second(derived) => derived,
+            _ => btime,
+        };
+        BootClock::new(boot_time, ticks_per_sec)
+    }
+
+    /// When the system booted.
+    pub fn boot_time(&self) -> SystemTime {
+        self.boot_time
+    }
+
+    /// The number of clock ticks per second.
+    pub fn ticks_per_sec(&self) -> u64 {
+        self.ticks_per_sec
+    }
+
+    /// `ticks` clock ticks after boot, as a duration.
+    pub fn since_boot(&self, ticks: u64) -> Duration {
+        // The constructors don't allow a rate of 0.
+        util::ticks_to_duration(ticks, self.ticks_per_sec).unwrap_or_default()
+    }
+
+    /// The wall-clock time `ticks` clock ticks after boot.
+    pub fn to_system_time(&self, ticks: u64) -> SystemTime {
+        self.boot_time + self.since_boot(ticks)
+    }
+
+    /// When a process started.
+    pub fn process_start(&self, stat: &ProcessStat) -> SystemTime {
+        self.to_system_time(stat.starttime)
+    }
+
+    /// How long a process has been running, given the uptime read at the same time as `stat`.
+    ///
+    /// This doesn't use the wall clock, so it's right even if the clock has changed.
+    pub fn process_age(&self, stat: &ProcessStat, uptime: &Uptime) -> Duration {
+        uptime.up.saturating_sub(self.since_boot(stat.starttime))
+    }
+}
+
+/// `btime` from `stat` as a `SystemTime`.
+fn btime(stat: &Stat) -> io::Result<SystemTime> {
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(stat.boot_time))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "btime is out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BootClock;
+    use crate::{process::Process, stat::Stat, uptime::Uptime};
+    use std::io;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn boot_clock() {
+        let stat = Stat {
+            boot_time: 1_000_000,
+            ..Stat::default()
+        };
+        let uptime = Uptime {
+            up: Duration::from_millis(5_250),
+            idle: Duration::from_secs(0),
+        };
+        let now = UNIX_EPOCH + Duration::from_millis(1_000_005_500);
+        let clock = BootClock::from_readings(&stat, &uptime, now, 100).unwrap();
+        // btime was truncated from 1_000_000.25.
+        assert_eq!(
+            clock.boot_time(),
+            UNIX_EPOCH + Duration::from_millis(1_000_000_250)
+        );
+        assert_eq!(
+            clock.to_system_time(150),
+            UNIX_EPOCH + Duration::from_millis(1_000_001_750)
+        );
+        // The readings weren't taken together, so only btime can be trusted.
+        let later = now + Duration::from_secs(3600);
+        let clock = BootClock::from_readings(&stat, &uptime, later, 100).unwrap();
+        assert!(BootClock::from_readings(&stat, &uptime, now, 0).is_err());
+        assert_eq!(
+            clock.boot_time(),
+            UNIX_EPOCH + Duration::from_secs(1_000_000)
+        );
+        assert_eq!(
+            BootClock::from_stat(&stat).unwrap().boot_time(),
+            UNIX_EPOCH + Duration::from_secs(1_000_000)
+        );
+
+        let malformed = Stat {
+            boot_time: u64::MAX,
+            ..Stat::default()
+        };
+        let err = BootClock::from_readings(&malformed, &uptime, now, 100).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(BootClock::from_stat(&malformed).is_err());
+    }
+
+    #[test]
+    fn from_system() {
+        let clock = BootClock::from_system().unwrap();
+        let stat = Process::current().stat().unwrap();
+        let started = clock.process_start(&stat);
+        assert!(started <= SystemTime::now() + Duration::from_secs(1));
+        assert!(started >= clock.boot_time());
+        let age = clock.process_age(&stat, &Uptime::from_system().unwrap());
+        assert!(age < Duration::from_secs(24 * 3600));
+    }
+}
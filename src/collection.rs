@@ -0,0 +1,44 @@
+//! Types shared by the collections of named entries, like `DiskStats` and `NetDev`.
+use std::collections::HashMap;
+use std::iter::FusedIterator;
+use std::slice;
+
+/// An iterator over the entries of a collection in the order the kernel listed them.
+///
+/// Returned by `iter` and `IntoIterator for &T` on `DiskStats` and `NetDev`.
+#[derive(Debug, Clone)]
+pub struct Iter<'a, T> {
+    order: slice::Iter<'a, String>,
+    inner: &'a HashMap<String, T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    pub(crate) fn new(order: &'a [String], inner: &'a HashMap<String, T>) -> Self {
+        Iter {
+            order: order.iter(),
+            inner,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.order.next().map(|name| &self.inner[name])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.order.next_back().map(|name| &self.inner[name])
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
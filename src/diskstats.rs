@@ -1,71 +1,298 @@
 //! Bindings to `/proc/diskstats`.
 use std::collections::HashMap;
-use std::fs::File;
-use std::io;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use std::{fmt, io};
 
-use crate::{util, Error};
+use crate::{
+    collection::Iter, parse::ParseMode, render::Seconds, util, Error, RatioFormat, RatioUnit,
+    Sectors,
+};
 
+#[derive(Debug)]
 pub struct DiskStats {
     inner: HashMap<String, DiskStat>,
+    /// Device names in the order the kernel listed them.
+    order: Vec<String>,
+    /// The raw file contents, kept to be reused by `refresh`.
+    buf: Vec<u8>,
+    mode: ParseMode,
+    /// Lines that weren't understood. Always empty unless parsed with `ParseMode::Lenient`.
+    pub extras: Vec<String>,
 }
 
 impl DiskStats {
     const PATH: &'static str = "/proc/diskstats";
     /// Parse the contents of `/proc/diskstats`.
     pub fn from_system() -> io::Result<Self> {
-        DiskStats::from_reader(File::open(Self::PATH)?)
+        DiskStats::parse_with(ParseMode::Strict)
     }
 
-    fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+    /// Parse the contents of `/proc/diskstats`, treating lines that aren't understood according
+    /// to `mode`. The mode is kept for `refresh`.
+    pub fn parse_with(mode: ParseMode) -> io::Result<Self> {
+        let mut stats = DiskStats {
+            inner: HashMap::new(),
+            order: Vec::new(),
+            buf: Vec::with_capacity(4096),
+            mode,
+            extras: Vec::new(),
+        };
+        stats.refresh()?;
+        Ok(stats)
+    }
+
+    /// Re-read `/proc/diskstats`, updating the counters in place.
+    ///
+    /// This is intended for sampling in a loop: the file is read into a buffer kept from the
+    /// last call and parsed directly from it. As long as the set of devices hasn't changed, no
+    /// memory is allocated. If it has, the map is rebuilt.
+    ///
+    /// If an error is returned, the contents of `self` are unspecified.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        self.refresh_from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Like `refresh`, but reading `/proc/diskstats`-formatted data from `reader`.
+    pub fn refresh_from_reader(&mut self, mut reader: impl io::Read) -> io::Result<()> {
+        self.buf.clear();
+        reader.read_to_end(&mut self.buf)?;
+        let input = std::str::from_utf8(&self.buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut seen = 0;
+        for line in input.lines() {
+            let (rest, major, minor, name) = match DiskStat::parse_device(line) {
+                Ok(device) => device,
+                // Let the full parse below collect the line.
+                Err(_) if self.mode == ParseMode::Lenient => break,
+                Err(e) => return Err(invalid_data(e)),
+            };
+            let stat = match self.order.get(seen) {
+                Some(expected) if expected == name => self.inner.get_mut(name),
+                _ => None,
+            };
+            match stat {
+                Some(stat) if (stat.major, stat.minor) == (major, minor) => {
+                    match stat.parse_counters(rest) {
+                        Ok(()) => (),
+                        Err(_) if self.mode == ParseMode::Lenient => break,
+                        Err(e) => return Err(invalid_data(e)),
+                    }
+                }
+                _ => break,
+            }
+            seen += 1;
+        }
+        if seen != self.order.len() || seen != input.lines().count() {
+            // The devices changed, so start again.
+            let fresh = DiskStats::from_reader_with(&self.buf[..], self.mode)?;
+            self.inner = fresh.inner;
+            self.order = fresh.order;
+            self.extras = fresh.extras;
+        }
+        Ok(())
+    }
+
+    /// Parse `/proc/diskstats`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        DiskStats::from_reader_with(reader, ParseMode::Strict)
+    }
+
+    /// Parse `/proc/diskstats`-formatted data from `reader`, treating lines that aren't
+    /// understood according to `mode`.
+    pub fn from_reader_with(reader: impl io::Read, mode: ParseMode) -> io::Result<Self> {
         let mut reader = util::LineParser::new(reader);
         let mut inner = HashMap::new();
+        let mut order = Vec::new();
+        let mut extras = Vec::new();
         loop {
-            match reader.parse_line(DiskStat::from_str) {
-                Ok(disk_stat) => {
+            match reader.parse_line_with_mode(mode, &mut extras, DiskStat::from_str) {
+                Ok(None) => (),
+                Ok(Some(disk_stat)) => {
+                    order.push(disk_stat.name.clone());
                     if inner.insert(disk_stat.name.clone(), disk_stat).is_some() {
-                        panic!("Duplicate device name in /proc/diskstats");
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "duplicate device name in /proc/diskstats",
+                        ));
                     }
                 }
                 Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
                 Err(e) => return Err(e),
             }
         }
-        Ok(DiskStats { inner })
+        Ok(DiskStats {
+            inner,
+            order,
+            buf: Vec::new(),
+            mode,
+            extras,
+        })
+    }
+
+    /// The counters for device `name`, like `sda`.
+    pub fn get(&self, name: &str) -> Option<&DiskStat> {
+        self.inner.get(name)
+    }
+
+    /// The number of devices.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no devices.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// The device names, in the order the kernel listed them.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+
+    /// Iterate over the devices in the order the kernel listed them (by device number, so
+    /// partitions follow their disk).
+    pub fn iter(&self) -> Iter<'_, DiskStat> {
+        Iter::new(&self.order, &self.inner)
+    }
+
+    /// The same as `iter`, which also yields the devices in the order the kernel listed them.
+    pub fn iter_ordered(&self) -> Iter<'_, DiskStat> {
+        self.iter()
+    }
+}
+
+impl DiskStats {
+    /// The change in each device's counters since `earlier`.
+    ///
+    /// Devices are paired up by name. A device present in only one snapshot (hotplugged or
+    /// removed in between), or whose device number changed, is reported in
+    /// `DiskStatsDelta::appeared` or `DiskStatsDelta::disappeared` rather than producing a bogus
+    /// delta.
+    pub fn diff(&self, earlier: &DiskStats) -> DiskStatsDelta {
+        let mut devices = Vec::new();
+        let mut appeared = Vec::new();
+        for current in self.iter() {
+            match earlier.inner.get(&current.name) {
+                Some(prev) if (prev.major, prev.minor) == (current.major, current.minor) => {
+                    devices.push(current.delta(prev))
+                }
+                _ => appeared.push(current.name.clone()),
+            }
+        }
+        let disappeared = earlier
+            .iter()
+            .filter(|prev| match self.inner.get(&prev.name) {
+                Some(current) => (prev.major, prev.minor) != (current.major, current.minor),
+                None => true,
+            })
+            .map(|prev| prev.name.clone())
+            .collect();
+        DiskStatsDelta {
+            devices,
+            appeared,
+            disappeared,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DiskStats {
+    /// Serializes as a sequence of devices, in kernel order.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
     }
+}
 
-    pub fn iter(&self) -> impl Iterator<Item = &DiskStat> {
-        self.inner.values()
+impl fmt::Display for DiskStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self
+            .order
+            .iter()
+            .map(|name| name.len())
+            .fold("device".len(), usize::max);
+        write!(
+            f,
+            "{:<name$} {:>10} {:>11} {:>11} {:>10} {:>11} {:>11} {:>9} {:>11}",
+            "device",
+            "reads",
+            "read",
+            "read time",
+            "writes",
+            "written",
+            "write time",
+            "in flight",
+            "io time",
+        )?;
+        for stat in self.iter() {
+            write!(
+                f,
+                "\n{:<name$} {:>10} {:>11} {:>11} {:>10} {:>11} {:>11} {:>9} {:>11}",
+                stat.name,
+                stat.reads_completed,
+                stat.sectors_read.bytes(),
+                Seconds(stat.time_reading),
+                stat.writes_completed,
+                stat.sectors_written.bytes(),
+                Seconds(stat.time_writing),
+                stat.io_in_progress,
+                Seconds(stat.time_io),
+            )?;
+        }
+        Ok(())
     }
 }
 
-impl std::ops::Deref for DiskStats {
-    type Target = HashMap<String, DiskStat>;
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+impl std::ops::Index<&str> for DiskStats {
+    type Output = DiskStat;
+    /// The counters for device `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no device called `name`.
+    fn index(&self, name: &str) -> &DiskStat {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no device named {:?}", name))
     }
 }
 
 impl IntoIterator for DiskStats {
-    type IntoIter = std::collections::hash_map::IntoIter<String, DiskStat>;
-    type Item = (String, DiskStat);
+    type IntoIter = std::vec::IntoIter<DiskStat>;
+    type Item = DiskStat;
+    /// The devices in the order the kernel listed them.
+    fn into_iter(mut self) -> Self::IntoIter {
+        let inner = &mut self.inner;
+        self.order
+            .iter()
+            .filter_map(|name| inner.remove(name))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DiskStats {
+    type IntoIter = Iter<'a, DiskStat>;
+    type Item = &'a DiskStat;
+    /// The devices in the order the kernel listed them.
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.into_iter()
+        self.iter()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DiskStat {
     pub major: u64,
     pub minor: u64,
     pub name: String,
     pub reads_completed: u64,
     pub reads_merged: u64,
-    pub sectors_read: u64,
+    pub sectors_read: Sectors,
     pub time_reading: Duration,
     pub writes_completed: u64,
     pub writes_merged: u64,
-    pub sectors_written: u64,
+    pub sectors_written: Sectors,
     // in ms
     pub time_writing: Duration,
     pub io_in_progress: u64,
@@ -75,63 +302,194 @@ pub struct DiskStat {
     pub time_io_weighted: Duration,
 }
 
+/// The changes in disk statistics between two snapshots, from `DiskStats::diff`.
+#[derive(Debug, Clone)]
+pub struct DiskStatsDelta {
+    /// Deltas for the devices present in both snapshots, in kernel order.
+    pub devices: Vec<DiskStatDelta>,
+    /// Devices that are only in the later snapshot.
+    pub appeared: Vec<String>,
+    /// Devices that are only in the earlier snapshot.
+    pub disappeared: Vec<String>,
+}
+
+impl DiskStatsDelta {
+    /// Get the delta for a device by name.
+    pub fn get(&self, name: &str) -> Option<&DiskStatDelta> {
+        self.devices.iter().find(|delta| delta.name == name)
+    }
+}
+
+/// The change in a device's counters between two snapshots.
+///
+/// Counters that went backwards (e.g. the device was reset, or a 32-bit counter wrapped) give a
+/// delta of 0.
+#[derive(Debug, Clone)]
+pub struct DiskStatDelta {
+    pub name: String,
+    pub reads_completed: u64,
+    pub reads_merged: u64,
+    pub sectors_read: Sectors,
+    pub time_reading: Duration,
+    pub writes_completed: u64,
+    pub writes_merged: u64,
+    pub sectors_written: Sectors,
+    pub time_writing: Duration,
+    /// The number of I/Os in progress at the time of the later snapshot (not a delta).
+    pub io_in_progress: u64,
+    pub time_io: Duration,
+    pub time_io_weighted: Duration,
+}
+
+impl DiskStatDelta {
+    /// The device's friendly name, like `DiskStat::display_name`.
+    pub fn display_name(&self) -> String {
+        display_name(Path::new(SYS_BLOCK), &self.name)
+    }
+
+    /// The proportion of `interval` the device was busy, from 0 to 1 (like `%util` in `iostat`).
+    pub fn utilization(&self, interval: Duration) -> f64 {
+        if interval == Duration::from_secs(0) {
+            return 0.0;
+        }
+        (self.time_io.as_secs_f64() / interval.as_secs_f64()).min(1.0)
+    }
+
+    /// `utilization`, expressed in `format`.
+    pub fn utilization_in(&self, interval: Duration, format: RatioFormat) -> f64 {
+        format.apply(self.utilization(interval))
+    }
+
+    /// `utilization` in thousandths of `unit` (see `RatioUnit::milli`).
+    pub fn utilization_milli(&self, interval: Duration, unit: RatioUnit) -> u64 {
+        unit.milli(self.utilization(interval))
+    }
+}
+
+/// Where the block devices are described in sysfs.
+const SYS_BLOCK: &str = "/sys/block";
+
+/// The name of a device-mapper device from `[sys_block]/dm-N/dm/name`, or `name` if it isn't one
+/// or its name can't be read.
+fn display_name(sys_block: &Path, name: &str) -> String {
+    if !name.starts_with("dm-") {
+        return name.to_owned();
+    }
+    match fs::read_to_string(sys_block.join(name).join("dm/name")) {
+        Ok(dm_name) if !dm_name.trim().is_empty() => dm_name.trim().to_owned(),
+        _ => name.to_owned(),
+    }
+}
+
 macro_rules! err_msg {
     ($inner:expr, $msg:expr) => {
         $inner.ok_or_else(|| Error::from($msg))
     };
 }
 
+fn invalid_data(e: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
 impl DiskStat {
+    /// The device's friendly name.
+    ///
+    /// The kernel names device-mapper devices (LVM volumes, LUKS containers, multipath) `dm-0`,
+    /// `dm-1` and so on. For those this reads the name they were created with, like
+    /// `vg0-root`, from `/sys/block/dm-N/dm/name`. Other devices, and dm devices whose name can't
+    /// be read, keep their kernel name.
+    pub fn display_name(&self) -> String {
+        display_name(Path::new(SYS_BLOCK), &self.name)
+    }
+
+    /// The `/dev` path of the device, from `major:minor`.
+    pub fn device_path(&self) -> io::Result<Option<PathBuf>> {
+        crate::device_name(self.major as u32, self.minor as u32)
+    }
+
     fn from_str(input: &str) -> Result<DiskStat, Error> {
+        let (input, major, minor, name) = DiskStat::parse_device(input)?;
+        let mut stat = DiskStat {
+            major,
+            minor,
+            name: name.to_owned(),
+            ..DiskStat::default()
+        };
+        stat.parse_counters(input)?;
+        Ok(stat)
+    }
+
+    /// Parse the device number and name at the start of a line.
+    fn parse_device(input: &str) -> Result<(&str, u64, u64, &str), Error> {
         let (input, major) = err_msg!(util::parse_u64(input), "major number")?;
         let (input, minor) = err_msg!(util::parse_u64(input), "minor number")?;
         let (input, name) = err_msg!(util::parse_token(input), "device name")?;
-        let name = name.to_owned();
+        Ok((input, major, minor, name))
+    }
+
+    /// Parse the counters following the device name into `self`.
+    fn parse_counters(&mut self, input: &str) -> Result<(), Error> {
         let (input, reads_completed) =
             err_msg!(util::parse_u64(input), "reads completed successfully")?;
         let (input, reads_merged) = err_msg!(util::parse_u64(input), "reads merged")?;
         let (input, sectors_read) = err_msg!(util::parse_u64(input), "sectors read")?;
         let (input, time_reading) = err_msg!(util::parse_u64(input), "time spent reading (ms)")?;
-        let time_reading = Duration::from_millis(time_reading);
         let (input, writes_completed) =
             err_msg!(util::parse_u64(input), "writes completed successfully")?;
         let (input, writes_merged) = err_msg!(util::parse_u64(input), "writes merged")?;
         let (input, sectors_written) = err_msg!(util::parse_u64(input), "sectors written")?;
         let (input, time_writing) = err_msg!(util::parse_u64(input), "time writing")?;
-        let time_writing = Duration::from_millis(time_writing);
         let (input, io_in_progress) =
             err_msg!(util::parse_u64(input), "I/Os currently in progress")?;
         let (input, time_io) = err_msg!(util::parse_u64(input), "time spent doing I/Os (ms)")?;
-        let time_io = Duration::from_millis(time_io);
         let (_input, time_io_weighted) = err_msg!(
             util::parse_u64(input),
             "weighted time spent doing I/Os (ms)"
         )?;
-        let time_io_weighted = Duration::from_millis(time_io_weighted);
         // We don't check remaining content as future linux may add extra columns.
-        Ok(DiskStat {
-            major,
-            minor,
-            name,
-            reads_completed,
-            reads_merged,
-            sectors_read,
-            time_reading,
-            writes_completed,
-            writes_merged,
-            sectors_written,
-            time_writing,
-            io_in_progress,
-            time_io,
-            time_io_weighted,
-        })
+        self.reads_completed = reads_completed;
+        self.reads_merged = reads_merged;
+        self.sectors_read = Sectors(sectors_read);
+        self.time_reading = Duration::from_millis(time_reading);
+        self.writes_completed = writes_completed;
+        self.writes_merged = writes_merged;
+        self.sectors_written = Sectors(sectors_written);
+        self.time_writing = Duration::from_millis(time_writing);
+        self.io_in_progress = io_in_progress;
+        self.time_io = Duration::from_millis(time_io);
+        self.time_io_weighted = Duration::from_millis(time_io_weighted);
+        Ok(())
+    }
+
+    fn delta(&self, earlier: &DiskStat) -> DiskStatDelta {
+        let duration =
+            |later: Duration, earlier: Duration| later.checked_sub(earlier).unwrap_or_default();
+        DiskStatDelta {
+            name: self.name.clone(),
+            reads_completed: self.reads_completed.saturating_sub(earlier.reads_completed),
+            reads_merged: self.reads_merged.saturating_sub(earlier.reads_merged),
+            sectors_read: self.sectors_read.saturating_sub(earlier.sectors_read),
+            time_reading: duration(self.time_reading, earlier.time_reading),
+            writes_completed: self
+                .writes_completed
+                .saturating_sub(earlier.writes_completed),
+            writes_merged: self.writes_merged.saturating_sub(earlier.writes_merged),
+            sectors_written: self.sectors_written.saturating_sub(earlier.sectors_written),
+            time_writing: duration(self.time_writing, earlier.time_writing),
+            io_in_progress: self.io_in_progress,
+            time_io: duration(self.time_io, earlier.time_io),
+            time_io_weighted: duration(self.time_io_weighted, earlier.time_io_weighted),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::DiskStats;
-    use std::io;
+    use super::{display_name, DiskStats};
+    use crate::util::TempDir;
+    use crate::{parse::ParseMode, RatioFormat, RatioUnit, Sectors};
+    use std::time::Duration;
+    use std::{fs, io};
 
     #[test]
     fn proc_diskstats() {
@@ -149,6 +507,114 @@ mod tests {
    8      33 sdc1 7279 0 1575472 91310 7 0 56 0 0 90670 95424
   11       0 sr0 0 0 0 0 0 0 0 0 0 0 0
 ";
-        let _stat = DiskStats::from_reader(io::Cursor::new(raw)).unwrap();
+        let stats = DiskStats::from_reader(io::Cursor::new(raw)).unwrap();
+        let names: Vec<_> = stats.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "sdb", "sdb1", "sdb2", "sdb3", "sda", "sda1", "sda2", "sda3", "sda5", "sdc",
+                "sdc1", "sr0"
+            ]
+        );
+        let ordered: Vec<_> = stats.iter_ordered().map(|s| s.name.as_str()).collect();
+        assert_eq!(ordered, names);
+    }
+
+    #[test]
+    fn diff() {
+        let earlier = "\
+   8       0 sda 446866 32893 8168064 20164 339296 376515 86758441 4343530 0 250860 4704740
+   8       1 sda1 143 30 11462 24 1 0 8 0 0 50 64
+   8      16 sdb 213 0 18712 564 0 0 0 0 0 217 794
+";
+        let later = "\
+   8       0 sda 446900 32893 8169064 20200 339296 376515 86758441 4343530 2 251260 4705140
+   8       1 sda1 140 30 11462 24 1 0 8 0 0 50 64
+   8      32 sdb 0 0 0 0 0 0 0 0 0 0 0
+   8      48 sdc 7354 0 1580168 91987 7 0 56 0 0 91374 96127
+";
+        let earlier = DiskStats::from_reader(io::Cursor::new(earlier)).unwrap();
+        let later = DiskStats::from_reader(io::Cursor::new(later)).unwrap();
+        let delta = later.diff(&earlier);
+        let sda = delta.get("sda").unwrap();
+        assert_eq!(sda.reads_completed, 34);
+        assert_eq!(sda.sectors_read, Sectors(1000));
+        assert_eq!(sda.sectors_read.bytes().bytes(), 512_000);
+        assert_eq!(sda.io_in_progress, 2);
+        assert_eq!(sda.time_io, Duration::from_millis(400));
+        assert_eq!(sda.utilization(Duration::from_secs(1)), 0.4);
+        let format = RatioFormat::percent().decimals(Some(0));
+        assert_eq!(sda.utilization_in(Duration::from_secs(1), format), 40.0);
+        assert_eq!(
+            sda.utilization_milli(Duration::from_secs(1), RatioUnit::Permille),
+            400_000
+        );
+        // counter went backwards
+        assert_eq!(delta.get("sda1").unwrap().reads_completed, 0);
+        // sdb was replaced by a different device
+        assert!(delta.get("sdb").is_none());
+        assert_eq!(delta.appeared, vec!["sdb", "sdc"]);
+        assert_eq!(delta.disappeared, vec!["sdb"]);
+    }
+
+    #[test]
+    fn refresh() {
+        let first = "\
+   8       0 sda 446866 32893 8168064 20164 339296 376515 86758441 4343530 0 250860 4704740
+   8       1 sda1 143 30 11462 24 1 0 8 0 0 50 64
+";
+        let second = "\
+   8       0 sda 446900 32893 8169064 20200 339296 376515 86758441 4343530 2 251260 4705140
+   8       1 sda1 150 30 11462 24 1 0 8 0 0 50 64
+";
+        let third = "\
+   8       0 sda 447000 32893 8169064 20200 339296 376515 86758441 4343530 2 251260 4705140
+   8      16 sdb 213 0 18712 564 0 0 0 0 0 217 794
+";
+        let mut stats = DiskStats::from_reader(io::Cursor::new(first)).unwrap();
+        stats.refresh_from_reader(io::Cursor::new(second)).unwrap();
+        assert_eq!(stats["sda"].reads_completed, 446900);
+        assert_eq!(stats["sda1"].reads_completed, 150);
+        stats.refresh_from_reader(io::Cursor::new(third)).unwrap();
+        assert_eq!(stats.names().collect::<Vec<_>>(), vec!["sda", "sdb"]);
+        assert_eq!(stats.len(), 2);
+        assert!(stats.get("sda1").is_none());
+        let names: Vec<_> = (&stats).into_iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["sda", "sdb"]);
+        assert_eq!(stats["sda"].reads_completed, 447000);
+        assert!(stats
+            .refresh_from_reader(io::Cursor::new("   8 0 sda 1 2\n"))
+            .is_err());
+    }
+
+    #[test]
+    fn lenient() {
+        let raw = "\
+   8       0 sda 446866 32893 8168064 20164 339296 376515 86758441 4343530 0 250860 4704740
+   8       1 sda1 143 30
+";
+        assert!(DiskStats::from_reader(io::Cursor::new(raw)).is_err());
+        let mut stats =
+            DiskStats::from_reader_with(io::Cursor::new(raw), ParseMode::Lenient).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats.extras, ["   8       1 sda1 143 30"]);
+        let fixed = raw.replace("sda1 143 30", "sda1 143 30 11462 24 1 0 8 0 0 50 64");
+        stats.refresh_from_reader(io::Cursor::new(fixed)).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.extras.is_empty());
+    }
+
+    #[test]
+    fn dm_display_name() {
+        let sys_block = TempDir::new("dm");
+        fs::create_dir_all(sys_block.join("dm-0/dm")).unwrap();
+        fs::write(sys_block.join("dm-0/dm/name"), "vg0-root\n").unwrap();
+        assert_eq!(display_name(&sys_block, "dm-0"), "vg0-root");
+        assert_eq!(display_name(&sys_block, "dm-1"), "dm-1");
+        assert_eq!(display_name(&sys_block, "sda"), "sda");
+
+        for stat in DiskStats::from_system().unwrap().iter() {
+            assert!(!stat.display_name().is_empty());
+        }
     }
 }
@@ -0,0 +1,3 @@
+//! Bindings to the driver-specific information in `/proc/driver`.
+pub mod nvidia;
+pub mod rtc;
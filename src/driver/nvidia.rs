@@ -0,0 +1,198 @@
+//! Bindings to `/proc/driver/nvidia`, created by the NVIDIA proprietary and open kernel modules.
+//!
+//! When the driver isn't loaded, everything here fails with `io::ErrorKind::NotFound`, so
+//! `ReadOutcome::from` turns it into `ReadOutcome::NotFound`.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use crate::{util, Error};
+
+const ROOT: &str = "/proc/driver/nvidia";
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The version of the loaded kernel module, e.g. `535.54.03`, from `/proc/driver/nvidia/version`.
+pub fn driver_version() -> io::Result<String> {
+    parse_driver_version(File::open(Path::new(ROOT).join("version"))?)
+}
+
+fn parse_driver_version(reader: impl io::Read) -> io::Result<String> {
+    let mut reader = util::LineParser::new(reader);
+    // e.g. `NVRM version: NVIDIA UNIX x86_64 Kernel Module  535.54.03  Tue Jun  6 22:20:39 UTC
+    // 2023`, or `NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  535.54.03  Release
+    // Build  (dvs-builder@U16-T02-35-3)  Tue Jun  6 22:17:57 UTC 2023`.
+    reader.parse_line(|line| {
+        let (_, value) = err_msg!(util::parse_key_value(line), "expected \"NVRM version:\"")?;
+        let version = value.split_ascii_whitespace().find(|token| {
+            token.contains('.') && token.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+        });
+        Ok::<_, Error>(err_msg!(version, "driver version")?.to_owned())
+    })
+}
+
+/// The GPUs the driver manages, from `/proc/driver/nvidia/gpus`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NvidiaGpus {
+    inner: Vec<NvidiaGpu>,
+}
+
+impl NvidiaGpus {
+    /// Read the information file of every GPU, sorted by bus location.
+    pub fn from_system() -> io::Result<Self> {
+        let mut locations = Vec::new();
+        for entry in fs::read_dir(Path::new(ROOT).join("gpus"))? {
+            locations.push(entry?.file_name().to_string_lossy().into_owned());
+        }
+        locations.sort();
+        let inner = locations
+            .iter()
+            .map(|location| NvidiaGpu::from_bus_location(location))
+            .collect::<io::Result<_>>()?;
+        Ok(NvidiaGpus { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &NvidiaGpu> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for NvidiaGpus {
+    type Target = Vec<NvidiaGpu>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for NvidiaGpus {
+    type IntoIter = std::vec::IntoIter<NvidiaGpu>;
+    type Item = NvidiaGpu;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// A GPU, from `/proc/driver/nvidia/gpus/[bus location]/information`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NvidiaGpu {
+    /// The model name, e.g. `Tesla T4`.
+    pub model: String,
+    /// The PCI address, e.g. `0000:00:1e.0`, matching the directory name.
+    pub bus_location: String,
+    pub irq: Option<u32>,
+    /// The UUID used by `nvidia-smi` and `CUDA_VISIBLE_DEVICES`, e.g. `GPU-5b1a...`.
+    pub uuid: Option<String>,
+    pub video_bios: Option<String>,
+    /// `PCIe`, `PCI` or `AGP`.
+    pub bus_type: Option<String>,
+    /// `N` in `/dev/nvidiaN`.
+    pub device_minor: Option<u32>,
+    /// Every `key: value` line, with the value as text.
+    pub values: HashMap<String, String>,
+}
+
+impl NvidiaGpu {
+    /// Parse the information file of the GPU at `bus_location`, e.g. `0000:00:1e.0`.
+    pub fn from_bus_location(bus_location: &str) -> io::Result<Self> {
+        let path = Path::new(ROOT)
+            .join("gpus")
+            .join(bus_location)
+            .join("information");
+        NvidiaGpu::from_reader(File::open(path)?)
+    }
+
+    /// Parse `/proc/driver/nvidia/gpus/[bus location]/information`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut gpu = NvidiaGpu::default();
+        loop {
+            match reader.parse_line(|line| gpu.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if gpu.model.is_empty() || gpu.bus_location.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "missing \"Model\" or \"Bus Location\"",
+            ));
+        }
+        Ok(gpu)
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        let (key, value) = match util::parse_key_value(input) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+        let parse_u32 = |msg| -> Result<Option<u32>, Error> {
+            Ok(Some(err_msg!(util::parse_u64(value), msg)?.1 as u32))
+        };
+        match key {
+            "Model" => self.model = value.to_owned(),
+            "Bus Location" => self.bus_location = value.to_owned(),
+            "IRQ" => self.irq = parse_u32("IRQ")?,
+            "GPU UUID" => self.uuid = Some(value.to_owned()),
+            "Video BIOS" => self.video_bios = Some(value.to_owned()),
+            "Bus Type" => self.bus_type = Some(value.to_owned()),
+            "Device Minor" => self.device_minor = parse_u32("Device Minor")?,
+            _ => (),
+        }
+        self.values.insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{driver_version, parse_driver_version, NvidiaGpu, NvidiaGpus};
+    use crate::ReadOutcome;
+    use std::io;
+
+    #[test]
+    fn proc_driver_nvidia() {
+        let raw = "\
+Model: \t\t Tesla T4
+IRQ:   \t\t 35
+GPU UUID: \t GPU-5b1a3f42-7c2e-2d0b-9a31-2f1c0e7d6a55
+Video BIOS: \t 90.04.38.00.03
+Bus Type: \t PCIe
+DMA Size: \t 47 bits
+DMA Mask: \t 0x7fffffffffff
+Bus Location: \t 0000:00:1e.0
+Device Minor: \t 0
+GPU Excluded:\t No
+";
+        let gpu = NvidiaGpu::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(gpu.model, "Tesla T4");
+        assert_eq!(gpu.bus_location, "0000:00:1e.0");
+        assert_eq!(gpu.irq, Some(35));
+        assert_eq!(gpu.bus_type.as_deref(), Some("PCIe"));
+        assert_eq!(gpu.device_minor, Some(0));
+        assert_eq!(gpu.values["DMA Size"], "47 bits");
+
+        let version = "\
+NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  535.54.03  Release Build  (dvs-builder@U16-T02-35-3)  Tue Jun  6 22:17:57 UTC 2023
+GCC version:  gcc version 12.2.0 (Debian 12.2.0-14)
+";
+        assert_eq!(
+            parse_driver_version(io::Cursor::new(version)).unwrap(),
+            "535.54.03"
+        );
+
+        // Most machines don't have the driver loaded.
+        match ReadOutcome::from(NvidiaGpus::from_system()) {
+            ReadOutcome::Ok(_) => {
+                driver_version().unwrap();
+            }
+            ReadOutcome::NotFound => (),
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Bindings to `/proc/driver/rtc`, the state of the system's hardware clock (`/dev/rtc0`).
+//!
+//! The file only exists when the kernel was built with `CONFIG_RTC_INTF_PROC` and there is a
+//! hardware clock, which virtual machines and containers often lack.
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::time::{Duration, SystemTime};
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The hardware clock, from `/proc/driver/rtc`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Rtc {
+    /// The time of day on the clock, from `rtc_time`.
+    pub time: RtcTime,
+    /// The date on the clock, from `rtc_date`.
+    pub date: RtcDate,
+    /// The wake alarm. `None` if the driver couldn't read it.
+    pub alarm: Option<RtcAlarm>,
+    /// Whether an interrupt is raised every second, from `update IRQ enabled`.
+    pub update_irq: Option<bool>,
+    /// Whether periodic interrupts are enabled, from `periodic IRQ enabled`.
+    pub periodic_irq: Option<bool>,
+    /// The rate of the periodic interrupts in Hz, from `periodic IRQ frequency`.
+    pub periodic_frequency: Option<u32>,
+    /// The highest periodic rate unprivileged users can set, from `max user IRQ frequency`.
+    pub max_user_frequency: Option<u32>,
+    /// Whether the clock's backup battery is good, from `batt_status`. Only some drivers (like
+    /// the PC's `rtc_cmos`) report it.
+    pub battery_ok: Option<bool>,
+    /// Every `key : value` line, with the value as text. These include driver-specific ones,
+    /// like `BCD` and `HPET_emulated` for `rtc_cmos`.
+    pub values: BTreeMap<String, String>,
+}
+
+/// The wake alarm of the hardware clock.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RtcAlarm {
+    /// The time of day the alarm goes off. `None` if any part of it isn't set.
+    pub time: Option<RtcTime>,
+    /// The date the alarm goes off. `None` if any part of it isn't set, as for clocks whose
+    /// alarm only has a time of day.
+    pub date: Option<RtcDate>,
+    /// Whether the alarm raises an interrupt, from `alarm_IRQ`.
+    pub enabled: bool,
+    /// Whether the alarm has gone off but not been handled, from `alrm_pending`.
+    pub pending: bool,
+}
+
+/// A time of day, as kept by the hardware clock.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RtcTime {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// A date, as kept by the hardware clock.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RtcDate {
+    pub year: u32,
+    /// From 1 to 12.
+    pub month: u8,
+    /// From 1 to 31.
+    pub day: u8,
+}
+
+impl Rtc {
+    /// Parse `/proc/driver/rtc`.
+    pub fn from_system() -> io::Result<Self> {
+        Rtc::from_reader(File::open("/proc/driver/rtc")?)
+    }
+
+    /// Parse `/proc/driver/rtc`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut rtc = Rtc::default();
+        let mut alarm = RtcAlarm::default();
+        let (mut has_time, mut has_date, mut has_alarm) = (false, false, false);
+        loop {
+            let result = reader.parse_line(|line| -> Result<(), Error> {
+                let (key, value) = match util::parse_key_value(line) {
+                    Some((key, value)) => (key.trim_end(), value),
+                    None => return Ok(()),
+                };
+                match key {
+                    "rtc_time" => {
+                        rtc.time = err_msg!(RtcTime::from_str(value)?, "rtc_time")?;
+                        has_time = true;
+                    }
+                    "rtc_date" => {
+                        rtc.date = err_msg!(RtcDate::from_str(value)?, "rtc_date")?;
+                        has_date = true;
+                    }
+                    "alrm_time" => {
+                        alarm.time = RtcTime::from_str(value)?;
+                        has_alarm = true;
+                    }
+                    "alrm_date" => alarm.date = RtcDate::from_str(value)?,
+                    "alarm_IRQ" => alarm.enabled = parse_yes_no(value, "alarm_IRQ")?,
+                    "alrm_pending" => alarm.pending = parse_yes_no(value, "alrm_pending")?,
+                    "update IRQ enabled" => {
+                        rtc.update_irq = Some(parse_yes_no(value, "update IRQ enabled")?)
+                    }
+                    "periodic IRQ enabled" => {
+                        rtc.periodic_irq = Some(parse_yes_no(value, "periodic IRQ enabled")?)
+                    }
+                    "periodic IRQ frequency" => {
+                        rtc.periodic_frequency = Some(parse_u32(value, "periodic IRQ frequency")?)
+                    }
+                    "max user IRQ frequency" => {
+                        rtc.max_user_frequency = Some(parse_u32(value, "max user IRQ frequency")?)
+                    }
+                    "batt_status" => {
+                        rtc.battery_ok = match value {
+                            "okay" => Some(true),
+                            "dead" => Some(false),
+                            _ => None,
+                        }
+                    }
+                    _ => (),
+                }
+                rtc.values.insert(key.to_owned(), value.to_owned());
+                Ok(())
+            });
+            match result {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if !has_time || !has_date {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "missing \"rtc_time\" or \"rtc_date\"",
+            ));
+        }
+        if has_alarm {
+            rtc.alarm = Some(alarm);
+        }
+        Ok(rtc)
+    }
+
+    /// The time on the clock, assuming it keeps UTC.
+    ///
+    /// Linux systems normally keep the hardware clock in UTC, but dual-boot machines often keep
+    /// local time (see `/etc/adjtime`). `None` if the date is invalid or before 1970.
+    pub fn utc(&self) -> Option<SystemTime> {
+        to_system_time(self.date, self.time)
+    }
+
+    /// How far the clock is ahead of `now`, in seconds, assuming it keeps UTC. Negative if it
+    /// is behind.
+    ///
+    /// The clock only counts whole seconds, so this is only accurate to a second.
+    pub fn offset_from(&self, now: SystemTime) -> Option<f64> {
+        let rtc = self.utc()?;
+        Some(match rtc.duration_since(now) {
+            Ok(ahead) => ahead.as_secs_f64(),
+            Err(behind) => -behind.duration().as_secs_f64(),
+        })
+    }
+}
+
+impl RtcAlarm {
+    /// When the alarm goes off, assuming the clock keeps UTC. `None` unless both the date and
+    /// time are set.
+    pub fn utc(&self) -> Option<SystemTime> {
+        to_system_time(self.date?, self.time?)
+    }
+}
+
+impl RtcTime {
+    /// Parses `HH:MM:SS`. `None` if any field is `**`, meaning it isn't set.
+    fn from_str(input: &str) -> Result<Option<RtcTime>, Error> {
+        let fields = parse_fields(input, ':', "time")?;
+        Ok(match fields[..] {
+            [Some(hour), Some(minute), Some(second)] => Some(RtcTime {
+                hour: hour as u8,
+                minute: minute as u8,
+                second: second as u8,
+            }),
+            _ => None,
+        })
+    }
+}
+
+impl RtcDate {
+    /// Parses `YYYY-MM-DD`. `None` if any field is `**`, meaning it isn't set.
+    fn from_str(input: &str) -> Result<Option<RtcDate>, Error> {
+        let fields = parse_fields(input, '-', "date")?;
+        Ok(match fields[..] {
+            [Some(year), Some(month), Some(day)] => Some(RtcDate {
+                year,
+                month: month as u8,
+                day: day as u8,
+            }),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for RtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+impl fmt::Display for RtcDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Splits the 3 fields of a date or time, where unset ones are all `*`.
+fn parse_fields(input: &str, separator: char, msg: &str) -> Result<Vec<Option<u32>>, Error> {
+    let fields = input
+        .split(separator)
+        .enumerate()
+        .map(|(idx, field)| {
+            if !field.is_empty() && field.bytes().all(|b| b == b'*') {
+                return Some(None);
+            }
+            // Only the year has more than 2 digits.
+            let max = if idx == 0 && separator == '-' {
+                9999
+            } else {
+                99
+            };
+            match util::parse_u64(field) {
+                Some(("", value)) if value <= max => Some(Some(value as u32)),
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+    match fields {
+        Some(fields) if fields.len() == 3 => Ok(fields),
+        _ => Err(msg.into()),
+    }
+}
+
+fn parse_yes_no(input: &str, msg: &str) -> Result<bool, Error> {
+    match input {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(msg.into()),
+    }
+}
+
+fn parse_u32(input: &str, msg: &str) -> Result<u32, Error> {
+    match util::parse_u64(input) {
+        Some(("", value)) if value <= u32::MAX as u64 => Ok(value as u32),
+        _ => Err(msg.into()),
+    }
+}
+
+/// The time at `date` and `time` UTC. `None` if the date or time is invalid, or before 1970.
+fn to_system_time(date: RtcDate, time: RtcTime) -> Option<SystemTime> {
+    if !(1..=12).contains(&date.month) || date.day < 1 || date.day > days_in_month(date) {
+        return None;
+    }
+    if time.hour > 23 || time.minute > 59 || time.second > 59 {
+        return None;
+    }
+    // Days since 1970-01-01, counting years from March so leap days come last.
+    let (year, month) = (date.year as i64, date.month as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + date.day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    let secs =
+        days * 86_400 + time.hour as i64 * 3600 + time.minute as i64 * 60 + time.second as i64;
+    let secs = u64::try_from(secs).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+fn days_in_month(date: RtcDate) -> u8 {
+    let leap = date.year.is_multiple_of(4)
+        && (!date.year.is_multiple_of(100) || date.year.is_multiple_of(400));
+    match date.month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rtc, RtcDate, RtcTime};
+    use crate::ReadOutcome;
+    use std::io;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn proc_driver_rtc() {
+        let raw = "\
+rtc_time\t: 14:03:27
+rtc_date\t: 2024-02-29
+alrm_time\t: 06:30:00
+alrm_date\t: ****-**-**
+alarm_IRQ\t: yes
+alrm_pending\t: no
+update IRQ enabled\t: no
+periodic IRQ enabled\t: no
+periodic IRQ frequency\t: 1024
+max user IRQ frequency\t: 64
+24hr\t\t: yes
+periodic_IRQ\t: no
+update_IRQ\t: no
+HPET_emulated\t: yes
+BCD\t\t: yes
+DST_enable\t: no
+periodic_freq\t: 1024
+batt_status\t: okay
+";
+        let rtc = Rtc::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(
+            rtc.time,
+            RtcTime {
+                hour: 14,
+                minute: 3,
+                second: 27
+            }
+        );
+        assert_eq!(rtc.date.to_string(), "2024-02-29");
+        let alarm = rtc.alarm.unwrap();
+        assert_eq!(alarm.time.unwrap().to_string(), "06:30:00");
+        assert_eq!(alarm.date, None);
+        assert!(alarm.enabled && !alarm.pending);
+        assert_eq!(alarm.utc(), None);
+        assert_eq!(rtc.update_irq, Some(false));
+        assert_eq!(rtc.periodic_frequency, Some(1024));
+        assert_eq!(rtc.max_user_frequency, Some(64));
+        assert_eq!(rtc.battery_ok, Some(true));
+        assert_eq!(rtc.values["HPET_emulated"], "yes");
+
+        let utc = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_215_407);
+        assert_eq!(rtc.utc(), Some(utc));
+        assert_eq!(rtc.offset_from(utc + Duration::from_secs(2)), Some(-2.0));
+
+        // Drivers that can't read the alarm leave out everything from `alrm_time` to
+        // `max user IRQ frequency`.
+        let minimal = "rtc_time\t: 00:00:00\nrtc_date\t: 1970-01-01\n24hr\t\t: yes\n";
+        let rtc = Rtc::from_reader(io::Cursor::new(minimal)).unwrap();
+        assert_eq!(rtc.alarm, None);
+        assert_eq!(rtc.battery_ok, None);
+        assert_eq!(rtc.utc(), Some(SystemTime::UNIX_EPOCH));
+        let invalid = RtcDate {
+            year: 2023,
+            month: 2,
+            day: 29,
+        };
+        assert_eq!(super::to_system_time(invalid, RtcTime::default()), None);
+
+        assert!(Rtc::from_reader(io::Cursor::new("rtc_time\t: 00:00:00\n")).is_err());
+        assert!(
+            Rtc::from_reader(io::Cursor::new("rtc_time\t: 0:0\nrtc_date\t: 1970-01-01\n")).is_err()
+        );
+
+        // Virtual machines and containers often have no hardware clock.
+        match ReadOutcome::from(Rtc::from_system()) {
+            ReadOutcome::Ok(rtc) => assert!(rtc.date.year >= 1970),
+            ReadOutcome::NotFound | ReadOutcome::PermissionDenied => (),
+            other => panic!("{:?}", other),
+        }
+    }
+}
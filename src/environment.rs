@@ -0,0 +1,420 @@
+//! Heuristics about the environment this process is running in.
+//!
+//! None of these are definitive: containers and virtual machines can hide most signs of
+//! themselves. Each result comes with a `Confidence` that callers can use to decide whether to
+//! trust it.
+use std::fs::{self, File};
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use crate::process::{self, container_id, Cgroup};
+
+/// How sure a detection heuristic is of its answer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// Only indirect hints were found.
+    Low,
+    /// A marker that is usually, but not always, reliable was found.
+    Medium,
+    /// The answer is based on a signal that is very hard to produce by accident.
+    High,
+}
+
+/// The container runtime a process is running under.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ContainerRuntime {
+    Docker,
+    Containerd,
+    Lxc,
+    /// A pod managed by Kubernetes, whatever the underlying runtime.
+    Kubernetes,
+    /// There are signs of a container, but not of which runtime manages it.
+    Unknown,
+}
+
+/// The result of `detect_container`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContainerDetection {
+    /// The container runtime, or `None` if we don't seem to be in a container.
+    pub runtime: Option<ContainerRuntime>,
+    pub confidence: Confidence,
+    /// The container id, if it could be found in the cgroup path.
+    pub id: Option<String>,
+}
+
+impl ContainerDetection {
+    /// Whether we seem to be in a container.
+    pub fn is_container(&self) -> bool {
+        self.runtime.is_some()
+    }
+}
+
+/// Guess whether we are running in a container, and which runtime manages it.
+///
+/// The signals used are, from strongest to weakest:
+///
+///  - the cgroup of pid 1 (`/proc/1/cgroup`), which usually names the runtime unless a cgroup
+///    namespace hides it;
+///  - marker files like `/.dockerenv` and the Kubernetes service account directory;
+///  - the pid and name of pid 1 (`/proc/1/sched`), which show the pid outside the pid namespace
+///    on older kernels, and which isn't an init system in most containers.
+pub fn detect_container() -> io::Result<ContainerDetection> {
+    let cgroups = process::cgroup::from_reader(File::open("/proc/1/cgroup")?)?;
+    let init = match fs::read_to_string("/proc/1/sched") {
+        Ok(sched) => sched.lines().next().and_then(parse_sched_header),
+        Err(ref e)
+            if e.kind() == io::ErrorKind::NotFound
+                || e.kind() == io::ErrorKind::PermissionDenied =>
+        {
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    let signals = ContainerSignals {
+        cgroups,
+        init,
+        dockerenv: Path::new("/.dockerenv").exists(),
+        containerenv: Path::new("/run/.containerenv").exists(),
+        kubernetes_secrets: Path::new("/var/run/secrets/kubernetes.io").exists(),
+    };
+    Ok(signals.classify())
+}
+
+/// Parse the first line of `/proc/[pid]/sched`, e.g. `systemd (1, #threads: 1)`, into the
+/// command name and pid.
+fn parse_sched_header(line: &str) -> Option<(String, u32)> {
+    // The command name may itself contain " (".
+    let idx = line.rfind(" (")?;
+    let pid = line[idx + 2..].split(',').next()?.parse().ok()?;
+    Some((line[..idx].to_owned(), pid))
+}
+
+struct ContainerSignals {
+    cgroups: Vec<Cgroup>,
+    /// The command name and pid of pid 1, as seen by `/proc/1/sched`.
+    init: Option<(String, u32)>,
+    dockerenv: bool,
+    /// Created by Podman.
+    containerenv: bool,
+    kubernetes_secrets: bool,
+}
+
+impl ContainerSignals {
+    fn classify(&self) -> ContainerDetection {
+        let detection = |runtime, confidence| ContainerDetection {
+            runtime,
+            confidence,
+            id: self
+                .cgroups
+                .iter()
+                .find_map(|cgroup| container_id(&cgroup.path))
+                .map(String::from),
+        };
+        let paths: Vec<&str> = self
+            .cgroups
+            .iter()
+            .filter_map(|cgroup| cgroup.path.to_str())
+            .collect();
+        let in_path = |needle: &str| paths.iter().any(|path| path.contains(needle));
+
+        if in_path("kubepods") {
+            return detection(Some(ContainerRuntime::Kubernetes), Confidence::High);
+        }
+        if in_path("/docker") {
+            return detection(Some(ContainerRuntime::Docker), Confidence::High);
+        }
+        if in_path("containerd") {
+            return detection(Some(ContainerRuntime::Containerd), Confidence::High);
+        }
+        if in_path("/lxc") {
+            return detection(Some(ContainerRuntime::Lxc), Confidence::High);
+        }
+        if self.kubernetes_secrets {
+            return detection(Some(ContainerRuntime::Kubernetes), Confidence::Medium);
+        }
+        if self.dockerenv {
+            return detection(Some(ContainerRuntime::Docker), Confidence::Medium);
+        }
+        let id = detection(None, Confidence::Low).id;
+        if id.is_some() || self.containerenv {
+            return detection(Some(ContainerRuntime::Unknown), Confidence::Medium);
+        }
+        match &self.init {
+            Some((_, pid)) if *pid != 1 => {
+                return detection(Some(ContainerRuntime::Unknown), Confidence::Medium)
+            }
+            Some((comm, _)) if !["systemd", "init"].contains(&comm.as_str()) => {
+                return detection(Some(ContainerRuntime::Unknown), Confidence::Low)
+            }
+            _ => (),
+        }
+        // systemd puts itself in `/init.scope`, which a cgroup namespace would hide.
+        if in_path("/init.scope") {
+            detection(None, Confidence::High)
+        } else {
+            detection(None, Confidence::Medium)
+        }
+    }
+}
+
+/// The hypervisor a virtual machine is running under.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Hypervisor {
+    /// KVM, including plain QEMU.
+    Kvm,
+    Xen,
+    VMware,
+    HyperV,
+    /// The cpu reports a hypervisor, but nothing says which.
+    Unknown,
+}
+
+/// The result of `detect_hypervisor`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HypervisorDetection {
+    /// The hypervisor, or `None` if we seem to be running on bare metal.
+    pub hypervisor: Option<Hypervisor>,
+    pub confidence: Confidence,
+}
+
+impl HypervisorDetection {
+    /// Whether we seem to be in a virtual machine.
+    pub fn is_virtual(&self) -> bool {
+        self.hypervisor.is_some()
+    }
+}
+
+/// Guess whether we are running in a virtual machine, and under which hypervisor.
+///
+/// The signals used are the `hypervisor` cpu flag in `/proc/cpuinfo` (x86 only), the presence of
+/// `/proc/xen`, and the DMI vendor and product names in `/sys/class/dmi/id` when they are
+/// available.
+///
+/// A container shares the host's kernel, so this describes the host it runs on.
+pub fn detect_hypervisor() -> io::Result<HypervisorDetection> {
+    let signals = HypervisorSignals {
+        hypervisor_flag: cpu_has_hypervisor_flag(File::open("/proc/cpuinfo")?)?,
+        proc_xen: Path::new("/proc/xen").exists(),
+        dmi: DmiIds {
+            sys_vendor: read_dmi_id("sys_vendor")?,
+            product_name: read_dmi_id("product_name")?,
+            bios_vendor: read_dmi_id("bios_vendor")?,
+        },
+    };
+    Ok(signals.classify())
+}
+
+/// Whether the first `flags` line of `/proc/cpuinfo` contains `hypervisor`, or `None` if there
+/// is no `flags` line (as on non-x86 architectures).
+fn cpu_has_hypervisor_flag(reader: impl io::Read) -> io::Result<Option<bool>> {
+    for line in io::BufReader::new(reader).lines() {
+        let line = line?;
+        if let Some((key, value)) = line.split_once(':') {
+            if key.trim_end() == "flags" {
+                return Ok(Some(
+                    value.split_ascii_whitespace().any(|f| f == "hypervisor"),
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Read `/sys/class/dmi/id/<name>`, or `None` if it isn't there (DMI is mostly x86 only).
+fn read_dmi_id(name: &str) -> io::Result<Option<String>> {
+    match fs::read_to_string(Path::new("/sys/class/dmi/id").join(name)) {
+        Ok(value) => Ok(Some(value.trim().to_owned())),
+        Err(ref e)
+            if e.kind() == io::ErrorKind::NotFound
+                || e.kind() == io::ErrorKind::PermissionDenied =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Default)]
+struct DmiIds {
+    sys_vendor: Option<String>,
+    product_name: Option<String>,
+    bios_vendor: Option<String>,
+}
+
+impl DmiIds {
+    fn hypervisor(&self) -> Option<Hypervisor> {
+        let lower = |value: &Option<String>| value.as_deref().unwrap_or("").to_ascii_lowercase();
+        let (vendor, product, bios) = (
+            lower(&self.sys_vendor),
+            lower(&self.product_name),
+            lower(&self.bios_vendor),
+        );
+        let any = |needle: &str| {
+            [&vendor, &product, &bios]
+                .iter()
+                .any(|s| s.contains(needle))
+        };
+        if any("vmware") {
+            Some(Hypervisor::VMware)
+        } else if any("xen") {
+            Some(Hypervisor::Xen)
+        // Physical Surface devices also have Microsoft as their vendor.
+        } else if vendor.contains("microsoft") && product.contains("virtual machine") {
+            Some(Hypervisor::HyperV)
+        } else if any("kvm") || any("qemu") {
+            Some(Hypervisor::Kvm)
+        } else {
+            None
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.sys_vendor.is_some() || self.product_name.is_some()
+    }
+}
+
+struct HypervisorSignals {
+    hypervisor_flag: Option<bool>,
+    proc_xen: bool,
+    dmi: DmiIds,
+}
+
+impl HypervisorSignals {
+    fn classify(&self) -> HypervisorDetection {
+        let detection = |hypervisor, confidence| HypervisorDetection {
+            hypervisor,
+            confidence,
+        };
+        if self.proc_xen {
+            return detection(Some(Hypervisor::Xen), Confidence::High);
+        }
+        match (self.dmi.hypervisor(), self.hypervisor_flag) {
+            (Some(hypervisor), Some(true)) => detection(Some(hypervisor), Confidence::High),
+            // The flag can be hidden from the guest, or not exist on this architecture.
+            (Some(hypervisor), _) => detection(Some(hypervisor), Confidence::Medium),
+            (None, Some(true)) => detection(Some(Hypervisor::Unknown), Confidence::High),
+            (None, Some(false)) if self.dmi.is_available() => detection(None, Confidence::High),
+            (None, Some(false)) => detection(None, Confidence::Medium),
+            (None, None) => detection(None, Confidence::Low),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cpu_has_hypervisor_flag, detect_container, detect_hypervisor, parse_sched_header,
+        Confidence, ContainerRuntime, ContainerSignals, DmiIds, Hypervisor, HypervisorSignals,
+    };
+    use crate::process;
+    use std::io;
+
+    const ID: &str = "4f1c3b1fa32c7e5e3d0a8e2b5f6d7c8b9a0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c";
+
+    fn signals(cgroup: &str, init: &str) -> ContainerSignals {
+        ContainerSignals {
+            cgroups: process::cgroup::from_reader(io::Cursor::new(cgroup)).unwrap(),
+            init: parse_sched_header(init),
+            dockerenv: false,
+            containerenv: false,
+            kubernetes_secrets: false,
+        }
+    }
+
+    #[test]
+    fn container_detection() {
+        let docker = signals(
+            &format!("0::/system.slice/docker-{}.scope\n", ID),
+            "node (1, #threads: 11)",
+        )
+        .classify();
+        assert_eq!(docker.runtime, Some(ContainerRuntime::Docker));
+        assert_eq!(docker.confidence, Confidence::High);
+        assert_eq!(docker.id.as_deref(), Some(ID));
+
+        let pod = signals(
+            &format!("4:memory:/kubepods/besteffort/pod1234/{}\n0::/\n", ID),
+            "pause (1, #threads: 1)",
+        )
+        .classify();
+        assert_eq!(pod.runtime, Some(ContainerRuntime::Kubernetes));
+
+        let lxc = signals(
+            "0::/lxc.payload.web/init.scope\n",
+            "systemd (1, #threads: 1)",
+        );
+        assert_eq!(lxc.classify().runtime, Some(ContainerRuntime::Lxc));
+
+        // A cgroup namespace hides the path, so fall back to the marker files.
+        let mut namespaced = signals("0::/\n", "nginx (1, #threads: 1)");
+        namespaced.dockerenv = true;
+        let detection = namespaced.classify();
+        assert_eq!(detection.runtime, Some(ContainerRuntime::Docker));
+        assert_eq!(detection.confidence, Confidence::Medium);
+        assert_eq!(detection.id, None);
+        namespaced.dockerenv = false;
+        let detection = namespaced.classify();
+        assert_eq!(detection.runtime, Some(ContainerRuntime::Unknown));
+        assert_eq!(detection.confidence, Confidence::Low);
+
+        let host = signals("0::/init.scope\n", "systemd (1, #threads: 1)").classify();
+        assert!(!host.is_container());
+        assert_eq!(host.confidence, Confidence::High);
+
+        detect_container().unwrap();
+    }
+
+    #[test]
+    fn sched_header() {
+        assert_eq!(
+            parse_sched_header("tmux: server (1, #threads: 1)"),
+            Some(("tmux: server".into(), 1))
+        );
+        assert_eq!(parse_sched_header("----"), None);
+    }
+
+    #[test]
+    fn hypervisor_detection() {
+        let cpuinfo = "\
+processor\t: 0
+vendor_id\t: GenuineIntel
+flags\t\t: fpu vme de pse tsc msr hypervisor lahf_lm
+";
+        assert_eq!(
+            cpu_has_hypervisor_flag(io::Cursor::new(cpuinfo)).unwrap(),
+            Some(true)
+        );
+        let arm = "processor\t: 0\nFeatures\t: fp asimd evtstrm\n";
+        assert_eq!(cpu_has_hypervisor_flag(io::Cursor::new(arm)).unwrap(), None);
+
+        let dmi = |vendor: &str, product: &str| DmiIds {
+            sys_vendor: Some(vendor.into()),
+            product_name: Some(product.into()),
+            bios_vendor: None,
+        };
+        let classify = |flag, dmi| {
+            HypervisorSignals {
+                hypervisor_flag: flag,
+                proc_xen: false,
+                dmi,
+            }
+            .classify()
+        };
+        let kvm = classify(Some(true), dmi("QEMU", "Standard PC (Q35 + ICH9, 2009)"));
+        assert_eq!(kvm.hypervisor, Some(Hypervisor::Kvm));
+        assert_eq!(kvm.confidence, Confidence::High);
+        let hyperv = classify(Some(true), dmi("Microsoft Corporation", "Virtual Machine"));
+        assert_eq!(hyperv.hypervisor, Some(Hypervisor::HyperV));
+        let surface = classify(Some(false), dmi("Microsoft Corporation", "Surface Pro 7"));
+        assert!(!surface.is_virtual());
+        assert_eq!(surface.confidence, Confidence::High);
+        let vmware = classify(None, dmi("VMware, Inc.", "VMware Virtual Platform"));
+        assert_eq!(vmware.hypervisor, Some(Hypervisor::VMware));
+        assert_eq!(vmware.confidence, Confidence::Medium);
+        // e.g. Firecracker, which has no DMI tables.
+        let unknown = classify(Some(true), DmiIds::default());
+        assert_eq!(unknown.hypervisor, Some(Hypervisor::Unknown));
+
+        detect_hypervisor().unwrap();
+    }
+}
@@ -0,0 +1,492 @@
+//! Process events (fork, exec, exit and so on) as they happen, from the kernel's proc connector.
+//!
+//! The proc connector is a netlink socket that the kernel sends a message to whenever a process
+//! forks, execs, exits, or changes its ids or name. Listening needs `CAP_NET_ADMIN` and only
+//! works in the initial network namespace, so `ProcessEvents::new` falls back to comparing
+//! process scans (see `process::ProcessSetDiff`) when it isn't available. The fallback only sees
+//! forks and exits, and misses processes that start and exit between scans.
+//!
+//! ```no_run
+//! use linux_proc::events::{ProcEvent, ProcessEvents};
+//! use std::time::Duration;
+//!
+//! let mut events = ProcessEvents::new(Duration::from_secs(1))?;
+//! loop {
+//!     for event in events.next_events()? {
+//!         if let ProcEvent::Exec { pid, .. } = event {
+//!             println!("{} exec'd", pid);
+//!         }
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::convert::TryInto;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::thread;
+use std::time::Duration;
+
+use crate::{
+    process::{ProcessScan, ProcessSetDiff, ProcessSnapshot},
+    Error,
+};
+
+/// Something a process did.
+///
+/// The ids are from the initial pid namespace. `pid` is the process (thread group) id, and `tid`
+/// the thread that did it, which is the same for single-threaded processes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProcEvent {
+    /// A process or thread was created.
+    ///
+    /// A new thread has `child_pid` set to the process it joined, and `child_tid` to itself. The
+    /// parent is the kernel's `real_parent`, which for a thread is the parent of its process.
+    Fork {
+        parent_pid: u32,
+        parent_tid: u32,
+        child_pid: u32,
+        child_tid: u32,
+    },
+    /// A process started running a new program.
+    Exec { pid: u32, tid: u32 },
+    /// A process changed its real or effective user id.
+    Uid {
+        pid: u32,
+        tid: u32,
+        ruid: u32,
+        euid: u32,
+    },
+    /// A process changed its real or effective group id.
+    Gid {
+        pid: u32,
+        tid: u32,
+        rgid: u32,
+        egid: u32,
+    },
+    /// A process started a new session.
+    Sid { pid: u32, tid: u32 },
+    /// A process was attached to or detached from a tracer.
+    Ptrace {
+        pid: u32,
+        tid: u32,
+        tracer_pid: u32,
+        tracer_tid: u32,
+    },
+    /// A thread changed its name (`comm`).
+    Comm { pid: u32, tid: u32, comm: String },
+    /// A process dumped core.
+    Coredump { pid: u32, tid: u32 },
+    /// A process or thread exited.
+    Exit {
+        pid: u32,
+        tid: u32,
+        /// The raw wait status, as returned by `waitpid`. `None` when found by scanning.
+        exit_status: Option<u32>,
+    },
+    /// An event this crate doesn't know about, with its type number.
+    Other(u32),
+}
+
+/// Where `ProcessEvents` gets its events from.
+#[derive(Debug)]
+enum Source {
+    Connector(ProcConnector),
+    Polling {
+        interval: Duration,
+        scan: ProcessScan,
+        last: Vec<ProcessSnapshot>,
+    },
+}
+
+/// A stream of process events, from the proc connector if possible or by scanning otherwise.
+#[derive(Debug)]
+pub struct ProcessEvents {
+    source: Source,
+}
+
+impl ProcessEvents {
+    /// Listen to the proc connector, or if we can't, scan processes every `interval`.
+    pub fn new(interval: Duration) -> io::Result<Self> {
+        match ProcConnector::connect() {
+            Ok(connector) => Ok(ProcessEvents {
+                source: Source::Connector(connector),
+            }),
+            Err(ref e) if ProcConnector::is_unavailable(e) => ProcessEvents::polling(interval),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Scan processes every `interval`, without trying the proc connector.
+    pub fn polling(interval: Duration) -> io::Result<Self> {
+        let scan = ProcessScan::new();
+        Ok(ProcessEvents {
+            source: Source::Polling {
+                interval,
+                last: scan.run()?,
+                scan,
+            },
+        })
+    }
+
+    /// Whether events come from the proc connector, rather than scanning.
+    pub fn is_realtime(&self) -> bool {
+        matches!(self.source, Source::Connector(_))
+    }
+
+    /// Block until there are events, and return them.
+    ///
+    /// When scanning, this waits for the interval and may return nothing.
+    pub fn next_events(&mut self) -> io::Result<Vec<ProcEvent>> {
+        match &mut self.source {
+            Source::Connector(connector) => connector.recv(),
+            Source::Polling {
+                interval,
+                scan,
+                last,
+            } => {
+                thread::sleep(*interval);
+                let current = scan.run()?;
+                let diff = ProcessSetDiff::between(last, &current);
+                *last = current;
+                Ok(events_from_diff(&diff))
+            }
+        }
+    }
+}
+
+/// The events implied by the difference between two scans. Scans only see whole processes, so
+/// the thread ids are the process ids.
+fn events_from_diff(diff: &ProcessSetDiff) -> Vec<ProcEvent> {
+    let exits = diff.exited.iter().map(|event| ProcEvent::Exit {
+        pid: event.key.pid,
+        tid: event.key.pid,
+        exit_status: None,
+    });
+    let forks = diff.started.iter().map(|event| ProcEvent::Fork {
+        parent_pid: event.ppid,
+        parent_tid: event.ppid,
+        child_pid: event.key.pid,
+        child_tid: event.key.pid,
+    });
+    exits.chain(forks).collect()
+}
+
+/// A netlink socket subscribed to the kernel's proc connector.
+///
+/// The subscription is cancelled when this is dropped.
+#[derive(Debug)]
+pub struct ProcConnector {
+    socket: OwnedFd,
+}
+
+/// The connector id of the proc connector, from `linux/connector.h`.
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+/// The operations sent to the proc connector, from `linux/cn_proc.h`.
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_CN_MCAST_IGNORE: u32 = 2;
+/// The sizes of `struct nlmsghdr` and `struct cn_msg`.
+const NLMSG_HDRLEN: usize = 16;
+const CN_MSG_LEN: usize = 20;
+
+impl ProcConnector {
+    /// Open a socket and subscribe to process events.
+    ///
+    /// This fails with `PermissionDenied` without `CAP_NET_ADMIN`. Check failures with
+    /// `is_unavailable` to decide whether to fall back to scanning.
+    pub fn connect() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            )
+        };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let connector = ProcConnector {
+            socket: unsafe { OwnedFd::from_raw_fd(fd) },
+        };
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+        let bound = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        connector.send_op(PROC_CN_MCAST_LISTEN)?;
+        Ok(connector)
+    }
+
+    /// Whether an error from `connect` means the proc connector can't be used here, rather than
+    /// something going wrong.
+    pub fn is_unavailable(e: &io::Error) -> bool {
+        e.kind() == io::ErrorKind::PermissionDenied
+            || matches!(
+                e.raw_os_error(),
+                Some(libc::EPROTONOSUPPORT) | Some(libc::EAFNOSUPPORT) | Some(libc::ECONNREFUSED)
+            )
+    }
+
+    /// Block until the kernel sends events, and return them.
+    ///
+    /// If events arrive faster than they're read the kernel drops some, and this fails with
+    /// `ENOBUFS`. The socket is still usable afterwards.
+    pub fn recv(&self) -> io::Result<Vec<ProcEvent>> {
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if len == -1 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            }
+            let events = parse_messages(&buf[..len as usize])
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            // The kernel acknowledges the subscription with an empty message.
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+
+    fn send_op(&self, op: u32) -> io::Result<()> {
+        let len = NLMSG_HDRLEN + CN_MSG_LEN + mem::size_of::<u32>();
+        let mut msg = Vec::with_capacity(len);
+        // struct nlmsghdr
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&std::process::id().to_ne_bytes());
+        // struct cn_msg
+        msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&(mem::size_of::<u32>() as u16).to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&op.to_ne_bytes());
+        let sent = unsafe {
+            libc::send(
+                self.socket.as_raw_fd(),
+                msg.as_ptr() as *const libc::c_void,
+                msg.len(),
+                0,
+            )
+        };
+        if sent == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        let _ = self.send_op(PROC_CN_MCAST_IGNORE);
+    }
+}
+
+impl AsRawFd for ProcConnector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for ProcConnector {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+/// Read a native-endian `u32` at `offset`.
+fn read_u32(buf: &[u8], offset: usize) -> Result<u32, Error> {
+    buf.get(offset..offset + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_ne_bytes)
+        .ok_or_else(|| Error::from("proc connector message too short"))
+}
+
+/// Parse the netlink messages in one datagram from the proc connector.
+fn parse_messages(mut buf: &[u8]) -> Result<Vec<ProcEvent>, Error> {
+    let mut events = Vec::new();
+    while buf.len() >= NLMSG_HDRLEN {
+        let len = read_u32(buf, 0)? as usize;
+        if len < NLMSG_HDRLEN || len > buf.len() {
+            return Err("bad netlink message length".into());
+        }
+        let ty = u16::from_ne_bytes([buf[4], buf[5]]);
+        if ty == libc::NLMSG_ERROR as u16 {
+            return Err("netlink error message".into());
+        }
+        let payload = &buf[NLMSG_HDRLEN..len];
+        if ty != libc::NLMSG_NOOP as u16
+            && read_u32(payload, 0)? == CN_IDX_PROC
+            && read_u32(payload, 4)? == CN_VAL_PROC
+        {
+            let data = payload
+                .get(CN_MSG_LEN..)
+                .ok_or_else(|| Error::from("proc connector message too short"))?;
+            events.extend(parse_event(data)?);
+        }
+        // Messages are padded to 4 bytes.
+        let next = (len + 3) & !3;
+        buf = buf.get(next..).unwrap_or(&[]);
+    }
+    Ok(events)
+}
+
+/// Parse a `struct proc_event`, or `None` for the acknowledgement of a subscription.
+fn parse_event(data: &[u8]) -> Result<Option<ProcEvent>, Error> {
+    // what, cpu and timestamp_ns come before the event data.
+    const DATA: usize = 16;
+    let what = read_u32(data, 0)?;
+    let field = |idx: usize| read_u32(data, DATA + idx * 4);
+    let event = match what {
+        0 => return Ok(None),
+        0x1 => ProcEvent::Fork {
+            parent_tid: field(0)?,
+            parent_pid: field(1)?,
+            child_tid: field(2)?,
+            child_pid: field(3)?,
+        },
+        0x2 => ProcEvent::Exec {
+            tid: field(0)?,
+            pid: field(1)?,
+        },
+        0x4 => ProcEvent::Uid {
+            tid: field(0)?,
+            pid: field(1)?,
+            ruid: field(2)?,
+            euid: field(3)?,
+        },
+        0x40 => ProcEvent::Gid {
+            tid: field(0)?,
+            pid: field(1)?,
+            rgid: field(2)?,
+            egid: field(3)?,
+        },
+        0x80 => ProcEvent::Sid {
+            tid: field(0)?,
+            pid: field(1)?,
+        },
+        0x100 => ProcEvent::Ptrace {
+            tid: field(0)?,
+            pid: field(1)?,
+            tracer_tid: field(2)?,
+            tracer_pid: field(3)?,
+        },
+        0x200 => {
+            let comm = data
+                .get(DATA + 8..DATA + 24)
+                .ok_or_else(|| Error::from("proc connector message too short"))?;
+            let end = comm.iter().position(|&b| b == 0).unwrap_or(comm.len());
+            ProcEvent::Comm {
+                tid: field(0)?,
+                pid: field(1)?,
+                comm: String::from_utf8_lossy(&comm[..end]).into_owned(),
+            }
+        }
+        0x4000_0000 => ProcEvent::Coredump {
+            tid: field(0)?,
+            pid: field(1)?,
+        },
+        0x8000_0000 => ProcEvent::Exit {
+            tid: field(0)?,
+            pid: field(1)?,
+            exit_status: Some(field(2)?),
+        },
+        other => ProcEvent::Other(other),
+    };
+    Ok(Some(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_messages, ProcConnector, ProcEvent, ProcessEvents};
+    use std::time::Duration;
+
+    /// A netlink message from the proc connector carrying an event.
+    fn message(what: u32, fields: &[u32]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in [what, 0, 0, 0].iter().chain(fields) {
+            data.extend_from_slice(&word.to_ne_bytes());
+        }
+        let mut msg = Vec::new();
+        let len = 16 + 20 + data.len();
+        for word in &[len as u32, 3, 0, 0, 1, 1, 0, 0] {
+            msg.extend_from_slice(&word.to_ne_bytes());
+        }
+        msg.extend_from_slice(&(data.len() as u32).to_ne_bytes());
+        msg.extend_from_slice(&data);
+        msg
+    }
+
+    #[test]
+    fn proc_connector_messages() {
+        let mut buf = message(0x1, &[100, 100, 200, 200]);
+        buf.extend(message(0x8000_0000, &[200, 200, 256, 17, 100, 100]));
+        let mut comm = vec![300, 300];
+        comm.extend(&[u32::from_ne_bytes(*b"bash"), 0, 0, 0]);
+        buf.extend(message(0x200, &comm));
+        buf.extend(message(0, &[0]));
+        let events = parse_messages(&buf).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ProcEvent::Fork {
+                    parent_pid: 100,
+                    parent_tid: 100,
+                    child_pid: 200,
+                    child_tid: 200,
+                },
+                ProcEvent::Exit {
+                    pid: 200,
+                    tid: 200,
+                    exit_status: Some(256),
+                },
+                ProcEvent::Comm {
+                    pid: 300,
+                    tid: 300,
+                    comm: "bash".into(),
+                },
+            ]
+        );
+        assert!(parse_messages(&buf[..20]).is_err());
+    }
+
+    #[test]
+    fn process_events() {
+        match ProcConnector::connect() {
+            Ok(connector) => drop(connector),
+            Err(ref e) => assert!(ProcConnector::is_unavailable(e), "{}", e),
+        }
+        let mut events = ProcessEvents::polling(Duration::from_millis(1)).unwrap();
+        assert!(!events.is_realtime());
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let found = events.next_events().unwrap();
+        child.wait().unwrap();
+        assert!(found.iter().any(|event| matches!(
+            event,
+            ProcEvent::Fork { child_pid, .. } if *child_pid == child.id()
+        )));
+    }
+}
@@ -0,0 +1,143 @@
+//! Bindings to `/proc/execdomains`, the registered execution domains.
+//!
+//! An execution domain lets processes with a given personality (see `process::Personality`) run
+//! binaries for another Unix, like SVR4 or SCO. Since Linux 4.1 the only domain is the built-in
+//! `Linux` one, so anything else is worth flagging in an audit.
+use std::fs::File;
+use std::io;
+use std::ops::RangeInclusive;
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The execution domains, from `/proc/execdomains`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecDomains {
+    inner: Vec<ExecDomain>,
+}
+
+/// An execution domain, from a line of `/proc/execdomains`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecDomain {
+    /// The personalities handled by the domain.
+    pub personalities: RangeInclusive<u32>,
+    pub name: String,
+    /// The module providing the domain, or `kernel` if it's built in.
+    pub module: String,
+}
+
+impl ExecDomain {
+    /// Whether this is the native `Linux` domain.
+    pub fn is_linux(&self) -> bool {
+        self.name == "Linux"
+    }
+}
+
+impl ExecDomains {
+    const PATH: &'static str = "/proc/execdomains";
+    /// Parse the contents of `/proc/execdomains`.
+    pub fn from_system() -> io::Result<Self> {
+        ExecDomains::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/execdomains`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(ExecDomain::from_str) {
+                Ok(domain) => inner.push(domain),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(ExecDomains { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ExecDomain> {
+        self.inner.iter()
+    }
+
+    /// The number of domains.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no domains.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The domains other than the native `Linux` one, which emulate another Unix.
+    pub fn foreign(&self) -> impl Iterator<Item = &ExecDomain> {
+        self.iter().filter(|domain| !domain.is_linux())
+    }
+}
+
+impl IntoIterator for ExecDomains {
+    type IntoIter = std::vec::IntoIter<ExecDomain>;
+    type Item = ExecDomain;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ExecDomains {
+    type IntoIter = std::slice::Iter<'a, ExecDomain>;
+    type Item = &'a ExecDomain;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl ExecDomain {
+    fn from_str(input: &str) -> Result<ExecDomain, Error> {
+        let (input, low) = err_msg!(util::parse_u64(input), "personality range")?;
+        let input = err_msg!(util::expect_bytes("-", input), "personality range")?;
+        let (input, high) = err_msg!(util::parse_u64(input), "personality range")?;
+        let (input, name) = err_msg!(util::parse_token(input), "name")?;
+        let (_, module) = err_msg!(util::parse_token(input), "module")?;
+        let module = err_msg!(
+            module
+                .strip_prefix('[')
+                .and_then(|module| module.strip_suffix(']')),
+            "module"
+        )?;
+        Ok(ExecDomain {
+            personalities: low as u32..=high as u32,
+            name: name.to_owned(),
+            module: module.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecDomains;
+    use std::io;
+
+    #[test]
+    fn proc_execdomains() {
+        let raw = "\
+0-0\tLinux           \t[kernel]
+1-1\tSVR4            \t[ibcs]
+";
+        let domains = ExecDomains::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(domains.len(), 2);
+        let linux = domains.iter().next().unwrap();
+        assert!(linux.is_linux());
+        assert_eq!(linux.personalities, 0..=0);
+        assert_eq!(linux.module, "kernel");
+        let foreign: Vec<_> = domains.foreign().map(|d| d.module.as_str()).collect();
+        assert_eq!(foreign, vec!["ibcs"]);
+        assert!(ExecDomains::from_reader(io::Cursor::new("0-0 Linux kernel\n")).is_err());
+
+        let domains = ExecDomains::from_system().unwrap();
+        assert!(domains.iter().any(|domain| domain.is_linux()));
+    }
+}
@@ -0,0 +1,291 @@
+//! Bindings to `/proc/fs/ext4`, with a directory for each mounted ext4 filesystem, named after
+//! its block device (e.g. `sda1` or `dm-0`).
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use crate::{util, Error};
+
+const ROOT: &str = "/proc/fs/ext4";
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The block devices of the mounted ext4 filesystems, sorted.
+pub fn devices() -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(ROOT) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut devices = Vec::new();
+    for entry in entries {
+        devices.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    devices.sort();
+    Ok(devices)
+}
+
+/// The mount options in effect for a filesystem, from `/proc/fs/ext4/[device]/options`.
+///
+/// Unlike `/proc/mounts`, this lists every option, including the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Ext4Options {
+    inner: Vec<String>,
+}
+
+impl Ext4Options {
+    /// Parse `/proc/fs/ext4/[device]/options`.
+    pub fn from_device(device: &str) -> io::Result<Self> {
+        Ext4Options::from_reader(File::open(Path::new(ROOT).join(device).join("options"))?)
+    }
+
+    /// Parse `/proc/fs/ext4/[device]/options`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(|line| Ok::<_, Error>(line.trim().to_owned())) {
+                Ok(option) if option.is_empty() => (),
+                Ok(option) => inner.push(option),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Ext4Options { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.inner.iter().map(String::as_str)
+    }
+
+    /// Whether the flag `name` is set, e.g. `"discard"`.
+    pub fn has_option(&self, name: &str) -> bool {
+        self.iter().any(|option| option == name)
+    }
+
+    /// The value of the option `name`, e.g. `"5"` for `"commit"`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.iter().find_map(|option| {
+            option
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('='))
+        })
+    }
+}
+
+impl std::ops::Deref for Ext4Options {
+    type Target = Vec<String>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for Ext4Options {
+    type IntoIter = std::vec::IntoIter<String>;
+    type Item = String;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// The free space of each block group, from `/proc/fs/ext4/[device]/mb_groups`.
+///
+/// Reading this loads the block bitmap of every group, which takes a while and causes a burst
+/// of I/O on large filesystems.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MbGroups {
+    inner: Vec<MbGroup>,
+}
+
+/// The free space in a block group.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MbGroup {
+    /// The group number.
+    pub group: u32,
+    /// The number of free blocks.
+    pub free: u64,
+    /// The number of free extents the free blocks are split into.
+    pub fragments: u64,
+    /// The first free block.
+    pub first: u64,
+    /// The number of free extents of each size, by order: `free_extents[n]` counts the
+    /// extents of `2^n` blocks (rounded down to a power of two).
+    pub free_extents: Vec<u64>,
+}
+
+/// The free space of a whole filesystem, summed over its block groups.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MbGroupsSummary {
+    pub groups: u64,
+    pub free: u64,
+    pub fragments: u64,
+    /// The number of free extents of each size, by order, as in `MbGroup::free_extents`.
+    pub free_extents: Vec<u64>,
+}
+
+impl MbGroupsSummary {
+    /// The average size of a free extent in blocks. Lower means more fragmented.
+    pub fn average_fragment_size(&self) -> f64 {
+        if self.fragments == 0 {
+            return 0.0;
+        }
+        self.free as f64 / self.fragments as f64
+    }
+}
+
+impl MbGroups {
+    /// Parse `/proc/fs/ext4/[device]/mb_groups`.
+    pub fn from_device(device: &str) -> io::Result<Self> {
+        MbGroups::from_reader(File::open(Path::new(ROOT).join(device).join("mb_groups"))?)
+    }
+
+    /// Parse `/proc/fs/ext4/[device]/mb_groups`-formatted data from `reader`.
+    ///
+    /// Groups whose bitmap couldn't be read are left out.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // `#group: free  frags first [ 2^0   2^1 ...`
+        reader.parse_line(util::parse_dummy)?;
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(MbGroup::from_str) {
+                Ok(Some(group)) => inner.push(group),
+                Ok(None) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(MbGroups { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MbGroup> {
+        self.inner.iter()
+    }
+
+    /// Sum the free space over every group.
+    pub fn summary(&self) -> MbGroupsSummary {
+        let mut summary = MbGroupsSummary::default();
+        for group in self.iter() {
+            summary.groups += 1;
+            summary.free += group.free;
+            summary.fragments += group.fragments;
+            if summary.free_extents.len() < group.free_extents.len() {
+                summary.free_extents.resize(group.free_extents.len(), 0);
+            }
+            for (total, count) in summary.free_extents.iter_mut().zip(&group.free_extents) {
+                *total += count;
+            }
+        }
+        summary
+    }
+}
+
+impl std::ops::Deref for MbGroups {
+    type Target = Vec<MbGroup>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for MbGroups {
+    type IntoIter = std::vec::IntoIter<MbGroup>;
+    type Item = MbGroup;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl MbGroup {
+    /// Parse `#0    : 40    1     2264  [ 0     0     0     1 ... ]`.
+    ///
+    /// Returns `None` for a group whose bitmap couldn't be read (`#12   : I/O error`).
+    fn from_str(input: &str) -> Result<Option<MbGroup>, Error> {
+        let input = err_msg!(util::expect_bytes("#", input), "expected \"#\"")?;
+        let (input, group) = err_msg!(util::parse_u64(input), "group")?;
+        let input = err_msg!(
+            util::expect_bytes(":", util::consume_space(input)),
+            "expected \":\""
+        )?;
+        let (input, free) = match util::parse_u64(input) {
+            Some(free) => free,
+            None => return Ok(None),
+        };
+        let (input, fragments) = err_msg!(util::parse_u64(input), "frags")?;
+        let (input, first) = err_msg!(util::parse_u64(input), "first")?;
+        let mut input = err_msg!(
+            util::expect_bytes("[", util::consume_space(input)),
+            "expected \"[\""
+        )?;
+        let mut free_extents = Vec::new();
+        while let Some((rest, count)) = util::parse_u64(input) {
+            free_extents.push(count);
+            input = rest;
+        }
+        err_msg!(
+            util::expect_bytes("]", util::consume_space(input)),
+            "expected \"]\""
+        )?;
+        Ok(Some(MbGroup {
+            group: group as u32,
+            free,
+            fragments,
+            first,
+            free_extents,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ext4Options, MbGroups};
+    use std::io;
+
+    #[test]
+    fn proc_fs_ext4_options() {
+        let raw = "rw\nbsddf\nnogrpid\nblock_validity\ndiscard\ncommit=5\nerrors=remount-ro\n";
+        let options = Ext4Options::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(options.len(), 7);
+        assert!(options.has_option("discard"));
+        assert!(!options.has_option("commit"));
+        assert_eq!(options.value("commit"), Some("5"));
+        assert_eq!(options.value("errors"), Some("remount-ro"));
+        assert_eq!(options.value("rw"), None);
+    }
+
+    #[test]
+    fn proc_fs_ext4_mb_groups() {
+        let raw = "\
+#group: free  frags first [ 2^0   2^1   2^2   2^3   2^4   2^5   2^6   2^7   2^8   2^9   2^10  2^11  2^12  2^13  ]
+#0    : 18223 2     14529 [ 1     3     2     2     3     3     0     1     0     1     1     0     0     2     ]
+#1    : 1555  181   74    [ 97    101   104   105   0     0     0     0     0     0     0     0     0     0     ]
+#2    : I/O error
+#3    : 0     0     32768 [ 0     0     0     0     0     0     0     0     0     0     0     0     0     0     ]
+";
+        let groups = MbGroups::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[1].group, 1);
+        assert_eq!(groups[1].fragments, 181);
+        assert_eq!(groups[0].free_extents.len(), 14);
+        assert_eq!(groups[0].free_extents[13], 2);
+        let summary = groups.summary();
+        assert_eq!(summary.groups, 3);
+        assert_eq!(summary.free, 19778);
+        assert_eq!(summary.fragments, 183);
+        assert_eq!(summary.free_extents[0], 98);
+        assert!((summary.average_fragment_size() - 108.07).abs() < 0.01);
+
+        let err = MbGroups::from_reader(io::Cursor::new("header\n#0 : 1 2 3 [ 1 2\n")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn ext4_devices() {
+        for device in super::devices().unwrap() {
+            Ext4Options::from_device(&device).unwrap();
+        }
+    }
+}
@@ -0,0 +1,2 @@
+//! Bindings to the per-filesystem information in `/proc/fs`.
+pub mod ext4;
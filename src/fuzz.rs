@@ -0,0 +1,191 @@
+//! Pure entry points into the parsers, for fuzzing.
+//!
+//! Each function parses a whole file's contents from a string, without touching the
+//! filesystem. Whatever the input, they return an error rather than panicking, and `parse_all`
+//! runs every one of them so a single fuzz target covers the crate:
+//!
+//! ```no_run
+//! // fuzz/fuzz_targets/parse_all.rs
+//! # macro_rules! fuzz_target { (|$data:ident: &[u8]| $body:block) => {} }
+//! fuzz_target!(|data: &[u8]| {
+//!     linux_proc::fuzz::parse_all(data);
+//! });
+//! ```
+use std::io;
+
+use crate::{
+    cgroup::{CgroupControllers, MemoryStat},
+    diskstats::DiskStats,
+    driver::rtc::Rtc,
+    execdomains::ExecDomains,
+    fs::ext4::MbGroups,
+    input::InputDevices,
+    kallsyms::{KernelSymbol, KernelSymbols},
+    latency::LatencyStats,
+    loadavg::LoadAvg,
+    meminfo::MemInfo,
+    mounts::Mounts,
+    mountstats::MountStats,
+    mtrr::Mtrrs,
+    net::{
+        bonding::Bond, conntrack::Conntrack, dev::NetDev, icmp::IcmpSockets, igmp::MulticastGroups,
+        packet::PacketSockets, raw::RawSockets, tcp::TcpSockets, udp::UdpSockets, vlan::VlanStats,
+        xfrm::XfrmStat,
+    },
+    nfs::{NfsClientStats, NfsServerStats},
+    pci::PciDevices,
+    pressure::Pressure,
+    process::{
+        self, Cgroup, IdMap, Limits, MemoryMap, NumaMap, ProcessIo, ProcessSched, ProcessStat,
+        Status,
+    },
+    schedstat::SchedStat,
+    slabinfo::{SlabCache, SlabCaches},
+    stat::Stat,
+    swaps::Swaps,
+    uptime::Uptime,
+};
+
+macro_rules! entry_points {
+    ($($name:ident($file:expr) -> $ty:ty = $parse:expr;)*) => {
+        $(
+            #[doc = concat!("Parse the contents of `", $file, "`.")]
+            pub fn $name(input: &str) -> io::Result<$ty> {
+                $parse(input.as_bytes())
+            }
+        )*
+
+        /// Run every parser over `input`, ignoring the results.
+        ///
+        /// Invalid UTF-8 is replaced with U+FFFD first, as the parsers do when reading files.
+        pub fn parse_all(input: &[u8]) {
+            let input = String::from_utf8_lossy(input);
+            $(
+                let _ = $name(&input);
+            )*
+        }
+    };
+}
+
+entry_points! {
+    parse_stat("/proc/stat") -> Stat = Stat::from_reader;
+    parse_diskstats("/proc/diskstats") -> DiskStats = DiskStats::from_reader;
+    parse_execdomains("/proc/execdomains") -> ExecDomains = ExecDomains::from_reader;
+    parse_loadavg("/proc/loadavg") -> LoadAvg = LoadAvg::from_reader;
+    parse_schedstat("/proc/schedstat") -> SchedStat = SchedStat::from_reader;
+    parse_meminfo("/proc/meminfo") -> MemInfo = MemInfo::from_reader;
+    parse_uptime("/proc/uptime") -> Uptime = Uptime::from_reader;
+    parse_swaps("/proc/swaps") -> Swaps = Swaps::from_reader;
+    parse_mounts("/proc/[pid]/mountinfo") -> Mounts = Mounts::from_reader;
+    parse_mountstats("/proc/[pid]/mountstats") -> MountStats = MountStats::from_reader;
+    parse_mtrr("/proc/mtrr") -> Mtrrs = Mtrrs::from_reader;
+    parse_kallsyms("/proc/kallsyms") -> Vec<KernelSymbol> =
+        |input| KernelSymbols::from_reader(input).collect();
+    parse_latency_stats("/proc/latency_stats") -> LatencyStats = LatencyStats::from_reader;
+    parse_slabinfo("/proc/slabinfo") -> Vec<SlabCache> =
+        |input| SlabCaches::from_reader(input)?.collect();
+    parse_input_devices("/proc/bus/input/devices") -> InputDevices = InputDevices::from_reader;
+    parse_pci_devices("/proc/bus/pci/devices") -> PciDevices = PciDevices::from_reader;
+    parse_pressure("/proc/pressure/*") -> Pressure = Pressure::from_reader;
+    parse_rtc("/proc/driver/rtc") -> Rtc = Rtc::from_reader;
+    parse_cgroups("/proc/cgroups") -> CgroupControllers = CgroupControllers::from_reader;
+    parse_cgroup_memory_stat("memory.stat") -> MemoryStat = MemoryStat::from_reader;
+    parse_mb_groups("/proc/fs/ext4/[device]/mb_groups") -> MbGroups = MbGroups::from_reader;
+    parse_net_dev("/proc/net/dev") -> NetDev = NetDev::from_reader;
+    parse_conntrack("/proc/net/nf_conntrack") -> Conntrack = Conntrack::from_reader;
+    parse_igmp("/proc/net/igmp") -> MulticastGroups = MulticastGroups::from_igmp_reader;
+    parse_igmp6("/proc/net/igmp6") -> MulticastGroups = MulticastGroups::from_igmp6_reader;
+    parse_packet("/proc/net/packet") -> PacketSockets = PacketSockets::from_reader;
+    parse_tcp("/proc/net/tcp") -> TcpSockets = TcpSockets::from_reader;
+    parse_udp("/proc/net/udp") -> UdpSockets = UdpSockets::from_reader;
+    parse_raw("/proc/net/raw") -> RawSockets = RawSockets::from_reader;
+    parse_icmp("/proc/net/icmp") -> IcmpSockets = IcmpSockets::from_reader;
+    parse_bond("/proc/net/bonding/[bond]") -> Bond = Bond::from_reader;
+    parse_vlan("/proc/net/vlan/[device]") -> VlanStats = VlanStats::from_reader;
+    parse_xfrm_stat("/proc/net/xfrm_stat") -> XfrmStat = XfrmStat::from_reader;
+    parse_nfs("/proc/net/rpc/nfs") -> NfsClientStats = NfsClientStats::from_reader;
+    parse_nfsd("/proc/net/rpc/nfsd") -> NfsServerStats = NfsServerStats::from_reader;
+    parse_process_stat("/proc/[pid]/stat") -> ProcessStat = ProcessStat::from_reader;
+    parse_process_status("/proc/[pid]/status") -> Status = Status::from_reader;
+    parse_process_io("/proc/[pid]/io") -> ProcessIo = ProcessIo::from_reader;
+    parse_process_sched("/proc/[pid]/sched") -> ProcessSched = ProcessSched::from_reader;
+    parse_limits("/proc/[pid]/limits") -> Limits = Limits::from_reader;
+    parse_maps("/proc/[pid]/maps") -> Vec<MemoryMap> = process::maps::from_reader;
+    parse_numa_maps("/proc/[pid]/numa_maps") -> Vec<NumaMap> = process::numa_maps::from_reader;
+    parse_process_cgroup("/proc/[pid]/cgroup") -> Vec<Cgroup> = process::cgroup::from_reader;
+    parse_id_map("/proc/[pid]/uid_map") -> IdMap = IdMap::from_reader;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_all, parse_cgroups};
+    use std::fs;
+
+    /// Feed mangled copies of real files through every parser, checking nothing panics.
+    #[test]
+    fn pathological_input() {
+        let mut inputs: Vec<Vec<u8>> = vec![
+            b"".to_vec(),
+            b"\n\n\n".to_vec(),
+            b"\0\0\0\n\0".to_vec(),
+            b"\xff\xfe invalid utf-8 \xc3\n".to_vec(),
+            b"cpu 99999999999999999999999999 1 2 3 4 5 6\n".to_vec(),
+            b"18446744073709551615 18446744073709551615 18446744073709551615\n".to_vec(),
+            b"18446744073709551615.99 18446744073709551615.99\n".to_vec(),
+            b"reg00: base=0x0 (0MB), size= 18446744073709551615MB, count=1: write-back\n".to_vec(),
+            b":\n: :\n=\n(\n)\n[ ]\n-\n.\n0x\n".to_vec(),
+            // A hierarchy id just past `u32::MAX`, which mustn't wrap around to 1.
+            b"#subsys_name\thierarchy\tnum_cgroups\tenabled\ncpu\t4294967297\t1\t1\n".to_vec(),
+        ];
+        assert!(
+            parse_cgroups("#subsys_name\thierarchy\tnum_cgroups\tenabled\ncpu\t1\t1\t1\n").is_ok()
+        );
+        assert!(parse_cgroups(&String::from_utf8_lossy(&inputs[9])).is_err());
+        for path in &[
+            "/proc/stat",
+            "/proc/diskstats",
+            "/proc/loadavg",
+            "/proc/uptime",
+            "/proc/self/mountinfo",
+            "/proc/net/dev",
+            "/proc/self/stat",
+            "/proc/self/status",
+            "/proc/self/limits",
+            "/proc/self/maps",
+            "/proc/self/sched",
+        ] {
+            if let Ok(contents) = fs::read(path) {
+                inputs.push(contents);
+            }
+        }
+        // A small xorshift generator, so failures are reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        const REPLACEMENTS: &[u8] = b"0123456789 \t\n:()-.\0\xff9";
+        for input in &inputs {
+            parse_all(input);
+            for _ in 0..50 {
+                let mut mangled = input.clone();
+                for _ in 0..=next() % 8 {
+                    if mangled.is_empty() {
+                        break;
+                    }
+                    let idx = next() as usize % mangled.len();
+                    match next() % 3 {
+                        0 => {
+                            mangled.remove(idx);
+                        }
+                        1 => mangled.insert(idx, b'9'),
+                        _ => mangled[idx] = REPLACEMENTS[next() as usize % REPLACEMENTS.len()],
+                    }
+                }
+                parse_all(&mangled);
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Bindings to `/proc/bus/input/devices`.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+/// The input devices known to the kernel.
+pub struct InputDevices {
+    inner: Vec<InputDevice>,
+}
+
+impl InputDevices {
+    const PATH: &'static str = "/proc/bus/input/devices";
+    /// Parse the contents of `/proc/bus/input/devices`.
+    pub fn from_system() -> io::Result<Self> {
+        InputDevices::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/bus/input/devices`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        let mut device = InputDevice::default();
+        let mut in_stanza = false;
+        loop {
+            match reader.parse_line(InputLine::from_str) {
+                Ok(InputLine::Blank) => {
+                    if in_stanza {
+                        inner.push(std::mem::take(&mut device));
+                        in_stanza = false;
+                    }
+                }
+                Ok(line) => {
+                    device.apply(line);
+                    in_stanza = true;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if in_stanza {
+            inner.push(device);
+        }
+        Ok(InputDevices { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InputDevice> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for InputDevices {
+    type Target = Vec<InputDevice>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for InputDevices {
+    type IntoIter = std::vec::IntoIter<InputDevice>;
+    type Item = InputDevice;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// A single stanza from `/proc/bus/input/devices`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputDevice {
+    /// The bus type (e.g. `0x03` for USB, `0x11` for i8042).
+    pub bus: u16,
+    pub vendor: u16,
+    pub product: u16,
+    pub version: u16,
+    /// The human-readable device name.
+    pub name: String,
+    /// The physical path to the device in the system hierarchy.
+    pub phys: String,
+    /// The path to the device in sysfs, relative to `/sys`.
+    pub sysfs: String,
+    /// A unique identification code for the device, often empty.
+    pub uniq: String,
+    /// The drivers attached to the device, for example `kbd`, `mouse0` or `event3`.
+    pub handlers: Vec<String>,
+    /// The capability bitmaps, keyed by name (`EV`, `KEY`, `REL`, `ABS`, ...).
+    pub bitmaps: HashMap<String, Bitmap>,
+}
+
+impl InputDevice {
+    /// Get a capability bitmap by name, e.g. `device.bitmap("EV")`.
+    pub fn bitmap(&self, name: &str) -> Option<&Bitmap> {
+        self.bitmaps.get(name)
+    }
+
+    fn apply(&mut self, line: InputLine) {
+        match line {
+            InputLine::Id {
+                bus,
+                vendor,
+                product,
+                version,
+            } => {
+                self.bus = bus;
+                self.vendor = vendor;
+                self.product = product;
+                self.version = version;
+            }
+            InputLine::Name(name) => self.name = name,
+            InputLine::Phys(phys) => self.phys = phys,
+            InputLine::Sysfs(sysfs) => self.sysfs = sysfs,
+            InputLine::Uniq(uniq) => self.uniq = uniq,
+            InputLine::Handlers(handlers) => self.handlers = handlers,
+            InputLine::Bitmap(name, bitmap) => {
+                self.bitmaps.insert(name, bitmap);
+            }
+            InputLine::Blank | InputLine::Unknown => (),
+        }
+    }
+}
+
+/// A capability bitmap, as printed by the kernel.
+///
+/// The kernel prints the bitmap as a list of hex words (each the width of a C `long`), with the
+/// most significant word first. They are stored here with the least significant word first so
+/// that bit `n` is in word `n / WORD_BITS`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    /// The width of each word in the bitmap (the width of a C `long`).
+    pub const WORD_BITS: usize = std::mem::size_of::<usize>() * 8;
+
+    /// Whether bit `bit` is set.
+    pub fn is_set(&self, bit: usize) -> bool {
+        match self.words.get(bit / Self::WORD_BITS) {
+            Some(word) => word & (1 << (bit % Self::WORD_BITS)) != 0,
+            None => false,
+        }
+    }
+
+    /// The words of the bitmap, least significant first.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Iterate over the indexes of set bits, in ascending order.
+    pub fn iter_set(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, word)| {
+            (0..Self::WORD_BITS)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| idx * Self::WORD_BITS + bit)
+        })
+    }
+
+    fn from_str(input: &str) -> Result<Bitmap, Error> {
+        let mut words = Vec::new();
+        let mut input = input;
+        while let Some((rest, word)) = util::parse_token(input) {
+            words.push(u64::from_str_radix(word, 16).map_err(|_| Error::from("bitmap word"))?);
+            input = rest;
+        }
+        words.reverse();
+        Ok(Bitmap { words })
+    }
+}
+
+enum InputLine {
+    Id {
+        bus: u16,
+        vendor: u16,
+        product: u16,
+        version: u16,
+    },
+    Name(String),
+    Phys(String),
+    Sysfs(String),
+    Uniq(String),
+    Handlers(Vec<String>),
+    Bitmap(String, Bitmap),
+    Blank,
+    /// Line types we don't know about (future kernels may add more).
+    Unknown,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Parse `Key=hex` from the input.
+fn parse_hex_field<'a>(key: &str, input: &'a str) -> Result<(&'a str, u16), Error> {
+    let input = err_msg!(util::expect_bytes(key, input), key)?;
+    let input = err_msg!(util::expect_bytes("=", input), "expected \"=\"")?;
+    let (input, value) = err_msg!(util::parse_token(input), key)?;
+    let value = u16::from_str_radix(value, 16).map_err(|_| Error::from(key))?;
+    Ok((input, value))
+}
+
+/// Parse `Key=value`, where value is the rest of the line, optionally in quotes.
+fn parse_string_field(key: &str, input: &str) -> Result<String, Error> {
+    let input = err_msg!(util::expect_bytes(key, input), key)?;
+    let input = err_msg!(util::expect_bytes("=", input), "expected \"=\"")?;
+    let value = input.trim_end_matches(['\n', '\r']);
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    Ok(value.to_owned())
+}
+
+impl InputLine {
+    fn from_str(input: &str) -> Result<InputLine, Error> {
+        if util::consume_space(input).is_empty() {
+            return Ok(InputLine::Blank);
+        }
+        let (input, kind) = err_msg!(util::parse_token(input), "line type")?;
+        let input = util::consume_space(input);
+        Ok(match kind {
+            "I:" => {
+                let (input, bus) = parse_hex_field("Bus", input)?;
+                let (input, vendor) = parse_hex_field("Vendor", input)?;
+                let (input, product) = parse_hex_field("Product", input)?;
+                let (_input, version) = parse_hex_field("Version", input)?;
+                InputLine::Id {
+                    bus,
+                    vendor,
+                    product,
+                    version,
+                }
+            }
+            "N:" => InputLine::Name(parse_string_field("Name", input)?),
+            "P:" => InputLine::Phys(parse_string_field("Phys", input)?),
+            "S:" => InputLine::Sysfs(parse_string_field("Sysfs", input)?),
+            "U:" => InputLine::Uniq(parse_string_field("Uniq", input)?),
+            "H:" => {
+                let input = err_msg!(util::expect_bytes("Handlers=", input), "Handlers")?;
+                InputLine::Handlers(input.split_whitespace().map(String::from).collect())
+            }
+            "B:" => {
+                let (name, bitmap) = err_msg!(input.split_once('='), "bitmap")?;
+                InputLine::Bitmap(name.to_owned(), Bitmap::from_str(bitmap)?)
+            }
+            _ => InputLine::Unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputDevices;
+    use std::io;
+
+    #[test]
+    fn proc_bus_input_devices() {
+        let raw = "\
+I: Bus=0019 Vendor=0000 Product=0001 Version=0000
+N: Name=\"Power Button\"
+P: Phys=PNP0C0C/button/input0
+S: Sysfs=/devices/LNXSYSTM:00/LNXSYBUS:00/PNP0C0C:00/input/input0
+U: Uniq=
+H: Handlers=kbd event0
+B: PROP=0
+B: EV=3
+B: KEY=10000000000000 0
+
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
+N: Name=\"AT Translated Set 2 keyboard\"
+P: Phys=isa0060/serio0/input0
+S: Sysfs=/devices/platform/i8042/serio0/input/input3
+U: Uniq=
+H: Handlers=sysrq kbd event3 leds
+B: PROP=0
+B: EV=120013
+B: KEY=402000000 3803078f800d001 feffffdfffefffff fffffffffffffffe
+B: MSC=10
+B: LED=7
+
+";
+        let devices = InputDevices::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(devices.len(), 2);
+        let power = &devices[0];
+        assert_eq!(power.name, "Power Button");
+        assert_eq!(power.uniq, "");
+        assert_eq!(power.handlers, vec!["kbd", "event0"]);
+        // KEY_POWER is 116.
+        assert!(power.bitmap("KEY").unwrap().is_set(116));
+        let keyboard = &devices[1];
+        assert_eq!(keyboard.bus, 0x11);
+        assert_eq!(keyboard.version, 0xab41);
+        assert_eq!(
+            keyboard
+                .bitmap("LED")
+                .unwrap()
+                .iter_set()
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+}
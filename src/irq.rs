@@ -0,0 +1,62 @@
+//! Bindings to the per-interrupt directories in `/proc/irq`.
+use std::io;
+use std::path::PathBuf;
+
+use crate::util;
+
+fn path(irq: u32, name: &str) -> PathBuf {
+    PathBuf::from(format!("/proc/irq/{}/{}", irq, name))
+}
+
+/// The cpus that interrupt `irq` may be delivered to, from `/proc/irq/[irq]/smp_affinity_list`.
+///
+/// Interrupt numbers are the row labels of `/proc/interrupts`.
+pub fn affinity(irq: u32) -> io::Result<Vec<u32>> {
+    let list = util::read_value::<String>(path(irq, "smp_affinity_list"))?;
+    util::parse_cpu_list(&list).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cannot parse cpu list {:?}", list),
+        )
+    })
+}
+
+/// Deliver interrupt `irq` only to `cpus`, like writing `smp_affinity`.
+///
+/// Requires root. `cpus` must not be empty, and the kernel rejects sets containing no online
+/// cpus. `irqbalance`, if running, may later move the interrupt again.
+#[cfg(feature = "write")]
+pub fn set_affinity(irq: u32, cpus: &[u32]) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "an interrupt must be allowed on at least one cpu",
+        ));
+    }
+    let list = cpus
+        .iter()
+        .map(|cpu| cpu.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    util::write_value(path(irq, "smp_affinity_list"), list)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    #[test]
+    fn irq_affinity() {
+        // Interrupt 0 (the timer) has no directory on some machines, so just check errors are
+        // sensible.
+        match super::affinity(0) {
+            Ok(_) => (),
+            Err(e) => assert_ne!(e.kind(), io::ErrorKind::InvalidData),
+        }
+        #[cfg(feature = "write")]
+        {
+            let err = super::set_affinity(0, &[]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Bindings to `/proc/kallsyms`, the kernel's symbol table.
+//!
+//! The table has hundreds of thousands of entries, so it's read one symbol at a time.
+use std::fs::File;
+use std::io;
+
+use crate::{
+    parse::{Entries, EntryParser},
+    util, Error,
+};
+
+/// A kernel symbol, from a line of `/proc/kallsyms`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KernelSymbol {
+    /// The address of the symbol.
+    ///
+    /// Unprivileged readers see every address as 0, depending on `kernel.kptr_restrict`.
+    pub address: u64,
+    /// The symbol type as printed by `nm`, e.g. `T` for a global function or `d` for local data.
+    pub kind: char,
+    pub name: String,
+    /// The module the symbol belongs to, or `None` if it's built into the kernel.
+    pub module: Option<String>,
+}
+
+/// An iterator over the symbols in `/proc/kallsyms`.
+///
+/// Collect it into an `io::Result<Vec<_>>` to read every symbol at once.
+pub struct KernelSymbols<R> {
+    entries: Entries<R, EntryParser<KernelSymbol>>,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl KernelSymbols<File> {
+    const PATH: &'static str = "/proc/kallsyms";
+    /// Iterate over the symbols in `/proc/kallsyms`.
+    pub fn from_system() -> io::Result<Self> {
+        Ok(KernelSymbols::from_reader(File::open(Self::PATH)?))
+    }
+}
+
+impl<R: io::Read> KernelSymbols<R> {
+    /// Iterate over `/proc/kallsyms`-formatted data from `reader`.
+    pub fn from_reader(reader: R) -> Self {
+        KernelSymbols {
+            entries: util::LineParser::new(reader)
+                .entries(KernelSymbol::from_str as EntryParser<_>),
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for KernelSymbols<R> {
+    type Item = io::Result<KernelSymbol>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+impl KernelSymbol {
+    fn from_str(input: &str) -> Result<KernelSymbol, Error> {
+        let (input, address) = err_msg!(util::parse_hex_u64(input), "address")?;
+        let (input, kind) = err_msg!(util::parse_token(input), "symbol type")?;
+        let mut chars = kind.chars();
+        let kind = match (chars.next(), chars.next()) {
+            (Some(kind), None) => kind,
+            _ => return Err("symbol type".into()),
+        };
+        let (input, name) = err_msg!(util::parse_token(input), "symbol name")?;
+        let module = match util::parse_token(input) {
+            Some((_, module)) => {
+                let module = module.strip_prefix('[').and_then(|m| m.strip_suffix(']'));
+                Some(err_msg!(module, "module in brackets")?.to_owned())
+            }
+            None => None,
+        };
+        Ok(KernelSymbol {
+            address,
+            kind,
+            name: name.to_owned(),
+            module,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KernelSymbols;
+    use std::io;
+
+    #[test]
+    fn proc_kallsyms() {
+        let raw = "\
+ffffffff81000000 T _stext
+ffffffff82a4c2c0 d __func__.0
+ffffffffc03c5000 t nvme_irq\t[nvme]
+";
+        let symbols = KernelSymbols::from_reader(io::Cursor::new(raw))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[0].address, 0xffffffff81000000);
+        assert_eq!(symbols[0].kind, 'T');
+        assert_eq!(symbols[1].name, "__func__.0");
+        assert_eq!(symbols[1].module, None);
+        assert_eq!(symbols[2].module.as_deref(), Some("nvme"));
+        assert!(KernelSymbols::from_reader(io::Cursor::new("ffff TT x\n"))
+            .next()
+            .unwrap()
+            .is_err());
+
+        let first = KernelSymbols::from_system().unwrap().next().unwrap();
+        assert!(!first.unwrap().name.is_empty());
+    }
+}
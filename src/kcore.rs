@@ -0,0 +1,281 @@
+//! Bindings to the headers of `/proc/kcore`.
+//!
+//! `/proc/kcore` presents the kernel's memory as an ELF core file. Only the ELF header and
+//! program headers are read here, which describe the memory ranges the file covers, so
+//! forensic and crash tools can plan what to capture. Reading it needs `CAP_SYS_RAWIO`.
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// The headers of `/proc/kcore`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Kcore {
+    /// The ELF machine type, e.g. 62 for x86_64 or 183 for aarch64.
+    pub machine: u16,
+    pub segments: Vec<KcoreSegment>,
+}
+
+/// A segment of `/proc/kcore`, from a program header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct KcoreSegment {
+    pub kind: SegmentKind,
+    /// Where the segment's contents start in the file.
+    pub offset: u64,
+    /// The kernel virtual address of the segment.
+    pub virtual_address: u64,
+    /// The physical address of the segment, if it maps physical memory directly.
+    pub physical_address: Option<u64>,
+    /// The size of the segment in the file.
+    pub file_size: u64,
+    /// The size of the segment in memory.
+    pub memory_size: u64,
+}
+
+/// The type of a segment.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SegmentKind {
+    /// A range of memory (`PT_LOAD`).
+    Load,
+    /// Notes about the kernel, like its version and registers (`PT_NOTE`).
+    Note,
+    Other(u32),
+}
+
+impl KcoreSegment {
+    /// The physical memory range this segment covers, if it maps physical memory.
+    pub fn physical_range(&self) -> Option<Range<u64>> {
+        let start = self.physical_address?;
+        Some(start..start.saturating_add(self.memory_size))
+    }
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads the fixed-size fields of an ELF structure.
+struct Fields<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+    wide: bool,
+}
+
+impl<'a> Fields<'a> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        if self.buf.len() < N {
+            return Err(invalid("ELF header too short"));
+        }
+        let (field, rest) = self.buf.split_at(N);
+        self.buf = rest;
+        Ok(field.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take()?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// An address or offset: 8 bytes in 64-bit files and 4 in 32-bit ones.
+    fn word(&mut self) -> io::Result<u64> {
+        if !self.wide {
+            return self.u32().map(u64::from);
+        }
+        let bytes = self.take()?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+}
+
+impl Kcore {
+    const PATH: &'static str = "/proc/kcore";
+    /// Read the headers of `/proc/kcore`.
+    pub fn from_system() -> io::Result<Self> {
+        Kcore::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Read the headers of an ELF core file from `reader`, without reading any segments.
+    pub fn from_reader(mut reader: impl Read + Seek) -> io::Result<Self> {
+        let mut ident = [0; 16];
+        reader.read_exact(&mut ident)?;
+        if ident[..4] != *b"\x7fELF" {
+            return Err(invalid("not an ELF file"));
+        }
+        let wide = match ident[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown ELF class")),
+        };
+        let big_endian = match ident[5] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown ELF byte order")),
+        };
+        // The rest of the header is 36 bytes in 32-bit files and 48 in 64-bit ones.
+        let mut header = [0; 48];
+        let header = &mut header[..if wide { 48 } else { 36 }];
+        reader.read_exact(header)?;
+        let mut fields = Fields {
+            buf: header,
+            big_endian,
+            wide,
+        };
+        let _kind = fields.u16()?;
+        let machine = fields.u16()?;
+        let _version = fields.u32()?;
+        let _entry = fields.word()?;
+        let phoff = fields.word()?;
+        let _shoff = fields.word()?;
+        let _flags = fields.u32()?;
+        let _ehsize = fields.u16()?;
+        let phentsize = u64::from(fields.u16()?);
+        let phnum = fields.u16()?;
+        if phnum == u16::MAX {
+            // The real count is in the first section header, which kcore never needs.
+            return Err(invalid("too many program headers"));
+        }
+        let entry_size = if wide { 56 } else { 32 };
+        if phentsize < entry_size {
+            return Err(invalid("program headers too small"));
+        }
+
+        let mut segments = Vec::with_capacity(phnum.into());
+        let mut entry = vec![0; entry_size as usize];
+        for idx in 0..u64::from(phnum) {
+            let offset = phentsize
+                .checked_mul(idx)
+                .and_then(|offset| offset.checked_add(phoff))
+                .ok_or_else(|| invalid("program header offset overflows"))?;
+            reader.seek(SeekFrom::Start(offset))?;
+            reader.read_exact(&mut entry)?;
+            let mut fields = Fields {
+                buf: &entry,
+                big_endian,
+                wide,
+            };
+            segments.push(KcoreSegment::parse(&mut fields)?);
+        }
+        Ok(Kcore { machine, segments })
+    }
+
+    /// The segments mapping memory, skipping notes.
+    pub fn load_segments(&self) -> impl Iterator<Item = &KcoreSegment> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.kind == SegmentKind::Load)
+    }
+
+    /// The physical memory ranges the file covers, in file order.
+    pub fn physical_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.load_segments()
+            .filter_map(|segment| segment.physical_range())
+    }
+}
+
+impl KcoreSegment {
+    fn parse(fields: &mut Fields) -> io::Result<KcoreSegment> {
+        let kind = match fields.u32()? {
+            1 => SegmentKind::Load,
+            4 => SegmentKind::Note,
+            other => SegmentKind::Other(other),
+        };
+        // The flags come second in 64-bit headers and second to last in 32-bit ones.
+        if fields.wide {
+            let _flags = fields.u32()?;
+        }
+        let offset = fields.word()?;
+        let virtual_address = fields.word()?;
+        let physical_address = fields.word()?;
+        let file_size = fields.word()?;
+        let memory_size = fields.word()?;
+        // Segments that don't map physical memory directly (like vmalloc space) have a physical
+        // address of -1.
+        let unmapped = if fields.wide {
+            u64::MAX
+        } else {
+            u64::from(u32::MAX)
+        };
+        Ok(KcoreSegment {
+            kind,
+            offset,
+            virtual_address,
+            physical_address: Some(physical_address).filter(|&addr| addr != unmapped),
+            file_size,
+            memory_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Kcore, SegmentKind};
+    use crate::ReadOutcome;
+    use std::io;
+
+    /// A little-endian 64-bit ELF core file with the given `(type, vaddr, paddr, size)` segments.
+    fn elf64(segments: &[(u32, u64, u64, u64)]) -> Vec<u8> {
+        let mut file = b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0".to_vec();
+        file.extend(4u16.to_le_bytes()); // ET_CORE
+        file.extend(62u16.to_le_bytes()); // x86_64
+        file.extend(1u32.to_le_bytes());
+        file.extend(0u64.to_le_bytes());
+        file.extend(64u64.to_le_bytes()); // phoff
+        file.extend(0u64.to_le_bytes());
+        file.extend(0u32.to_le_bytes());
+        file.extend(64u16.to_le_bytes());
+        file.extend(56u16.to_le_bytes());
+        file.extend((segments.len() as u16).to_le_bytes());
+        file.extend([0; 6]);
+        for &(kind, vaddr, paddr, size) in segments {
+            file.extend(kind.to_le_bytes());
+            file.extend(7u32.to_le_bytes());
+            file.extend(0x1000u64.to_le_bytes());
+            file.extend(vaddr.to_le_bytes());
+            file.extend(paddr.to_le_bytes());
+            file.extend(size.to_le_bytes());
+            file.extend(size.to_le_bytes());
+            file.extend(0x1000u64.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn proc_kcore() {
+        let raw = elf64(&[
+            (4, 0, 0, 0x1000),
+            (1, 0xffff888000001000, 0x1000, 0x9e000),
+            (1, 0xffffc90000000000, u64::MAX, 0x1000),
+        ]);
+        let kcore = Kcore::from_reader(io::Cursor::new(&raw)).unwrap();
+        assert_eq!(kcore.machine, 62);
+        assert_eq!(kcore.segments.len(), 3);
+        assert_eq!(kcore.segments[0].kind, SegmentKind::Note);
+        assert_eq!(kcore.load_segments().count(), 2);
+        assert_eq!(
+            kcore.physical_ranges().collect::<Vec<_>>(),
+            vec![0x1000..0x9f000]
+        );
+        assert!(Kcore::from_reader(io::Cursor::new(&raw[..100])).is_err());
+        assert!(Kcore::from_reader(io::Cursor::new(b"not an elf file at all")).is_err());
+
+        if let ReadOutcome::Ok(kcore) = ReadOutcome::from(Kcore::from_system()) {
+            assert!(kcore.load_segments().count() > 0);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Bindings to `/proc/latency_stats`, the latencytop statistics.
+//!
+//! The kernel records why tasks were blocked (by their kernel backtrace) and for how long, but
+//! only if it was built with `CONFIG_LATENCYTOP` and recording has been switched on with
+//! `/proc/sys/kernel/latencytop`. Without `CONFIG_LATENCYTOP` the files don't exist, which can be
+//! told apart from other errors with `ReadOutcome`:
+//!
+//! ```no_run
+//! use linux_proc::{latency::LatencyStats, ReadOutcome};
+//!
+//! match ReadOutcome::from(LatencyStats::from_system()) {
+//!     ReadOutcome::Ok(stats) => {
+//!         for record in &stats {
+//!             println!("{:?} max {:?}", record.cause(), record.max);
+//!         }
+//!     }
+//!     ReadOutcome::NotFound => println!("kernel built without CONFIG_LATENCYTOP"),
+//!     _ => println!("cannot read /proc/latency_stats"),
+//! }
+//! ```
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The latencies recorded by latencytop, from `/proc/latency_stats` or `/proc/[pid]/latency`.
+///
+/// Empty unless recording is enabled (see `LatencyStats::is_enabled`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyStats {
+    inner: Vec<LatencyRecord>,
+}
+
+/// The latencies with one cause, from a line of `/proc/latency_stats`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LatencyRecord {
+    /// The number of times a task was blocked.
+    pub count: u64,
+    /// The total time blocked.
+    pub total: Duration,
+    /// The longest time blocked.
+    pub max: Duration,
+    /// The kernel functions the task was blocked in, innermost first.
+    pub backtrace: Vec<String>,
+}
+
+impl LatencyRecord {
+    /// The innermost function in the backtrace that isn't part of the scheduler, which is usually
+    /// the best description of what the task was waiting for.
+    pub fn cause(&self) -> Option<&str> {
+        const SCHEDULER: &[&str] = &["schedule", "io_schedule", "__schedule", "preempt_schedule"];
+        self.backtrace
+            .iter()
+            .map(String::as_str)
+            .find(|frame| !SCHEDULER.contains(frame) && !frame.starts_with("schedule_"))
+    }
+
+    /// The mean time blocked.
+    pub fn average(&self) -> Duration {
+        match self.count {
+            0 => Duration::from_secs(0),
+            count => Duration::from_nanos((self.total.as_nanos() / u128::from(count)) as u64),
+        }
+    }
+}
+
+impl LatencyStats {
+    const PATH: &'static str = "/proc/latency_stats";
+    const ENABLED_PATH: &'static str = "/proc/sys/kernel/latencytop";
+    const HEADER: &'static str = "Latency Top version : v0.1";
+
+    /// Parse the latencies of the whole system, from `/proc/latency_stats`.
+    pub fn from_system() -> io::Result<Self> {
+        LatencyStats::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse the latencies of process `pid`, from `/proc/[pid]/latency`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        LatencyStats::from_reader(File::open(format!("/proc/{}/latency", pid))?)
+    }
+
+    /// Parse `/proc/latency_stats`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(|line| match line.trim_end() == Self::HEADER {
+            true => Ok(()),
+            false => Err(Error::from("expected latencytop version v0.1")),
+        })?;
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(LatencyRecord::from_str) {
+                Ok(record) => inner.push(record),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(LatencyStats { inner })
+    }
+
+    /// Whether latencytop is recording, from `/proc/sys/kernel/latencytop`.
+    ///
+    /// Returns `false` rather than an error if the kernel was built without `CONFIG_LATENCYTOP`.
+    pub fn is_enabled() -> io::Result<bool> {
+        match util::read_value::<u8>(Self::ENABLED_PATH) {
+            Ok(enabled) => Ok(enabled != 0),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Start or stop recording latencies, by writing `/proc/sys/kernel/latencytop`.
+    #[cfg(feature = "write")]
+    pub fn set_enabled(enabled: bool) -> io::Result<()> {
+        util::write_value(Self::ENABLED_PATH, u8::from(enabled))
+    }
+
+    /// Forget the latencies recorded so far, system-wide.
+    #[cfg(feature = "write")]
+    pub fn clear() -> io::Result<()> {
+        // Writing anything clears the records.
+        util::write_value(Self::PATH, "erase")
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, LatencyRecord> {
+        self.inner.iter()
+    }
+
+    /// The number of causes recorded.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The records, worst first by longest latency.
+    pub fn by_max(&self) -> Vec<&LatencyRecord> {
+        let mut records: Vec<_> = self.iter().collect();
+        records.sort_by_key(|record| std::cmp::Reverse(record.max));
+        records
+    }
+}
+
+impl IntoIterator for LatencyStats {
+    type IntoIter = std::vec::IntoIter<LatencyRecord>;
+    type Item = LatencyRecord;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a LatencyStats {
+    type IntoIter = std::slice::Iter<'a, LatencyRecord>;
+    type Item = &'a LatencyRecord;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl LatencyRecord {
+    fn from_str(input: &str) -> Result<LatencyRecord, Error> {
+        let (input, count) = err_msg!(util::parse_u64(input), "count")?;
+        let (input, total) = err_msg!(util::parse_u64(input), "total latency")?;
+        let (input, max) = err_msg!(util::parse_u64(input), "max latency")?;
+        Ok(LatencyRecord {
+            count,
+            total: Duration::from_micros(total),
+            max: Duration::from_micros(max),
+            backtrace: input.split_whitespace().map(str::to_owned).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LatencyStats;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_latency_stats() {
+        let raw = "\
+Latency Top version : v0.1
+12 48213 9150 schedule io_schedule folio_wait_bit_common filemap_fault __do_fault
+3 120 60 schedule do_nanosleep hrtimer_nanosleep
+1 5 5
+";
+        let stats = LatencyStats::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.len(), 3);
+        let fault = &stats.iter().next().unwrap();
+        assert_eq!(fault.count, 12);
+        assert_eq!(fault.total, Duration::from_micros(48213));
+        assert_eq!(fault.max, Duration::from_micros(9150));
+        assert_eq!(fault.backtrace.len(), 5);
+        assert_eq!(fault.cause(), Some("folio_wait_bit_common"));
+        assert_eq!(fault.average(), Duration::from_nanos(4017750));
+        let by_max: Vec<_> = stats.by_max().iter().map(|r| r.count).collect();
+        assert_eq!(by_max, vec![12, 3, 1]);
+        assert_eq!(stats.iter().nth(2).unwrap().cause(), None);
+
+        let empty = LatencyStats::from_reader(io::Cursor::new("Latency Top version : v0.1\n"));
+        assert!(empty.unwrap().is_empty());
+        assert!(LatencyStats::from_reader(io::Cursor::new("1 2 3 schedule\n")).is_err());
+    }
+
+    #[test]
+    fn from_system() {
+        if crate::ReadOutcome::from(LatencyStats::from_system()).is_ok() {
+            // The file exists, so recording can be turned on.
+            assert!(std::path::Path::new("/proc/sys/kernel/latencytop").exists());
+        }
+        LatencyStats::is_enabled().unwrap();
+    }
+}
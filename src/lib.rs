@@ -1,12 +1,62 @@
 //! Parsers for the contents of the `/proc` directory.
 //!
 
+pub mod cgroup;
+pub mod clock;
+pub mod collection;
 pub mod diskstats;
+pub mod driver;
+pub mod environment;
+#[cfg(feature = "events")]
+pub mod events;
+pub mod execdomains;
+pub mod fs;
+pub mod fuzz;
+pub mod input;
+pub mod irq;
+pub mod kallsyms;
+pub mod kcore;
+pub mod latency;
+pub mod loadavg;
+pub mod meminfo;
+pub mod mounts;
+pub mod mountstats;
+pub mod mtrr;
+pub mod net;
+pub mod nfs;
+pub mod notify;
+pub mod pagemap;
+pub mod parse;
+pub mod pci;
+pub mod pressure;
+pub mod process;
+pub mod render;
+pub mod schedstat;
+pub mod slabinfo;
+pub mod snapshot;
 pub mod stat;
+pub mod swaps;
+pub mod sys;
+#[cfg(feature = "write")]
+pub mod sysrq;
+pub mod system_info;
+#[cfg(any(test, feature = "testdata"))]
+pub mod testdata;
+pub mod units;
 pub mod uptime;
 mod util;
+#[cfg(feature = "zfs")]
+pub mod zfs;
 
-use std::fmt;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+pub use crate::system_info::system_info;
+pub use crate::units::{Jiffies, Kibibytes, Pages, RatioFormat, RatioUnit, Sectors};
+pub use crate::util::device_name;
 
 /// A very simple error handler.
 pub struct Error(String);
@@ -17,7 +67,7 @@ impl From<String> for Error {
     }
 }
 
-impl<'a> From<&'a str> for Error {
+impl From<&str> for Error {
     fn from(f: &str) -> Error {
         Error(f.into())
     }
@@ -36,3 +86,410 @@ impl fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+/// A size in bytes.
+///
+/// Files like `/proc/meminfo` and `/proc/[pid]/status` print sizes as `12345 kB`, where a kB is
+/// 1024 bytes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// A size given in kB (1024 bytes), saturating at `u64::MAX` bytes.
+    pub fn from_kib(kib: u64) -> Self {
+        ByteSize(kib.saturating_mul(1024))
+    }
+
+    /// The size in bytes.
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+
+    /// The size in kB (1024 bytes), rounded down.
+    pub fn kib(self) -> u64 {
+        self.0 / 1024
+    }
+}
+
+impl fmt::Display for ByteSize {
+    /// Formats the size with binary units, e.g. `1.50 GiB`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        if self.0 < 1024 {
+            return f.pad(&format!("{} B", self.0));
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        f.pad(&format!("{:.2} {}", value, UNITS[unit]))
+    }
+}
+
+/// The outcome of reading a file from `/proc`, distinguishing the common reasons a file might not
+/// be usable.
+///
+/// Every `from_system` constructor returns an `io::Result`, where parse failures are reported with
+/// `io::ErrorKind::InvalidData`. Convert the result with `ReadOutcome::from` to decide how to
+/// degrade, for example skipping a metric when its feature isn't compiled into the kernel.
+///
+/// ```no_run
+/// use linux_proc::{stat::Stat, ReadOutcome};
+///
+/// match ReadOutcome::from(Stat::from_system()) {
+///     ReadOutcome::Ok(stat) => println!("{} processes running", stat.procs_running),
+///     ReadOutcome::NotFound => println!("/proc is not mounted"),
+///     ReadOutcome::PermissionDenied => println!("try running as root"),
+///     ReadOutcome::Unsupported => println!("not available on this architecture"),
+///     ReadOutcome::ParseError(e) | ReadOutcome::Io(e) => println!("error: {}", e),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum ReadOutcome<T> {
+    /// The file was read and parsed successfully.
+    Ok(T),
+    /// The file doesn't exist, usually because the feature isn't compiled into the kernel.
+    NotFound,
+    /// The file exists but we aren't allowed to read it, usually because we aren't root.
+    PermissionDenied,
+    /// The file never exists on this cpu architecture, like `/proc/mtrr` outside x86.
+    Unsupported,
+    /// The file was read, but its contents weren't understood.
+    ParseError(io::Error),
+    /// Any other io error.
+    Io(io::Error),
+}
+
+impl<T> ReadOutcome<T> {
+    /// The parsed value, if there was one.
+    pub fn ok(self) -> Option<T> {
+        match self {
+            ReadOutcome::Ok(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether the file was read and parsed successfully.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ReadOutcome::Ok(_))
+    }
+
+    /// Map the parsed value, leaving other outcomes unchanged.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ReadOutcome<U> {
+        match self {
+            ReadOutcome::Ok(value) => ReadOutcome::Ok(f(value)),
+            ReadOutcome::NotFound => ReadOutcome::NotFound,
+            ReadOutcome::PermissionDenied => ReadOutcome::PermissionDenied,
+            ReadOutcome::Unsupported => ReadOutcome::Unsupported,
+            ReadOutcome::ParseError(e) => ReadOutcome::ParseError(e),
+            ReadOutcome::Io(e) => ReadOutcome::Io(e),
+        }
+    }
+
+    /// Turn the outcome back into an `io::Result`.
+    pub fn into_result(self) -> io::Result<T> {
+        match self {
+            ReadOutcome::Ok(value) => Ok(value),
+            ReadOutcome::NotFound => Err(io::ErrorKind::NotFound.into()),
+            ReadOutcome::PermissionDenied => Err(io::ErrorKind::PermissionDenied.into()),
+            ReadOutcome::Unsupported => Err(io::ErrorKind::Unsupported.into()),
+            ReadOutcome::ParseError(e) | ReadOutcome::Io(e) => Err(e),
+        }
+    }
+}
+
+impl<T> From<io::Result<T>> for ReadOutcome<T> {
+    fn from(result: io::Result<T>) -> Self {
+        match result {
+            Ok(value) => ReadOutcome::Ok(value),
+            Err(e) => match e.kind() {
+                io::ErrorKind::NotFound => ReadOutcome::NotFound,
+                io::ErrorKind::PermissionDenied => ReadOutcome::PermissionDenied,
+                io::ErrorKind::Unsupported => ReadOutcome::Unsupported,
+                // A file that ends early is as malformed as one with bad contents.
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                    ReadOutcome::ParseError(e)
+                }
+                _ => ReadOutcome::Io(e),
+            },
+        }
+    }
+}
+
+/// A value read from `/proc`, with the time it was read.
+///
+/// Rates calculated from two snapshots should divide by the time that really passed between
+/// them, which can be much longer than the nominal sampling interval on a loaded machine. Wrap any
+/// `from_system` call with `Timestamped::read` to record it:
+///
+/// ```no_run
+/// use linux_proc::{diskstats::DiskStats, Timestamped};
+///
+/// let earlier = Timestamped::read(DiskStats::from_system)?;
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// let later = Timestamped::read(DiskStats::from_system)?;
+/// let elapsed = later.elapsed_since(&earlier);
+/// for delta in &later.diff(&earlier).devices {
+///     println!("{}: {:.1}% busy", delta.name, delta.utilization(elapsed) * 100.0);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamped<T> {
+    pub value: T,
+    /// When the value was read, for measuring intervals.
+    pub taken_at: Instant,
+    /// When the value was read by the wall clock, for reporting. This can jump, so don't use it
+    /// to measure intervals.
+    pub wall_time: SystemTime,
+}
+
+impl<T> Timestamped<T> {
+    /// Stamp `value` with the current time.
+    pub fn now(value: T) -> Self {
+        Timestamped {
+            value,
+            taken_at: Instant::now(),
+            wall_time: SystemTime::now(),
+        }
+    }
+
+    /// Call `read`, stamping the result with the time just before the call.
+    pub fn read(read: impl FnOnce() -> io::Result<T>) -> io::Result<Self> {
+        let taken_at = Instant::now();
+        let wall_time = SystemTime::now();
+        Ok(Timestamped {
+            value: read()?,
+            taken_at,
+            wall_time,
+        })
+    }
+
+    /// The time between `earlier` and this snapshot, or zero if `earlier` was taken later.
+    pub fn elapsed_since<U>(&self, earlier: &Timestamped<U>) -> Duration {
+        self.taken_at.saturating_duration_since(earlier.taken_at)
+    }
+
+    /// Map the value, keeping the timestamp.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Timestamped<U> {
+        Timestamped {
+            value: f(self.value),
+            taken_at: self.taken_at,
+            wall_time: self.wall_time,
+        }
+    }
+
+    /// Discard the timestamp.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::ops::Deref for Timestamped<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// A value parsed from `/proc`, with the exact text it was parsed from.
+///
+/// This is for debugging: when a parser gives the wrong answer, or fails, on some kernel, the raw
+/// text is what a bug report needs. Pass the file's path and any `from_reader` constructor:
+///
+/// ```no_run
+/// use linux_proc::{stat::Stat, ParsedWithSource};
+///
+/// match ParsedWithSource::read("/proc/stat", Stat::from_reader) {
+///     Ok(stat) => println!("{} cpus, parsed from:\n{}", stat.cpus.len(), stat.raw),
+///     // The error's `Display` includes the text that failed to parse.
+///     Err(e) => eprintln!("{}", e),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedWithSource<T> {
+    pub value: T,
+    /// The text that was parsed. Any invalid UTF-8 is replaced with `U+FFFD`.
+    pub raw: String,
+}
+
+impl<T> ParsedWithSource<T> {
+    /// Read the whole of `path`, then parse it with `parse`.
+    pub fn read(
+        path: impl AsRef<Path>,
+        parse: impl FnOnce(io::Cursor<Vec<u8>>) -> io::Result<T>,
+    ) -> Result<Self, SourceError> {
+        let path = path.as_ref();
+        let raw = std::fs::read(path).map_err(|error| SourceError {
+            path: Some(path.to_owned()),
+            raw: None,
+            error,
+        })?;
+        Self::parse(raw, parse).map_err(|e| SourceError {
+            path: Some(path.to_owned()),
+            ..e
+        })
+    }
+
+    /// Read everything from `reader`, then parse it with `parse`.
+    pub fn from_reader(
+        mut reader: impl io::Read,
+        parse: impl FnOnce(io::Cursor<Vec<u8>>) -> io::Result<T>,
+    ) -> Result<Self, SourceError> {
+        let mut raw = Vec::new();
+        if let Err(error) = reader.read_to_end(&mut raw) {
+            return Err(SourceError {
+                path: None,
+                raw: None,
+                error,
+            });
+        }
+        Self::parse(raw, parse)
+    }
+
+    fn parse(
+        raw: Vec<u8>,
+        parse: impl FnOnce(io::Cursor<Vec<u8>>) -> io::Result<T>,
+    ) -> Result<Self, SourceError> {
+        let result = parse(io::Cursor::new(raw.clone()));
+        let raw = String::from_utf8_lossy(&raw).into_owned();
+        match result {
+            Ok(value) => Ok(ParsedWithSource { value, raw }),
+            Err(error) => Err(SourceError {
+                path: None,
+                raw: Some(raw),
+                error,
+            }),
+        }
+    }
+
+    /// Map the value, keeping the source.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ParsedWithSource<U> {
+        ParsedWithSource {
+            value: f(self.value),
+            raw: self.raw,
+        }
+    }
+
+    /// Discard the source.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::ops::Deref for ParsedWithSource<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// The error from `ParsedWithSource`, with the text that failed to parse.
+///
+/// `Display` prints the error followed by the raw text, ready to paste into a bug report.
+#[derive(Debug)]
+pub struct SourceError {
+    /// The file that was read, unless it came from `ParsedWithSource::from_reader`.
+    pub path: Option<PathBuf>,
+    /// The text that failed to parse, or `None` if reading it failed.
+    pub raw: Option<String>,
+    pub error: io::Error,
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = self.path.as_ref().map(|path| path.display());
+        match path {
+            Some(ref path) => write!(f, "{}: {}", path, self.error)?,
+            None => write!(f, "{}", self.error)?,
+        }
+        if let Some(ref raw) = self.raw {
+            match path {
+                Some(path) => writeln!(f, "\n--- {} ---", path)?,
+                None => writeln!(f, "\n--- source ---")?,
+            }
+            f.write_str(raw)?;
+            if !raw.ends_with('\n') {
+                writeln!(f)?;
+            }
+            f.write_str("---")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<SourceError> for io::Error {
+    /// Keep the kind of the original error, with the path and source in the message.
+    fn from(e: SourceError) -> io::Error {
+        io::Error::new(e.error.kind(), e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteSize, ParsedWithSource, ReadOutcome, Timestamped};
+    use std::{fs::File, io, time::Duration};
+
+    #[test]
+    fn byte_size_display() {
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize::from_kib(1536).to_string(), "1.50 MiB");
+        assert_eq!(ByteSize(u64::MAX).to_string(), "16.00 EiB");
+        assert_eq!(format!("{:>10}", ByteSize(1024)), "  1.00 KiB");
+    }
+
+    #[test]
+    fn read_outcome() {
+        let missing = File::open("/proc/this_file_does_not_exist");
+        assert!(matches!(ReadOutcome::from(missing), ReadOutcome::NotFound));
+        let invalid: io::Result<()> = Err(io::Error::new(io::ErrorKind::InvalidData, "bad"));
+        assert!(matches!(
+            ReadOutcome::from(invalid),
+            ReadOutcome::ParseError(_)
+        ));
+        assert_eq!(
+            ReadOutcome::from(Ok::<_, io::Error>(3)).map(|v| v * 2).ok(),
+            Some(6)
+        );
+    }
+
+    #[test]
+    fn timestamped() {
+        let earlier = Timestamped::read(crate::uptime::Uptime::from_system).unwrap();
+        let later = Timestamped::now(());
+        assert!(later.taken_at >= earlier.taken_at);
+        assert_eq!(earlier.elapsed_since(&later), Duration::from_secs(0));
+        let up = earlier.map(|uptime| uptime.up);
+        assert_eq!(*up, up.value);
+        assert!(Timestamped::<()>::read(|| Err(io::ErrorKind::NotFound.into())).is_err());
+    }
+
+    #[test]
+    fn parsed_with_source() {
+        use crate::{loadavg::LoadAvg, uptime::Uptime};
+
+        let uptime = ParsedWithSource::read("/proc/uptime", Uptime::from_reader).unwrap();
+        assert_eq!(Uptime::from_reader(uptime.raw.as_bytes()).unwrap(), *uptime);
+
+        let raw = "0.50 0.40 garbage 1/100 1234\n";
+        let e = ParsedWithSource::from_reader(raw.as_bytes(), LoadAvg::from_reader).unwrap_err();
+        assert_eq!(e.error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.raw.as_deref(), Some(raw));
+        assert!(e.to_string().contains("garbage"));
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::InvalidData);
+
+        let e = ParsedWithSource::read("/proc/this_file_does_not_exist", LoadAvg::from_reader)
+            .unwrap_err();
+        assert_eq!(e.error.kind(), io::ErrorKind::NotFound);
+        assert!(e.raw.is_none());
+    }
+}
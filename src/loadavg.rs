@@ -0,0 +1,187 @@
+//! Bindings to `/proc/loadavg`.
+use std::fs::File;
+use std::time::Duration;
+use std::{fmt, io};
+
+use crate::{
+    stat::{Stat, StatOptions},
+    util, Error,
+};
+
+/// The load averages from `/proc/loadavg`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LoadAvg {
+    /// The number of runnable or uninterruptible tasks, averaged over 1 minute.
+    pub one: f64,
+    /// The number of runnable or uninterruptible tasks, averaged over 5 minutes.
+    pub five: f64,
+    /// The number of runnable or uninterruptible tasks, averaged over 15 minutes.
+    pub fifteen: f64,
+    /// The number of currently runnable scheduling entities (processes and threads).
+    pub running: u64,
+    /// The number of scheduling entities that currently exist.
+    pub total: u64,
+    /// The pid of the most recently created process.
+    pub last_pid: u32,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl LoadAvg {
+    const PATH: &'static str = "/proc/loadavg";
+    /// Parse the contents of `/proc/loadavg`.
+    pub fn from_system() -> io::Result<Self> {
+        LoadAvg::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/loadavg`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(Self::from_str)
+    }
+
+    fn from_str(input: &str) -> Result<LoadAvg, Error> {
+        let (input, one) = err_msg!(util::parse_f64(input), "1 minute load average")?;
+        let (input, five) = err_msg!(util::parse_f64(input), "5 minute load average")?;
+        let (input, fifteen) = err_msg!(util::parse_f64(input), "15 minute load average")?;
+        let (input, running) = err_msg!(util::parse_u64(input), "running")?;
+        let input = err_msg!(util::expect_bytes("/", input), "expected \"/\"")?;
+        let (input, total) = err_msg!(util::parse_u64(input), "total")?;
+        let (_input, last_pid) = err_msg!(util::parse_u64(input), "last pid")?;
+        Ok(LoadAvg {
+            one,
+            five,
+            fifteen,
+            running,
+            total,
+            last_pid: last_pid as u32,
+        })
+    }
+}
+
+impl fmt::Display for LoadAvg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "load average: {:.2}, {:.2}, {:.2} ({} running of {} tasks, last pid {})",
+            self.one, self.five, self.fifteen, self.running, self.total, self.last_pid
+        )
+    }
+}
+
+/// A snapshot of the scheduler saturation metrics: run queue length, blocked tasks, context
+/// switches and the load averages.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SchedulerPressure {
+    /// The number of processes running or ready to run (from `/proc/stat`).
+    pub procs_running: u64,
+    /// The number of processes blocked waiting for I/O (from `/proc/stat`).
+    pub procs_blocked: u64,
+    /// The number of context switches since boot (from `/proc/stat`).
+    pub context_switches: u64,
+    pub load: LoadAvg,
+}
+
+impl SchedulerPressure {
+    /// Read `/proc/stat` and `/proc/loadavg`.
+    pub fn from_system() -> io::Result<Self> {
+        let options = StatOptions::new()
+            .per_cpu(false)
+            .interrupts(false)
+            .softirqs(false);
+        let stat = Stat::parse_with(options)?;
+        Ok(SchedulerPressure::new(&stat, LoadAvg::from_system()?))
+    }
+
+    /// Combine an existing `Stat` and `LoadAvg`.
+    pub fn new(stat: &Stat, load: LoadAvg) -> Self {
+        SchedulerPressure {
+            procs_running: stat.procs_running,
+            procs_blocked: stat.procs_blocked,
+            context_switches: stat.context_switches,
+            load,
+        }
+    }
+
+    /// How things changed since `earlier`, which was taken `interval` before this snapshot.
+    pub fn delta(&self, earlier: &SchedulerPressure, interval: Duration) -> SchedulerPressureDelta {
+        let switches = self
+            .context_switches
+            .saturating_sub(earlier.context_switches);
+        let secs = interval.as_secs_f64();
+        SchedulerPressureDelta {
+            procs_running: self.procs_running,
+            procs_blocked: self.procs_blocked,
+            procs_running_change: self.procs_running as i64 - earlier.procs_running as i64,
+            procs_blocked_change: self.procs_blocked as i64 - earlier.procs_blocked as i64,
+            context_switches_per_sec: if secs > 0.0 {
+                switches as f64 / secs
+            } else {
+                0.0
+            },
+            load: self.load,
+        }
+    }
+}
+
+/// The trend in scheduler pressure between two snapshots.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SchedulerPressureDelta {
+    /// The number of runnable processes in the later snapshot.
+    pub procs_running: u64,
+    /// The number of blocked processes in the later snapshot.
+    pub procs_blocked: u64,
+    /// How much the number of runnable processes grew (or shrank) between snapshots.
+    pub procs_running_change: i64,
+    /// How much the number of blocked processes grew (or shrank) between snapshots.
+    pub procs_blocked_change: i64,
+    /// The context switch rate between the snapshots.
+    pub context_switches_per_sec: f64,
+    /// The load averages in the later snapshot.
+    pub load: LoadAvg,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadAvg, SchedulerPressure};
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_loadavg() {
+        let raw = "\
+0.20 0.18 0.12 1/80 11206
+";
+        let load = LoadAvg::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(load.one, 0.20);
+        assert_eq!(load.fifteen, 0.12);
+        assert_eq!((load.running, load.total), (1, 80));
+        assert_eq!(load.last_pid, 11206);
+    }
+
+    #[test]
+    fn pressure_delta() {
+        let load = LoadAvg::from_reader(io::Cursor::new("1.00 0.50 0.25 3/100 200\n")).unwrap();
+        let earlier = SchedulerPressure {
+            procs_running: 4,
+            procs_blocked: 0,
+            context_switches: 1000,
+            load,
+        };
+        let later = SchedulerPressure {
+            procs_running: 2,
+            procs_blocked: 3,
+            context_switches: 3000,
+            load,
+        };
+        let delta = later.delta(&earlier, Duration::from_secs(2));
+        assert_eq!(delta.procs_running_change, -2);
+        assert_eq!(delta.procs_blocked_change, 3);
+        assert_eq!(delta.context_switches_per_sec, 1000.0);
+    }
+}
@@ -0,0 +1,352 @@
+//! Bindings to `/proc/meminfo`.
+use std::collections::HashMap;
+use std::fs::File;
+use std::{fmt, io};
+
+use crate::{util, ByteSize, Error};
+
+/// System memory usage, from `/proc/meminfo`.
+///
+/// The set of fields depends on the kernel version and configuration, so they're kept in a map
+/// keyed by name, e.g. `"MemTotal"`. Accessors are provided for the common ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    inner: HashMap<String, MemValue>,
+    order: Vec<String>,
+}
+
+/// A value from `/proc/meminfo`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+pub enum MemValue {
+    /// An amount of memory, printed in kB.
+    Size(ByteSize),
+    /// A count without a unit, like `HugePages_Total`.
+    Count(u64),
+}
+
+impl MemValue {
+    /// The amount of memory, if this is a size.
+    pub fn size(self) -> Option<ByteSize> {
+        match self {
+            MemValue::Size(size) => Some(size),
+            MemValue::Count(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for MemValue {
+    /// Sizes are formatted with binary units, counts as plain numbers.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MemValue::Size(size) => size.fmt(f),
+            MemValue::Count(count) => count.fmt(f),
+        }
+    }
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl MemInfo {
+    const PATH: &'static str = "/proc/meminfo";
+    /// Parse the contents of `/proc/meminfo`.
+    pub fn from_system() -> io::Result<Self> {
+        MemInfo::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/meminfo`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = HashMap::new();
+        let mut order = Vec::new();
+        loop {
+            match reader.parse_line(Self::parse_line) {
+                Ok((name, value)) => {
+                    order.push(name.clone());
+                    if inner.insert(name, value).is_some() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "duplicate field in /proc/meminfo",
+                        ));
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(MemInfo { inner, order })
+    }
+
+    fn parse_line(input: &str) -> Result<(String, MemValue), Error> {
+        let (name, value) = err_msg!(util::parse_key_value(input), "expected \"name: value\"")?;
+        let value = match value.strip_suffix("kB") {
+            Some(kib) => {
+                let (_, kib) = err_msg!(util::parse_u64(kib), "size")?;
+                MemValue::Size(ByteSize::from_kib(kib))
+            }
+            None => {
+                let (_, count) = err_msg!(util::parse_u64(value), "count")?;
+                MemValue::Count(count)
+            }
+        };
+        Ok((name.to_owned(), value))
+    }
+
+    /// The field `name`, like `MemTotal`.
+    pub fn get(&self, name: &str) -> Option<MemValue> {
+        self.inner.get(name).copied()
+    }
+
+    /// The number of fields.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Iterate over the fields in the order the kernel listed them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, MemValue)> {
+        self.order
+            .iter()
+            .map(move |name| (name.as_str(), self.inner[name]))
+    }
+
+    /// The field `name` as an amount of memory, if it's present and is a size.
+    pub fn size(&self, name: &str) -> Option<ByteSize> {
+        self.get(name).and_then(MemValue::size)
+    }
+
+    /// Total usable memory (`MemTotal`).
+    pub fn total(&self) -> Option<ByteSize> {
+        self.size("MemTotal")
+    }
+
+    /// Memory not used for anything (`MemFree`).
+    pub fn free(&self) -> Option<ByteSize> {
+        self.size("MemFree")
+    }
+
+    /// An estimate of the memory available for new programs without swapping (`MemAvailable`,
+    /// since 3.14).
+    pub fn available(&self) -> Option<ByteSize> {
+        self.size("MemAvailable")
+    }
+
+    /// The figures `free` and dashboards show, derived consistently from the raw fields.
+    ///
+    /// `None` if a field it needs is missing. See `MemSummary` for how each is calculated.
+    pub fn summary(&self) -> Option<MemSummary> {
+        let total = self.total()?;
+        let free = self.free()?;
+        let kib = |name| self.size(name).map(ByteSize::kib).unwrap_or(0);
+        let buff_cache = ByteSize::from_kib(kib("Buffers") + kib("Cached") + kib("SReclaimable"));
+        // Before 3.14, estimate it the way `free` did then.
+        let available = self
+            .available()
+            .unwrap_or_else(|| ByteSize::from_kib(free.kib() + buff_cache.kib()));
+        let swap_total = self.size("SwapTotal")?;
+        let swap_free = self.size("SwapFree")?;
+        Some(MemSummary {
+            total,
+            used: ByteSize(total.bytes().saturating_sub(available.bytes())),
+            free,
+            buff_cache,
+            available,
+            swap_total,
+            swap_used: ByteSize(swap_total.bytes().saturating_sub(swap_free.bytes())),
+            committed: self.size("Committed_AS")?,
+            commit_limit: self.size("CommitLimit")?,
+        })
+    }
+}
+
+/// Memory and swap usage, with the derived figures `free -m` shows. From `MemInfo::summary`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MemSummary {
+    /// `MemTotal`.
+    pub total: ByteSize,
+    /// `total - available`, as `free` calculates it since procps-ng 4.0.
+    pub used: ByteSize,
+    /// `MemFree`.
+    pub free: ByteSize,
+    /// `Buffers + Cached + SReclaimable`, the `buff/cache` column of `free`.
+    pub buff_cache: ByteSize,
+    /// `MemAvailable`, or `free + buff_cache` on kernels before 3.14.
+    pub available: ByteSize,
+    /// `SwapTotal`.
+    pub swap_total: ByteSize,
+    /// `SwapTotal - SwapFree`.
+    pub swap_used: ByteSize,
+    /// The memory promised to processes, whether or not they've touched it (`Committed_AS`).
+    pub committed: ByteSize,
+    /// How much can be committed when overcommit is disabled (`CommitLimit`).
+    pub commit_limit: ByteSize,
+}
+
+impl MemSummary {
+    /// The proportion of memory used, from 0 to 1.
+    pub fn used_ratio(&self) -> f64 {
+        ratio(self.used, self.total).min(1.0)
+    }
+
+    /// The proportion of memory available, from 0 to 1.
+    pub fn available_ratio(&self) -> f64 {
+        ratio(self.available, self.total).min(1.0)
+    }
+
+    /// `committed` as a proportion of `commit_limit`.
+    ///
+    /// This can be over 1: `commit_limit` is only enforced when `vm.overcommit_memory` is 2.
+    /// Otherwise, a high ratio means the OOM killer may be needed if processes use what they
+    /// asked for.
+    pub fn commit_ratio(&self) -> f64 {
+        ratio(self.committed, self.commit_limit)
+    }
+
+    /// The proportion of swap used, from 0 to 1, or 0 without swap.
+    pub fn swap_ratio(&self) -> f64 {
+        ratio(self.swap_used, self.swap_total).min(1.0)
+    }
+}
+
+fn ratio(part: ByteSize, whole: ByteSize) -> f64 {
+    if whole.bytes() == 0 {
+        return 0.0;
+    }
+    part.bytes() as f64 / whole.bytes() as f64
+}
+
+impl fmt::Display for MemInfo {
+    /// One `name: value` line per field, in the order the kernel listed them.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self
+            .order
+            .iter()
+            .map(|name| name.len() + 1)
+            .max()
+            .unwrap_or(0);
+        for (idx, (field, value)) in self.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:<name$} {:>12}", format!("{}:", field), value)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Index<&str> for MemInfo {
+    type Output = MemValue;
+    /// The field `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no field called `name`.
+    fn index(&self, name: &str) -> &MemValue {
+        self.inner
+            .get(name)
+            .unwrap_or_else(|| panic!("no field named {:?}", name))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MemInfo {
+    /// Serializes as a map from field name to value, in kernel order. Sizes are in bytes.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemInfo, MemValue};
+    use crate::ByteSize;
+    use std::io;
+
+    #[test]
+    fn proc_meminfo() {
+        let raw = "\
+MemTotal:        6147400 kB
+MemFree:         1032256 kB
+MemAvailable:    5495180 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+        let meminfo = MemInfo::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(meminfo.total(), Some(ByteSize::from_kib(6147400)));
+        assert_eq!(meminfo.available(), Some(ByteSize::from_kib(5495180)));
+        assert_eq!(meminfo["HugePages_Total"], MemValue::Count(0));
+        assert_eq!(
+            meminfo.get("MemFree"),
+            Some(MemValue::Size(ByteSize::from_kib(1032256)))
+        );
+        assert_eq!(meminfo.get("Cached"), None);
+        assert_eq!(meminfo.len(), 5);
+        assert_eq!(meminfo.size("HugePages_Total"), None);
+        assert_eq!(
+            meminfo.iter().last(),
+            Some(("Hugepagesize", MemValue::Size(ByteSize(2048 * 1024))))
+        );
+        assert_eq!(
+            meminfo.to_string(),
+            "\
+MemTotal:            5.86 GiB
+MemFree:          1008.06 MiB
+MemAvailable:        5.24 GiB
+HugePages_Total:            0
+Hugepagesize:        2.00 MiB"
+        );
+        assert!(MemInfo::from_system().unwrap().total().is_some());
+    }
+
+    #[test]
+    fn summary() {
+        let raw = "\
+MemTotal:        8000000 kB
+MemFree:         1000000 kB
+MemAvailable:    6000000 kB
+Buffers:          200000 kB
+Cached:          3000000 kB
+SwapCached:            0 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+CommitLimit:     6000000 kB
+Committed_AS:    9000000 kB
+SReclaimable:     300000 kB
+";
+        let summary = MemInfo::from_reader(io::Cursor::new(raw))
+            .unwrap()
+            .summary()
+            .unwrap();
+        assert_eq!(summary.used, ByteSize::from_kib(2_000_000));
+        assert_eq!(summary.buff_cache, ByteSize::from_kib(3_500_000));
+        assert_eq!(summary.swap_used, ByteSize::from_kib(500_000));
+        assert_eq!(summary.used_ratio(), 0.25);
+        assert_eq!(summary.available_ratio(), 0.75);
+        assert_eq!(summary.commit_ratio(), 1.5);
+        assert_eq!(summary.swap_ratio(), 0.25);
+
+        // Without MemAvailable, and without swap.
+        let raw = raw
+            .replace("MemAvailable:    6000000 kB\n", "")
+            .replace("SwapTotal:       2000000 kB", "SwapTotal:             0 kB")
+            .replace("SwapFree:        1500000 kB", "SwapFree:              0 kB");
+        let summary = MemInfo::from_reader(io::Cursor::new(raw))
+            .unwrap()
+            .summary()
+            .unwrap();
+        assert_eq!(summary.available, ByteSize::from_kib(4_500_000));
+        assert_eq!(summary.swap_ratio(), 0.0);
+        assert!(MemInfo::default().summary().is_none());
+
+        let summary = MemInfo::from_system().unwrap().summary().unwrap();
+        assert!(summary.used <= summary.total);
+    }
+}
@@ -0,0 +1,460 @@
+//! Bindings to `/proc/[pid]/mountinfo`.
+//!
+//! `mountinfo` is a superset of `/proc/[pid]/mounts`: as well as the device, mount point, type
+//! and options it records which part of the source filesystem is mounted, which is what
+//! distinguishes bind mounts.
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use crate::{util, Error};
+
+/// The mounts visible to a process, from `/proc/[pid]/mountinfo`.
+pub struct Mounts {
+    inner: Vec<Mount>,
+}
+
+impl Mounts {
+    /// Parse the mounts visible to the current process, from `/proc/self/mountinfo`.
+    pub fn from_system() -> io::Result<Self> {
+        Mounts::from_reader(File::open("/proc/self/mountinfo")?)
+    }
+
+    /// Parse the mounts visible to process `pid`, which depend on its mount namespace.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        Mounts::from_reader(File::open(format!("/proc/{}/mountinfo", pid))?)
+    }
+
+    /// Parse `/proc/[pid]/mountinfo`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(Mount::from_str) {
+                Ok(mount) => inner.push(mount),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Mounts { inner })
+    }
+
+    /// The mount at `mount_point`.
+    ///
+    /// When several filesystems are mounted at the same place only the last is visible, so
+    /// that's the one returned.
+    pub fn get(&self, mount_point: &str) -> Option<&Mount> {
+        self.inner
+            .iter()
+            .rev()
+            .find(|mount| mount.mount_point.as_os_str() == mount_point)
+    }
+
+    /// The number of mounts.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no mounts.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The mount points, in the order the kernel listed them.
+    pub fn names(&self) -> impl Iterator<Item = &Path> {
+        self.inner.iter().map(|mount| mount.mount_point.as_path())
+    }
+
+    /// Iterate over the mounts in the order the kernel listed them, which is the order they were
+    /// mounted.
+    pub fn iter(&self) -> std::slice::Iter<'_, Mount> {
+        self.inner.iter()
+    }
+
+    /// Whether `mount` is a bind mount: whether an earlier mount of the same filesystem has a
+    /// root that covers `mount`'s root.
+    ///
+    /// The first mount of a filesystem counts as the original, so a filesystem mounted twice
+    /// looks like a bind mount the second time, which is how the kernel treats it too. Mounts of
+    /// disjoint subtrees, like btrfs subvolumes, aren't bind mounts.
+    pub fn is_bind(&self, mount: &Mount) -> bool {
+        self.inner
+            .iter()
+            .take_while(|earlier| earlier.mount_id != mount.mount_id)
+            .any(|earlier| {
+                (earlier.major, earlier.minor) == (mount.major, mount.minor)
+                    && mount.root.starts_with(&earlier.root)
+            })
+    }
+
+    /// The bind mounts, as decided by `is_bind`, in the order the kernel listed them.
+    pub fn bind_mounts(&self) -> impl Iterator<Item = &Mount> {
+        self.iter().filter(move |mount| self.is_bind(mount))
+    }
+
+    /// Compare with the mounts of another process (for example pid 1), matching mounts by mount
+    /// point, source and filesystem type.
+    ///
+    /// A process in its own mount namespace, like a container, will have mounts that the host
+    /// doesn't and vice versa.
+    pub fn compare<'a>(&'a self, other: &'a Mounts) -> MountComparison<'a> {
+        let same = |a: &Mount, b: &Mount| {
+            a.mount_point == b.mount_point && a.source == b.source && a.fs_type == b.fs_type
+        };
+        MountComparison {
+            only_here: self
+                .iter()
+                .filter(|mount| !other.iter().any(|o| same(mount, o)))
+                .collect(),
+            only_there: other
+                .iter()
+                .filter(|mount| !self.iter().any(|o| same(mount, o)))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Mounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = |header: &str, value: fn(&Mount) -> usize| {
+            self.iter().map(value).fold(header.len(), usize::max)
+        };
+        let point = width("mount point", |m| m.mount_point.as_os_str().len());
+        let fs_type = width("type", |m| m.fs_type.len());
+        let source = width("source", |m| m.source.len());
+        write!(
+            f,
+            "{:<point$} {:<fs_type$} {:<source$} options",
+            "mount point", "type", "source",
+        )?;
+        for mount in self {
+            write!(
+                f,
+                "\n{:<point$} {:<fs_type$} {:<source$} {}",
+                mount.mount_point.display().to_string(),
+                mount.fs_type,
+                mount.source,
+                mount.mount_options.join(","),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Index<&str> for Mounts {
+    type Output = Mount;
+    /// The mount at `mount_point`, as returned by `get`.
+    ///
+    /// # Panics
+    ///
+    /// If nothing is mounted at `mount_point`.
+    fn index(&self, mount_point: &str) -> &Mount {
+        self.get(mount_point)
+            .unwrap_or_else(|| panic!("nothing mounted at {:?}", mount_point))
+    }
+}
+
+impl std::ops::Index<usize> for Mounts {
+    type Output = Mount;
+    /// The `idx`th mount in the order the kernel listed them.
+    fn index(&self, idx: usize) -> &Mount {
+        &self.inner[idx]
+    }
+}
+
+impl IntoIterator for Mounts {
+    type IntoIter = std::vec::IntoIter<Mount>;
+    type Item = Mount;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Mounts {
+    type IntoIter = std::slice::Iter<'a, Mount>;
+    type Item = &'a Mount;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The result of `Mounts::compare`.
+#[derive(Debug, Clone)]
+pub struct MountComparison<'a> {
+    /// Mounts that only the first set has.
+    pub only_here: Vec<&'a Mount>,
+    /// Mounts that only the second set has.
+    pub only_there: Vec<&'a Mount>,
+}
+
+/// A mount, from a line of `/proc/[pid]/mountinfo`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mount {
+    /// A unique id for the mount.
+    pub mount_id: u64,
+    /// The id of the parent mount.
+    pub parent_id: u64,
+    /// The major number of the device holding the filesystem.
+    pub major: u32,
+    /// The minor number of the device holding the filesystem.
+    pub minor: u32,
+    /// The directory within the filesystem that forms the root of this mount.
+    pub root: PathBuf,
+    /// The mount point, relative to the process's root.
+    pub mount_point: PathBuf,
+    /// Per-mount options, e.g. `rw` or `noatime`.
+    pub mount_options: Vec<String>,
+    /// Optional tagged fields, e.g. `shared:1` or `master:2`.
+    pub optional_fields: Vec<String>,
+    /// The filesystem type, e.g. `ext4`, `tmpfs` or `overlay`.
+    pub fs_type: String,
+    /// Filesystem-specific source, e.g. `/dev/sda1`, or `none`.
+    pub source: String,
+    /// Per-superblock (filesystem-specific) options.
+    pub super_options: Vec<String>,
+}
+
+/// A rough classification of a mount.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MountKind {
+    /// An overlay filesystem, as used by most container runtimes.
+    Overlay,
+    /// An in-memory filesystem.
+    Tmpfs,
+    /// A mount whose root is a subdirectory (or file) of the filesystem, not its root.
+    ///
+    /// Bind mounts of subdirectories are like this, but so are btrfs subvolume mounts (roots like
+    /// `/@home`) and other filesystems mounted from a subdirectory by design. Use
+    /// `Mounts::is_bind` to tell bind mounts apart.
+    Subtree,
+    /// Anything else.
+    Other,
+}
+
+/// The layers of an overlay mount.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OverlayDirs {
+    /// The read-only lower layers, topmost first.
+    pub lower: Vec<PathBuf>,
+    /// The writable upper layer, if the mount is writable.
+    pub upper: Option<PathBuf>,
+    /// The overlay's scratch directory, if the mount is writable.
+    pub work: Option<PathBuf>,
+}
+
+impl Mount {
+    /// The `/dev` path of the device holding the filesystem, from `major:minor`.
+    ///
+    /// `None` for filesystems without a block device, like `proc` or `tmpfs`.
+    pub fn device_path(&self) -> io::Result<Option<PathBuf>> {
+        crate::device_name(self.major, self.minor)
+    }
+
+    /// Classify the mount.
+    pub fn kind(&self) -> MountKind {
+        match self.fs_type.as_str() {
+            "overlay" => MountKind::Overlay,
+            "tmpfs" => MountKind::Tmpfs,
+            _ if self.root.as_os_str() != "/" => MountKind::Subtree,
+            _ => MountKind::Other,
+        }
+    }
+
+    /// The layer directories, if this is an overlay mount.
+    pub fn overlay_dirs(&self) -> Option<OverlayDirs> {
+        if self.kind() != MountKind::Overlay {
+            return None;
+        }
+        let option = |name: &str| {
+            self.super_options
+                .iter()
+                .find_map(|opt| opt.strip_prefix(name)?.strip_prefix('='))
+        };
+        Some(OverlayDirs {
+            lower: option("lowerdir")
+                .map(|dirs| dirs.split(':').map(PathBuf::from).collect())
+                .unwrap_or_default(),
+            upper: option("upperdir").map(PathBuf::from),
+            work: option("workdir").map(PathBuf::from),
+        })
+    }
+
+    /// Whether the mount has the given per-mount option, e.g. `ro`.
+    pub fn has_option(&self, option: &str) -> bool {
+        self.mount_options.iter().any(|o| o == option)
+    }
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Undo the kernel's octal escaping of space, tab, newline and backslash (e.g. `\040`).
+pub(crate) fn unescape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(idx) = rest.find('\\') {
+        output.push_str(&rest[..idx]);
+        let escape = &rest[idx + 1..];
+        match escape
+            .get(..3)
+            .and_then(|oct| u8::from_str_radix(oct, 8).ok())
+        {
+            Some(byte) => {
+                output.push(char::from(byte));
+                rest = &escape[3..];
+            }
+            None => {
+                output.push('\\');
+                rest = escape;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn split_options(options: &str) -> Vec<String> {
+    options.split(',').map(unescape).collect()
+}
+
+impl Mount {
+    fn from_str(input: &str) -> Result<Mount, Error> {
+        let (input, mount_id) = err_msg!(util::parse_u64(input), "mount id")?;
+        let (input, parent_id) = err_msg!(util::parse_u64(input), "parent id")?;
+        let (input, major) = err_msg!(util::parse_u64(input), "major")?;
+        let input = err_msg!(util::expect_bytes(":", input), "expected \":\"")?;
+        let (input, minor) = err_msg!(util::parse_u64(input), "minor")?;
+        let (input, root) = err_msg!(util::parse_token(input), "root")?;
+        let (input, mount_point) = err_msg!(util::parse_token(input), "mount point")?;
+        let (mut input, mount_options) = err_msg!(util::parse_token(input), "mount options")?;
+        let mut optional_fields = Vec::new();
+        loop {
+            let (rest, field) = err_msg!(util::parse_token(input), "optional fields")?;
+            input = rest;
+            if field == "-" {
+                break;
+            }
+            optional_fields.push(field.to_owned());
+        }
+        let (input, fs_type) = err_msg!(util::parse_token(input), "filesystem type")?;
+        let (input, source) = err_msg!(util::parse_token(input), "mount source")?;
+        let super_options = util::parse_token(input)
+            .map(|(_, options)| split_options(options))
+            .unwrap_or_default();
+        Ok(Mount {
+            mount_id,
+            parent_id,
+            major: major as u32,
+            minor: minor as u32,
+            root: PathBuf::from(unescape(root)),
+            mount_point: PathBuf::from(unescape(mount_point)),
+            mount_options: split_options(mount_options),
+            optional_fields,
+            fs_type: fs_type.to_owned(),
+            source: unescape(source),
+            super_options,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MountKind, Mounts};
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    const HOST: &str = "\
+23 28 0:22 / /proc rw,relatime shared:13 - proc proc rw
+26 25 0:24 / /dev/shm rw,relatime - tmpfs tmpfs rw,size=6147400k
+28 1 254:0 / / rw,relatime shared:1 - ext4 /dev/vda rw
+40 28 254:0 /home/user/My\\040Files /mnt/files ro,relatime shared:1 - ext4 /dev/vda rw
+";
+
+    #[test]
+    fn proc_pid_mountinfo() {
+        let mounts = Mounts::from_reader(io::Cursor::new(HOST)).unwrap();
+        assert_eq!(mounts.len(), 4);
+        assert_eq!(mounts[0].optional_fields, vec!["shared:13"]);
+        assert_eq!(mounts[1].kind(), MountKind::Tmpfs);
+        assert_eq!(mounts[2].kind(), MountKind::Other);
+        assert_eq!((mounts[2].major, mounts[2].minor), (254, 0));
+        let bind = &mounts[3];
+        assert_eq!(bind.kind(), MountKind::Subtree);
+        assert!(mounts.is_bind(bind));
+        assert!(!mounts.is_bind(&mounts[2]));
+        assert_eq!(mounts.bind_mounts().count(), 1);
+        assert_eq!(bind.root, Path::new("/home/user/My Files"));
+        assert!(bind.has_option("ro"));
+        assert_eq!(mounts["/mnt/files"], *bind);
+        assert!(mounts.get("/mnt").is_none());
+        let names: Vec<_> = mounts.names().collect();
+        assert_eq!(names[..2], [Path::new("/proc"), Path::new("/dev/shm")]);
+        assert_eq!((&mounts).into_iter().count(), 4);
+    }
+
+    #[test]
+    fn display() {
+        let mounts = Mounts::from_reader(io::Cursor::new(HOST)).unwrap();
+        assert_eq!(
+            mounts.to_string(),
+            "\
+mount point type  source   options
+/proc       proc  proc     rw,relatime
+/dev/shm    tmpfs tmpfs    rw,relatime
+/           ext4  /dev/vda rw,relatime
+/mnt/files  ext4  /dev/vda ro,relatime"
+        );
+    }
+
+    #[test]
+    fn btrfs_subvolumes() {
+        let raw = "\
+29 1 0:26 /@ / rw,relatime shared:1 - btrfs /dev/nvme0n1p2 rw,subvol=/@
+31 29 0:26 /@home /home rw,relatime shared:2 - btrfs /dev/nvme0n1p2 rw,subvol=/@home
+45 31 0:26 /@home/user/src /srv/src rw,relatime shared:2 - btrfs /dev/nvme0n1p2 rw,subvol=/@home
+";
+        let mounts = Mounts::from_reader(io::Cursor::new(raw)).unwrap();
+        assert!(mounts
+            .iter()
+            .all(|mount| mount.kind() == MountKind::Subtree));
+        assert!(!mounts.is_bind(&mounts[0]));
+        assert!(!mounts.is_bind(&mounts[1]));
+        assert!(mounts.is_bind(&mounts[2]));
+    }
+
+    #[test]
+    fn overlay() {
+        let raw = "\
+612 530 0:52 / / rw,relatime - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/ABC:/var/lib/docker/overlay2/l/DEF,upperdir=/var/lib/docker/overlay2/123/diff,workdir=/var/lib/docker/overlay2/123/work
+";
+        let container = Mounts::from_reader(io::Cursor::new(raw)).unwrap();
+        let root = &container[0];
+        assert_eq!(root.kind(), MountKind::Overlay);
+        let dirs = root.overlay_dirs().unwrap();
+        assert_eq!(
+            dirs.lower,
+            vec![
+                PathBuf::from("/var/lib/docker/overlay2/l/ABC"),
+                PathBuf::from("/var/lib/docker/overlay2/l/DEF")
+            ]
+        );
+        assert_eq!(
+            dirs.upper.as_deref(),
+            Some(Path::new("/var/lib/docker/overlay2/123/diff"))
+        );
+
+        let host = Mounts::from_reader(io::Cursor::new(HOST)).unwrap();
+        let comparison = container.compare(&host);
+        assert_eq!(comparison.only_here.len(), 1);
+        assert_eq!(comparison.only_there.len(), 4);
+    }
+
+    #[test]
+    fn current_process() {
+        let mounts = Mounts::from_pid(std::process::id()).unwrap();
+        assert!(mounts.iter().any(|m| m.mount_point == Path::new("/proc")));
+    }
+}
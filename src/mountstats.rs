@@ -0,0 +1,477 @@
+//! Bindings to `/proc/[pid]/mountstats`, the per-mount statistics used by `nfsstat -m`,
+//! `mountstats` and `nfsiostat`.
+//!
+//! Every mount gets a `device` line, but only NFS mounts have statistics after it: the mount
+//! options, event counters, bytes transferred, RPC transport counters and, most usefully, the
+//! count, retransmits and latency of each NFS operation.
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{mounts::unescape, util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The statistics for the mounts visible to a process, from `/proc/[pid]/mountstats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountStats {
+    inner: Vec<MountStat>,
+}
+
+/// A mount, from a `device` line of `/proc/[pid]/mountstats` and the lines following it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MountStat {
+    /// The mounted device, like `server:/export`. `None` if the kernel printed `no device`.
+    pub device: Option<String>,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    /// The version of the statistics format, like `1.1`. Only NFS mounts have statistics.
+    pub statvers: Option<String>,
+    /// The statistics of an NFS mount.
+    pub nfs: Option<NfsMountStats>,
+}
+
+/// The statistics of an NFS mount.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NfsMountStats {
+    /// The mount options the client is using, including defaults, like `vers=4.2`.
+    pub options: Vec<String>,
+    /// The time since the filesystem was mounted.
+    pub age: Duration,
+    /// The event counters (`events:`), in the order listed in `NFS_EVENTS`. Older kernels have
+    /// fewer.
+    pub events: Vec<u64>,
+    pub bytes: NfsBytes,
+    /// The RPC program and version, like `100003/4`.
+    pub rpc_program: Option<String>,
+    /// The RPC transports (`xprt:`). There can be several with `nconnect`.
+    pub transports: Vec<NfsTransport>,
+    /// The per-operation statistics, in the order the kernel listed them.
+    pub operations: Vec<NfsOperation>,
+}
+
+/// The names of the counters in `NfsMountStats::events`.
+pub const NFS_EVENTS: [&str; 27] = [
+    "inoderevalidate",
+    "dentryrevalidate",
+    "datainvalidate",
+    "attrinvalidate",
+    "vfsopen",
+    "vfslookup",
+    "vfsaccess",
+    "vfsupdatepage",
+    "vfsreadpage",
+    "vfsreadpages",
+    "vfswritepage",
+    "vfswritepages",
+    "vfsgetdents",
+    "vfssetattr",
+    "vfsflush",
+    "vfsfsync",
+    "vfslock",
+    "vfsrelease",
+    "congestionwait",
+    "setattrtrunc",
+    "extendwrite",
+    "sillyrename",
+    "shortread",
+    "shortwrite",
+    "delay",
+    "pnfs_read",
+    "pnfs_write",
+];
+
+/// The bytes transferred on an NFS mount (`bytes:`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NfsBytes {
+    /// Bytes read by applications through the page cache.
+    pub normal_read: u64,
+    /// Bytes written by applications through the page cache.
+    pub normal_write: u64,
+    /// Bytes read by applications with `O_DIRECT`.
+    pub direct_read: u64,
+    /// Bytes written by applications with `O_DIRECT`.
+    pub direct_write: u64,
+    /// Bytes read from the server.
+    pub server_read: u64,
+    /// Bytes written to the server.
+    pub server_write: u64,
+    /// Pages read.
+    pub read_pages: u64,
+    /// Pages written.
+    pub write_pages: u64,
+}
+
+/// An RPC transport of an NFS mount (`xprt:`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NfsTransport {
+    /// The protocol, like `tcp`, `udp` or `rdma`.
+    pub protocol: String,
+    /// The counters, whose meaning depends on the protocol and kernel version. For `tcp` they
+    /// start with the local port, bind count, connect count, connect time, idle time, sends and
+    /// receives.
+    pub counters: Vec<u64>,
+}
+
+/// The statistics for one NFS operation, like `READ` or `GETATTR`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NfsOperation {
+    pub name: String,
+    /// The number of operations.
+    pub ops: u64,
+    /// The number of RPCs sent, including retransmissions.
+    pub transmissions: u64,
+    /// The number of times an RPC timed out (a "major" timeout).
+    pub major_timeouts: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// The total time RPCs waited to be sent.
+    pub queue_time: Duration,
+    /// The total round trip time, from sending to receiving the reply.
+    pub rtt: Duration,
+    /// The total time from the operation starting to it completing.
+    pub execute_time: Duration,
+    /// The number of operations that failed. Added in Linux 5.3.
+    pub errors: Option<u64>,
+}
+
+impl NfsOperation {
+    /// The number of times an RPC was sent again.
+    pub fn retransmissions(&self) -> u64 {
+        self.transmissions.saturating_sub(self.ops)
+    }
+
+    /// The mean round trip time, or zero if there were no operations.
+    pub fn average_rtt(&self) -> Duration {
+        average(self.rtt, self.ops)
+    }
+
+    /// The mean execute time, or zero if there were no operations.
+    pub fn average_execute_time(&self) -> Duration {
+        average(self.execute_time, self.ops)
+    }
+}
+
+fn average(total: Duration, count: u64) -> Duration {
+    match count {
+        0 => Duration::from_secs(0),
+        count => Duration::from_nanos((total.as_nanos() / u128::from(count)) as u64),
+    }
+}
+
+impl NfsMountStats {
+    /// The statistics for operation `name`, like `READ`.
+    pub fn operation(&self, name: &str) -> Option<&NfsOperation> {
+        self.operations.iter().find(|op| op.name == name)
+    }
+
+    /// The event counter called `name` (see `NFS_EVENTS`).
+    pub fn event(&self, name: &str) -> Option<u64> {
+        let idx = NFS_EVENTS.iter().position(|&event| event == name)?;
+        self.events.get(idx).copied()
+    }
+}
+
+impl MountStats {
+    /// Parse the statistics for the mounts visible to the current process, from
+    /// `/proc/self/mountstats`.
+    pub fn from_system() -> io::Result<Self> {
+        MountStats::from_reader(File::open("/proc/self/mountstats")?)
+    }
+
+    /// Parse the statistics for the mounts visible to process `pid`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        MountStats::from_reader(File::open(format!("/proc/{}/mountstats", pid))?)
+    }
+
+    /// Parse `/proc/[pid]/mountstats`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner: Vec<MountStat> = Vec::new();
+        let mut in_operations = false;
+        loop {
+            let line = match reader.parse_line(Line::from_str) {
+                Ok(line) => line,
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            };
+            if let Line::Device(mount) = line {
+                inner.push(*mount);
+                in_operations = false;
+                continue;
+            }
+            let nfs = match inner.last_mut().and_then(|mount| mount.nfs.as_mut()) {
+                Some(nfs) => nfs,
+                None if line == Line::Blank => continue,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "statistics for a mount without statvers in mountstats",
+                    ))
+                }
+            };
+            match line {
+                Line::Device(_) | Line::Blank => (),
+                Line::Rpc(program) => nfs.rpc_program = program,
+                Line::Operations => in_operations = true,
+                Line::Field(key, value) if in_operations => nfs
+                    .operations
+                    .push(NfsOperation::from_str(key, &value).map_err(invalid_data)?),
+                Line::Field(key, value) => nfs.apply(&key, &value).map_err(invalid_data)?,
+            }
+        }
+        Ok(MountStats { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, MountStat> {
+        self.inner.iter()
+    }
+
+    /// The number of mounts.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no mounts.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The NFS mounts, with their statistics.
+    pub fn nfs(&self) -> impl Iterator<Item = (&MountStat, &NfsMountStats)> {
+        self.iter()
+            .filter_map(|mount| mount.nfs.as_ref().map(|nfs| (mount, nfs)))
+    }
+}
+
+impl IntoIterator for MountStats {
+    type IntoIter = std::vec::IntoIter<MountStat>;
+    type Item = MountStat;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MountStats {
+    type IntoIter = std::slice::Iter<'a, MountStat>;
+    type Item = &'a MountStat;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn invalid_data(e: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A line of `/proc/[pid]/mountstats`.
+#[derive(Debug, PartialEq, Eq)]
+enum Line {
+    Device(Box<MountStat>),
+    /// `RPC iostats version: 1.1  p/v: 100003/4 (nfs)`, with the program and version.
+    Rpc(Option<String>),
+    /// `per-op statistics`, after which each field is an operation.
+    Operations,
+    /// A `key: value` line.
+    Field(String, String),
+    Blank,
+}
+
+impl Line {
+    fn from_str(input: &str) -> Result<Line, Error> {
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            Ok(Line::Blank)
+        } else if input.starts_with("device ") || input.starts_with("no device ") {
+            MountStat::from_str(input).map(|mount| Line::Device(Box::new(mount)))
+        } else if trimmed == "per-op statistics" {
+            Ok(Line::Operations)
+        } else if let Some(rest) = trimmed.strip_prefix("RPC iostats version:") {
+            let program = rest
+                .split_once("p/v:")
+                .and_then(|(_, program)| util::parse_token(program))
+                .map(|(_, program)| program.to_owned());
+            Ok(Line::Rpc(program))
+        } else {
+            let (key, value) = err_msg!(trimmed.split_once(':'), "expected key: value")?;
+            Ok(Line::Field(key.trim().to_owned(), value.trim().to_owned()))
+        }
+    }
+}
+
+impl MountStat {
+    fn from_str(input: &str) -> Result<MountStat, Error> {
+        let (input, device) = match util::expect_bytes("no device", input) {
+            Some(input) => (input, None),
+            None => {
+                let input = err_msg!(util::expect_bytes("device", input), "device")?;
+                let (input, device) = err_msg!(util::parse_token(input), "device")?;
+                (input, Some(unescape(device)))
+            }
+        };
+        let input = err_msg!(util::expect_bytes("mounted on", input), "mounted on")?;
+        let (input, mount_point) = err_msg!(util::parse_token(input), "mount point")?;
+        let input = err_msg!(util::expect_bytes("with fstype", input), "with fstype")?;
+        let (input, fs_type) = err_msg!(util::parse_token(input), "fstype")?;
+        let statvers = util::parse_token(input)
+            .and_then(|(_, token)| token.strip_prefix("statvers="))
+            .map(str::to_owned);
+        Ok(MountStat {
+            device,
+            mount_point: PathBuf::from(unescape(mount_point)),
+            fs_type: fs_type.to_owned(),
+            nfs: statvers.as_ref().map(|_| NfsMountStats::default()),
+            statvers,
+        })
+    }
+}
+
+/// Parse space-separated integers.
+fn parse_counters(input: &str) -> Result<Vec<u64>, Error> {
+    input
+        .split_whitespace()
+        .map(|counter| {
+            counter
+                .parse()
+                .map_err(|_| Error::from(format!("bad counter \"{}\"", counter)))
+        })
+        .collect()
+}
+
+impl NfsMountStats {
+    fn apply(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "opts" => self.options = value.split(',').map(str::to_owned).collect(),
+            "age" => {
+                let (_, age) = err_msg!(util::parse_u64(value), "age")?;
+                self.age = Duration::from_secs(age);
+            }
+            "events" => self.events = parse_counters(value)?,
+            "bytes" => {
+                let counters = parse_counters(value)?;
+                let counter = |idx: usize| err_msg!(counters.get(idx).copied(), "bytes");
+                self.bytes = NfsBytes {
+                    normal_read: counter(0)?,
+                    normal_write: counter(1)?,
+                    direct_read: counter(2)?,
+                    direct_write: counter(3)?,
+                    server_read: counter(4)?,
+                    server_write: counter(5)?,
+                    read_pages: counter(6)?,
+                    write_pages: counter(7)?,
+                };
+            }
+            "xprt" => {
+                let (counters, protocol) = err_msg!(util::parse_token(value), "xprt protocol")?;
+                self.transports.push(NfsTransport {
+                    protocol: protocol.to_owned(),
+                    counters: parse_counters(counters)?,
+                });
+            }
+            // Capabilities, security flavor, NFSv4 state and so on aren't statistics.
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+impl NfsOperation {
+    fn from_str(name: String, value: &str) -> Result<NfsOperation, Error> {
+        let counters = parse_counters(value)?;
+        if counters.len() < 8 {
+            return Err(format!("too few counters for operation {}", name).into());
+        }
+        Ok(NfsOperation {
+            name,
+            ops: counters[0],
+            transmissions: counters[1],
+            major_timeouts: counters[2],
+            bytes_sent: counters[3],
+            bytes_received: counters[4],
+            queue_time: Duration::from_millis(counters[5]),
+            rtt: Duration::from_millis(counters[6]),
+            execute_time: Duration::from_millis(counters[7]),
+            errors: counters.get(8).copied(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MountStats;
+    use std::io;
+    use std::path::Path;
+    use std::time::Duration;
+
+    const RAW: &str = "\
+device proc mounted on /proc with fstype proc
+device server:/export mounted on /mnt/my\\040share with fstype nfs4 statvers=1.1
+\topts:\trw,vers=4.2,rsize=1048576,wsize=1048576,namlen=255,acregmin=3,hard,proto=tcp,timeo=600
+\tage:\t3600
+\timpl_id:\tname='',domain='',date='0,0'
+\tcaps:\tcaps=0x3fff7,wtmult=512,dtsize=32768,bsize=0,namlen=255
+\tsec:\tflavor=1,pseudoflavor=1
+\tevents:\t13 208 0 3 5 101 21 0 0 0 0 0 7 0 0 0 0 5 0 0 0 0 0 0 0 0 0
+\tbytes:\t1036 20 0 0 1036 20 1 1
+\tRPC iostats version: 1.1  p/v: 100003/4 (nfs)
+\txprt:\ttcp 0 1 1 0 30 31 31 0 31 0 2 0 0
+\tper-op statistics
+\t        NULL: 1 1 0 44 24 0 0 0 0
+\t        READ: 10 12 1 1600 12000 5 250 260 0
+\t     GETATTR: 4 4 0 700 900 0 8 9
+
+no device mounted on /mnt/gone with fstype tmpfs
+";
+
+    #[test]
+    fn proc_pid_mountstats() {
+        let stats = MountStats::from_reader(io::Cursor::new(RAW)).unwrap();
+        assert_eq!(stats.len(), 3);
+        let (mount, nfs) = stats.nfs().next().unwrap();
+        assert_eq!(mount.device.as_deref(), Some("server:/export"));
+        assert_eq!(mount.mount_point, Path::new("/mnt/my share"));
+        assert_eq!(mount.statvers.as_deref(), Some("1.1"));
+        assert!(nfs.options.iter().any(|opt| opt == "vers=4.2"));
+        assert_eq!(nfs.age, Duration::from_secs(3600));
+        assert_eq!(nfs.event("vfsopen"), Some(5));
+        assert_eq!(nfs.bytes.server_read, 1036);
+        assert_eq!(nfs.rpc_program.as_deref(), Some("100003/4"));
+        assert_eq!(nfs.transports[0].protocol, "tcp");
+        assert_eq!(nfs.transports[0].counters.len(), 13);
+        assert_eq!(nfs.operations.len(), 3);
+        let read = nfs.operation("READ").unwrap();
+        assert_eq!(read.retransmissions(), 2);
+        assert_eq!(read.major_timeouts, 1);
+        assert_eq!(read.average_rtt(), Duration::from_millis(25));
+        assert_eq!(read.average_execute_time(), Duration::from_millis(26));
+        assert_eq!(read.errors, Some(0));
+        assert_eq!(nfs.operation("GETATTR").unwrap().errors, None);
+        let gone = stats.iter().nth(2).unwrap();
+        assert_eq!(gone.device, None);
+        assert!(gone.nfs.is_none());
+    }
+
+    #[test]
+    fn errors() {
+        for raw in &[
+            "device proc mounted on /proc\n",
+            "device proc mounted on /proc with fstype proc\n\tage:\t1\n",
+            "device a:/b mounted on /b with fstype nfs statvers=1.1\n\tper-op statistics\n\tREAD: 1 2\n",
+        ] {
+            assert!(MountStats::from_reader(io::Cursor::new(raw)).is_err());
+        }
+    }
+
+    #[test]
+    fn from_system() {
+        let stats = MountStats::from_system().unwrap();
+        assert!(stats
+            .iter()
+            .any(|mount| mount.mount_point == Path::new("/")));
+    }
+}
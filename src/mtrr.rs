@@ -0,0 +1,185 @@
+//! Bindings to `/proc/mtrr`, the x86 memory type range registers.
+//!
+//! The file only exists on x86. On other architectures `Mtrrs::from_system` fails with
+//! `io::ErrorKind::Unsupported` (`ReadOutcome::Unsupported`) rather than `NotFound`, so callers
+//! can tell "not on this machine" apart from "never on this kind of machine". Parsing captured
+//! data with `from_reader` works everywhere.
+use std::io;
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The caching behaviour the cpu uses for a range of physical memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MtrrType {
+    Uncachable,
+    WriteCombining,
+    WriteThrough,
+    WriteProtect,
+    WriteBack,
+}
+
+impl MtrrType {
+    fn from_str(input: &str) -> Result<MtrrType, Error> {
+        Ok(match input {
+            "uncachable" => MtrrType::Uncachable,
+            "write-combining" => MtrrType::WriteCombining,
+            "write-through" => MtrrType::WriteThrough,
+            "write-protect" => MtrrType::WriteProtect,
+            "write-back" => MtrrType::WriteBack,
+            other => return Err(format!("unknown mtrr type {:?}", other).into()),
+        })
+    }
+}
+
+/// The variable-range MTRRs in use, from `/proc/mtrr`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Mtrrs {
+    inner: Vec<MtrrRegister>,
+}
+
+/// A variable-range MTRR, from a line of `/proc/mtrr`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MtrrRegister {
+    /// The register number, e.g. 2 for `reg02`.
+    pub register: u32,
+    /// The physical address the range starts at.
+    pub base: u64,
+    /// The length of the range in bytes.
+    pub size: u64,
+    /// The number of times the range has been added, e.g. by different drivers.
+    pub count: u32,
+    pub kind: MtrrType,
+}
+
+impl Mtrrs {
+    const PATH: &'static str = "/proc/mtrr";
+
+    /// Parse the contents of `/proc/mtrr`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn from_system() -> io::Result<Self> {
+        Mtrrs::from_reader(std::fs::File::open(Self::PATH)?)
+    }
+
+    /// Parse the contents of `/proc/mtrr`.
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    pub fn from_system() -> io::Result<Self> {
+        Err(util::unsupported_arch(Self::PATH))
+    }
+
+    /// Parse `/proc/mtrr`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(MtrrRegister::from_str) {
+                Ok(register) => inner.push(register),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Mtrrs { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MtrrRegister> {
+        self.inner.iter()
+    }
+
+    /// The register covering physical address `address`, if any.
+    ///
+    /// Where ranges overlap the cpu uses the most restrictive type, but this returns the first.
+    pub fn lookup(&self, address: u64) -> Option<&MtrrRegister> {
+        self.iter()
+            .find(|reg| address >= reg.base && address - reg.base < reg.size)
+    }
+}
+
+impl std::ops::Deref for Mtrrs {
+    type Target = Vec<MtrrRegister>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for Mtrrs {
+    type IntoIter = std::vec::IntoIter<MtrrRegister>;
+    type Item = MtrrRegister;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl MtrrRegister {
+    // e.g. `reg01: base=0x080000000 ( 2048MB), size= 1024MB, count=1: write-back`
+    fn from_str(input: &str) -> Result<MtrrRegister, Error> {
+        let input = err_msg!(util::expect_bytes("reg", input), "expected \"reg\"")?;
+        let (input, register) = err_msg!(util::parse_u64(input), "register")?;
+        let input = err_msg!(util::expect_bytes(":", input), "expected \":\"")?;
+        let input = err_msg!(util::expect_bytes("base=0x", input), "expected \"base=0x\"")?;
+        let (input, base) = err_msg!(util::parse_hex_u64(input), "base")?;
+        // Skip the base in MB.
+        let idx = err_msg!(input.find("size="), "expected \"size=\"")?;
+        let (input, size) = err_msg!(util::parse_u64(&input[idx + 5..]), "size")?;
+        let (input, unit) = if let Some(input) = input.strip_prefix("MB") {
+            (input, 1 << 20)
+        } else if let Some(input) = input.strip_prefix("KB") {
+            (input, 1 << 10)
+        } else {
+            return Err("expected \"MB\" or \"KB\"".into());
+        };
+        let size = err_msg!(size.checked_mul(unit), "size overflows")?;
+        let input = err_msg!(util::expect_bytes(",", input), "expected \",\"")?;
+        let input = err_msg!(util::expect_bytes("count=", input), "expected \"count=\"")?;
+        let (input, count) = err_msg!(util::parse_u64(input), "count")?;
+        let input = err_msg!(util::expect_bytes(":", input), "expected \":\"")?;
+        Ok(MtrrRegister {
+            register: register as u32,
+            base,
+            size,
+            count: count as u32,
+            kind: MtrrType::from_str(input.trim())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MtrrType, Mtrrs};
+    use crate::ReadOutcome;
+    use std::io;
+
+    #[test]
+    fn proc_mtrr() {
+        let raw = "\
+reg00: base=0x000000000 (    0MB), size= 2048MB, count=1: write-back
+reg01: base=0x080000000 ( 2048MB), size= 1024MB, count=1: write-back
+reg02: base=0x0c0000000 ( 3072MB), size=  256MB, count=1: uncachable
+reg03: base=0x0000a0000 (    0MB), size=  128KB, count=2: write-combining
+";
+        let mtrrs = Mtrrs::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(mtrrs.len(), 4);
+        assert_eq!(mtrrs[1].base, 0x8000_0000);
+        assert_eq!(mtrrs[1].size, 1 << 30);
+        assert_eq!(mtrrs[2].kind, MtrrType::Uncachable);
+        assert_eq!(mtrrs[3].size, 128 * 1024);
+        assert_eq!(mtrrs[3].count, 2);
+        assert_eq!(mtrrs.lookup(0xc000_1000).unwrap().register, 2);
+        assert_eq!(mtrrs.lookup(0x1_0000_0000), None);
+
+        // Virtual machines often have no MTRRs, or no /proc/mtrr at all.
+        let outcome = ReadOutcome::from(Mtrrs::from_system());
+        if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+            assert!(matches!(
+                outcome,
+                ReadOutcome::Ok(_) | ReadOutcome::NotFound
+            ));
+        } else {
+            assert!(matches!(outcome, ReadOutcome::Unsupported));
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Bindings to `/proc/net/bonding`, the state of each bonded interface and its slaves.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{util, Error};
+
+/// Every bonded interface, keyed by name, from the files in `/proc/net/bonding`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bonds {
+    inner: HashMap<String, Bond>,
+    /// Bond names, sorted.
+    order: Vec<String>,
+}
+
+/// A bonded interface, from `/proc/net/bonding/[bond]`.
+///
+/// The file's layout depends on the bonding mode, so only the common values are typed.
+/// Everything before the first slave is available as text in `values`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bond {
+    /// The bonding mode, e.g. `fault-tolerance (active-backup)` or `IEEE 802.3ad Dynamic link
+    /// aggregation`.
+    pub mode: String,
+    /// The link status of the bond as a whole.
+    pub mii_status: Option<MiiStatus>,
+    /// The slave carrying traffic, in the active-backup, alb and tlb modes.
+    pub active_slave: Option<String>,
+    /// How often the slaves' links are checked, if MII monitoring is enabled.
+    pub mii_polling_interval: Option<Duration>,
+    pub slaves: Vec<BondSlave>,
+    /// Every `key: value` line describing the bond, with the value as text.
+    pub values: HashMap<String, String>,
+}
+
+/// An interface in a bond.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BondSlave {
+    /// The interface name.
+    pub interface: String,
+    pub mii_status: Option<MiiStatus>,
+    /// The link speed in Mbit/s, if known.
+    pub speed: Option<u32>,
+    /// The duplex mode, if known.
+    pub duplex: Option<Duplex>,
+    /// How many times the link has gone down since the slave was added.
+    pub link_failure_count: u64,
+    /// The slave's own MAC address, e.g. `52:54:00:12:34:56`.
+    pub permanent_hw_addr: Option<String>,
+    /// Every `key: value` line describing the slave, with the value as text.
+    pub values: HashMap<String, String>,
+}
+
+/// The state of a link, as seen by MII monitoring.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MiiStatus {
+    Up,
+    Down,
+    /// The link failed and is waiting out the down delay.
+    GoingDown,
+    /// The link recovered and is waiting out the up delay.
+    GoingBack,
+}
+
+/// A link's duplex mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Duplex {
+    Full,
+    Half,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl Bonds {
+    const PATH: &'static str = "/proc/net/bonding";
+    /// Parse every file in `/proc/net/bonding`.
+    ///
+    /// Empty if the bonding driver isn't loaded.
+    pub fn from_system() -> io::Result<Self> {
+        let entries = match fs::read_dir(Self::PATH) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Bonds::default()),
+            Err(e) => return Err(e),
+        };
+        let mut inner = HashMap::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match File::open(entry.path()) {
+                Ok(file) => {
+                    inner.insert(name, Bond::from_reader(file)?);
+                }
+                // The bond was deleted while we were reading.
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let mut order: Vec<_> = inner.keys().cloned().collect();
+        order.sort();
+        Ok(Bonds { inner, order })
+    }
+
+    /// The bond called `name`, like `bond0`.
+    pub fn get(&self, name: &str) -> Option<&Bond> {
+        self.inner.get(name)
+    }
+
+    /// The number of bonds.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no bonds.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Iterate over the bonds, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Bond)> {
+        self.order
+            .iter()
+            .map(move |name| (name.as_str(), &self.inner[name]))
+    }
+}
+
+impl IntoIterator for Bonds {
+    type IntoIter = std::vec::IntoIter<(String, Bond)>;
+    type Item = (String, Bond);
+    /// The bonds, sorted by name.
+    fn into_iter(mut self) -> Self::IntoIter {
+        let inner = &mut self.inner;
+        self.order
+            .into_iter()
+            .filter_map(|name| inner.remove(&name).map(|bond| (name, bond)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl Bond {
+    /// Parse `/proc/net/bonding/[name]`.
+    pub fn from_name(name: &str) -> io::Result<Self> {
+        Bond::from_reader(File::open(Path::new(Bonds::PATH).join(name))?)
+    }
+
+    /// Parse `/proc/net/bonding/[bond]`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut bond = Bond::default();
+        loop {
+            match reader.parse_line(|line| bond.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(bond)
+    }
+
+    /// The slaves whose link is up.
+    pub fn slaves_up(&self) -> impl Iterator<Item = &BondSlave> {
+        self.slaves
+            .iter()
+            .filter(|slave| slave.mii_status == Some(MiiStatus::Up))
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        // Blank lines separate sections, and the 802.3ad sections have headings without values.
+        let (key, value) = match util::parse_key_value(input) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+        let key = key.trim_end();
+        if key == "Slave Interface" {
+            self.slaves.push(BondSlave::new(value));
+            return Ok(());
+        }
+        if let Some(slave) = self.slaves.last_mut() {
+            return slave.parse_value(key, value);
+        }
+        match key {
+            "Bonding Mode" => self.mode = value.to_owned(),
+            "MII Status" => self.mii_status = Some(MiiStatus::from_str(value)?),
+            "Currently Active Slave" if value != "None" => {
+                self.active_slave = Some(value.to_owned())
+            }
+            "MII Polling Interval (ms)" => match err_msg!(util::parse_u64(value), key)?.1 {
+                0 => (),
+                millis => self.mii_polling_interval = Some(Duration::from_millis(millis)),
+            },
+            _ => (),
+        }
+        self.values.insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+}
+
+impl BondSlave {
+    fn new(interface: &str) -> BondSlave {
+        BondSlave {
+            interface: interface.to_owned(),
+            mii_status: None,
+            speed: None,
+            duplex: None,
+            link_failure_count: 0,
+            permanent_hw_addr: None,
+            values: HashMap::new(),
+        }
+    }
+
+    fn parse_value(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "MII Status" => self.mii_status = Some(MiiStatus::from_str(value)?),
+            // `1000 Mbps`, or `Unknown`.
+            "Speed" => self.speed = util::parse_u64(value).map(|(_, speed)| speed as u32),
+            "Duplex" => {
+                self.duplex = match value {
+                    "full" => Some(Duplex::Full),
+                    "half" => Some(Duplex::Half),
+                    _ => None,
+                }
+            }
+            "Link Failure Count" => {
+                self.link_failure_count = err_msg!(util::parse_u64(value), key)?.1
+            }
+            "Permanent HW addr" => self.permanent_hw_addr = Some(value.to_owned()),
+            _ => (),
+        }
+        self.values.insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+}
+
+impl MiiStatus {
+    fn from_str(input: &str) -> Result<MiiStatus, Error> {
+        match input {
+            "up" => Ok(MiiStatus::Up),
+            "down" => Ok(MiiStatus::Down),
+            "going down" => Ok(MiiStatus::GoingDown),
+            "going back" => Ok(MiiStatus::GoingBack),
+            other => Err(format!("unknown MII status {:?}", other).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bond, Bonds, Duplex, MiiStatus};
+    use std::{io, time::Duration};
+
+    #[test]
+    fn proc_net_bonding() {
+        let raw = "\
+Ethernet Channel Bonding Driver: v5.15.0
+
+Bonding Mode: fault-tolerance (active-backup)
+Primary Slave: None
+Currently Active Slave: eth0
+MII Status: up
+MII Polling Interval (ms): 100
+Up Delay (ms): 0
+Down Delay (ms): 0
+Peer Notification Delay (ms): 0
+
+Slave Interface: eth0
+MII Status: up
+Speed: 1000 Mbps
+Duplex: full
+Link Failure Count: 0
+Permanent HW addr: 52:54:00:12:34:56
+Slave queue ID: 0
+
+Slave Interface: eth1
+MII Status: down
+Speed: Unknown
+Duplex: Unknown
+Link Failure Count: 2
+Permanent HW addr: 52:54:00:12:34:57
+Slave queue ID: 0
+";
+        let bond = Bond::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(bond.mode, "fault-tolerance (active-backup)");
+        assert_eq!(bond.mii_status, Some(MiiStatus::Up));
+        assert_eq!(bond.active_slave.as_deref(), Some("eth0"));
+        assert_eq!(bond.mii_polling_interval, Some(Duration::from_millis(100)));
+        assert_eq!(bond.values["Primary Slave"], "None");
+        assert_eq!(bond.slaves.len(), 2);
+        let eth0 = &bond.slaves[0];
+        assert_eq!(eth0.speed, Some(1000));
+        assert_eq!(eth0.duplex, Some(Duplex::Full));
+        assert_eq!(eth0.permanent_hw_addr.as_deref(), Some("52:54:00:12:34:56"));
+        let eth1 = &bond.slaves[1];
+        assert_eq!(eth1.mii_status, Some(MiiStatus::Down));
+        assert_eq!(eth1.speed, None);
+        assert_eq!(eth1.duplex, None);
+        assert_eq!(eth1.link_failure_count, 2);
+        assert_eq!(eth1.values["Slave queue ID"], "0");
+        assert_eq!(bond.slaves_up().count(), 1);
+
+        let invalid = "MII Status: sideways\n";
+        let err = Bond::from_reader(io::Cursor::new(invalid)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bonds() {
+        let bonds = Bonds::from_system().unwrap();
+        assert_eq!(bonds.iter().count(), bonds.len());
+        for (name, bond) in bonds.iter() {
+            assert_eq!(bonds.get(name), Some(bond));
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Bindings to `/proc/net/nf_conntrack` and the `/proc/sys/net/netfilter` counters.
+use std::fs::File;
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::{util, Error};
+
+/// The connection tracking table, from `/proc/net/nf_conntrack`.
+///
+/// Reading the table requires root (`CAP_NET_ADMIN`), and it only exists when the
+/// `nf_conntrack` module is loaded.
+pub struct Conntrack {
+    inner: Vec<ConntrackEntry>,
+}
+
+impl Conntrack {
+    const PATH: &'static str = "/proc/net/nf_conntrack";
+    /// Parse the contents of `/proc/net/nf_conntrack`.
+    pub fn from_system() -> io::Result<Self> {
+        Conntrack::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/net/nf_conntrack`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(ConntrackEntry::from_str) {
+                Ok(entry) => inner.push(entry),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Conntrack { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConntrackEntry> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for Conntrack {
+    type Target = Vec<ConntrackEntry>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for Conntrack {
+    type IntoIter = std::vec::IntoIter<ConntrackEntry>;
+    type Item = ConntrackEntry;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// A tracked connection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConntrackEntry {
+    /// The network layer protocol, `ipv4` or `ipv6`.
+    pub l3_protocol: String,
+    /// The transport layer protocol, e.g. `tcp`, `udp` or `icmp`.
+    pub l4_protocol: String,
+    /// The transport layer protocol number, e.g. `6` for tcp.
+    pub l4_protocol_number: u8,
+    /// How long until the entry expires if no more packets are seen.
+    pub timeout: Duration,
+    /// The protocol state, for protocols that have one (e.g. `ESTABLISHED` for tcp).
+    pub state: Option<String>,
+    /// The tuple in the direction of the packet that created the connection.
+    pub original: ConntrackTuple,
+    /// The tuple expected for replies (after any NAT).
+    pub reply: ConntrackTuple,
+    /// Status flags, such as `ASSURED` or `UNREPLIED`.
+    pub flags: Vec<String>,
+    /// The connection mark, if the kernel was built with mark support.
+    pub mark: Option<u32>,
+    /// The conntrack zone, if not the default.
+    pub zone: Option<u16>,
+}
+
+impl ConntrackEntry {
+    /// Whether the entry has the given status flag, e.g. `ASSURED`.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Whether the connection is being NATed (the reply tuple isn't the original reversed).
+    pub fn is_nat(&self) -> bool {
+        self.original.src != self.reply.dst || self.original.dst != self.reply.src
+    }
+}
+
+/// The addresses (and ports, for protocols that have them) in one direction of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConntrackTuple {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub sport: Option<u16>,
+    pub dport: Option<u16>,
+    /// Packets seen in this direction, when accounting is enabled (`nf_conntrack_acct`).
+    pub packets: Option<u64>,
+    /// Bytes seen in this direction, when accounting is enabled (`nf_conntrack_acct`).
+    pub bytes: Option<u64>,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// A tuple being built up from `key=value` pairs.
+#[derive(Default)]
+struct PartialTuple {
+    src: Option<IpAddr>,
+    dst: Option<IpAddr>,
+    sport: Option<u16>,
+    dport: Option<u16>,
+    packets: Option<u64>,
+    bytes: Option<u64>,
+}
+
+impl PartialTuple {
+    fn finish(self) -> Result<ConntrackTuple, Error> {
+        Ok(ConntrackTuple {
+            src: err_msg!(self.src, "src")?,
+            dst: err_msg!(self.dst, "dst")?,
+            sport: self.sport,
+            dport: self.dport,
+            packets: self.packets,
+            bytes: self.bytes,
+        })
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str, msg: &str) -> Result<T, Error> {
+    value.parse().map_err(|_| Error::from(msg))
+}
+
+impl ConntrackEntry {
+    fn from_str(input: &str) -> Result<ConntrackEntry, Error> {
+        let (input, l3_protocol) = err_msg!(util::parse_token(input), "l3 protocol")?;
+        let (input, _l3_number) = err_msg!(util::parse_u64(input), "l3 protocol number")?;
+        let (input, l4_protocol) = err_msg!(util::parse_token(input), "l4 protocol")?;
+        let (input, l4_protocol_number) = err_msg!(util::parse_u64(input), "l4 protocol number")?;
+        let (mut input, timeout) = err_msg!(util::parse_u64(input), "timeout")?;
+
+        let mut state = None;
+        let mut original = PartialTuple::default();
+        let mut reply = PartialTuple::default();
+        let mut in_reply = false;
+        let mut flags = Vec::new();
+        let mut mark = None;
+        let mut zone = None;
+        while let Some((rest, token)) = util::parse_token(input) {
+            input = rest;
+            if let Some(flag) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+                flags.push(flag.to_owned());
+                continue;
+            }
+            let (key, value) = match token.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    // The only bare word is the protocol state, before the tuples.
+                    state = Some(token.to_owned());
+                    continue;
+                }
+            };
+            // The second `src=` starts the reply tuple.
+            if key == "src" && original.src.is_some() {
+                in_reply = true;
+            }
+            let tuple = if in_reply { &mut reply } else { &mut original };
+            match key {
+                "src" => tuple.src = Some(parse(value, "src")?),
+                "dst" => tuple.dst = Some(parse(value, "dst")?),
+                "sport" => tuple.sport = Some(parse(value, "sport")?),
+                "dport" => tuple.dport = Some(parse(value, "dport")?),
+                "packets" => tuple.packets = Some(parse(value, "packets")?),
+                "bytes" => tuple.bytes = Some(parse(value, "bytes")?),
+                "mark" => mark = Some(parse(value, "mark")?),
+                "zone" => zone = Some(parse(value, "zone")?),
+                // e.g. `use`, `secctx`, icmp `type`/`code`/`id`.
+                _ => (),
+            }
+        }
+        Ok(ConntrackEntry {
+            l3_protocol: l3_protocol.to_owned(),
+            l4_protocol: l4_protocol.to_owned(),
+            l4_protocol_number: l4_protocol_number as u8,
+            timeout: Duration::from_secs(timeout),
+            state,
+            original: original.finish()?,
+            reply: reply.finish()?,
+            flags,
+            mark,
+            zone,
+        })
+    }
+}
+
+/// The size of the connection tracking table, from `/proc/sys/net/netfilter`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ConntrackCount {
+    /// The number of entries in the table.
+    pub count: u64,
+    /// The maximum number of entries, after which new connections are dropped.
+    pub max: u64,
+}
+
+impl ConntrackCount {
+    const COUNT_PATH: &'static str = "/proc/sys/net/netfilter/nf_conntrack_count";
+    const MAX_PATH: &'static str = "/proc/sys/net/netfilter/nf_conntrack_max";
+
+    /// Read the table size counters. Unlike the table itself, these don't require root.
+    pub fn from_system() -> io::Result<Self> {
+        Ok(ConntrackCount {
+            count: util::read_value(Self::COUNT_PATH)?,
+            max: util::read_value(Self::MAX_PATH)?,
+        })
+    }
+
+    /// How full the table is, from 0 to 1.
+    pub fn utilization(&self) -> f64 {
+        if self.max == 0 {
+            return 0.0;
+        }
+        self.count as f64 / self.max as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Conntrack, ConntrackCount};
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_net_nf_conntrack() {
+        let raw = "\
+ipv4     2 tcp      6 431999 ESTABLISHED src=10.0.0.5 dst=93.184.216.34 sport=51234 dport=443 packets=12 bytes=2048 src=93.184.216.34 dst=203.0.113.7 sport=443 dport=51234 packets=10 bytes=8192 [ASSURED] mark=0 zone=0 use=2
+ipv4     2 udp      17 28 src=10.0.0.5 dst=8.8.8.8 sport=41000 dport=53 [UNREPLIED] src=8.8.8.8 dst=10.0.0.5 sport=53 dport=41000 mark=0 use=2
+ipv4     2 icmp     1 29 src=10.0.0.5 dst=1.1.1.1 type=8 code=0 id=1234 src=1.1.1.1 dst=10.0.0.5 type=0 code=0 id=1234 mark=0 use=2
+ipv6     10 tcp      6 117 TIME_WAIT src=2001:0db8:0000:0000:0000:0000:0000:0001 dst=2001:0db8:0000:0000:0000:0000:0000:0002 sport=40000 dport=22 src=2001:0db8:0000:0000:0000:0000:0000:0002 dst=2001:0db8:0000:0000:0000:0000:0000:0001 sport=22 dport=40000 [ASSURED] mark=0 use=2
+";
+        let table = Conntrack::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(table.len(), 4);
+        let tcp = &table[0];
+        assert_eq!(tcp.l4_protocol, "tcp");
+        assert_eq!(tcp.timeout, Duration::from_secs(431999));
+        assert_eq!(tcp.state.as_deref(), Some("ESTABLISHED"));
+        assert_eq!(tcp.original.dport, Some(443));
+        assert_eq!(tcp.reply.bytes, Some(8192));
+        assert!(tcp.has_flag("ASSURED"));
+        assert!(tcp.is_nat());
+        let udp = &table[1];
+        assert_eq!(udp.state, None);
+        assert!(udp.has_flag("UNREPLIED"));
+        assert!(!udp.is_nat());
+        assert_eq!(table[2].original.sport, None);
+        assert_eq!(table[3].l3_protocol, "ipv6");
+        assert_eq!(table[3].state.as_deref(), Some("TIME_WAIT"));
+    }
+
+    #[test]
+    fn utilization() {
+        let count = ConntrackCount {
+            count: 16384,
+            max: 65536,
+        };
+        assert_eq!(count.utilization(), 0.25);
+    }
+}
@@ -0,0 +1,425 @@
+//! Bindings to `/proc/net/dev`.
+use std::collections::HashMap;
+use std::fs::File;
+use std::time::Duration;
+use std::{fmt, io};
+
+use crate::{collection::Iter, util, ByteSize, Error};
+
+/// Network interface statistics, from `/proc/net/dev`.
+#[derive(Debug)]
+pub struct NetDev {
+    inner: HashMap<String, NetDevStat>,
+    /// Interface names in the order the kernel listed them.
+    order: Vec<String>,
+}
+
+impl NetDev {
+    const PATH: &'static str = "/proc/net/dev";
+    /// Parse the contents of `/proc/net/dev`.
+    pub fn from_system() -> io::Result<Self> {
+        NetDev::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/[pid]/net/dev`, the interfaces in the network namespace of process `pid`.
+    ///
+    /// Container monitors can use this to collect a container's interface counters by sampling
+    /// any process inside it.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        NetDev::from_reader(File::open(format!("/proc/{}/net/dev", pid))?)
+    }
+
+    /// Parse `/proc/net/dev`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // two header lines
+        reader.parse_line(util::parse_dummy)?;
+        reader.parse_line(util::parse_dummy)?;
+        let mut inner = HashMap::new();
+        let mut order = Vec::new();
+        loop {
+            match reader.parse_line(NetDevStat::from_str) {
+                Ok(stat) => {
+                    order.push(stat.name.clone());
+                    if inner.insert(stat.name.clone(), stat).is_some() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "duplicate interface name in /proc/net/dev",
+                        ));
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(NetDev { inner, order })
+    }
+
+    /// The counters for interface `name`, like `eth0`.
+    pub fn get(&self, name: &str) -> Option<&NetDevStat> {
+        self.inner.get(name)
+    }
+
+    /// The number of interfaces.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no interfaces.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// The interface names, in the order the kernel listed them.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+
+    /// Iterate over the interfaces in the order the kernel listed them.
+    pub fn iter(&self) -> Iter<'_, NetDevStat> {
+        Iter::new(&self.order, &self.inner)
+    }
+
+    /// The change in each interface's counters since `earlier`.
+    ///
+    /// Interfaces are paired up by name; those present in only one snapshot are reported in
+    /// `NetDevDelta::appeared` or `NetDevDelta::disappeared`. See `NetDevStatDelta` for how
+    /// counters that go backwards are handled.
+    pub fn diff(&self, earlier: &NetDev) -> NetDevDelta {
+        let mut interfaces = Vec::new();
+        let mut appeared = Vec::new();
+        for current in self.iter() {
+            match earlier.inner.get(&current.name) {
+                Some(prev) => interfaces.push(current.delta(prev)),
+                None => appeared.push(current.name.clone()),
+            }
+        }
+        let disappeared = earlier
+            .order
+            .iter()
+            .filter(|name| !self.inner.contains_key(*name))
+            .cloned()
+            .collect();
+        NetDevDelta {
+            interfaces,
+            appeared,
+            disappeared,
+        }
+    }
+}
+
+impl fmt::Display for NetDev {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self
+            .order
+            .iter()
+            .map(|name| name.len())
+            .fold("interface".len(), usize::max);
+        write!(
+            f,
+            "{:<name$} {:>11} {:>10} {:>7} {:>7} {:>11} {:>10} {:>7} {:>7}",
+            "interface",
+            "received",
+            "rx packets",
+            "rx errs",
+            "rx drop",
+            "sent",
+            "tx packets",
+            "tx errs",
+            "tx drop",
+        )?;
+        for stat in self {
+            write!(
+                f,
+                "\n{:<name$} {:>11} {:>10} {:>7} {:>7} {:>11} {:>10} {:>7} {:>7}",
+                stat.name,
+                ByteSize(stat.rx_bytes),
+                stat.rx_packets,
+                stat.rx_errs,
+                stat.rx_drop,
+                ByteSize(stat.tx_bytes),
+                stat.tx_packets,
+                stat.tx_errs,
+                stat.tx_drop,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Index<&str> for NetDev {
+    type Output = NetDevStat;
+    /// The counters for interface `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no interface called `name`.
+    fn index(&self, name: &str) -> &NetDevStat {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no interface named {:?}", name))
+    }
+}
+
+impl IntoIterator for NetDev {
+    type IntoIter = std::vec::IntoIter<NetDevStat>;
+    type Item = NetDevStat;
+    /// The interfaces in the order the kernel listed them.
+    fn into_iter(mut self) -> Self::IntoIter {
+        let inner = &mut self.inner;
+        self.order
+            .iter()
+            .filter_map(|name| inner.remove(name))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NetDev {
+    type IntoIter = Iter<'a, NetDevStat>;
+    type Item = &'a NetDevStat;
+    /// The interfaces in the order the kernel listed them.
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The counters for a single network interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NetDevStat {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errs: u64,
+    pub rx_drop: u64,
+    pub rx_fifo: u64,
+    pub rx_frame: u64,
+    pub rx_compressed: u64,
+    pub rx_multicast: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errs: u64,
+    pub tx_drop: u64,
+    pub tx_fifo: u64,
+    pub tx_colls: u64,
+    pub tx_carrier: u64,
+    pub tx_compressed: u64,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The difference between two readings of a counter.
+///
+/// Some older kernels and drivers keep 32-bit counters, which wrap after 4GiB. If the counter
+/// went backwards and both readings fit in 32 bits we assume it wrapped once. Otherwise the
+/// counter was reset (e.g. the driver was reloaded), and everything counted since is the later
+/// reading.
+fn counter_delta(later: u64, earlier: u64) -> u64 {
+    if later >= earlier {
+        later - earlier
+    } else if earlier <= u64::from(u32::MAX) {
+        later + (u64::from(u32::MAX) + 1 - earlier)
+    } else {
+        later
+    }
+}
+
+impl NetDevStat {
+    fn from_str(input: &str) -> Result<NetDevStat, Error> {
+        // Old kernels don't put a space between the colon and the first number.
+        let (name, input) = err_msg!(input.split_once(':'), "expected \":\"")?;
+        let name = name.trim().to_owned();
+        let (input, rx_bytes) = err_msg!(util::parse_u64(input), "rx bytes")?;
+        let (input, rx_packets) = err_msg!(util::parse_u64(input), "rx packets")?;
+        let (input, rx_errs) = err_msg!(util::parse_u64(input), "rx errs")?;
+        let (input, rx_drop) = err_msg!(util::parse_u64(input), "rx drop")?;
+        let (input, rx_fifo) = err_msg!(util::parse_u64(input), "rx fifo")?;
+        let (input, rx_frame) = err_msg!(util::parse_u64(input), "rx frame")?;
+        let (input, rx_compressed) = err_msg!(util::parse_u64(input), "rx compressed")?;
+        let (input, rx_multicast) = err_msg!(util::parse_u64(input), "rx multicast")?;
+        let (input, tx_bytes) = err_msg!(util::parse_u64(input), "tx bytes")?;
+        let (input, tx_packets) = err_msg!(util::parse_u64(input), "tx packets")?;
+        let (input, tx_errs) = err_msg!(util::parse_u64(input), "tx errs")?;
+        let (input, tx_drop) = err_msg!(util::parse_u64(input), "tx drop")?;
+        let (input, tx_fifo) = err_msg!(util::parse_u64(input), "tx fifo")?;
+        let (input, tx_colls) = err_msg!(util::parse_u64(input), "tx colls")?;
+        let (input, tx_carrier) = err_msg!(util::parse_u64(input), "tx carrier")?;
+        let (_input, tx_compressed) = err_msg!(util::parse_u64(input), "tx compressed")?;
+        Ok(NetDevStat {
+            name,
+            rx_bytes,
+            rx_packets,
+            rx_errs,
+            rx_drop,
+            rx_fifo,
+            rx_frame,
+            rx_compressed,
+            rx_multicast,
+            tx_bytes,
+            tx_packets,
+            tx_errs,
+            tx_drop,
+            tx_fifo,
+            tx_colls,
+            tx_carrier,
+            tx_compressed,
+        })
+    }
+
+    fn delta(&self, earlier: &NetDevStat) -> NetDevStatDelta {
+        NetDevStatDelta(NetDevStat {
+            name: self.name.clone(),
+            rx_bytes: counter_delta(self.rx_bytes, earlier.rx_bytes),
+            rx_packets: counter_delta(self.rx_packets, earlier.rx_packets),
+            rx_errs: counter_delta(self.rx_errs, earlier.rx_errs),
+            rx_drop: counter_delta(self.rx_drop, earlier.rx_drop),
+            rx_fifo: counter_delta(self.rx_fifo, earlier.rx_fifo),
+            rx_frame: counter_delta(self.rx_frame, earlier.rx_frame),
+            rx_compressed: counter_delta(self.rx_compressed, earlier.rx_compressed),
+            rx_multicast: counter_delta(self.rx_multicast, earlier.rx_multicast),
+            tx_bytes: counter_delta(self.tx_bytes, earlier.tx_bytes),
+            tx_packets: counter_delta(self.tx_packets, earlier.tx_packets),
+            tx_errs: counter_delta(self.tx_errs, earlier.tx_errs),
+            tx_drop: counter_delta(self.tx_drop, earlier.tx_drop),
+            tx_fifo: counter_delta(self.tx_fifo, earlier.tx_fifo),
+            tx_colls: counter_delta(self.tx_colls, earlier.tx_colls),
+            tx_carrier: counter_delta(self.tx_carrier, earlier.tx_carrier),
+            tx_compressed: counter_delta(self.tx_compressed, earlier.tx_compressed),
+        })
+    }
+}
+
+/// The changes in interface statistics between two snapshots, from `NetDev::diff`.
+#[derive(Debug, Clone)]
+pub struct NetDevDelta {
+    /// Deltas for the interfaces present in both snapshots, in kernel order.
+    pub interfaces: Vec<NetDevStatDelta>,
+    /// Interfaces that are only in the later snapshot.
+    pub appeared: Vec<String>,
+    /// Interfaces that are only in the earlier snapshot.
+    pub disappeared: Vec<String>,
+}
+
+impl NetDevDelta {
+    /// Get the delta for an interface by name.
+    pub fn get(&self, name: &str) -> Option<&NetDevStatDelta> {
+        self.interfaces.iter().find(|delta| delta.name == name)
+    }
+}
+
+/// The change in an interface's counters between two snapshots.
+///
+/// The fields are the same as `NetDevStat`, but hold the amount each counter increased by.
+/// Counters that went backwards are corrected for 32-bit wraparound or resets, so the deltas are
+/// never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetDevStatDelta(pub NetDevStat);
+
+impl NetDevStatDelta {
+    /// Received bytes per second over `interval`.
+    pub fn rx_bytes_per_sec(&self, interval: Duration) -> f64 {
+        per_sec(self.rx_bytes, interval)
+    }
+
+    /// Transmitted bytes per second over `interval`.
+    pub fn tx_bytes_per_sec(&self, interval: Duration) -> f64 {
+        per_sec(self.tx_bytes, interval)
+    }
+}
+
+fn per_sec(count: u64, interval: Duration) -> f64 {
+    if interval == Duration::from_secs(0) {
+        return 0.0;
+    }
+    count as f64 / interval.as_secs_f64()
+}
+
+impl std::ops::Deref for NetDevStatDelta {
+    type Target = NetDevStat;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{counter_delta, NetDev};
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_net_dev() {
+        let raw = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 15805131    3668    0    0    0     0          0         0 15805131    3668    0    0    0     0       0          0
+  eth0: 17410022    1292    0    0    0     0          0         0    96733    1171    0    0    0     0       0          0
+  eth1:4294967000 10 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+";
+        let dev = NetDev::from_reader(io::Cursor::new(raw)).unwrap();
+        let names: Vec<_> = dev.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["lo", "eth0", "eth1"]);
+        assert_eq!(dev["eth0"].rx_bytes, 17410022);
+        assert_eq!(dev["eth0"].tx_packets, 1171);
+        assert_eq!(dev["eth1"].rx_bytes, 4294967000);
+        assert_eq!(dev.names().collect::<Vec<_>>(), names);
+        assert_eq!(dev.len(), 3);
+        assert!(dev.get("eth2").is_none());
+        assert_eq!(
+            dev.to_string(),
+            "\
+interface    received rx packets rx errs rx drop        sent tx packets tx errs tx drop
+lo          15.07 MiB       3668       0       0   15.07 MiB       3668       0       0
+eth0        16.60 MiB       1292       0       0   94.47 KiB       1171       0       0
+eth1         4.00 GiB         10       0       0         0 B          0       0       0"
+        );
+        let owned: Vec<_> = dev.into_iter().map(|s| s.name).collect();
+        assert_eq!(owned, vec!["lo", "eth0", "eth1"]);
+    }
+
+    #[test]
+    fn from_pid() {
+        let dev = NetDev::from_pid(std::process::id()).unwrap();
+        let system = NetDev::from_system().unwrap();
+        // We're in the same namespace as ourselves.
+        assert_eq!(dev.order, system.order);
+    }
+
+    #[test]
+    fn diff() {
+        let earlier = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+  eth0: 4294967000 10 0 0 0 0 0 0 5000000000 10 0 0 0 0 0 0
+  eth1: 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+";
+        let later = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+  eth0: 704 12 0 0 0 0 0 0 1000 11 0 0 0 0 0 0
+  wg0: 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+";
+        let earlier = NetDev::from_reader(io::Cursor::new(earlier)).unwrap();
+        let later = NetDev::from_reader(io::Cursor::new(later)).unwrap();
+        let delta = later.diff(&earlier);
+        let eth0 = delta.get("eth0").unwrap();
+        // 32-bit wrap
+        assert_eq!(eth0.rx_bytes, 1000);
+        assert_eq!(eth0.rx_packets, 2);
+        // 64-bit counter reset
+        assert_eq!(eth0.tx_bytes, 1000);
+        assert_eq!(eth0.rx_bytes_per_sec(Duration::from_millis(500)), 2000.0);
+        assert_eq!(delta.appeared, vec!["wg0"]);
+        assert_eq!(delta.disappeared, vec!["eth1"]);
+    }
+
+    #[test]
+    fn counter_wrap() {
+        assert_eq!(counter_delta(10, 4), 6);
+        assert_eq!(counter_delta(5, u64::from(u32::MAX) - 4), 10);
+        assert_eq!(counter_delta(5, u64::from(u32::MAX) + 10), 5);
+    }
+}
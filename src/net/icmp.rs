@@ -0,0 +1,141 @@
+//! Bindings to `/proc/net/icmp` and `/proc/net/icmp6`, the open ICMP "ping" sockets.
+//!
+//! Ping sockets (`SOCK_DGRAM` with `IPPROTO_ICMP`) let unprivileged users send echo requests, if
+//! their group is in `net.ipv4.ping_group_range`. The tables list the echo identifier in place
+//! of the local port.
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use super::socket_table::{self, SocketRow};
+use super::tcp::TcpState;
+use crate::Error;
+
+/// The open ping sockets in the network namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IcmpSockets {
+    inner: Vec<IcmpSocket>,
+}
+
+/// An open ping socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IcmpSocket {
+    /// The local address, with the echo identifier as the port. The identifier is `0` until the
+    /// socket is bound or first sends.
+    pub local: SocketAddr,
+    /// The address the socket is `connect`ed to, or the unspecified address.
+    pub remote: SocketAddr,
+    pub state: TcpState,
+    /// The number of bytes waiting to be sent.
+    pub tx_queue: u64,
+    /// The number of bytes received but not yet read.
+    pub rx_queue: u64,
+    /// The user owning the socket.
+    pub uid: u32,
+    /// The socket's inode, matching the `socket:[inode]` links in `/proc/[pid]/fd`.
+    pub inode: u64,
+    /// The number of replies dropped, usually because the receive queue was full.
+    pub drops: u64,
+}
+
+impl IcmpSockets {
+    /// Parse the IPv4 and IPv6 sockets in `/proc/net/icmp` and `/proc/net/icmp6`.
+    ///
+    /// `icmp6` is missing when IPv6 is disabled, and then only the IPv4 sockets are returned.
+    pub fn from_system() -> io::Result<Self> {
+        let inner =
+            socket_table::read_tables(Path::new("/proc/net"), "icmp", IcmpSocket::from_str)?;
+        Ok(IcmpSockets { inner })
+    }
+
+    /// Parse the sockets in the network namespace of process `pid`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        let inner =
+            socket_table::read_tables(&socket_table::pid_dir(pid), "icmp", IcmpSocket::from_str)?;
+        Ok(IcmpSockets { inner })
+    }
+
+    /// Parse `/proc/net/icmp`- or `/proc/net/icmp6`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let inner = socket_table::parse_table(reader, IcmpSocket::from_str)?;
+        Ok(IcmpSockets { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, IcmpSocket> {
+        self.inner.iter()
+    }
+
+    /// The number of sockets.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no sockets.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl IntoIterator for IcmpSockets {
+    type IntoIter = std::vec::IntoIter<IcmpSocket>;
+    type Item = IcmpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IcmpSockets {
+    type IntoIter = std::slice::Iter<'a, IcmpSocket>;
+    type Item = &'a IcmpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IcmpSocket {
+    /// The echo identifier, which replies are matched on.
+    pub fn identifier(&self) -> u16 {
+        self.local.port()
+    }
+
+    fn from_str(input: &str) -> Result<IcmpSocket, Error> {
+        let (input, row) = SocketRow::from_str(input)?;
+        Ok(IcmpSocket {
+            local: row.local,
+            remote: row.remote,
+            state: row.state,
+            tx_queue: row.tx_queue,
+            rx_queue: row.rx_queue,
+            uid: row.uid,
+            inode: row.inode,
+            drops: socket_table::parse_drops(input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IcmpSockets;
+    use crate::net::tcp::TcpState;
+    use std::io;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn proc_net_icmp() {
+        let raw = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  250: 00000000:0C2E 00000000:0000 07 00000000:00000000 00:00000000 00000000  1000        0 91224 2 0000000000000000 0
+";
+        let sockets = IcmpSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sockets.len(), 1);
+        let ping = sockets.iter().next().unwrap();
+        assert_eq!(ping.identifier(), 0x0C2E);
+        assert_eq!(ping.local.ip(), Ipv4Addr::UNSPECIFIED);
+        assert_eq!(ping.state, TcpState::Close);
+        assert_eq!(ping.uid, 1000);
+    }
+
+    #[test]
+    fn icmp_sockets() {
+        IcmpSockets::from_system().unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+//! Bindings to `/proc/net/igmp` and `/proc/net/igmp6`, the multicast groups joined on each
+//! interface.
+//!
+//! These are useful for checking that discovery protocols like mDNS (`224.0.0.251`, `ff02::fb`)
+//! or SSDP (`239.255.255.250`) have actually joined their groups on the expected interfaces.
+use std::fs::File;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The multicast group memberships of every interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MulticastGroups {
+    inner: Vec<MulticastGroup>,
+}
+
+/// A multicast group joined on an interface.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MulticastGroup {
+    /// The interface index.
+    pub index: u32,
+    /// The interface name.
+    pub interface: String,
+    pub address: IpAddr,
+    /// The number of times the group was joined on this interface, by sockets and by the kernel
+    /// itself.
+    pub users: u32,
+}
+
+enum IgmpLine {
+    Interface(u32, String),
+    Group(IpAddr, u32),
+}
+
+impl MulticastGroups {
+    const IGMP_PATH: &'static str = "/proc/net/igmp";
+    const IGMP6_PATH: &'static str = "/proc/net/igmp6";
+
+    /// Parse the contents of `/proc/net/igmp` and `/proc/net/igmp6`.
+    ///
+    /// The IPv6 groups are left out if IPv6 is disabled.
+    pub fn from_system() -> io::Result<Self> {
+        let mut groups = MulticastGroups::from_igmp_reader(File::open(Self::IGMP_PATH)?)?;
+        match File::open(Self::IGMP6_PATH) {
+            Ok(file) => groups
+                .inner
+                .extend(MulticastGroups::from_igmp6_reader(file)?.inner),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+        Ok(groups)
+    }
+
+    /// Parse `/proc/net/igmp`-formatted data from `reader`.
+    pub fn from_igmp_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(util::parse_dummy)?;
+        let mut inner = Vec::new();
+        let mut interface = None;
+        loop {
+            match reader.parse_line(Self::parse_igmp_line) {
+                Ok(IgmpLine::Interface(index, name)) => interface = Some((index, name)),
+                Ok(IgmpLine::Group(address, users)) => {
+                    let (index, name) = interface.as_ref().ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "group before any interface")
+                    })?;
+                    inner.push(MulticastGroup {
+                        index: *index,
+                        interface: name.clone(),
+                        address,
+                        users,
+                    });
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(MulticastGroups { inner })
+    }
+
+    /// Parse `/proc/net/igmp6`-formatted data from `reader`.
+    pub fn from_igmp6_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(MulticastGroup::from_igmp6_str) {
+                Ok(group) => inner.push(group),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(MulticastGroups { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MulticastGroup> {
+        self.inner.iter()
+    }
+
+    /// The groups joined on the interface called `interface`.
+    pub fn on_interface<'a>(
+        &'a self,
+        interface: &'a str,
+    ) -> impl Iterator<Item = &'a MulticastGroup> + 'a {
+        self.iter()
+            .filter(move |group| group.interface == interface)
+    }
+
+    /// Whether `address` has been joined on the interface called `interface`.
+    pub fn is_member(&self, interface: &str, address: IpAddr) -> bool {
+        self.on_interface(interface)
+            .any(|group| group.address == address)
+    }
+
+    fn parse_igmp_line(input: &str) -> Result<IgmpLine, Error> {
+        if input.starts_with('\t') {
+            // `\t\t\t\t010000E0     1 0:00000000\t\t0`: the group, users, timer and reporter.
+            let (input, address) = err_msg!(util::parse_hex_ipv4(input), "group address")?;
+            let (_input, users) = err_msg!(util::parse_u64(input), "users")?;
+            Ok(IgmpLine::Group(IpAddr::V4(address), users as u32))
+        } else {
+            // `1\tlo        :     1      V3`: the index, name, group count and querier version.
+            // The name is padded to 10 characters, so longer names run straight into the `:`.
+            let (input, index) = err_msg!(util::parse_u64(input), "interface index")?;
+            let (name, _input) = err_msg!(input.split_once(':'), "interface name")?;
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(Error::from("interface name"));
+            }
+            Ok(IgmpLine::Interface(index as u32, name.to_owned()))
+        }
+    }
+}
+
+impl std::ops::Deref for MulticastGroups {
+    type Target = Vec<MulticastGroup>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for MulticastGroups {
+    type IntoIter = std::vec::IntoIter<MulticastGroup>;
+    type Item = MulticastGroup;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl MulticastGroup {
+    fn from_igmp6_str(input: &str) -> Result<MulticastGroup, Error> {
+        let (input, index) = err_msg!(util::parse_u64(input), "interface index")?;
+        let (input, interface) = err_msg!(util::parse_token(input), "interface name")?;
+        // Unlike the IPv4 file, the address is printed in network order.
+        let (input, octets) = err_msg!(util::parse_hex_bytes::<16>(input), "group address")?;
+        let (_input, users) = err_msg!(util::parse_u64(input), "users")?;
+        Ok(MulticastGroup {
+            index: index as u32,
+            interface: interface.to_owned(),
+            address: IpAddr::V6(Ipv6Addr::from(octets)),
+            users: users as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MulticastGroups;
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn proc_net_igmp() {
+        let group = |word: u32| format!("{:08X}", u32::from_ne_bytes(word.to_be_bytes()));
+        let raw = format!(
+            "\
+Idx\tDevice    : Count Querier\tGroup    Users Timer\tReporter
+1\tlo        :     1      V3
+\t\t\t\t{}     1 0:00000000\t\t0
+2\teth0      :     2      V2
+\t\t\t\t{}     2 0:00000000\t\t1
+\t\t\t\t{}     1 0:00000000\t\t0
+3\twlp0s20f3xx:     1      V3
+\t\t\t\t{}     1 0:00000000\t\t0
+",
+            group(0xe000_0001),
+            group(0xe000_00fb),
+            group(0xe000_0001),
+            group(0xe000_0001),
+        );
+        let groups = MulticastGroups::from_igmp_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(groups.len(), 4);
+        let mdns = IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251));
+        assert!(groups.is_member("eth0", mdns));
+        assert!(!groups.is_member("lo", mdns));
+        assert_eq!(groups.on_interface("eth0").next().unwrap().users, 2);
+        assert_eq!(groups[0].index, 1);
+        assert_eq!(groups[3].interface, "wlp0s20f3xx");
+        assert_eq!(groups[3].index, 3);
+
+        let orphan = "header\n\t\t\t\t010000E0     1 0:00000000\t\t0\n";
+        let err = MulticastGroups::from_igmp_reader(io::Cursor::new(orphan)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn proc_net_igmp6() {
+        let raw = "\
+1    lo              ff020000000000000000000000000001     1 0000000C 0
+4    eth0            ff0200000000000000000000000000fb     3 00000004 0
+";
+        let groups = MulticastGroups::from_igmp6_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(groups.len(), 2);
+        let mdns: Ipv6Addr = "ff02::fb".parse().unwrap();
+        assert!(groups.is_member("eth0", IpAddr::V6(mdns)));
+        assert_eq!(groups[1].users, 3);
+        assert_eq!(groups[1].index, 4);
+    }
+
+    #[test]
+    fn multicast_groups() {
+        MulticastGroups::from_system().unwrap();
+    }
+}
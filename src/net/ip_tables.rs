@@ -0,0 +1,118 @@
+//! Bindings to `/proc/net/{ip,ip6,arp}_tables_{names,matches,targets}`.
+//!
+//! These show which netfilter tables are in use, and which match and target extensions are
+//! loaded, without needing the `iptables` binary. The files only exist once the corresponding
+//! x_tables module is loaded, so `io::ErrorKind::NotFound` means the family isn't in use.
+//!
+//! The kernel doesn't expose per-table rule or packet counters under `/proc`; those are only
+//! available over netlink. nftables has no `/proc` interface at all.
+use std::fs::File;
+use std::io;
+
+use crate::util;
+
+/// The protocol family of a set of x_tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Family {
+    /// `iptables`
+    Ipv4,
+    /// `ip6tables`
+    Ipv6,
+    /// `arptables`
+    Arp,
+}
+
+impl Family {
+    fn prefix(self) -> &'static str {
+        match self {
+            Family::Ipv4 => "/proc/net/ip_tables",
+            Family::Ipv6 => "/proc/net/ip6_tables",
+            Family::Arp => "/proc/net/arp_tables",
+        }
+    }
+}
+
+/// The active tables and loaded extensions for a protocol family.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IpTables {
+    pub family: Family,
+    /// The tables that have been initialised, e.g. `filter`, `nat` or `mangle`.
+    pub tables: Vec<String>,
+    /// The match extensions that are loaded, e.g. `conntrack` or `multiport`.
+    pub matches: Vec<String>,
+    /// The target extensions that are loaded, e.g. `MASQUERADE` or `REJECT`.
+    pub targets: Vec<String>,
+}
+
+impl IpTables {
+    /// Read the tables and extensions for `family`.
+    pub fn from_system(family: Family) -> io::Result<Self> {
+        let read = |suffix: &str| {
+            let path = format!("{}_{}", family.prefix(), suffix);
+            parse_names(File::open(path)?)
+        };
+        Ok(IpTables {
+            family,
+            tables: read("names")?,
+            matches: read("matches")?,
+            targets: read("targets")?,
+        })
+    }
+
+    /// Whether the given table (e.g. `nat`) is active.
+    pub fn has_table(&self, table: &str) -> bool {
+        self.tables.iter().any(|t| t == table)
+    }
+}
+
+/// Parse a file with one name per line.
+///
+/// Extensions registered for several revisions are listed once per revision, so duplicates are
+/// removed (keeping the first occurrence).
+fn parse_names(reader: impl io::Read) -> io::Result<Vec<String>> {
+    let mut reader = util::LineParser::new(reader);
+    let mut names: Vec<String> = Vec::new();
+    loop {
+        match reader.parse_line(|line| Ok::<_, crate::Error>(line.trim().to_owned())) {
+            Ok(name) => {
+                if !name.is_empty() && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_names;
+    use std::io;
+
+    #[test]
+    fn proc_net_ip_tables_names() {
+        let raw = "\
+nat
+filter
+mangle
+";
+        let names = parse_names(io::Cursor::new(raw)).unwrap();
+        assert_eq!(names, vec!["nat", "filter", "mangle"]);
+    }
+
+    #[test]
+    fn proc_net_ip_tables_matches() {
+        let raw = "\
+conntrack
+conntrack
+conntrack
+addrtype
+addrtype
+icmp
+";
+        let names = parse_names(io::Cursor::new(raw)).unwrap();
+        assert_eq!(names, vec!["conntrack", "addrtype", "icmp"]);
+    }
+}
@@ -0,0 +1,18 @@
+//! Bindings to `/proc/net` and related networking counters.
+pub mod bonding;
+pub mod conntrack;
+pub mod dev;
+pub mod icmp;
+pub mod igmp;
+pub mod ip_tables;
+pub mod owners;
+pub mod packet;
+pub mod ports;
+pub mod raw;
+mod socket_table;
+pub mod tcp;
+pub mod udp;
+pub mod vlan;
+pub mod xfrm;
+
+pub use self::ports::{listening_ports, EphemeralPortUsage, ListeningPort, Protocol};
@@ -0,0 +1,124 @@
+//! Finding the processes that hold sockets open, like `ss -p` or `netstat -p`.
+use std::collections::HashMap;
+use std::io;
+
+use crate::process::{exited, FdTarget, Process};
+
+/// The processes holding each socket open, keyed by socket inode.
+///
+/// Look up the `inode` of an entry in one of the `/proc/net` socket tables to find its owners.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocketOwners {
+    inner: HashMap<u64, Vec<SocketOwner>>,
+}
+
+/// A process holding a socket open through one of its file descriptors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SocketOwner {
+    pub pid: u32,
+    pub fd: u32,
+    /// The process's command name.
+    pub comm: String,
+}
+
+/// Map every open socket to the processes holding it.
+///
+/// Only processes whose file descriptors we may read are included, so run as root to see every
+/// owner. Processes that exit while being scanned are skipped. A socket shared after a `fork`, or
+/// `dup`ed, has several owners.
+pub fn resolve_socket_owners() -> io::Result<SocketOwners> {
+    let mut inner: HashMap<u64, Vec<SocketOwner>> = HashMap::new();
+    for process in Process::all()? {
+        let process = process?;
+        let fds = match process.fds() {
+            Ok(fds) => fds,
+            Err(ref e) if exited(e) || e.kind() == io::ErrorKind::PermissionDenied => continue,
+            Err(e) => return Err(e),
+        };
+        let mut sockets = fds
+            .into_iter()
+            .filter_map(|fd| match fd.target {
+                FdTarget::Socket(inode) => Some((inode, fd.fd)),
+                _ => None,
+            })
+            .peekable();
+        if sockets.peek().is_none() {
+            continue;
+        }
+        let stat = match process.stat() {
+            Ok(stat) => stat,
+            Err(ref e) if exited(e) => continue,
+            Err(e) => return Err(e),
+        };
+        for (inode, fd) in sockets {
+            inner.entry(inode).or_default().push(SocketOwner {
+                pid: stat.pid,
+                fd,
+                comm: stat.comm.clone(),
+            });
+        }
+    }
+    Ok(SocketOwners { inner })
+}
+
+impl SocketOwners {
+    /// The owners of the socket with inode `inode`, empty if it isn't held by any process we can
+    /// see.
+    pub fn owners(&self, inode: u64) -> &[SocketOwner] {
+        self.get(inode).unwrap_or(&[])
+    }
+
+    /// The owners of the socket with inode `inode`, or `None` if it isn't held by any process we
+    /// can see.
+    pub fn get(&self, inode: u64) -> Option<&[SocketOwner]> {
+        self.inner.get(&inode).map(Vec::as_slice)
+    }
+
+    /// The number of sockets with owners.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether no sockets have owners.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over the sockets and their owners, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[SocketOwner])> {
+        self.inner
+            .iter()
+            .map(|(&inode, owners)| (inode, owners.as_slice()))
+    }
+}
+
+impl IntoIterator for SocketOwners {
+    type IntoIter = std::collections::hash_map::IntoIter<u64, Vec<SocketOwner>>;
+    type Item = (u64, Vec<SocketOwner>);
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_socket_owners;
+    use std::os::unix::{fs::MetadataExt, io::AsRawFd, net::UnixDatagram};
+
+    #[test]
+    fn socket_owners() {
+        let (socket, _other) = UnixDatagram::pair().unwrap();
+        let fd = socket.as_raw_fd();
+        let inode = std::fs::metadata(format!("/proc/self/fd/{}", fd))
+            .unwrap()
+            .ino();
+        let owners = resolve_socket_owners().unwrap();
+        let owner = &owners.owners(inode)[0];
+        assert_eq!(owner.pid, std::process::id());
+        assert_eq!(owner.fd, fd as u32);
+        assert_eq!(owners.owners(0), &[]);
+        assert!(owners.get(0).is_none());
+        assert!(owners.iter().any(|(socket, _)| socket == inode));
+        assert_eq!(owners.iter().count(), owners.len());
+    }
+}
@@ -0,0 +1,157 @@
+//! Bindings to `/proc/net/packet`, the open packet sockets (`AF_PACKET`).
+//!
+//! Packet sockets see traffic below the IP layer, so they are used by packet capture tools like
+//! `tcpdump`, and by DHCP clients. An unexpected packet socket receiving every protocol is a sign
+//! of a sniffer.
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The `ETH_P_ALL` protocol, for sockets receiving every protocol.
+pub const ETH_P_ALL: u16 = 0x0003;
+
+/// The open packet sockets in the network namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PacketSockets {
+    inner: Vec<PacketSocket>,
+}
+
+/// An open packet socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PacketSocket {
+    pub kind: PacketSocketKind,
+    /// The ethertype received, e.g. `0x0800` for IPv4 or `ETH_P_ALL`. `0` means the socket
+    /// doesn't receive anything until bound to a protocol.
+    pub protocol: u16,
+    /// The index of the interface the socket is bound to, or `0` for all interfaces.
+    pub interface_index: u32,
+    /// Whether the socket is hooked into the receive path.
+    pub running: bool,
+    /// The number of bytes waiting in the receive queue. Frames delivered through a mapped
+    /// `PACKET_RX_RING` aren't counted.
+    pub receive_queue: u64,
+    /// The user owning the socket.
+    pub uid: u32,
+    /// The socket's inode, matching the `socket:[inode]` links in `/proc/[pid]/fd`.
+    pub inode: u64,
+}
+
+/// The type of a packet socket.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PacketSocketKind {
+    /// `SOCK_RAW`: frames including the link-level header.
+    Raw,
+    /// `SOCK_DGRAM`: frames with the link-level header removed.
+    Datagram,
+    /// Any other type, e.g. the obsolete `SOCK_PACKET`.
+    Other(u32),
+}
+
+impl PacketSockets {
+    const PATH: &'static str = "/proc/net/packet";
+    /// Parse the contents of `/proc/net/packet`.
+    pub fn from_system() -> io::Result<Self> {
+        PacketSockets::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/net/packet`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut table = util::TableParser::new(reader)?;
+        let mut inner = Vec::new();
+        loop {
+            match table.parse_row(PacketSocket::from_row) {
+                Ok(socket) => inner.push(socket),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(PacketSockets { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PacketSocket> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for PacketSockets {
+    type Target = Vec<PacketSocket>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for PacketSockets {
+    type IntoIter = std::vec::IntoIter<PacketSocket>;
+    type Item = PacketSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl PacketSocket {
+    /// Whether the socket receives every protocol (`ETH_P_ALL`), as packet capture tools do.
+    pub fn receives_all_protocols(&self) -> bool {
+        self.protocol == ETH_P_ALL
+    }
+
+    fn from_row(row: &util::TableRow) -> Result<PacketSocket, Error> {
+        let column = |name: &'static str| err_msg!(row.get_by_name(name), name);
+        let number = |name: &'static str| -> Result<u64, Error> {
+            let (_, value) = err_msg!(util::parse_u64(column(name)?), name)?;
+            Ok(value)
+        };
+        let (_, protocol) = err_msg!(util::parse_hex_u64(column("Proto")?), "Proto")?;
+        Ok(PacketSocket {
+            kind: match number("Type")? {
+                2 => PacketSocketKind::Datagram,
+                3 => PacketSocketKind::Raw,
+                other => PacketSocketKind::Other(other as u32),
+            },
+            protocol: protocol as u16,
+            interface_index: number("Iface")? as u32,
+            running: number("R")? != 0,
+            receive_queue: number("Rmem")?,
+            uid: number("User")? as u32,
+            inode: number("Inode")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PacketSocketKind, PacketSockets};
+    use std::io;
+
+    #[test]
+    fn proc_net_packet() {
+        let raw = "\
+sk               RefCnt Type Proto  Iface R Rmem   User   Inode
+0000000000000000 3      3    0003   2     1 0      0      41234
+0000000000000000 3      2    0800   0     1 2304   1000   41876
+";
+        let sockets = PacketSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sockets.len(), 2);
+        let sniffer = &sockets[0];
+        assert_eq!(sniffer.kind, PacketSocketKind::Raw);
+        assert!(sniffer.receives_all_protocols());
+        assert_eq!(sniffer.interface_index, 2);
+        assert_eq!(sniffer.inode, 41234);
+        let dhcp = &sockets[1];
+        assert_eq!(dhcp.kind, PacketSocketKind::Datagram);
+        assert_eq!(dhcp.protocol, 0x0800);
+        assert_eq!(dhcp.receive_queue, 2304);
+        assert_eq!(dhcp.uid, 1000);
+    }
+
+    #[test]
+    fn packet_sockets() {
+        PacketSockets::from_system().unwrap();
+    }
+}
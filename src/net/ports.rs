@@ -0,0 +1,282 @@
+//! Which ports are open, and which processes have them open, like `ss -tulpn`.
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use super::{
+    icmp::IcmpSockets,
+    owners::resolve_socket_owners,
+    raw::RawSockets,
+    tcp::{TcpSockets, TcpState},
+    udp::UdpSockets,
+};
+use crate::{
+    sys::net::{ip_local_port_range, PortRange},
+    units::{RatioFormat, RatioUnit},
+};
+
+/// The transport protocol and IP version of a socket, named after the `/proc/net` table it is
+/// listed in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Raw,
+    Raw6,
+    Icmp,
+    Icmp6,
+}
+
+impl Protocol {
+    /// The table name, like `tcp6`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+            Protocol::Raw => "raw",
+            Protocol::Raw6 => "raw6",
+            Protocol::Icmp => "icmp",
+            Protocol::Icmp6 => "icmp6",
+        }
+    }
+
+    /// The IPv6 counterpart of an IPv4 protocol, if `address` is IPv6.
+    fn for_address(self, address: IpAddr) -> Self {
+        if !address.is_ipv6() {
+            return self;
+        }
+        match self {
+            Protocol::Tcp => Protocol::Tcp6,
+            Protocol::Udp => Protocol::Udp6,
+            Protocol::Raw => Protocol::Raw6,
+            Protocol::Icmp => Protocol::Icmp6,
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A socket waiting for connections, datagrams or packets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListeningPort {
+    pub protocol: Protocol,
+    /// The address bound to, unspecified (`0.0.0.0` or `::`) for all addresses.
+    pub address: IpAddr,
+    /// The port, or for raw sockets the IP protocol number, or for ICMP sockets the echo
+    /// identifier.
+    pub port: u16,
+    /// The user owning the socket.
+    pub uid: u32,
+    pub inode: u64,
+    /// The process holding the socket open, if we may see it. When several do, e.g. the workers
+    /// of a pre-forking server, this is the lowest pid.
+    pub pid: Option<u32>,
+    /// The command name of the process in `pid`.
+    pub comm: Option<String>,
+}
+
+/// The TCP sockets in `LISTEN`, the unconnected UDP sockets with a port, and every raw and ICMP
+/// socket, in the current network namespace, and the processes that own them.
+///
+/// Sorted by protocol, then port, then address. Owners are found by scanning every process's
+/// file descriptors (see `owners::resolve_socket_owners`), so run as root to see them all.
+pub fn listening_ports() -> io::Result<Vec<ListeningPort>> {
+    let owners = resolve_socket_owners()?;
+    let tcp = TcpSockets::from_system()?;
+    let udp = UdpSockets::from_system()?;
+    let raw = RawSockets::from_system()?;
+    let icmp = IcmpSockets::from_system()?;
+    let tcp = tcp
+        .listening_sockets()
+        .map(|socket| (Protocol::Tcp, socket.local, socket.uid, socket.inode));
+    let udp = udp
+        .unconnected()
+        .filter(|socket| socket.local.port() != 0)
+        .map(|socket| (Protocol::Udp, socket.local, socket.uid, socket.inode));
+    let raw = raw.iter().map(|socket| {
+        let local = SocketAddr::new(socket.local.ip(), socket.protocol.into());
+        (Protocol::Raw, local, socket.uid, socket.inode)
+    });
+    let icmp = icmp
+        .iter()
+        .map(|socket| (Protocol::Icmp, socket.local, socket.uid, socket.inode));
+    let mut ports: Vec<ListeningPort> = tcp
+        .chain(udp)
+        .chain(raw)
+        .chain(icmp)
+        .map(|(protocol, local, uid, inode)| {
+            let owner = owners.owners(inode).iter().min_by_key(|owner| owner.pid);
+            ListeningPort {
+                protocol: protocol.for_address(local.ip()),
+                address: local.ip(),
+                port: local.port(),
+                uid,
+                inode,
+                pid: owner.map(|owner| owner.pid),
+                comm: owner.map(|owner| owner.comm.clone()),
+            }
+        })
+        .collect();
+    ports.sort_by_key(|port| (port.protocol, port.port, port.address));
+    Ok(ports)
+}
+
+/// How much of the ephemeral port range (`net.ipv4.ip_local_port_range`) outgoing TCP
+/// connections are using.
+///
+/// A local port can be reused for connections to different destinations, so the range runs out
+/// one destination at a time: when every port has a connection, or a `TIME_WAIT` socket, to the
+/// same address and port, `connect` to it fails with `EADDRNOTAVAIL`. `utilization` measures
+/// the busiest destination for that reason. Sockets whose local port is in the range but which
+/// were explicitly bound there are counted too, as the kernel can't use those ports either.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EphemeralPortUsage {
+    pub range: PortRange,
+    /// Connections from a port in the range that are open.
+    pub established: u32,
+    /// Connections from a port in the range that are closed, but whose port is held for
+    /// `TIME_WAIT` (60 seconds).
+    pub time_wait: u32,
+    /// Connections from a port in the range in any other state, e.g. opening or closing.
+    pub other: u32,
+    /// The destination with the most connections from ports in the range, and how many.
+    pub busiest: Option<(SocketAddr, u32)>,
+}
+
+impl EphemeralPortUsage {
+    /// Read the port range from `/proc/sys` and the connections from `/proc/net/tcp` and
+    /// `/proc/net/tcp6`.
+    pub fn from_system() -> io::Result<Self> {
+        Ok(EphemeralPortUsage::from_sockets(
+            ip_local_port_range()?,
+            &TcpSockets::from_system()?,
+        ))
+    }
+
+    /// Count the connections in `sockets` from ports in `range`.
+    pub fn from_sockets(range: PortRange, sockets: &TcpSockets) -> Self {
+        let mut usage = EphemeralPortUsage {
+            range,
+            established: 0,
+            time_wait: 0,
+            other: 0,
+            busiest: None,
+        };
+        let mut destinations: HashMap<SocketAddr, u32> = HashMap::new();
+        for socket in sockets {
+            if socket.state == TcpState::Listen || !range.contains(socket.local.port()) {
+                continue;
+            }
+            match socket.state {
+                TcpState::Established => usage.established += 1,
+                TcpState::TimeWait => usage.time_wait += 1,
+                _ => usage.other += 1,
+            }
+            *destinations.entry(socket.remote).or_default() += 1;
+        }
+        // Ties go to the lowest address, so the result doesn't depend on hash order.
+        usage.busiest = destinations
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)));
+        usage
+    }
+
+    /// The number of connections from ports in the range.
+    pub fn in_use(&self) -> u32 {
+        self.established + self.time_wait + self.other
+    }
+
+    /// The proportion of the range used by connections to the busiest destination, from 0 to 1.
+    ///
+    /// At 1, new connections to that destination fail.
+    pub fn utilization(&self) -> f64 {
+        match (self.busiest, self.range.len()) {
+            (Some((_, count)), len) if len > 0 => (f64::from(count) / f64::from(len)).min(1.0),
+            _ => 0.0,
+        }
+    }
+
+    /// `utilization`, expressed in `format`.
+    pub fn utilization_in(&self, format: RatioFormat) -> f64 {
+        format.apply(self.utilization())
+    }
+
+    /// `utilization` in thousandths of `unit` (see `RatioUnit::milli`).
+    pub fn utilization_milli(&self, unit: RatioUnit) -> u64 {
+        unit.milli(self.utilization())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{listening_ports, EphemeralPortUsage, Protocol};
+    use crate::{net::tcp::TcpSockets, sys::net::PortRange, units::RatioUnit};
+    use std::io;
+    use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+
+    #[test]
+    fn ports() {
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let ports = listening_ports().unwrap();
+        for (protocol, address) in &[
+            (Protocol::Tcp, tcp.local_addr().unwrap()),
+            (Protocol::Udp, udp.local_addr().unwrap()),
+        ] {
+            let port = ports
+                .iter()
+                .find(|port| port.protocol == *protocol && port.port == address.port())
+                .unwrap();
+            assert_eq!(port.address, address.ip());
+            assert_eq!(port.pid, Some(std::process::id()));
+            assert!(port.comm.is_some());
+        }
+        assert_eq!(Protocol::Udp6.to_string(), "udp6");
+    }
+
+    #[test]
+    fn ephemeral_port_usage() {
+        if !cfg!(target_endian = "little") {
+            return;
+        }
+        let raw = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:9C40 00000000:0000 0A 00000000:00000080 00:00000000 00000000     0        0 1 1 0
+   1: 0F02000A:9C41 0A00000A:0CEA 01 00000000:00000000 00:00000000 00000000     0        0 2 1 0
+   2: 0F02000A:9C42 0A00000A:0CEA 06 00000000:00000000 03:00000F9E 00000000     0        0 0 3 0
+   3: 0F02000A:9C43 0B00000A:01BB 01 00000000:00000000 00:00000000 00000000     0        0 3 1 0
+   4: 0F02000A:0016 0202000A:D3A4 01 00000000:00000000 00:00000000 00000000     0        0 4 1 0
+";
+        let sockets = TcpSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        let range = PortRange {
+            low: 40000,
+            high: 40003,
+        };
+        let usage = EphemeralPortUsage::from_sockets(range, &sockets);
+        // The listening socket and the ssh connection aren't from the range.
+        assert_eq!((usage.established, usage.time_wait, usage.other), (2, 1, 0));
+        assert_eq!(usage.in_use(), 3);
+        let mysql = SocketAddr::from(([10, 0, 0, 10], 3306));
+        assert_eq!(usage.busiest, Some((mysql, 2)));
+        assert_eq!(usage.utilization(), 0.5);
+        assert_eq!(usage.utilization_milli(RatioUnit::Percent), 50_000);
+    }
+
+    #[test]
+    fn ephemeral_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let usage = EphemeralPortUsage::from_system().unwrap();
+        assert!(usage.in_use() >= 1);
+        assert!(usage.utilization() > 0.0 && usage.utilization() <= 1.0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Bindings to `/proc/net/raw` and `/proc/net/raw6`, the open raw IP sockets (`SOCK_RAW`).
+//!
+//! Raw sockets send and receive whole IP packets for one protocol, which the tables list in
+//! place of the local port. They need `CAP_NET_RAW`, so they are rarer than packet sockets, and
+//! mostly used by routing daemons and older `ping`s.
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use super::socket_table::{self, SocketRow};
+use super::tcp::TcpState;
+use crate::Error;
+
+/// The open raw sockets in the network namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RawSockets {
+    inner: Vec<RawSocket>,
+}
+
+/// An open raw socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawSocket {
+    /// The local address, with the port always `0`: see `protocol`.
+    pub local: SocketAddr,
+    /// The address the socket is `connect`ed to, or the unspecified address.
+    pub remote: SocketAddr,
+    /// The IP protocol number the socket was opened with, e.g. `1` for ICMP or `255` for
+    /// `IPPROTO_RAW` (send only).
+    pub protocol: u8,
+    pub state: TcpState,
+    /// The number of bytes waiting to be sent.
+    pub tx_queue: u64,
+    /// The number of bytes received but not yet read.
+    pub rx_queue: u64,
+    /// The user owning the socket.
+    pub uid: u32,
+    /// The socket's inode, matching the `socket:[inode]` links in `/proc/[pid]/fd`.
+    pub inode: u64,
+    /// The number of packets dropped, usually because the receive queue was full.
+    pub drops: u64,
+}
+
+impl RawSockets {
+    /// Parse the IPv4 and IPv6 sockets in `/proc/net/raw` and `/proc/net/raw6`.
+    ///
+    /// `raw6` is missing when IPv6 is disabled, and then only the IPv4 sockets are returned.
+    pub fn from_system() -> io::Result<Self> {
+        let inner = socket_table::read_tables(Path::new("/proc/net"), "raw", RawSocket::from_str)?;
+        Ok(RawSockets { inner })
+    }
+
+    /// Parse the sockets in the network namespace of process `pid`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        let inner =
+            socket_table::read_tables(&socket_table::pid_dir(pid), "raw", RawSocket::from_str)?;
+        Ok(RawSockets { inner })
+    }
+
+    /// Parse `/proc/net/raw`- or `/proc/net/raw6`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let inner = socket_table::parse_table(reader, RawSocket::from_str)?;
+        Ok(RawSockets { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, RawSocket> {
+        self.inner.iter()
+    }
+
+    /// The number of sockets.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no sockets.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The sockets for IP protocol `protocol`.
+    pub fn for_protocol(&self, protocol: u8) -> impl Iterator<Item = &RawSocket> {
+        self.iter()
+            .filter(move |socket| socket.protocol == protocol)
+    }
+}
+
+impl IntoIterator for RawSockets {
+    type IntoIter = std::vec::IntoIter<RawSocket>;
+    type Item = RawSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a RawSockets {
+    type IntoIter = std::slice::Iter<'a, RawSocket>;
+    type Item = &'a RawSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl RawSocket {
+    fn from_str(input: &str) -> Result<RawSocket, Error> {
+        let (input, row) = SocketRow::from_str(input)?;
+        let mut local = row.local;
+        let protocol = local.port() as u8;
+        local.set_port(0);
+        Ok(RawSocket {
+            local,
+            remote: row.remote,
+            protocol,
+            state: row.state,
+            tx_queue: row.tx_queue,
+            rx_queue: row.rx_queue,
+            uid: row.uid,
+            inode: row.inode,
+            drops: socket_table::parse_drops(input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawSockets;
+    use std::io;
+    use std::net::{Ipv6Addr, SocketAddr};
+
+    #[test]
+    fn proc_net_raw() {
+        let raw = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+   58: 00000000000000000000000000000000:003A 00000000000000000000000000000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 30412 2 0000000000000000 3
+";
+        let sockets = RawSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sockets.len(), 1);
+        let icmpv6 = sockets.for_protocol(58).next().unwrap();
+        assert_eq!(icmpv6.local, SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)));
+        assert_eq!(icmpv6.inode, 30412);
+        assert_eq!(icmpv6.drops, 3);
+        assert_eq!(sockets.for_protocol(1).count(), 0);
+    }
+
+    #[test]
+    fn raw_sockets() {
+        RawSockets::from_system().unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+//! The row layout shared by the `/proc/net` socket tables: `tcp`, `udp`, `raw` and `icmp`, and
+//! their IPv6 counterparts.
+use std::fs::File;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use super::tcp::TcpState;
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The columns every socket table starts with, up to the inode.
+pub(super) struct SocketRow {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: TcpState,
+    pub tx_queue: u64,
+    pub rx_queue: u64,
+    pub retransmits: u64,
+    pub uid: u32,
+    pub timeouts: u64,
+    pub inode: u64,
+}
+
+impl SocketRow {
+    /// Parse the common columns, returning the protocol-specific ones that follow.
+    pub fn from_str(input: &str) -> Result<(&str, SocketRow), Error> {
+        let (input, _) = err_msg!(util::parse_token(input), "sl")?;
+        let (input, local) = err_msg!(parse_address(input), "local_address")?;
+        let (input, remote) = err_msg!(parse_address(input), "rem_address")?;
+        let (input, state) = err_msg!(util::parse_token(input), "st")?;
+        let state = err_msg!(TcpState::from_hex(state), "st")?;
+        let (input, tx_queue) = err_msg!(util::parse_hex_u64(input), "tx_queue")?;
+        let input = err_msg!(util::expect_bytes(":", input), "tx_queue")?;
+        let (input, rx_queue) = err_msg!(util::parse_hex_u64(input), "rx_queue")?;
+        // The timer, which is only meaningful for a moment.
+        let (input, _) = err_msg!(util::parse_token(input), "tr:tm->when")?;
+        let (input, retransmits) = err_msg!(util::parse_hex_u64(input), "retrnsmt")?;
+        let (input, uid) = err_msg!(util::parse_u64(input), "uid")?;
+        let (input, timeouts) = err_msg!(util::parse_u64(input), "timeout")?;
+        let (input, inode) = err_msg!(util::parse_u64(input), "inode")?;
+        Ok((
+            input,
+            SocketRow {
+                local,
+                remote,
+                state,
+                tx_queue,
+                rx_queue,
+                retransmits,
+                uid: util::to_u32(uid, "uid")?,
+                timeouts,
+                inode,
+            },
+        ))
+    }
+}
+
+/// Parse the `ref pointer drops` columns that end the datagram tables, returning `drops`.
+pub(super) fn parse_drops(input: &str) -> Result<u64, Error> {
+    let (input, _) = err_msg!(util::parse_u64(input), "ref")?;
+    let (input, _) = err_msg!(util::parse_token(input), "pointer")?;
+    let (_, drops) = err_msg!(util::parse_u64(input), "drops")?;
+    Ok(drops)
+}
+
+/// Parse a socket table, with its header line, using `parse_row` for each row.
+pub(super) fn parse_table<T>(
+    reader: impl io::Read,
+    parse_row: fn(&str) -> Result<T, Error>,
+) -> io::Result<Vec<T>> {
+    let mut reader = util::LineParser::new(reader);
+    // header line
+    reader.parse_line(util::parse_dummy)?;
+    let mut rows = Vec::new();
+    loop {
+        match reader.parse_line(parse_row) {
+            Ok(row) => rows.push(row),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(rows)
+}
+
+/// Parse the IPv4 table `name` in `dir`, and its IPv6 counterpart if IPv6 is enabled.
+pub(super) fn read_tables<T>(
+    dir: &Path,
+    name: &str,
+    parse_row: fn(&str) -> Result<T, Error>,
+) -> io::Result<Vec<T>> {
+    let mut rows = parse_table(File::open(dir.join(name))?, parse_row)?;
+    match File::open(dir.join(format!("{}6", name))) {
+        Ok(file) => rows.extend(parse_table(file, parse_row)?),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e),
+    }
+    Ok(rows)
+}
+
+/// The `/proc/[pid]/net` directory.
+pub(super) fn pid_dir(pid: u32) -> std::path::PathBuf {
+    Path::new("/proc").join(pid.to_string()).join("net")
+}
+
+/// Parses `ADDRESS:PORT`, with an 8 digit IPv4 or 32 digit IPv6 address.
+fn parse_address(input: &str) -> Option<(&str, SocketAddr)> {
+    let (rest, token) = util::parse_token(input)?;
+    let (address, port) = token.split_once(':')?;
+    let ip = match address.len() {
+        8 => IpAddr::V4(util::parse_hex_ipv4(address)?.1),
+        32 => IpAddr::V6(util::parse_hex_ipv6(address)?.1),
+        _ => return None,
+    };
+    let port = u16::from_str_radix(port, 16).ok()?;
+    Some((rest, SocketAddr::new(ip, port)))
+}
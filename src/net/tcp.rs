@@ -0,0 +1,286 @@
+//! Bindings to `/proc/net/tcp` and `/proc/net/tcp6`, the open TCP sockets.
+//!
+//! Both files have the same layout: addresses and ports are in hex, with IPv4 addresses as one
+//! native-endian word and IPv6 addresses as four. The state is the kernel's `TCP_*` code, also
+//! in hex, which `TcpState` turns back into the names `netstat` prints.
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use super::socket_table::{self, SocketRow};
+use crate::Error;
+
+/// The open TCP sockets in the network namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TcpSockets {
+    inner: Vec<TcpSocket>,
+}
+
+/// An open TCP socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TcpSocket {
+    pub local: SocketAddr,
+    /// The peer's address, or the unspecified address and port `0` for listening sockets.
+    pub remote: SocketAddr,
+    pub state: TcpState,
+    /// The number of bytes sent but not yet acknowledged. Always `0` for listening sockets.
+    pub tx_queue: u64,
+    /// The number of bytes received but not yet read. For listening sockets, the number of
+    /// connections waiting in the accept queue.
+    pub rx_queue: u64,
+    /// The number of unrecovered retransmission timeouts.
+    pub retransmits: u64,
+    /// The user owning the socket.
+    pub uid: u32,
+    /// The number of unanswered zero-window probes.
+    pub timeouts: u64,
+    /// The socket's inode, matching the `socket:[inode]` links in `/proc/[pid]/fd`. `0` for
+    /// sockets in `TIME_WAIT`, which no longer belong to a process.
+    pub inode: u64,
+}
+
+/// The state of a TCP socket, from the kernel's `TCP_*` codes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TcpState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    /// A connection request on a listening socket, before the handshake completes.
+    NewSynRecv,
+    /// Any code added after this crate was written.
+    Other(u8),
+}
+
+impl TcpState {
+    /// The state with kernel code `code`.
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x01 => TcpState::Established,
+            0x02 => TcpState::SynSent,
+            0x03 => TcpState::SynRecv,
+            0x04 => TcpState::FinWait1,
+            0x05 => TcpState::FinWait2,
+            0x06 => TcpState::TimeWait,
+            0x07 => TcpState::Close,
+            0x08 => TcpState::CloseWait,
+            0x09 => TcpState::LastAck,
+            0x0A => TcpState::Listen,
+            0x0B => TcpState::Closing,
+            0x0C => TcpState::NewSynRecv,
+            other => TcpState::Other(other),
+        }
+    }
+
+    /// The state with the hex code `hex`, as in the `st` column, e.g. `0A` for `Listen`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        u8::from_str_radix(hex, 16).ok().map(TcpState::from_code)
+    }
+
+    /// The kernel's code for this state.
+    pub fn code(self) -> u8 {
+        match self {
+            TcpState::Established => 0x01,
+            TcpState::SynSent => 0x02,
+            TcpState::SynRecv => 0x03,
+            TcpState::FinWait1 => 0x04,
+            TcpState::FinWait2 => 0x05,
+            TcpState::TimeWait => 0x06,
+            TcpState::Close => 0x07,
+            TcpState::CloseWait => 0x08,
+            TcpState::LastAck => 0x09,
+            TcpState::Listen => 0x0A,
+            TcpState::Closing => 0x0B,
+            TcpState::NewSynRecv => 0x0C,
+            TcpState::Other(other) => other,
+        }
+    }
+}
+
+impl From<u8> for TcpState {
+    fn from(code: u8) -> Self {
+        TcpState::from_code(code)
+    }
+}
+
+impl fmt::Display for TcpState {
+    /// The name `netstat` uses, like `ESTABLISHED` or `TIME_WAIT`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TcpState::Established => "ESTABLISHED",
+            TcpState::SynSent => "SYN_SENT",
+            TcpState::SynRecv => "SYN_RECV",
+            TcpState::FinWait1 => "FIN_WAIT1",
+            TcpState::FinWait2 => "FIN_WAIT2",
+            TcpState::TimeWait => "TIME_WAIT",
+            TcpState::Close => "CLOSE",
+            TcpState::CloseWait => "CLOSE_WAIT",
+            TcpState::LastAck => "LAST_ACK",
+            TcpState::Listen => "LISTEN",
+            TcpState::Closing => "CLOSING",
+            TcpState::NewSynRecv => "NEW_SYN_RECV",
+            TcpState::Other(code) => return write!(f, "UNKNOWN({:02X})", code),
+        };
+        f.write_str(name)
+    }
+}
+
+impl TcpSockets {
+    /// Parse the IPv4 and IPv6 sockets in `/proc/net/tcp` and `/proc/net/tcp6`.
+    ///
+    /// `tcp6` is missing when IPv6 is disabled, and then only the IPv4 sockets are returned.
+    pub fn from_system() -> io::Result<Self> {
+        let inner = socket_table::read_tables(Path::new("/proc/net"), "tcp", TcpSocket::from_str)?;
+        Ok(TcpSockets { inner })
+    }
+
+    /// Parse the sockets in the network namespace of process `pid`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        let inner =
+            socket_table::read_tables(&socket_table::pid_dir(pid), "tcp", TcpSocket::from_str)?;
+        Ok(TcpSockets { inner })
+    }
+
+    /// Parse `/proc/net/tcp`- or `/proc/net/tcp6`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let inner = socket_table::parse_table(reader, TcpSocket::from_str)?;
+        Ok(TcpSockets { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, TcpSocket> {
+        self.inner.iter()
+    }
+
+    /// The number of sockets.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no sockets.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The sockets in `state`.
+    pub fn in_state(&self, state: TcpState) -> impl Iterator<Item = &TcpSocket> {
+        self.iter().filter(move |socket| socket.state == state)
+    }
+
+    /// The sockets accepting connections.
+    pub fn listening_sockets(&self) -> impl Iterator<Item = &TcpSocket> {
+        self.in_state(TcpState::Listen)
+    }
+
+    /// The sockets with an open connection.
+    pub fn established(&self) -> impl Iterator<Item = &TcpSocket> {
+        self.in_state(TcpState::Established)
+    }
+}
+
+impl IntoIterator for TcpSockets {
+    type IntoIter = std::vec::IntoIter<TcpSocket>;
+    type Item = TcpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TcpSockets {
+    type IntoIter = std::slice::Iter<'a, TcpSocket>;
+    type Item = &'a TcpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl TcpSocket {
+    fn from_str(input: &str) -> Result<TcpSocket, Error> {
+        let (_, row) = SocketRow::from_str(input)?;
+        Ok(TcpSocket {
+            local: row.local,
+            remote: row.remote,
+            state: row.state,
+            tx_queue: row.tx_queue,
+            rx_queue: row.rx_queue,
+            retransmits: row.retransmits,
+            uid: row.uid,
+            timeouts: row.timeouts,
+            inode: row.inode,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TcpSockets, TcpState};
+    use std::io;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+
+    #[test]
+    fn tcp_state() {
+        assert_eq!(TcpState::from_hex("0A"), Some(TcpState::Listen));
+        assert_eq!(TcpState::from_hex("zz"), None);
+        assert_eq!(TcpState::from(0x06), TcpState::TimeWait);
+        assert_eq!(TcpState::from_code(0x42), TcpState::Other(0x42));
+        for code in 0..=0x0D {
+            assert_eq!(TcpState::from_code(code).code(), code);
+        }
+        assert_eq!(TcpState::Established.to_string(), "ESTABLISHED");
+        assert_eq!(TcpState::FinWait2.to_string(), "FIN_WAIT2");
+        assert_eq!(TcpState::Other(0x0D).to_string(), "UNKNOWN(0D)");
+    }
+
+    #[test]
+    fn proc_net_tcp() {
+        if !cfg!(target_endian = "little") {
+            return;
+        }
+        let raw = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000002 00:00000000 00000000     0        0 21013 1 0000000000000000 100 0 0 10 0
+   1: 0F02000A:0016 0202000A:D3A4 01 00000024:00000000 01:00000016 00000000  1000        0 43851 4 0000000000000000 20 4 29 10 -1
+";
+        let sockets = TcpSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sockets.len(), 2);
+        let listening: Vec<_> = sockets.listening_sockets().collect();
+        assert_eq!(listening.len(), 1);
+        let cups = listening[0];
+        assert_eq!(cups.local, SocketAddr::from((Ipv4Addr::LOCALHOST, 631)));
+        assert_eq!(cups.remote, SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)));
+        // Two connections waiting to be accepted.
+        assert_eq!(cups.tx_queue, 0);
+        assert_eq!(cups.rx_queue, 2);
+        assert_eq!(cups.inode, 21013);
+        let ssh = sockets.established().next().unwrap();
+        assert_eq!(ssh.local, SocketAddr::from(([10, 0, 2, 15], 22)));
+        assert_eq!(ssh.remote, SocketAddr::from(([10, 0, 2, 2], 54180)));
+        assert_eq!(ssh.tx_queue, 0x24);
+        assert_eq!(ssh.uid, 1000);
+
+        let raw = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 52011 1 0000000000000000 100 0 0 10 0
+";
+        let sockets = TcpSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        let socket = sockets.iter().next().unwrap();
+        assert_eq!(socket.local, SocketAddr::from((Ipv6Addr::LOCALHOST, 8080)));
+        assert_eq!(socket.state, TcpState::Listen);
+    }
+
+    #[test]
+    fn tcp_sockets() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let sockets = TcpSockets::from_system().unwrap();
+        assert!(sockets
+            .listening_sockets()
+            .any(|socket| socket.local == address));
+        TcpSockets::from_pid(std::process::id()).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+//! Bindings to `/proc/net/udp` and `/proc/net/udp6`, the open UDP sockets.
+//!
+//! The layout is the same as `/proc/net/tcp`, with a drop counter on the end. UDP reuses the TCP
+//! state codes: sockets are `Close` until `connect`ed, and `Established` after.
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use super::socket_table::{self, SocketRow};
+use super::tcp::TcpState;
+use crate::Error;
+
+/// The open UDP sockets in the network namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct UdpSockets {
+    inner: Vec<UdpSocket>,
+}
+
+/// An open UDP socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UdpSocket {
+    pub local: SocketAddr,
+    /// The address the socket is `connect`ed to, or the unspecified address and port `0`.
+    pub remote: SocketAddr,
+    pub state: TcpState,
+    /// The number of bytes waiting to be sent.
+    pub tx_queue: u64,
+    /// The number of bytes received but not yet read.
+    pub rx_queue: u64,
+    /// The user owning the socket.
+    pub uid: u32,
+    /// The socket's inode, matching the `socket:[inode]` links in `/proc/[pid]/fd`.
+    pub inode: u64,
+    /// The number of datagrams dropped, usually because the receive queue was full.
+    pub drops: u64,
+}
+
+impl UdpSockets {
+    /// Parse the IPv4 and IPv6 sockets in `/proc/net/udp` and `/proc/net/udp6`.
+    ///
+    /// `udp6` is missing when IPv6 is disabled, and then only the IPv4 sockets are returned.
+    pub fn from_system() -> io::Result<Self> {
+        let inner = socket_table::read_tables(Path::new("/proc/net"), "udp", UdpSocket::from_str)?;
+        Ok(UdpSockets { inner })
+    }
+
+    /// Parse the sockets in the network namespace of process `pid`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        let inner =
+            socket_table::read_tables(&socket_table::pid_dir(pid), "udp", UdpSocket::from_str)?;
+        Ok(UdpSockets { inner })
+    }
+
+    /// Parse `/proc/net/udp`- or `/proc/net/udp6`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let inner = socket_table::parse_table(reader, UdpSocket::from_str)?;
+        Ok(UdpSockets { inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, UdpSocket> {
+        self.inner.iter()
+    }
+
+    /// The number of sockets.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no sockets.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The sockets that aren't `connect`ed, so receive from anyone. These are what `ss -lu`
+    /// lists as listening.
+    pub fn unconnected(&self) -> impl Iterator<Item = &UdpSocket> {
+        self.iter().filter(|socket| socket.state == TcpState::Close)
+    }
+}
+
+impl IntoIterator for UdpSockets {
+    type IntoIter = std::vec::IntoIter<UdpSocket>;
+    type Item = UdpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UdpSockets {
+    type IntoIter = std::slice::Iter<'a, UdpSocket>;
+    type Item = &'a UdpSocket;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl UdpSocket {
+    fn from_str(input: &str) -> Result<UdpSocket, Error> {
+        let (input, row) = SocketRow::from_str(input)?;
+        Ok(UdpSocket {
+            local: row.local,
+            remote: row.remote,
+            state: row.state,
+            tx_queue: row.tx_queue,
+            rx_queue: row.rx_queue,
+            uid: row.uid,
+            inode: row.inode,
+            drops: socket_table::parse_drops(input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UdpSockets;
+    use crate::net::tcp::TcpState;
+    use std::io;
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    #[test]
+    fn proc_net_udp() {
+        if !cfg!(target_endian = "little") {
+            return;
+        }
+        let raw = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  221: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 18554 2 0000000000000000 0
+  735: 0F02000A:0044 0202000A:0043 01 00000000:00000340 00:00000000 00000000     0        0 23190 2 0000000000000000 12
+";
+        let sockets = UdpSockets::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sockets.len(), 2);
+        let resolver: Vec<_> = sockets.unconnected().collect();
+        assert_eq!(resolver.len(), 1);
+        assert_eq!(
+            resolver[0].local,
+            SocketAddr::from((Ipv4Addr::new(127, 0, 0, 53), 53))
+        );
+        assert_eq!(resolver[0].uid, 101);
+        assert_eq!(resolver[0].inode, 18554);
+        let dhcp = &sockets.iter().nth(1).unwrap();
+        assert_eq!(dhcp.state, TcpState::Established);
+        assert_eq!(dhcp.remote, SocketAddr::from(([10, 0, 2, 2], 67)));
+        assert_eq!(dhcp.rx_queue, 0x340);
+        assert_eq!(dhcp.drops, 12);
+    }
+
+    #[test]
+    fn udp_sockets() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        let sockets = UdpSockets::from_system().unwrap();
+        assert!(sockets.unconnected().any(|socket| socket.local == address));
+    }
+}
@@ -0,0 +1,268 @@
+//! Bindings to `/proc/net/vlan`, the 802.1Q VLAN interfaces.
+//!
+//! Only exists once the `8021q` module is loaded.
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::{util, Error};
+
+const ROOT: &str = "/proc/net/vlan";
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The VLAN interfaces, from `/proc/net/vlan/config`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct VlanConfig {
+    /// How new VLAN interfaces are named, e.g. `VLAN_NAME_TYPE_RAW_PLUS_VID_NO_PAD` for
+    /// `eth0.100`.
+    pub name_type: String,
+    inner: Vec<Vlan>,
+}
+
+/// A VLAN interface.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Vlan {
+    /// The VLAN interface's name, e.g. `eth0.100`.
+    pub name: String,
+    /// The VLAN id, from 0 to 4095.
+    pub id: u16,
+    /// The interface the VLAN is on.
+    pub parent: String,
+}
+
+impl VlanConfig {
+    /// Parse the contents of `/proc/net/vlan/config`.
+    pub fn from_system() -> io::Result<Self> {
+        VlanConfig::from_reader(File::open(Path::new(ROOT).join("config"))?)
+    }
+
+    /// Parse `/proc/net/vlan/config`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(util::parse_dummy)?;
+        let name_type = reader.parse_line(|line| {
+            let value = util::expect_bytes("Name-Type:", line).ok_or("expected \"Name-Type:\"")?;
+            Ok::<_, Error>(value.trim().to_owned())
+        })?;
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(Vlan::from_str) {
+                Ok(vlan) => inner.push(vlan),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(VlanConfig { name_type, inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vlan> {
+        self.inner.iter()
+    }
+
+    /// The VLANs on the interface called `parent`.
+    pub fn on_parent<'a>(&'a self, parent: &'a str) -> impl Iterator<Item = &'a Vlan> + 'a {
+        self.iter().filter(move |vlan| vlan.parent == parent)
+    }
+}
+
+impl std::ops::Deref for VlanConfig {
+    type Target = Vec<Vlan>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for VlanConfig {
+    type IntoIter = std::vec::IntoIter<Vlan>;
+    type Item = Vlan;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl Vlan {
+    /// Parse the details of this VLAN from `/proc/net/vlan/[name]`.
+    pub fn stats(&self) -> io::Result<VlanStats> {
+        VlanStats::from_name(&self.name)
+    }
+
+    fn from_str(input: &str) -> Result<Vlan, Error> {
+        // `eth0.100       | 100  | eth0`
+        let mut fields = input.split('|').map(str::trim);
+        let name = err_msg!(fields.next(), "vlan name")?;
+        let (_, id) = err_msg!(fields.next().and_then(util::parse_u64), "vlan id")?;
+        let parent = err_msg!(fields.next(), "parent device")?;
+        if name.is_empty() || parent.is_empty() || id > 4095 {
+            return Err("invalid vlan".into());
+        }
+        Ok(Vlan {
+            name: name.to_owned(),
+            id: id as u16,
+            parent: parent.to_owned(),
+        })
+    }
+}
+
+/// The details of a VLAN interface, from `/proc/net/vlan/[name]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct VlanStats {
+    pub name: String,
+    pub id: u16,
+    /// Whether the VLAN header is removed from received frames before they are passed up.
+    pub reorder_hdr: bool,
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub rx_multicast: u64,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    /// The interface the VLAN is on.
+    pub parent: String,
+    /// The `skb->priority` given to received frames, indexed by their 802.1p priority.
+    pub ingress_priority_map: [u32; 8],
+    /// The 802.1p priority given to sent frames, for each mapped `skb->priority`.
+    pub egress_priority_map: Vec<(u32, u8)>,
+}
+
+impl VlanStats {
+    /// Parse `/proc/net/vlan/[name]`.
+    pub fn from_name(name: &str) -> io::Result<Self> {
+        VlanStats::from_reader(File::open(Path::new(ROOT).join(name))?)
+    }
+
+    /// Parse `/proc/net/vlan/[name]`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut stats = reader.parse_line(VlanStats::parse_header)?;
+        loop {
+            match reader.parse_line(|line| stats.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Parse `eth0.100  VID: 100\t REORDER_HDR: 1  dev->priv_flags: 1001`.
+    fn parse_header(input: &str) -> Result<VlanStats, Error> {
+        let (input, name) = err_msg!(util::parse_token(input), "vlan name")?;
+        let input = err_msg!(
+            util::expect_bytes("VID:", input.trim_start()),
+            "expected VID"
+        )?;
+        let (input, id) = err_msg!(util::parse_u64(input), "vlan id")?;
+        let input = err_msg!(
+            util::expect_bytes("REORDER_HDR:", input.trim_start()),
+            "expected REORDER_HDR"
+        )?;
+        let (_, reorder_hdr) = err_msg!(util::parse_u64(input), "REORDER_HDR")?;
+        Ok(VlanStats {
+            name: name.to_owned(),
+            id: id as u16,
+            reorder_hdr: reorder_hdr != 0,
+            ..VlanStats::default()
+        })
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        let input = input.trim();
+        if let Some(parent) = util::expect_bytes("Device:", input) {
+            self.parent = parent.trim().to_owned();
+        } else if let Some(map) = util::expect_bytes("INGRESS priority mappings:", input) {
+            for pair in map.split_ascii_whitespace() {
+                let (from, to) = err_msg!(parse_mapping(pair), "ingress mapping")?;
+                let slot = err_msg!(
+                    self.ingress_priority_map.get_mut(from as usize),
+                    "ingress priority out of range"
+                )?;
+                *slot = to as u32;
+            }
+        } else if let Some(map) = util::expect_bytes("EGRESS priority mappings:", input) {
+            self.egress_priority_map = map
+                .split_ascii_whitespace()
+                .map(|pair| {
+                    let (from, to) = err_msg!(parse_mapping(pair), "egress mapping")?;
+                    Ok((from as u32, to as u8))
+                })
+                .collect::<Result<_, Error>>()?;
+        } else if !input.is_empty() {
+            // `total frames received            0`
+            let idx = err_msg!(input.rfind(' '), "counter")?;
+            let (_, value) = err_msg!(util::parse_u64(&input[idx..]), "counter value")?;
+            match input[..idx].trim_end() {
+                "total frames received" => self.rx_packets = value,
+                "total bytes received" => self.rx_bytes = value,
+                "Broadcast/Multicast Rcvd" => self.rx_multicast = value,
+                "total frames transmitted" => self.tx_packets = value,
+                "total bytes transmitted" => self.tx_bytes = value,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `from:to` priority mapping.
+fn parse_mapping(input: &str) -> Option<(u64, u64)> {
+    let (input, from) = util::parse_u64(input)?;
+    let input = util::expect_bytes(":", input)?;
+    let (_, to) = util::parse_u64(input)?;
+    Some((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VlanConfig, VlanStats};
+    use std::io;
+
+    #[test]
+    fn proc_net_vlan_config() {
+        let raw = "\
+VLAN Dev name\t | VLAN ID
+Name-Type: VLAN_NAME_TYPE_RAW_PLUS_VID_NO_PAD
+eth0.100       | 100  | eth0
+eth0.200       | 200  | eth0
+bond0.10       | 10  | bond0
+";
+        let config = VlanConfig::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(config.name_type, "VLAN_NAME_TYPE_RAW_PLUS_VID_NO_PAD");
+        assert_eq!(config.len(), 3);
+        assert_eq!(config[2].name, "bond0.10");
+        assert_eq!(config[2].id, 10);
+        assert_eq!(config[2].parent, "bond0");
+        assert_eq!(config.on_parent("eth0").count(), 2);
+    }
+
+    #[test]
+    fn proc_net_vlan_device() {
+        let raw = "\
+eth0.100  VID: 100\t REORDER_HDR: 1  dev->priv_flags: 1001
+         total frames received         1234
+          total bytes received       567890
+      Broadcast/Multicast Rcvd           12
+
+      total frames transmitted          987
+       total bytes transmitted        65432
+Device: eth0
+INGRESS priority mappings: 0:0  1:0  2:0  3:0  4:0  5:5  6:0 7:0
+ EGRESS priority mappings: 3:5 7:6 
+";
+        let stats = VlanStats::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.name, "eth0.100");
+        assert_eq!(stats.id, 100);
+        assert!(stats.reorder_hdr);
+        assert_eq!(stats.rx_packets, 1234);
+        assert_eq!(stats.rx_bytes, 567_890);
+        assert_eq!(stats.rx_multicast, 12);
+        assert_eq!(stats.tx_packets, 987);
+        assert_eq!(stats.tx_bytes, 65432);
+        assert_eq!(stats.parent, "eth0");
+        assert_eq!(stats.ingress_priority_map, [0, 0, 0, 0, 0, 5, 0, 0]);
+        assert_eq!(stats.egress_priority_map, vec![(3, 5), (7, 6)]);
+    }
+}
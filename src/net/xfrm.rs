@@ -0,0 +1,162 @@
+//! Bindings to `/proc/net/xfrm_stat`, the IPsec error counters.
+//!
+//! Only exists if the kernel was built with `CONFIG_XFRM_STATISTICS`. Every counter counts
+//! packets dropped for the named reason, so on a healthy VPN gateway they should all stay at, or
+//! close to, zero.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+/// The IPsec error counters, keyed by name, e.g. `XfrmInNoStates`.
+///
+/// The set of counters grows with each kernel version, so they are kept in a map. See the kernel
+/// documentation (`Documentation/networking/xfrm_proc.rst`) for what each one means.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XfrmStat {
+    inner: HashMap<String, u64>,
+    /// Counter names in the order the kernel listed them.
+    order: Vec<String>,
+}
+
+impl XfrmStat {
+    const PATH: &'static str = "/proc/net/xfrm_stat";
+    /// Parse the contents of `/proc/net/xfrm_stat`.
+    pub fn from_system() -> io::Result<Self> {
+        XfrmStat::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/net/xfrm_stat`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = HashMap::new();
+        let mut order = Vec::new();
+        loop {
+            match reader.parse_line(Self::parse_counter) {
+                Ok((name, value)) => {
+                    if inner.insert(name.clone(), value).is_some() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "duplicate counter in /proc/net/xfrm_stat",
+                        ));
+                    }
+                    order.push(name);
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(XfrmStat { inner, order })
+    }
+
+    /// The counter `name`, like `XfrmInNoStates`.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.inner.get(name).copied()
+    }
+
+    /// The number of counters.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no counters.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Iterate over the counters in the order the kernel listed them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.order
+            .iter()
+            .map(move |name| (name.as_str(), self.inner[name]))
+    }
+
+    /// The sum of the inbound counters (`XfrmIn*`).
+    pub fn in_errors(&self) -> u64 {
+        self.sum("XfrmIn")
+    }
+
+    /// The sum of the outbound counters (`XfrmOut*`).
+    pub fn out_errors(&self) -> u64 {
+        self.sum("XfrmOut")
+    }
+
+    /// The sum of the forwarding counters (`XfrmFwd*`).
+    pub fn fwd_errors(&self) -> u64 {
+        self.sum("XfrmFwd")
+    }
+
+    fn sum(&self, prefix: &str) -> u64 {
+        self.inner
+            .iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .fold(0, |total, (_, value)| total.saturating_add(*value))
+    }
+
+    fn parse_counter(input: &str) -> Result<(String, u64), Error> {
+        let (input, name) = util::parse_token(input).ok_or("counter name")?;
+        let (_, value) = util::parse_u64(input).ok_or("counter value")?;
+        Ok((name.to_owned(), value))
+    }
+}
+
+impl std::ops::Index<&str> for XfrmStat {
+    type Output = u64;
+    /// The counter `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no counter called `name`.
+    fn index(&self, name: &str) -> &u64 {
+        self.inner
+            .get(name)
+            .unwrap_or_else(|| panic!("no counter named {:?}", name))
+    }
+}
+
+impl IntoIterator for XfrmStat {
+    type IntoIter = std::vec::IntoIter<(String, u64)>;
+    type Item = (String, u64);
+    /// The counters in the order the kernel listed them.
+    fn into_iter(mut self) -> Self::IntoIter {
+        let inner = &mut self.inner;
+        self.order
+            .into_iter()
+            .filter_map(|name| inner.remove(&name).map(|value| (name, value)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XfrmStat;
+    use std::io;
+
+    #[test]
+    fn proc_net_xfrm_stat() {
+        let raw = "\
+XfrmInError             \t0
+XfrmInNoStates          \t12
+XfrmInStateInvalid      \t3
+XfrmOutBundleGenError   \t5
+XfrmOutNoStates         \t1
+XfrmFwdHdrError         \t2
+";
+        let stat = XfrmStat::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stat.len(), 6);
+        assert_eq!(stat["XfrmInNoStates"], 12);
+        assert_eq!(stat.get("XfrmOutBundleGenError"), Some(5));
+        assert_eq!(stat.in_errors(), 15);
+        assert_eq!(stat.out_errors(), 6);
+        assert_eq!(stat.fwd_errors(), 2);
+        assert_eq!(stat.iter().nth(1), Some(("XfrmInNoStates", 12)));
+        let owned: Vec<_> = stat.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(owned[..2], ["XfrmInError", "XfrmInNoStates"]);
+
+        let duplicate = "XfrmInError 0\nXfrmInError 1\n";
+        let err = XfrmStat::from_reader(io::Cursor::new(duplicate)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
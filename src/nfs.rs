@@ -0,0 +1,393 @@
+//! Bindings to the NFS statistics in `/proc/net/rpc/nfs`, `/proc/net/rpc/nfsd` and
+//! `/proc/fs/nfsd`, as reported by `nfsstat`.
+//!
+//! The client file only exists once the `nfs` module is loaded, and the server files once `nfsd`
+//! is.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+/// The names of the NFSv3 procedures, in the order their counts are listed.
+pub const NFSV3_PROCEDURES: [&str; 22] = [
+    "null",
+    "getattr",
+    "setattr",
+    "lookup",
+    "access",
+    "readlink",
+    "read",
+    "write",
+    "create",
+    "mkdir",
+    "symlink",
+    "mknod",
+    "remove",
+    "rmdir",
+    "rename",
+    "link",
+    "readdir",
+    "readdirplus",
+    "fsstat",
+    "fsinfo",
+    "pathconf",
+    "commit",
+];
+
+/// Parse a file of `keyword value...` lines, where the values are integers.
+///
+/// Values after the first non-integer (like the obsolete histogram on the server's `th` line)
+/// are ignored.
+fn parse_lines(reader: impl io::Read) -> io::Result<HashMap<String, Vec<u64>>> {
+    let mut reader = util::LineParser::new(reader);
+    let mut lines = HashMap::new();
+    loop {
+        let line = reader.parse_line(|line| {
+            let mut tokens = line.split_ascii_whitespace();
+            let key = tokens.next().ok_or("empty line")?;
+            let values = tokens.map_while(|token| token.parse().ok()).collect();
+            Ok::<_, Error>((key.to_owned(), values))
+        });
+        match line {
+            Ok((key, values)) => {
+                lines.insert(key, values);
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(lines)
+}
+
+/// The `index`th value on the line starting with `key`, or 0 if it's missing.
+fn value(lines: &HashMap<String, Vec<u64>>, key: &str, index: usize) -> u64 {
+    lines
+        .get(key)
+        .and_then(|values| values.get(index))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// The per-procedure counts of each NFS version, from the `procN` lines.
+fn procedures(lines: &HashMap<String, Vec<u64>>) -> Vec<NfsProcedures> {
+    let mut procedures: Vec<_> = lines
+        .iter()
+        .filter_map(|(key, values)| {
+            let version = key.strip_prefix("proc")?.parse().ok()?;
+            // The first value is the number of procedures.
+            let counts = values.get(1..)?.to_vec();
+            Some(NfsProcedures { version, counts })
+        })
+        .collect();
+    procedures.sort_by_key(|procedures| procedures.version);
+    procedures
+}
+
+/// The number of calls of each procedure of an NFS version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NfsProcedures {
+    /// The NFS version, e.g. 3 or 4.
+    pub version: u32,
+    /// The count for each procedure, in procedure number order.
+    pub counts: Vec<u64>,
+}
+
+impl NfsProcedures {
+    /// The count for the procedure called `name`, e.g. `"read"`.
+    ///
+    /// Only NFSv3 procedures can be looked up by name, since the NFSv4 lists change between
+    /// kernel versions.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        if self.version != 3 {
+            return None;
+        }
+        let idx = NFSV3_PROCEDURES.iter().position(|&known| known == name)?;
+        self.counts.get(idx).copied()
+    }
+
+    /// The total number of calls.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// NFS client statistics, from `/proc/net/rpc/nfs`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NfsClientStats {
+    /// The number of RPC calls made.
+    pub rpc_calls: u64,
+    /// The number of RPC calls resent because the server didn't answer in time.
+    pub rpc_retransmissions: u64,
+    /// The number of times the client had to refresh its credentials.
+    pub rpc_auth_refreshes: u64,
+    /// The calls of each procedure, for each NFS version, ordered by version.
+    pub procedures: Vec<NfsProcedures>,
+}
+
+impl NfsClientStats {
+    const PATH: &'static str = "/proc/net/rpc/nfs";
+    /// Parse the contents of `/proc/net/rpc/nfs`.
+    pub fn from_system() -> io::Result<Self> {
+        NfsClientStats::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/net/rpc/nfs`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let lines = parse_lines(reader)?;
+        Ok(NfsClientStats {
+            rpc_calls: value(&lines, "rpc", 0),
+            rpc_retransmissions: value(&lines, "rpc", 1),
+            rpc_auth_refreshes: value(&lines, "rpc", 2),
+            procedures: procedures(&lines),
+        })
+    }
+
+    /// The proportion of RPC calls that were retransmitted, from 0 to 1.
+    ///
+    /// Anything above a fraction of a percent points at an overloaded server or a lossy network.
+    pub fn retransmission_ratio(&self) -> f64 {
+        if self.rpc_calls == 0 {
+            return 0.0;
+        }
+        self.rpc_retransmissions as f64 / self.rpc_calls as f64
+    }
+
+    /// The calls of each procedure of NFS version `version`.
+    pub fn version(&self, version: u32) -> Option<&NfsProcedures> {
+        self.procedures.iter().find(|p| p.version == version)
+    }
+}
+
+/// NFS server statistics, from `/proc/net/rpc/nfsd`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NfsServerStats {
+    /// Requests answered from the reply cache (retransmissions of requests already handled).
+    pub reply_cache_hits: u64,
+    pub reply_cache_misses: u64,
+    /// Requests that bypass the reply cache, like reads.
+    pub reply_cache_nocache: u64,
+    /// The number of bytes read from disk for clients.
+    pub read_bytes: u64,
+    /// The number of bytes written to disk for clients.
+    pub write_bytes: u64,
+    /// The number of nfsd threads.
+    pub threads: u64,
+    /// The number of RPC calls received.
+    pub rpc_calls: u64,
+    /// The number of RPC calls rejected, for any reason.
+    pub rpc_bad_calls: u64,
+    /// The calls of each procedure, for each NFS version, ordered by version.
+    pub procedures: Vec<NfsProcedures>,
+    /// The count of each NFSv4 operation inside compound calls, by operation number.
+    pub v4_operations: Vec<u64>,
+}
+
+impl NfsServerStats {
+    const PATH: &'static str = "/proc/net/rpc/nfsd";
+    /// Parse the contents of `/proc/net/rpc/nfsd`.
+    pub fn from_system() -> io::Result<Self> {
+        NfsServerStats::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/net/rpc/nfsd`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let lines = parse_lines(reader)?;
+        Ok(NfsServerStats {
+            reply_cache_hits: value(&lines, "rc", 0),
+            reply_cache_misses: value(&lines, "rc", 1),
+            reply_cache_nocache: value(&lines, "rc", 2),
+            read_bytes: value(&lines, "io", 0),
+            write_bytes: value(&lines, "io", 1),
+            threads: value(&lines, "th", 0),
+            rpc_calls: value(&lines, "rpc", 0),
+            rpc_bad_calls: value(&lines, "rpc", 1),
+            procedures: procedures(&lines),
+            v4_operations: lines
+                .get("proc4ops")
+                .and_then(|values| values.get(1..))
+                .map(<[u64]>::to_vec)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The calls of each procedure of NFS version `version`.
+    pub fn version(&self, version: u32) -> Option<&NfsProcedures> {
+        self.procedures.iter().find(|p| p.version == version)
+    }
+}
+
+/// The nfsd thread pools, from `/proc/fs/nfsd/pool_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NfsdPools {
+    inner: Vec<NfsdPool>,
+}
+
+/// The counters of an nfsd thread pool. There is one pool unless nfsd is configured per cpu or
+/// per NUMA node.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NfsdPool {
+    pub id: u32,
+    /// The number of requests received.
+    pub packets_arrived: u64,
+    /// The number of times a request had to wait because no thread was idle.
+    pub sockets_enqueued: u64,
+    /// The number of times an idle thread was woken to handle a request.
+    pub threads_woken: u64,
+    /// The number of times an idle thread gave up waiting for work, if the kernel reports it.
+    pub threads_timedout: Option<u64>,
+}
+
+impl NfsdPools {
+    const PATH: &'static str = "/proc/fs/nfsd/pool_stats";
+    /// Parse the contents of `/proc/fs/nfsd/pool_stats`.
+    pub fn from_system() -> io::Result<Self> {
+        NfsdPools::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/fs/nfsd/pool_stats`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // `# pool packets-arrived sockets-enqueued threads-woken threads-timedout`
+        reader.parse_line(util::parse_dummy)?;
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(NfsdPool::from_str) {
+                Ok(pool) => inner.push(pool),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(NfsdPools { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &NfsdPool> {
+        self.inner.iter()
+    }
+
+    /// The proportion of requests that had to wait for a thread, from 0 to 1, across all pools.
+    ///
+    /// If this stays well above 0, more nfsd threads would help.
+    pub fn saturation(&self) -> f64 {
+        let arrived: u64 = self.iter().map(|pool| pool.packets_arrived).sum();
+        let enqueued: u64 = self.iter().map(|pool| pool.sockets_enqueued).sum();
+        if arrived == 0 {
+            return 0.0;
+        }
+        enqueued as f64 / arrived as f64
+    }
+}
+
+impl std::ops::Deref for NfsdPools {
+    type Target = Vec<NfsdPool>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for NfsdPools {
+    type IntoIter = std::vec::IntoIter<NfsdPool>;
+    type Item = NfsdPool;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl NfsdPool {
+    fn from_str(input: &str) -> Result<NfsdPool, Error> {
+        let (input, id) = util::parse_u64(input).ok_or("pool")?;
+        let (input, packets_arrived) = util::parse_u64(input).ok_or("packets-arrived")?;
+        let (input, sockets_enqueued) = util::parse_u64(input).ok_or("sockets-enqueued")?;
+        let (input, threads_woken) = util::parse_u64(input).ok_or("threads-woken")?;
+        Ok(NfsdPool {
+            id: id as u32,
+            packets_arrived,
+            sockets_enqueued,
+            threads_woken,
+            threads_timedout: util::parse_u64(input).map(|(_, value)| value),
+        })
+    }
+}
+
+/// The number of nfsd threads, from `/proc/fs/nfsd/threads`.
+pub fn nfsd_threads() -> io::Result<u32> {
+    util::read_value("/proc/fs/nfsd/threads")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NfsClientStats, NfsServerStats, NfsdPools};
+    use std::io;
+
+    #[test]
+    fn proc_net_rpc_nfs() {
+        let raw = "\
+net 0 0 0 0
+rpc 20000 30 0
+proc3 22 0 5000 10 3000 2000 0 9000 1000 0 0 0 0 0 0 0 0 0 0 0 1 0 0
+proc4 69 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+";
+        let stats = NfsClientStats::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.rpc_calls, 20000);
+        assert_eq!(stats.rpc_retransmissions, 30);
+        assert_eq!(stats.retransmission_ratio(), 0.0015);
+        assert_eq!(stats.procedures.len(), 2);
+        let v3 = stats.version(3).unwrap();
+        assert_eq!(v3.counts.len(), 22);
+        assert_eq!(v3.get("getattr"), Some(5000));
+        assert_eq!(v3.get("read"), Some(9000));
+        assert_eq!(v3.get("fsinfo"), Some(1));
+        assert_eq!(v3.get("frobnicate"), None);
+        assert_eq!(v3.total(), 20011);
+        assert_eq!(stats.version(4).unwrap().counts.len(), 69);
+        assert_eq!(stats.version(4).unwrap().get("read"), None);
+    }
+
+    #[test]
+    fn proc_net_rpc_nfsd() {
+        let raw = "\
+rc 12 3456 7890
+fh 0 0 0 0 0
+io 1048576 2097152
+th 8 0 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000
+ra 0 0 0 0 0 0 0 0 0 0 0 0
+net 11358 0 11358 4
+rpc 11358 2 0 2 0
+proc3 22 2 100 0 50 0 0 300 200 0 0 0 0 0 0 0 0 0 0 0 0 0 10
+proc4ops 4 0 0 7 9
+";
+        let stats = NfsServerStats::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.reply_cache_hits, 12);
+        assert_eq!(stats.reply_cache_misses, 3456);
+        assert_eq!(stats.reply_cache_nocache, 7890);
+        assert_eq!(stats.read_bytes, 1_048_576);
+        assert_eq!(stats.write_bytes, 2_097_152);
+        assert_eq!(stats.threads, 8);
+        assert_eq!(stats.rpc_calls, 11358);
+        assert_eq!(stats.rpc_bad_calls, 2);
+        assert_eq!(stats.version(3).unwrap().get("write"), Some(200));
+        assert_eq!(stats.version(3).unwrap().get("commit"), Some(10));
+        assert_eq!(stats.v4_operations, vec![0, 0, 7, 9]);
+    }
+
+    #[test]
+    fn proc_fs_nfsd_pool_stats() {
+        let raw = "\
+# pool packets-arrived sockets-enqueued threads-woken threads-timedout
+0 1000 50 950 3
+1 1000 150 850 0
+";
+        let pools = NfsdPools::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools[1].id, 1);
+        assert_eq!(pools[0].threads_timedout, Some(3));
+        assert_eq!(pools.saturation(), 0.1);
+
+        let recent = "\
+# pool packets-arrived sockets-enqueued threads-woken threads-timedout
+0 10 0 10
+";
+        let pools = NfsdPools::from_reader(io::Cursor::new(recent)).unwrap();
+        assert_eq!(pools[0].threads_timedout, None);
+    }
+}
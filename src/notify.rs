@@ -0,0 +1,266 @@
+//! Waiting for changes to `/proc` and `/sys` files that support `poll`, rather than re-reading
+//! them on a timer.
+//!
+//! A few files signal changes with `POLLPRI` (and `POLLERR`):
+//!
+//!  - `/proc/swaps`, when swap is turned on or off,
+//!  - `/proc/[pid]/mounts` and `mountinfo`, when the process's mount namespace changes,
+//!  - PSI triggers (see `pressure::PressureTrigger`, with the `write` feature), when stalls pass
+//!    their threshold.
+//!
+//! A `Notifier` watches any number of these with one `epoll` instance. Call `wait` from your
+//! own loop, or `into_channel` to get events from a background thread.
+//!
+//! ```no_run
+//! use linux_proc::{mounts::Mounts, notify::Notifier};
+//!
+//! let mut notifier = Notifier::new()?;
+//! let mounts = notifier.watch_mounts()?;
+//! loop {
+//!     for event in notifier.wait(None)? {
+//!         if event.id == mounts {
+//!             println!("{} mounts", Mounts::from_system()?.len());
+//!         }
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "write")]
+use crate::pressure::PressureTrigger;
+
+/// Identifies something a `Notifier` is watching, in the events it returns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WatchId(u64);
+
+/// Something that happened to a watched file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Event {
+    pub id: WatchId,
+    pub kind: EventKind,
+}
+
+/// What happened to a watched file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// The file changed, or a PSI trigger fired. Re-read the file to see the new contents.
+    Changed,
+    /// The thing being watched went away, like the cgroup of a PSI trigger. It stays registered
+    /// until `unwatch`ed, but won't report changes again.
+    Gone,
+}
+
+/// The things a `Notifier` keeps open.
+#[derive(Debug)]
+enum Source {
+    File(File),
+    #[cfg(feature = "write")]
+    Pressure(PressureTrigger),
+}
+
+impl Source {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Source::File(file) => file.as_raw_fd(),
+            #[cfg(feature = "write")]
+            Source::Pressure(trigger) => trigger.as_raw_fd(),
+        }
+    }
+
+    /// How to interpret `EPOLLERR`, which files raise alongside `EPOLLPRI` to mean "changed",
+    /// but PSI triggers raise to mean the cgroup was removed.
+    #[cfg_attr(not(feature = "write"), allow(unused_variables))]
+    fn event_kind(&self, events: u32) -> EventKind {
+        match self {
+            Source::File(_) => EventKind::Changed,
+            #[cfg(feature = "write")]
+            Source::Pressure(_) if events & libc::EPOLLERR as u32 != 0 => EventKind::Gone,
+            #[cfg(feature = "write")]
+            Source::Pressure(_) => EventKind::Changed,
+        }
+    }
+}
+
+/// Watches pollable `/proc` and `/sys` files for changes, using `epoll`.
+#[derive(Debug)]
+pub struct Notifier {
+    epoll: OwnedFd,
+    sources: HashMap<u64, Source>,
+    next_id: u64,
+}
+
+impl Notifier {
+    /// The most events returned from one `wait`.
+    const MAX_EVENTS: usize = 32;
+
+    /// A notifier watching nothing.
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Notifier {
+            epoll: unsafe { OwnedFd::from_raw_fd(fd) },
+            sources: HashMap::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Watch `/proc/swaps`, which changes when swap is turned on or off.
+    pub fn watch_swaps(&mut self) -> io::Result<WatchId> {
+        self.watch_path("/proc/swaps")
+    }
+
+    /// Watch `/proc/self/mountinfo`, which changes when anything is mounted or unmounted in the
+    /// current mount namespace.
+    pub fn watch_mounts(&mut self) -> io::Result<WatchId> {
+        self.watch_path("/proc/self/mountinfo")
+    }
+
+    /// Watch any file that signals changes with `POLLPRI` or `POLLERR`.
+    ///
+    /// Files that don't support `poll` at all are rejected by the kernel with `EPERM`. Files that
+    /// support it but never signal changes (most of `/proc`) are accepted, and never report
+    /// anything.
+    pub fn watch_path(&mut self, path: impl AsRef<Path>) -> io::Result<WatchId> {
+        self.add(Source::File(File::open(path)?))
+    }
+
+    /// Watch a PSI trigger, reporting `Changed` whenever it fires.
+    #[cfg(feature = "write")]
+    pub fn watch_pressure(&mut self, trigger: PressureTrigger) -> io::Result<WatchId> {
+        self.add(Source::Pressure(trigger))
+    }
+
+    /// Stop watching `id`, closing its file.
+    pub fn unwatch(&mut self, id: WatchId) -> io::Result<()> {
+        let source = self.sources.remove(&id.0).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "not watched by this notifier")
+        })?;
+        self.ctl(libc::EPOLL_CTL_DEL, source.as_raw_fd(), id.0)
+    }
+
+    /// The number of things being watched.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether nothing is being watched.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Block until something changes, or `timeout` passes, and return what changed.
+    ///
+    /// A timeout gives an empty list.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<Event>> {
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_millis().min(i32::MAX as u128) as libc::c_int,
+            None => -1,
+        };
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; Self::MAX_EVENTS];
+        let count = loop {
+            let count = unsafe {
+                libc::epoll_wait(
+                    self.epoll.as_raw_fd(),
+                    events.as_mut_ptr(),
+                    Self::MAX_EVENTS as libc::c_int,
+                    timeout,
+                )
+            };
+            if count >= 0 {
+                break count as usize;
+            }
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        };
+        Ok(events[..count]
+            .iter()
+            .filter_map(|event| {
+                let id = event.u64;
+                let source = self.sources.get(&id)?;
+                Some(Event {
+                    id: WatchId(id),
+                    kind: source.event_kind(event.events),
+                })
+            })
+            .collect())
+    }
+
+    /// Wait for events on a background thread, sending them to the returned channel.
+    ///
+    /// The thread stops after an error (which it sends first), or when it next has an event to
+    /// send after the receiver is dropped.
+    pub fn into_channel(mut self) -> mpsc::Receiver<io::Result<Event>> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loop {
+            match self.wait(None) {
+                Ok(events) => {
+                    for event in events {
+                        if sender.send(Ok(event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            }
+        });
+        receiver
+    }
+
+    fn add(&mut self, source: Source) -> io::Result<WatchId> {
+        let id = self.next_id;
+        self.ctl(libc::EPOLL_CTL_ADD, source.as_raw_fd(), id)?;
+        self.next_id += 1;
+        self.sources.insert(id, source);
+        Ok(WatchId(id))
+    }
+
+    fn ctl(&self, op: libc::c_int, fd: RawFd, id: u64) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: libc::EPOLLPRI as u32,
+            u64: id,
+        };
+        match unsafe { libc::epoll_ctl(self.epoll.as_raw_fd(), op, fd, &mut event) } {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Notifier, WatchId};
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn notifier() {
+        let mut notifier = Notifier::new().unwrap();
+        assert!(notifier.is_empty());
+        let mounts = notifier.watch_mounts().unwrap();
+        let swaps = notifier.watch_swaps().unwrap();
+        assert_ne!(mounts, swaps);
+        assert_eq!(notifier.len(), 2);
+        // Nothing has changed since the files were opened.
+        let events = notifier.wait(Some(Duration::from_millis(10))).unwrap();
+        assert!(events.is_empty());
+        notifier.unwatch(swaps).unwrap();
+        assert_eq!(notifier.len(), 1);
+        let err = notifier.unwatch(WatchId(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
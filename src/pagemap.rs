@@ -0,0 +1,307 @@
+//! Bindings to `/proc/[pid]/pagemap`, `/proc/kpagecount` and `/proc/kpageflags`.
+//!
+//! These binary files map a process's virtual pages to physical page frames, and describe each
+//! frame. They are for investigating memory in detail, e.g. checking which parts of a mapping
+//! are resident or backed by transparent huge pages. Reading them needs `CAP_SYS_ADMIN`; without
+//! it the physical frame numbers in the page map read as 0 and the `kpage*` files can't be
+//! opened at all.
+//!
+//! ```no_run
+//! use linux_proc::{pagemap::{KPageFlags, KPages, PageMap}, process};
+//!
+//! let mut pagemap = PageMap::from_pid(std::process::id())?;
+//! let mut kpages = KPages::from_system()?;
+//! let heap = process::Process::current()
+//!     .maps()?
+//!     .into_iter()
+//!     .find(|map| map.pathname.as_deref() == Some("[heap]"))
+//!     .unwrap();
+//! for entry in pagemap.entries(heap.start..heap.end, process::page_size())? {
+//!     if let Some(pfn) = entry.pfn() {
+//!         let huge = kpages.flags(pfn)?.contains(KPageFlags::THP);
+//!         println!("frame {:#x} huge: {}", pfn, huge);
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use bitflags::bitflags;
+
+/// Each entry in these files is a native-endian `u64`.
+const ENTRY_SIZE: u64 = 8;
+
+/// Read `count` entries starting at entry `index`.
+fn read_entries(reader: &mut (impl Read + Seek), index: u64, count: u64) -> io::Result<Vec<u64>> {
+    let offset = index
+        .checked_mul(ENTRY_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "index out of range"))?;
+    let len = count
+        .checked_mul(ENTRY_SIZE)
+        .and_then(|len| usize::try_from(len).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "too many entries"))?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf
+        .chunks_exact(ENTRY_SIZE as usize)
+        .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+/// A process's page table, from `/proc/[pid]/pagemap`.
+///
+/// The file holds one entry per virtual page, so it's read by address rather than all at once.
+#[derive(Debug)]
+pub struct PageMap<R = File> {
+    reader: R,
+}
+
+impl PageMap {
+    /// Open `/proc/[pid]/pagemap`.
+    pub fn from_pid(pid: u32) -> io::Result<Self> {
+        Ok(PageMap::from_reader(File::open(format!(
+            "/proc/{}/pagemap",
+            pid
+        ))?))
+    }
+}
+
+impl<R: Read + Seek> PageMap<R> {
+    /// Read `/proc/[pid]/pagemap`-formatted data from `reader`.
+    pub fn from_reader(reader: R) -> Self {
+        PageMap { reader }
+    }
+
+    /// The entry for the page containing `address`.
+    ///
+    /// Fails with `InvalidInput` if `page_size` is `0`.
+    pub fn entry(&mut self, address: u64, page_size: u64) -> io::Result<PageMapEntry> {
+        check_page_size(page_size)?;
+        let entries = read_entries(&mut self.reader, address / page_size, 1)?;
+        Ok(PageMapEntry(entries[0]))
+    }
+
+    /// The entries for the pages overlapping `addresses`, e.g. a `MemoryMap`'s `start..end`.
+    ///
+    /// Fails with `InvalidInput` if `page_size` is `0`.
+    pub fn entries(
+        &mut self,
+        addresses: Range<u64>,
+        page_size: u64,
+    ) -> io::Result<Vec<PageMapEntry>> {
+        check_page_size(page_size)?;
+        let first = addresses.start / page_size;
+        let end = addresses.end.div_ceil(page_size);
+        let entries = read_entries(&mut self.reader, first, end.saturating_sub(first))?;
+        Ok(entries.into_iter().map(PageMapEntry).collect())
+    }
+}
+
+fn check_page_size(page_size: u64) -> io::Result<()> {
+    if page_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "page size must not be 0",
+        ));
+    }
+    Ok(())
+}
+
+/// The entry for one virtual page in `/proc/[pid]/pagemap`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct PageMapEntry(pub u64);
+
+impl PageMapEntry {
+    const PFN_MASK: u64 = (1 << 55) - 1;
+
+    /// Whether the page is in physical memory.
+    pub fn present(self) -> bool {
+        self.0 & (1 << 63) != 0
+    }
+
+    /// Whether the page is swapped out.
+    pub fn swapped(self) -> bool {
+        self.0 & (1 << 62) != 0
+    }
+
+    /// Whether the page is mapped from a file or shared anonymously (since 3.5).
+    pub fn file_or_shared(self) -> bool {
+        self.0 & (1 << 61) != 0
+    }
+
+    /// Whether the page is mapped by this process only (since 4.2).
+    pub fn exclusive(self) -> bool {
+        self.0 & (1 << 56) != 0
+    }
+
+    /// Whether the page has been written since the soft-dirty bits were last cleared.
+    pub fn soft_dirty(self) -> bool {
+        self.0 & (1 << 55) != 0
+    }
+
+    /// The physical frame number, if the page is present.
+    ///
+    /// This is 0 without `CAP_SYS_ADMIN`, which is reported as `None`.
+    pub fn pfn(self) -> Option<u64> {
+        match self.0 & Self::PFN_MASK {
+            pfn if self.present() && pfn != 0 => Some(pfn),
+            _ => None,
+        }
+    }
+
+    /// The swap type and offset, if the page is swapped out.
+    pub fn swap(self) -> Option<(u8, u64)> {
+        if self.swapped() {
+            Some(((self.0 & 0x1f) as u8, (self.0 & Self::PFN_MASK) >> 5))
+        } else {
+            None
+        }
+    }
+}
+
+bitflags! {
+    /// The state of a physical page frame, from `/proc/kpageflags`.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct KPageFlags: u64 {
+        const LOCKED = 1 << 0;
+        const ERROR = 1 << 1;
+        const REFERENCED = 1 << 2;
+        const UPTODATE = 1 << 3;
+        const DIRTY = 1 << 4;
+        const LRU = 1 << 5;
+        const ACTIVE = 1 << 6;
+        const SLAB = 1 << 7;
+        const WRITEBACK = 1 << 8;
+        const RECLAIM = 1 << 9;
+        /// A free page, owned by the buddy allocator.
+        const BUDDY = 1 << 10;
+        /// The page is mapped into at least one process.
+        const MMAP = 1 << 11;
+        const ANON = 1 << 12;
+        const SWAPCACHE = 1 << 13;
+        const SWAPBACKED = 1 << 14;
+        /// The first page of a compound (huge) page.
+        const COMPOUND_HEAD = 1 << 15;
+        /// A page of a compound (huge) page other than the first.
+        const COMPOUND_TAIL = 1 << 16;
+        /// Part of a hugetlbfs page.
+        const HUGE = 1 << 17;
+        const UNEVICTABLE = 1 << 18;
+        const HWPOISON = 1 << 19;
+        /// There is no page frame at this address.
+        const NOPAGE = 1 << 20;
+        const KSM = 1 << 21;
+        /// Part of a transparent huge page.
+        const THP = 1 << 22;
+        const OFFLINE = 1 << 23;
+        const ZERO_PAGE = 1 << 24;
+        const IDLE = 1 << 25;
+        const PGTABLE = 1 << 26;
+    }
+}
+
+/// The physical page frame tables `/proc/kpagecount` and `/proc/kpageflags`, indexed by frame
+/// number.
+#[derive(Debug)]
+pub struct KPages<R = File> {
+    count: R,
+    flags: R,
+}
+
+impl KPages {
+    /// Open `/proc/kpagecount` and `/proc/kpageflags`.
+    pub fn from_system() -> io::Result<Self> {
+        Ok(KPages::from_readers(
+            File::open("/proc/kpagecount")?,
+            File::open("/proc/kpageflags")?,
+        ))
+    }
+}
+
+impl<R: Read + Seek> KPages<R> {
+    /// Read `/proc/kpagecount` and `/proc/kpageflags`-formatted data from `count` and `flags`.
+    pub fn from_readers(count: R, flags: R) -> Self {
+        KPages { count, flags }
+    }
+
+    /// The number of times frame `pfn` is mapped.
+    pub fn count(&mut self, pfn: u64) -> io::Result<u64> {
+        Ok(read_entries(&mut self.count, pfn, 1)?[0])
+    }
+
+    /// The state of frame `pfn`.
+    pub fn flags(&mut self, pfn: u64) -> io::Result<KPageFlags> {
+        let flags = read_entries(&mut self.flags, pfn, 1)?[0];
+        Ok(KPageFlags::from_bits_retain(flags))
+    }
+
+    /// The state of the frames in `pfns`, read together.
+    pub fn flags_range(&mut self, pfns: Range<u64>) -> io::Result<Vec<KPageFlags>> {
+        let count = pfns.end.saturating_sub(pfns.start);
+        let flags = read_entries(&mut self.flags, pfns.start, count)?;
+        Ok(flags
+            .into_iter()
+            .map(KPageFlags::from_bits_retain)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KPageFlags, KPages, PageMap, PageMapEntry};
+    use crate::{process, ReadOutcome};
+    use std::io;
+
+    fn table(entries: &[u64]) -> io::Cursor<Vec<u8>> {
+        io::Cursor::new(entries.iter().flat_map(|e| e.to_ne_bytes()).collect())
+    }
+
+    #[test]
+    fn pagemap() {
+        let present = 1 << 63 | 1 << 56 | 0x1234;
+        let swapped = 1 << 62 | 7 << 5 | 2;
+        let mut pagemap = PageMap::from_reader(table(&[0, present, swapped]));
+        let entries = pagemap.entries(4096..3 * 4096, 4096).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].pfn(), Some(0x1234));
+        assert!(entries[0].exclusive());
+        assert_eq!(entries[1].swap(), Some((2, 7)));
+        assert_eq!(entries[1].pfn(), None);
+        assert_eq!(pagemap.entry(100, 4096).unwrap(), PageMapEntry(0));
+        assert!(pagemap.entry(3 * 4096, 4096).is_err());
+        let err = pagemap.entries(0..4096, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            pagemap.entry(100, 0).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let mut kpages = KPages::from_readers(table(&[0, 2]), table(&[1 << 22 | 1 << 11, 0]));
+        assert_eq!(kpages.count(1).unwrap(), 2);
+        assert_eq!(kpages.flags(0).unwrap(), KPageFlags::THP | KPageFlags::MMAP);
+        assert_eq!(kpages.flags_range(0..2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn proc_pid_pagemap() {
+        let page_size = process::page_size();
+        let value = Box::new(42u64);
+        let address = &*value as *const u64 as u64;
+        if let ReadOutcome::Ok(mut pagemap) =
+            ReadOutcome::from(PageMap::from_pid(std::process::id()))
+        {
+            let entry = pagemap.entry(address, page_size).unwrap();
+            assert!(entry.present());
+            if let (Some(pfn), ReadOutcome::Ok(mut kpages)) =
+                (entry.pfn(), ReadOutcome::from(KPages::from_system()))
+            {
+                assert!(kpages.count(pfn).unwrap() >= 1);
+                assert!(!kpages.flags(pfn).unwrap().contains(KPageFlags::NOPAGE));
+            }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Building blocks for parsing files in `/proc`.
+//!
+//! These are the helpers the parsers in this crate are built on. They are public so you can parse
+//! files this crate doesn't cover (yet) in the same way.
+//!
+//! Every parse is attempted on one line at a time. If it fails, the line is kept so a different
+//! parser can be tried. Running out of lines is reported as an `io::ErrorKind::UnexpectedEof`
+//! error.
+//!
+//! ```
+//! use linux_proc::parse::LineParser;
+//! use std::io;
+//!
+//! let raw = "\
+//! nr_free_pages 123456
+//! nr_zone_inactive_anon 789
+//! ";
+//! let mut lines = LineParser::new(io::Cursor::new(raw));
+//! assert_eq!(lines.peek_line().unwrap(), Some("nr_free_pages 123456\n"));
+//! let free = lines
+//!     .try_parse_line(|line| match line.trim().strip_prefix("nr_free_pages ") {
+//!         Some(value) => value.parse::<u64>().map_err(|_| "bad number"),
+//!         None => Err("wrong line"),
+//!     })
+//!     .unwrap();
+//! assert_eq!(free, Ok(123456));
+//! assert!(lines.skip_line().unwrap());
+//! assert!(!lines.skip_line().unwrap());
+//! ```
+use std::io;
+
+use crate::Error;
+
+/// How a parser treats content it doesn't understand.
+///
+/// New kernels regularly add lines and change formats. Strict parsing reports this as an
+/// `io::ErrorKind::InvalidData` error, so nothing is silently misread. Lenient parsing keeps
+/// whatever it could parse, and collects the lines it couldn't into the result's `extras`, so
+/// the crate keeps working while still showing what it missed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseMode {
+    /// Fail on anything unexpected. The default.
+    #[default]
+    Strict,
+    /// Collect unexpected lines into `extras` instead of failing.
+    Lenient,
+}
+
+/// A helper to facilitate parsing line by line while reusing a string buffer.
+///
+/// Lines that aren't valid UTF-8 (process names can contain any byte) are converted lossily,
+/// with invalid sequences replaced by U+FFFD.
+pub struct LineParser<R> {
+    reader: io::BufReader<R>,
+    buffer: String,
+    /// The raw bytes of the line being read.
+    bytes: Vec<u8>,
+}
+
+impl<R> LineParser<R>
+where
+    R: io::Read,
+{
+    pub fn new(reader: R) -> LineParser<R> {
+        LineParser {
+            reader: io::BufReader::new(reader),
+            buffer: String::with_capacity(100),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Make sure the next line is in the buffer. Returns `false` at the end of the input.
+    fn fill(&mut self) -> io::Result<bool> {
+        // Only fetch next line if we consumed the previous
+        if self.buffer.is_empty() {
+            self.bytes.clear();
+            let read = io::BufRead::read_until(&mut self.reader, b'\n', &mut self.bytes)?;
+            if read == 0 {
+                return Ok(false);
+            }
+            self.buffer.push_str(&String::from_utf8_lossy(&self.bytes));
+        }
+        Ok(true)
+    }
+
+    /// If the parse fails, the line is available for trying different parsers.
+    ///
+    /// A failed parse is reported as an `io::ErrorKind::InvalidData` error.
+    pub fn parse_line<F, E, Val>(&mut self, parser: F) -> io::Result<Val>
+    where
+        F: FnOnce(&str) -> Result<Val, E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.try_parse_line(parser)?
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, Box::new(e)))
+    }
+
+    /// Like `parse_line`, but the parser's error is returned separately from io errors, so a
+    /// line that didn't match isn't confused with a broken file.
+    ///
+    /// If the parse fails, the line is available for trying different parsers.
+    pub fn try_parse_line<F, E, Val>(&mut self, parser: F) -> io::Result<Result<Val, E>>
+    where
+        F: FnOnce(&str) -> Result<Val, E>,
+    {
+        if !self.fill()? {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let parsed = parser(&self.buffer);
+        if parsed.is_ok() {
+            // we've succeeded so clear the buffer.
+            self.buffer.clear();
+        }
+        Ok(parsed)
+    }
+
+    /// Parse the next line according to `mode`.
+    ///
+    /// In strict mode this is `parse_line`. In lenient mode a line that fails to parse is
+    /// consumed and pushed (without its newline) onto `extras`, and `None` is returned.
+    pub fn parse_line_with_mode<F, E, Val>(
+        &mut self,
+        mode: ParseMode,
+        extras: &mut Vec<String>,
+        parser: F,
+    ) -> io::Result<Option<Val>>
+    where
+        F: FnOnce(&str) -> Result<Val, E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        match mode {
+            ParseMode::Strict => self.parse_line(parser).map(Some),
+            ParseMode::Lenient => match self.try_parse_line(parser)? {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => {
+                    extras.push(self.buffer.trim_end_matches('\n').to_owned());
+                    self.buffer.clear();
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// The next line, including its newline, without consuming it. `None` at the end of the
+    /// input.
+    pub fn peek_line(&mut self) -> io::Result<Option<&str>> {
+        if self.fill()? {
+            Ok(Some(&self.buffer))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Consume the next line without parsing it. Returns `false` at the end of the input.
+    pub fn skip_line(&mut self) -> io::Result<bool> {
+        let filled = self.fill()?;
+        self.buffer.clear();
+        Ok(filled)
+    }
+
+    /// Parse every remaining line with `parser`, one at a time.
+    ///
+    /// This reads large files like `/proc/kallsyms` without holding all the entries in memory.
+    /// The iterator ends after the last line, or after the first error.
+    pub fn entries<F, E, Val>(self, parser: F) -> Entries<R, F>
+    where
+        F: FnMut(&str) -> Result<Val, E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Entries {
+            lines: self,
+            parser,
+            done: false,
+        }
+    }
+}
+
+/// A line parser for the entries of a file, as stored in the crate's iterator types.
+pub(crate) type EntryParser<Val> = fn(&str) -> Result<Val, Error>;
+
+/// An iterator parsing one line at a time, from `LineParser::entries`.
+///
+/// Collect it into an `io::Result<Vec<_>>` to read every entry at once.
+pub struct Entries<R, F> {
+    lines: LineParser<R>,
+    parser: F,
+    done: bool,
+}
+
+impl<R, F, E, Val> Iterator for Entries<R, F>
+where
+    R: io::Read,
+    F: FnMut(&str) -> Result<Val, E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Item = io::Result<Val>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.lines.parse_line(&mut self.parser) {
+            Ok(value) => Some(Ok(value)),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R, F, E, Val> std::iter::FusedIterator for Entries<R, F>
+where
+    R: io::Read,
+    F: FnMut(&str) -> Result<Val, E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+}
+
+/// A helper for whitespace-aligned tables whose first line names the columns, like `/proc/swaps`
+/// or `/proc/interrupts`.
+///
+/// The set of columns varies between kernels (and, for per-cpu tables, machines), so rows are
+/// looked up by column name rather than position.
+pub struct TableParser<R> {
+    lines: LineParser<R>,
+    columns: Vec<String>,
+}
+
+impl<R> TableParser<R>
+where
+    R: io::Read,
+{
+    /// Read the header line naming the columns.
+    pub fn new(reader: R) -> io::Result<TableParser<R>> {
+        let mut lines = LineParser::new(reader);
+        let columns = lines.parse_line(|line| {
+            Ok::<_, Error>(line.split_ascii_whitespace().map(String::from).collect())
+        })?;
+        Ok(TableParser { lines, columns })
+    }
+
+    /// The column names, in order.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Parse the next row. Returns an `UnexpectedEof` error once there are no more rows.
+    pub fn parse_row<F, E, Val>(&mut self, parser: F) -> io::Result<Val>
+    where
+        F: FnOnce(&TableRow) -> Result<Val, E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let columns = &self.columns;
+        self.lines.parse_line(|line| {
+            parser(&TableRow {
+                columns,
+                fields: line.split_ascii_whitespace().collect(),
+            })
+        })
+    }
+}
+
+/// A row of a table, from `TableParser::parse_row`.
+pub struct TableRow<'a> {
+    columns: &'a [String],
+    fields: Vec<&'a str>,
+}
+
+impl<'a> TableRow<'a> {
+    /// All the fields in the row, which may be more or fewer than there are columns.
+    pub fn fields(&self) -> &[&'a str] {
+        &self.fields
+    }
+
+    /// The field at `idx`.
+    pub fn get(&self, idx: usize) -> Option<&'a str> {
+        self.fields.get(idx).copied()
+    }
+
+    /// The field in the column called `name`.
+    pub fn get_by_name(&self, name: &str) -> Option<&'a str> {
+        let idx = self.columns.iter().position(|column| column == name)?;
+        self.get(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineParser, ParseMode, TableParser};
+    use crate::Error;
+    use std::io;
+
+    #[test]
+    fn line_parser() {
+        let raw = "\
+first 1
+second 2
+";
+        let mut lines = LineParser::new(io::Cursor::new(raw));
+        let number = |line: &str| {
+            line.split_whitespace()
+                .nth(1)
+                .and_then(|n| n.parse::<u64>().ok())
+                .ok_or(Error::from("number"))
+        };
+        let name = |line: &str| -> Result<String, Error> {
+            Ok(line.split_whitespace().next().unwrap().to_owned())
+        };
+        assert!(lines
+            .try_parse_line(|_| Err::<(), _>("no"))
+            .unwrap()
+            .is_err());
+        let err = lines
+            .parse_line(|_| Err::<(), _>(Error::from("no")))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(lines.peek_line().unwrap(), Some("first 1\n"));
+        assert_eq!(lines.parse_line(name).unwrap(), "first");
+        assert!(lines.skip_line().unwrap());
+        assert_eq!(lines.peek_line().unwrap(), None);
+        assert!(!lines.skip_line().unwrap());
+        let eof = lines.parse_line(number).unwrap_err();
+        assert_eq!(eof.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn line_parser_invalid_utf8() {
+        let mut lines = LineParser::new(io::Cursor::new(&b"Name:\tab\xffc\n"[..]));
+        assert_eq!(lines.peek_line().unwrap(), Some("Name:\tab\u{fffd}c\n"));
+    }
+
+    #[test]
+    fn entries() {
+        let number = |line: &str| {
+            line.trim()
+                .parse::<u64>()
+                .map_err(|_| Error::from("number"))
+        };
+        let entries = LineParser::new(io::Cursor::new("1\n2\n3\n")).entries(number);
+        assert_eq!(
+            entries.collect::<io::Result<Vec<_>>>().unwrap(),
+            vec![1, 2, 3]
+        );
+        let mut entries = LineParser::new(io::Cursor::new("1\nx\n3\n")).entries(number);
+        assert_eq!(entries.next().unwrap().unwrap(), 1);
+        assert!(entries.next().unwrap().is_err());
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn parse_mode() {
+        let raw = "1\nnot a number\n3\n";
+        let number = |line: &str| line.trim().parse::<u64>();
+        let mut lines = LineParser::new(io::Cursor::new(raw));
+        let mut extras = Vec::new();
+        let mut numbers = Vec::new();
+        loop {
+            match lines.parse_line_with_mode(ParseMode::Lenient, &mut extras, number) {
+                Ok(Some(value)) => numbers.push(value),
+                Ok(None) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+        assert_eq!(numbers, vec![1, 3]);
+        assert_eq!(extras, vec!["not a number"]);
+
+        let mut lines = LineParser::new(io::Cursor::new(raw));
+        let mut extras = Vec::new();
+        assert_eq!(
+            lines
+                .parse_line_with_mode(ParseMode::Strict, &mut extras, number)
+                .unwrap(),
+            Some(1)
+        );
+        let err = lines
+            .parse_line_with_mode(ParseMode::Strict, &mut extras, number)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(extras.is_empty());
+    }
+
+    #[test]
+    fn table_parser() {
+        let raw = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/sda2                               partition\t8388604\t\t0\t\t-2
+";
+        let mut table = TableParser::new(io::Cursor::new(raw)).unwrap();
+        assert_eq!(table.columns().len(), 5);
+        table
+            .parse_row(|row| {
+                assert_eq!(row.fields().len(), 5);
+                assert_eq!(row.get(0), Some("/dev/sda2"));
+                assert_eq!(row.get_by_name("Size"), Some("8388604"));
+                assert_eq!(row.get_by_name("Missing"), None);
+                Ok::<_, Error>(())
+            })
+            .unwrap();
+        let eof = table.parse_row(|_| Ok::<_, Error>(())).unwrap_err();
+        assert_eq!(eof.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,211 @@
+//! Bindings to `/proc/bus/pci/devices`.
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+/// The PCI devices known to the kernel.
+pub struct PciDevices {
+    inner: Vec<PciDevice>,
+}
+
+impl PciDevices {
+    const PATH: &'static str = "/proc/bus/pci/devices";
+    /// Parse the contents of `/proc/bus/pci/devices`.
+    pub fn from_system() -> io::Result<Self> {
+        PciDevices::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/bus/pci/devices`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(PciDevice::from_str) {
+                Ok(device) => inner.push(device),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(PciDevices { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PciDevice> {
+        self.inner.iter()
+    }
+}
+
+impl std::ops::Deref for PciDevices {
+    type Target = Vec<PciDevice>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for PciDevices {
+    type IntoIter = std::vec::IntoIter<PciDevice>;
+    type Item = PciDevice;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// Something that can turn PCI ids into human-readable names, for example a parsed `pci.ids`
+/// database.
+///
+/// This crate doesn't ship a database, so callers can plug in whichever one they have available.
+pub trait NameLookup {
+    /// The name of the vendor with the given id.
+    fn vendor_name(&self, vendor: u16) -> Option<String>;
+    /// The name of the device with the given vendor and device ids.
+    fn device_name(&self, vendor: u16, device: u16) -> Option<String>;
+}
+
+/// A PCI device, from a line of `/proc/bus/pci/devices`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PciDevice {
+    pub bus: u8,
+    pub slot: u8,
+    pub function: u8,
+    pub vendor: u16,
+    pub device: u16,
+    pub irq: u64,
+    /// The 6 standard base address registers followed by the expansion ROM.
+    pub bars: [Bar; 7],
+    /// The name of the driver bound to the device, if any.
+    pub driver: Option<String>,
+}
+
+/// A base address register, with the size of the region it maps.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bar {
+    /// The start of the region.
+    pub address: u64,
+    /// The low flag bits of the register: 2 bits for I/O BARs, 4 for memory BARs.
+    pub flags: u8,
+    /// The size of the region in bytes, `0` if the BAR is unused.
+    pub size: u64,
+}
+
+impl Bar {
+    /// Whether the region is in I/O space rather than memory space.
+    pub fn is_io(&self) -> bool {
+        self.flags & 0x1 != 0
+    }
+
+    /// Whether this BAR maps anything.
+    pub fn is_used(&self) -> bool {
+        self.size != 0
+    }
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+fn parse_hex<'a>(input: &'a str, msg: &str) -> Result<(&'a str, u64), Error> {
+    let (input, token) = err_msg!(util::parse_token(input), msg)?;
+    let value = u64::from_str_radix(token, 16).map_err(|_| Error::from(msg))?;
+    Ok((input, value))
+}
+
+impl PciDevice {
+    fn from_str(input: &str) -> Result<PciDevice, Error> {
+        let (input, bus_devfn) = parse_hex(input, "bus and devfn")?;
+        let (input, ids) = parse_hex(input, "vendor and device ids")?;
+        let (mut input, irq) = parse_hex(input, "irq")?;
+        let mut bars = [Bar::default(); 7];
+        for bar in bars.iter_mut() {
+            let (rest, value) = parse_hex(input, "base address")?;
+            // I/O BARs only use the low 2 bits for flags, memory BARs the low 4.
+            let flag_bits = if value & 0x1 != 0 { 0x3 } else { 0xf };
+            bar.address = value & !flag_bits;
+            bar.flags = (value & flag_bits) as u8;
+            input = rest;
+        }
+        for bar in bars.iter_mut() {
+            let (rest, size) = parse_hex(input, "region size")?;
+            bar.size = size;
+            input = rest;
+        }
+        let driver = util::parse_token(input).map(|(_, driver)| driver.to_owned());
+        Ok(PciDevice {
+            bus: (bus_devfn >> 8) as u8,
+            slot: ((bus_devfn >> 3) & 0x1f) as u8,
+            function: (bus_devfn & 0x7) as u8,
+            vendor: (ids >> 16) as u16,
+            device: ids as u16,
+            irq,
+            bars,
+            driver,
+        })
+    }
+
+    /// The device's name, looked up using the given database.
+    pub fn name(&self, lookup: &impl NameLookup) -> Option<String> {
+        lookup.device_name(self.vendor, self.device)
+    }
+
+    /// The device vendor's name, looked up using the given database.
+    pub fn vendor_name(&self, lookup: &impl NameLookup) -> Option<String> {
+        lookup.vendor_name(self.vendor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NameLookup, PciDevices};
+    use std::io;
+
+    struct Lookup;
+
+    impl NameLookup for Lookup {
+        fn vendor_name(&self, vendor: u16) -> Option<String> {
+            match vendor {
+                0x8086 => Some("Intel Corporation".into()),
+                _ => None,
+            }
+        }
+        fn device_name(&self, vendor: u16, device: u16) -> Option<String> {
+            match (vendor, device) {
+                (0x8086, 0x10d3) => Some("82574L Gigabit Network Connection".into()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn proc_bus_pci_devices() {
+        let raw = "\
+0000\t80861237\t0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0\t               0
+0010\t808610d3\t2b\t        febc0000\t        feba0000\t            c001\t        febe0000\t            e005\t               0\t               0\t           20000\t           20000\t              20\t            4000\t               8\t               0\t               0\te1000e
+";
+        let devices = PciDevices::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].driver, None);
+        let nic = &devices[1];
+        assert_eq!((nic.bus, nic.slot, nic.function), (0, 2, 0));
+        assert_eq!((nic.vendor, nic.device), (0x8086, 0x10d3));
+        assert_eq!(nic.irq, 0x2b);
+        assert_eq!(nic.bars[0].address, 0xfebc0000);
+        assert_eq!(nic.bars[0].size, 0x20000);
+        assert!(nic.bars[2].is_io());
+        assert_eq!(nic.bars[2].address, 0xc000);
+        // Bits 2 and 3 are part of an I/O address.
+        assert!(nic.bars[4].is_io());
+        assert_eq!(nic.bars[4].address, 0xe004);
+        assert_eq!(nic.bars[4].flags, 0x1);
+        assert!(!nic.bars[5].is_used());
+        assert_eq!(nic.driver.as_deref(), Some("e1000e"));
+        assert_eq!(
+            nic.name(&Lookup).as_deref(),
+            Some("82574L Gigabit Network Connection")
+        );
+        assert_eq!(
+            nic.vendor_name(&Lookup).as_deref(),
+            Some("Intel Corporation")
+        );
+    }
+}
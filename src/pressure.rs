@@ -0,0 +1,386 @@
+//! Bindings to `/proc/pressure`, the pressure stall information (PSI) added in Linux 4.20.
+//!
+//! Each file says how much of the time some (or all) non-idle tasks were stalled waiting for a
+//! resource. Cgroup v2 directories have the same files, as `cpu.pressure` and so on.
+//!
+//! With the `write` feature, a `PressureTrigger` asks the kernel to wake you up when stalls pass
+//! a threshold, rather than polling the averages.
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{util, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The resources the kernel tracks pressure for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+    /// Time spent handling interrupts. Added in Linux 6.1, and only has a `full` line.
+    Irq,
+}
+
+impl Resource {
+    /// The file in `/proc/pressure` for this resource.
+    pub fn path(self) -> &'static str {
+        match self {
+            Resource::Cpu => "/proc/pressure/cpu",
+            Resource::Memory => "/proc/pressure/memory",
+            Resource::Io => "/proc/pressure/io",
+            Resource::Irq => "/proc/pressure/irq",
+        }
+    }
+}
+
+/// The pressure on a resource, from a file in `/proc/pressure`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Pressure {
+    /// Time when at least one task was stalled on the resource. `None` for `irq`.
+    pub some: Option<PressureStats>,
+    /// Time when every non-idle task was stalled on the resource at once, so the cpu was doing
+    /// nothing useful. `None` for `cpu` before Linux 5.13.
+    pub full: Option<PressureStats>,
+}
+
+/// A line of a pressure file.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PressureStats {
+    /// The percentage of the last 10 seconds spent stalled.
+    pub avg10: f64,
+    /// The percentage of the last 60 seconds spent stalled.
+    pub avg60: f64,
+    /// The percentage of the last 300 seconds spent stalled.
+    pub avg300: f64,
+    /// The total time spent stalled since boot (or since the cgroup was created).
+    pub total: Duration,
+}
+
+/// Which line of a pressure file a trigger watches.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PressureKind {
+    Some,
+    Full,
+}
+
+impl PressureKind {
+    /// The name of the line, `some` or `full`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PressureKind::Some => "some",
+            PressureKind::Full => "full",
+        }
+    }
+}
+
+impl Pressure {
+    /// Parse the pressure on `resource`, from `/proc/pressure`.
+    pub fn from_system(resource: Resource) -> io::Result<Self> {
+        Pressure::from_path(resource.path())
+    }
+
+    /// Parse a pressure file at `path`, like a cgroup's `memory.pressure`.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Pressure::from_reader(File::open(path)?)
+    }
+
+    /// Parse `/proc/pressure/*`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut pressure = Pressure::default();
+        loop {
+            match reader.parse_line(|line| pressure.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if pressure.some.is_none() && pressure.full.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no some or full line in pressure file",
+            ));
+        }
+        Ok(pressure)
+    }
+
+    /// The stats for one line of the file.
+    pub fn get(&self, kind: PressureKind) -> Option<&PressureStats> {
+        match kind {
+            PressureKind::Some => self.some.as_ref(),
+            PressureKind::Full => self.full.as_ref(),
+        }
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        let (input, kind) = err_msg!(util::parse_token(input), "some or full")?;
+        let stats = PressureStats::from_str(input)?;
+        match kind {
+            "some" => self.some = Some(stats),
+            "full" => self.full = Some(stats),
+            other => return Err(format!("expected some or full, found \"{}\"", other).into()),
+        }
+        Ok(())
+    }
+}
+
+impl PressureStats {
+    fn from_str(input: &str) -> Result<PressureStats, Error> {
+        let input = err_msg!(util::expect_bytes("avg10=", input), "avg10")?;
+        let (input, avg10) = err_msg!(util::parse_f64(input), "avg10")?;
+        let input = err_msg!(util::expect_bytes("avg60=", input), "avg60")?;
+        let (input, avg60) = err_msg!(util::parse_f64(input), "avg60")?;
+        let input = err_msg!(util::expect_bytes("avg300=", input), "avg300")?;
+        let (input, avg300) = err_msg!(util::parse_f64(input), "avg300")?;
+        let input = err_msg!(util::expect_bytes("total=", input), "total")?;
+        let (_, total) = err_msg!(util::parse_u64(input), "total")?;
+        Ok(PressureStats {
+            avg10,
+            avg60,
+            avg300,
+            total: Duration::from_micros(total),
+        })
+    }
+}
+
+#[cfg(feature = "write")]
+pub use self::trigger::PressureTrigger;
+
+#[cfg(feature = "write")]
+mod trigger {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{PressureKind, Resource};
+
+    /// A PSI trigger: a file descriptor that becomes readable (with `POLLPRI`) when tasks are
+    /// stalled on a resource for at least `threshold` within any `window`.
+    ///
+    /// The trigger lasts until it's dropped. Wait for it with `wait`, watch it alongside other
+    /// files with `notify::Notifier::watch_pressure`, or register its file descriptor with your
+    /// own event loop.
+    ///
+    /// ```no_run
+    /// use linux_proc::pressure::{PressureKind, PressureTrigger, Resource};
+    /// use std::time::Duration;
+    ///
+    /// // Wake up when tasks are stalled on memory for 150ms in any second.
+    /// let trigger = PressureTrigger::new(
+    ///     Resource::Memory,
+    ///     PressureKind::Some,
+    ///     Duration::from_millis(150),
+    ///     Duration::from_secs(1),
+    /// )?;
+    /// loop {
+    ///     if trigger.wait(None)? {
+    ///         println!("memory pressure");
+    ///     }
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[derive(Debug)]
+    pub struct PressureTrigger {
+        file: File,
+    }
+
+    impl PressureTrigger {
+        /// The shortest window the kernel accepts.
+        pub const MIN_WINDOW: Duration = Duration::from_millis(500);
+        /// The longest window the kernel accepts.
+        pub const MAX_WINDOW: Duration = Duration::from_secs(10);
+
+        /// Register a trigger on `resource` in `/proc/pressure`.
+        ///
+        /// Before Linux 6.5 this requires `CAP_SYS_RESOURCE`. After, unprivileged users may
+        /// register triggers whose window is a multiple of 2 seconds.
+        pub fn new(
+            resource: Resource,
+            kind: PressureKind,
+            threshold: Duration,
+            window: Duration,
+        ) -> io::Result<Self> {
+            PressureTrigger::for_path(resource.path(), kind, threshold, window)
+        }
+
+        /// Register a trigger on the pressure file at `path`, like a cgroup's
+        /// `memory.pressure`.
+        pub fn for_path(
+            path: impl AsRef<Path>,
+            kind: PressureKind,
+            threshold: Duration,
+            window: Duration,
+        ) -> io::Result<Self> {
+            let spec = PressureTrigger::spec(kind, threshold, window)?;
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(path)?;
+            // The kernel parses the spec from a single write, including the nul terminator.
+            let mut buf = spec.into_bytes();
+            buf.push(0);
+            let written = file.write(&buf)?;
+            if written != buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "short write registering pressure trigger",
+                ));
+            }
+            Ok(PressureTrigger { file })
+        }
+
+        /// The text written to register a trigger, like `some 150000 1000000`.
+        ///
+        /// Fails with `InvalidInput` if the kernel would reject the window or threshold.
+        pub fn spec(
+            kind: PressureKind,
+            threshold: Duration,
+            window: Duration,
+        ) -> io::Result<String> {
+            if window < Self::MIN_WINDOW || window > Self::MAX_WINDOW {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "pressure trigger window must be between 500ms and 10s",
+                ));
+            }
+            if threshold.is_zero() || threshold > window {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "pressure trigger threshold must be positive and no longer than the window",
+                ));
+            }
+            Ok(format!(
+                "{} {} {}",
+                kind.as_str(),
+                threshold.as_micros(),
+                window.as_micros()
+            ))
+        }
+
+        /// Block until the trigger fires, or `timeout` passes. Returns whether it fired.
+        ///
+        /// Fails if the file being watched went away, for example because its cgroup was
+        /// removed.
+        pub fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+            let timeout = match timeout {
+                Some(timeout) => timeout.as_millis().min(i32::MAX as u128) as libc::c_int,
+                None => -1,
+            };
+            let mut fds = [libc::pollfd {
+                fd: self.file.as_raw_fd(),
+                events: libc::POLLPRI,
+                revents: 0,
+            }];
+            loop {
+                match unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout) } {
+                    -1 => {
+                        let e = io::Error::last_os_error();
+                        if e.kind() != io::ErrorKind::Interrupted {
+                            return Err(e);
+                        }
+                    }
+                    0 => return Ok(false),
+                    _ => break,
+                }
+            }
+            if fds[0].revents & libc::POLLERR != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "the pressure file being watched has gone away",
+                ));
+            }
+            Ok(fds[0].revents & libc::POLLPRI != 0)
+        }
+    }
+
+    impl AsRawFd for PressureTrigger {
+        fn as_raw_fd(&self) -> RawFd {
+            self.file.as_raw_fd()
+        }
+    }
+
+    impl AsFd for PressureTrigger {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.file.as_fd()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pressure, PressureKind, Resource};
+    use crate::ReadOutcome;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_pressure() {
+        let raw = "\
+some avg10=3.26 avg60=5.55 avg300=5.20 total=396847878
+full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+";
+        let pressure = Pressure::from_reader(io::Cursor::new(raw)).unwrap();
+        let some = pressure.get(PressureKind::Some).unwrap();
+        assert_eq!(some.avg10, 3.26);
+        assert_eq!(some.avg300, 5.2);
+        assert_eq!(some.total, Duration::from_micros(396847878));
+        assert_eq!(pressure.full.unwrap().avg60, 0.0);
+
+        let irq = "full avg10=0.10 avg60=0.00 avg300=0.00 total=1234\n";
+        let pressure = Pressure::from_reader(io::Cursor::new(irq)).unwrap();
+        assert_eq!(pressure.some, None);
+        assert_eq!(pressure.full.unwrap().total, Duration::from_micros(1234));
+
+        for raw in &[
+            "",
+            "half avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+            "some avg10=0.00\n",
+        ] {
+            assert!(Pressure::from_reader(io::Cursor::new(raw)).is_err());
+        }
+    }
+
+    #[test]
+    fn from_system() {
+        if let ReadOutcome::Ok(pressure) =
+            ReadOutcome::from(Pressure::from_system(Resource::Memory))
+        {
+            assert!(pressure.some.is_some());
+        }
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn trigger_spec() {
+        use super::PressureTrigger;
+
+        let spec = PressureTrigger::spec(
+            PressureKind::Some,
+            Duration::from_millis(150),
+            Duration::from_secs(1),
+        );
+        assert_eq!(spec.unwrap(), "some 150000 1000000");
+        for (threshold, window) in &[
+            (Duration::from_millis(150), Duration::from_millis(100)),
+            (Duration::from_secs(20), Duration::from_secs(20)),
+            (Duration::from_secs(0), Duration::from_secs(1)),
+            (Duration::from_secs(2), Duration::from_secs(1)),
+        ] {
+            let err = PressureTrigger::spec(PressureKind::Full, *threshold, *window).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Decoding the capability masks in `/proc/[pid]/status`.
+use std::fmt;
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// A set of capabilities (see `capabilities(7)`), as in the `Cap*` fields of
+    /// `/proc/[pid]/status`.
+    ///
+    /// Displays as a comma-separated list of names, like `CAP_NET_ADMIN,CAP_SYS_PTRACE`, with
+    /// capabilities added after this crate was written shown by number.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+    pub struct Capabilities: u64 {
+        const CHOWN = 1 << 0;
+        const DAC_OVERRIDE = 1 << 1;
+        const DAC_READ_SEARCH = 1 << 2;
+        const FOWNER = 1 << 3;
+        const FSETID = 1 << 4;
+        const KILL = 1 << 5;
+        const SETGID = 1 << 6;
+        const SETUID = 1 << 7;
+        const SETPCAP = 1 << 8;
+        const LINUX_IMMUTABLE = 1 << 9;
+        const NET_BIND_SERVICE = 1 << 10;
+        const NET_BROADCAST = 1 << 11;
+        const NET_ADMIN = 1 << 12;
+        const NET_RAW = 1 << 13;
+        const IPC_LOCK = 1 << 14;
+        const IPC_OWNER = 1 << 15;
+        const SYS_MODULE = 1 << 16;
+        const SYS_RAWIO = 1 << 17;
+        const SYS_CHROOT = 1 << 18;
+        const SYS_PTRACE = 1 << 19;
+        const SYS_PACCT = 1 << 20;
+        /// The catch-all administrative capability, which implies many others in practice.
+        const SYS_ADMIN = 1 << 21;
+        const SYS_BOOT = 1 << 22;
+        const SYS_NICE = 1 << 23;
+        const SYS_RESOURCE = 1 << 24;
+        const SYS_TIME = 1 << 25;
+        const SYS_TTY_CONFIG = 1 << 26;
+        const MKNOD = 1 << 27;
+        const LEASE = 1 << 28;
+        const AUDIT_WRITE = 1 << 29;
+        const AUDIT_CONTROL = 1 << 30;
+        const SETFCAP = 1 << 31;
+        const MAC_OVERRIDE = 1 << 32;
+        const MAC_ADMIN = 1 << 33;
+        const SYSLOG = 1 << 34;
+        const WAKE_ALARM = 1 << 35;
+        const BLOCK_SUSPEND = 1 << 36;
+        const AUDIT_READ = 1 << 37;
+        /// Performance monitoring (since 5.8; before that, `SYS_ADMIN`).
+        const PERFMON = 1 << 38;
+        /// Loading BPF programs (since 5.8; before that, `SYS_ADMIN`).
+        const BPF = 1 << 39;
+        const CHECKPOINT_RESTORE = 1 << 40;
+
+        // Unknown bits are kept.
+        const _ = !0;
+    }
+}
+
+impl Capabilities {
+    /// The capability numbered `number`, as in `<linux/capability.h>`, e.g. `12` for
+    /// `NET_ADMIN`.
+    pub fn from_number(number: u32) -> Option<Self> {
+        1u64.checked_shl(number).map(Capabilities::from_bits_retain)
+    }
+
+    /// The numbers of the capabilities in the set, in increasing order.
+    pub fn numbers(self) -> impl Iterator<Item = u32> {
+        (0..u64::BITS).filter(move |number| self.bits() & (1 << number) != 0)
+    }
+
+    /// The name of the capability numbered `number`, like `CAP_NET_ADMIN`, if it's known.
+    pub fn name(number: u32) -> Option<&'static str> {
+        CAP_NAMES.get(number as usize).copied()
+    }
+}
+
+/// The names of the known capabilities, by number.
+const CAP_NAMES: [&str; 41] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, number) in self.numbers().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            match Capabilities::name(number) {
+                Some(name) => f.write_str(name)?,
+                None => write!(f, "{}", number)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Capabilities, CAP_NAMES};
+
+    #[test]
+    fn capabilities() {
+        // Docker's default set.
+        let docker = Capabilities::from_bits_retain(0xa80425fb);
+        assert!(docker.contains(Capabilities::NET_BIND_SERVICE | Capabilities::KILL));
+        assert!(!docker.contains(Capabilities::SYS_ADMIN));
+        assert_eq!(
+            docker.to_string(),
+            "CAP_CHOWN,CAP_DAC_OVERRIDE,CAP_FOWNER,CAP_FSETID,CAP_KILL,CAP_SETGID,CAP_SETUID,\
+             CAP_SETPCAP,CAP_NET_BIND_SERVICE,CAP_NET_RAW,CAP_SYS_CHROOT,CAP_MKNOD,\
+             CAP_AUDIT_WRITE,CAP_SETFCAP"
+        );
+        let extra = Capabilities::NET_ADMIN | Capabilities::from_number(45).unwrap();
+        assert_eq!((docker | extra).numbers().count(), 16);
+        assert_eq!(extra.to_string(), "CAP_NET_ADMIN,45");
+        assert_eq!(Capabilities::empty().to_string(), "");
+        assert_eq!(Capabilities::from_number(64), None);
+        assert_eq!(Capabilities::name(19), Some("CAP_SYS_PTRACE"));
+        assert_eq!(Capabilities::name(41), None);
+        for (number, name) in CAP_NAMES.iter().enumerate() {
+            let flag = Capabilities::from_number(number as u32).unwrap();
+            assert_eq!(flag.iter_names().next().unwrap().0, &name[4..]);
+        }
+    }
+}
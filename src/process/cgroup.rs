@@ -0,0 +1,81 @@
+//! Bindings to `/proc/[pid]/cgroup`.
+use std::io;
+use std::path::PathBuf;
+
+use crate::{util, Error};
+
+/// The cgroup a process belongs to in one hierarchy, from a line of `/proc/[pid]/cgroup`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Cgroup {
+    /// The hierarchy id. Always `0` for the cgroup v2 unified hierarchy.
+    pub hierarchy: u32,
+    /// The controllers bound to the hierarchy, e.g. `cpu` and `cpuacct`. Empty for cgroup v2.
+    pub controllers: Vec<String>,
+    /// The path of the cgroup, relative to the root of the hierarchy.
+    pub path: PathBuf,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Parse `/proc/[pid]/cgroup`-formatted data from `reader`.
+pub(crate) fn from_reader(reader: impl io::Read) -> io::Result<Vec<Cgroup>> {
+    let mut reader = util::LineParser::new(reader);
+    let mut cgroups = Vec::new();
+    loop {
+        match reader.parse_line(Cgroup::from_str) {
+            Ok(cgroup) => cgroups.push(cgroup),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(cgroups)
+}
+
+impl Cgroup {
+    fn from_str(input: &str) -> Result<Cgroup, Error> {
+        let input = input.trim_end_matches(['\n', '\r']);
+        let (hierarchy, rest) = err_msg!(input.split_once(':'), "expected \":\"")?;
+        let hierarchy = hierarchy.parse().map_err(|_| Error::from("hierarchy id"))?;
+        // The path may contain colons, so only split off the controllers.
+        let (controllers, path) = err_msg!(rest.split_once(':'), "expected \":\"")?;
+        let controllers = controllers
+            .split(',')
+            .filter(|controller| !controller.is_empty())
+            .map(String::from)
+            .collect();
+        Ok(Cgroup {
+            hierarchy,
+            controllers,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::Path;
+
+    #[test]
+    fn proc_pid_cgroup() {
+        let raw = "\
+12:cpu,cpuacct:/system.slice/docker-4f1c.scope
+1:name=systemd:/system.slice/docker-4f1c.scope
+0::/system.slice/docker-4f1c.scope
+";
+        let cgroups = super::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(cgroups.len(), 3);
+        assert_eq!(cgroups[0].controllers, vec!["cpu", "cpuacct"]);
+        assert_eq!(cgroups[1].controllers, vec!["name=systemd"]);
+        assert_eq!(cgroups[2].hierarchy, 0);
+        assert!(cgroups[2].controllers.is_empty());
+        assert_eq!(
+            cgroups[2].path,
+            Path::new("/system.slice/docker-4f1c.scope")
+        );
+    }
+}
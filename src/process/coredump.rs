@@ -0,0 +1,60 @@
+//! Bindings to `/proc/[pid]/coredump_filter`.
+use std::io;
+
+use bitflags::bitflags;
+
+use crate::{util, Error};
+
+bitflags! {
+    /// Which kinds of memory mapping are written to a core dump (see `core(5)`).
+    ///
+    /// The kernel default is `ANON_PRIVATE | ANON_SHARED | ELF_HEADERS | HUGETLB_PRIVATE`.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct CoredumpFilter: u32 {
+        const ANON_PRIVATE = 1 << 0;
+        const ANON_SHARED = 1 << 1;
+        const FILE_PRIVATE = 1 << 2;
+        const FILE_SHARED = 1 << 3;
+        /// The first page of each mapped ELF file, enough to identify the build.
+        const ELF_HEADERS = 1 << 4;
+        const HUGETLB_PRIVATE = 1 << 5;
+        const HUGETLB_SHARED = 1 << 6;
+        const DAX_PRIVATE = 1 << 7;
+        const DAX_SHARED = 1 << 8;
+
+        // Unknown bits are kept.
+        const _ = !0;
+    }
+}
+
+impl CoredumpFilter {
+    /// Parse `/proc/[pid]/coredump_filter`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(Self::from_str)
+    }
+
+    fn from_str(input: &str) -> Result<CoredumpFilter, Error> {
+        let (_, value) = util::parse_hex_u64(input).ok_or("coredump filter")?;
+        Ok(CoredumpFilter::from_bits_retain(value as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoredumpFilter;
+    use std::io;
+
+    #[test]
+    fn proc_pid_coredump_filter() {
+        let filter = CoredumpFilter::from_reader(io::Cursor::new("00000033\n")).unwrap();
+        assert_eq!(
+            filter,
+            CoredumpFilter::ANON_PRIVATE
+                | CoredumpFilter::ANON_SHARED
+                | CoredumpFilter::ELF_HEADERS
+                | CoredumpFilter::HUGETLB_PRIVATE
+        );
+        assert!(CoredumpFilter::from_reader(io::Cursor::new("zz\n")).is_err());
+    }
+}
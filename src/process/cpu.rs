@@ -0,0 +1,193 @@
+//! Per-process cpu usage, like the `%CPU` column in `top`.
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use super::{exited, Process, ProcessStat};
+use crate::stat::{Stat, StatOptions};
+use crate::{util, RatioFormat, RatioUnit};
+
+/// Calculates the cpu usage of processes between successive samples.
+///
+/// Processes are tracked by pid and start time, so a pid that is reused between samples is
+/// treated as a new process rather than producing a bogus (possibly negative) delta. Processes
+/// that exit are forgotten.
+#[derive(Debug, Clone)]
+pub struct ProcessCpuTracker {
+    ticks_per_sec: u64,
+    /// The system-wide cpu time at the last sample, and the number of cpus.
+    last_total: Option<(u64, usize)>,
+    /// `(starttime, utime + stime)` for each pid at the last sample.
+    last: HashMap<u32, (u64, u64)>,
+}
+
+/// The cpu usage of a process between two samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessCpuUsage {
+    pub pid: u32,
+    /// The filename of the executable, from `ProcessStat::comm`.
+    pub comm: String,
+    /// The cpu time used, in user and system mode.
+    pub cpu_time: Duration,
+    /// The cpu used as a percentage of one cpu, so a process with 4 busy threads can reach 400%.
+    pub percent: f64,
+}
+
+impl ProcessCpuUsage {
+    /// The cpu used as a fraction of one cpu, expressed in `format`.
+    pub fn cpu_in(&self, format: RatioFormat) -> f64 {
+        format.apply(self.percent / 100.0)
+    }
+
+    /// The cpu used in thousandths of `unit` of one cpu (see `RatioUnit::milli`).
+    pub fn cpu_milli(&self, unit: RatioUnit) -> u64 {
+        unit.milli(self.percent / 100.0)
+    }
+}
+
+impl Default for ProcessCpuTracker {
+    fn default() -> Self {
+        ProcessCpuTracker::new()
+    }
+}
+
+impl ProcessCpuTracker {
+    /// A tracker with no samples.
+    pub fn new() -> Self {
+        ProcessCpuTracker {
+            ticks_per_sec: super::clock_ticks_per_sec(),
+            last_total: None,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Read `/proc/stat` and `/proc/[pid]/stat` for every process, and calculate each process's
+    /// usage since the last sample.
+    ///
+    /// The first sample returns nothing, since there is nothing to compare it to.
+    pub fn sample(&mut self) -> io::Result<Vec<ProcessCpuUsage>> {
+        let options = StatOptions::new().interrupts(false).softirqs(false);
+        let stat = Stat::parse_with(options)?;
+        let mut processes = Vec::new();
+        for process in Process::all()? {
+            match process?.stat() {
+                Ok(stat) => processes.push(stat),
+                Err(ref e) if exited(e) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(self.update(&stat, processes))
+    }
+
+    /// Calculate the usage of `processes` since the last sample, given a `Stat` read at the same
+    /// time (including the per-cpu lines).
+    ///
+    /// The first sample returns nothing, since there is nothing to compare it to.
+    pub fn update(
+        &mut self,
+        stat: &Stat,
+        processes: impl IntoIterator<Item = ProcessStat>,
+    ) -> Vec<ProcessCpuUsage> {
+        let total = stat.cpu_totals.total().get();
+        let cpus = stat.cpus.len().max(1);
+        let previous = std::mem::take(&mut self.last);
+        let last_total = self.last_total.replace((total, cpus));
+        let mut usage = Vec::new();
+        for process in processes {
+            let ticks = process.utime.saturating_add(process.stime);
+            self.last.insert(process.pid, (process.starttime, ticks));
+            let (last_total, last_cpus) = match last_total {
+                Some(last_total) => last_total,
+                None => continue,
+            };
+            let delta = match previous.get(&process.pid) {
+                Some(&(starttime, last_ticks)) if starttime == process.starttime => {
+                    ticks.saturating_sub(last_ticks)
+                }
+                // A new process (or a reused pid): everything it has used was since the last
+                // sample.
+                _ => ticks,
+            };
+            // The total is summed over all cpus, so scale it to the time elapsed on one.
+            let elapsed = total.saturating_sub(last_total) as f64 / cpus.max(last_cpus) as f64;
+            usage.push(ProcessCpuUsage {
+                pid: process.pid,
+                comm: process.comm,
+                // The rate from `sysconf` is never 0.
+                cpu_time: util::ticks_to_duration(delta, self.ticks_per_sec).unwrap_or_default(),
+                percent: if elapsed > 0.0 {
+                    delta as f64 / elapsed * 100.0
+                } else {
+                    0.0
+                },
+            });
+        }
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessCpuTracker;
+    use crate::process::{stat::test_stat, ProcessStat};
+    use crate::stat::Stat;
+    use crate::{RatioFormat, RatioUnit};
+    use std::io;
+    use std::time::Duration;
+
+    fn stat(total_user: u64) -> Stat {
+        let raw = format!(
+            "\
+cpu  {} 0 0 0 0 0 0 0 0 0
+cpu0 0 0 0 0 0 0 0 0 0 0
+cpu1 0 0 0 0 0 0 0 0 0 0
+intr 0
+ctxt 0
+btime 0
+processes 0
+procs_running 0
+procs_blocked 0
+softirq 0
+",
+            total_user
+        );
+        Stat::from_reader(io::Cursor::new(raw)).unwrap()
+    }
+
+    fn process(pid: u32, utime: u64, starttime: u64) -> ProcessStat {
+        test_stat(pid, 'S', utime, starttime)
+    }
+
+    #[test]
+    fn process_cpu_tracker() {
+        let mut tracker = ProcessCpuTracker::new();
+        tracker.ticks_per_sec = 100;
+        let first = tracker.update(&stat(1000), vec![process(10, 100, 5), process(11, 50, 5)]);
+        assert!(first.is_empty());
+        // 200 ticks across 2 cpus is 100 ticks of wall time.
+        let second = tracker.update(
+            &stat(1200),
+            vec![
+                process(10, 150, 5),
+                process(11, 20, 900),
+                process(12, 10, 1100),
+            ],
+        );
+        assert_eq!(second.len(), 3);
+        assert_eq!(second[0].percent, 50.0);
+        assert_eq!(second[0].cpu_time, Duration::from_millis(500));
+        assert_eq!(second[0].cpu_in(RatioFormat::new()), 0.5);
+        assert_eq!(second[0].cpu_milli(RatioUnit::Percent), 50_000);
+        // pid 11 was reused, and pid 12 is new
+        assert_eq!(second[1].percent, 20.0);
+        assert_eq!(second[2].percent, 10.0);
+    }
+
+    #[test]
+    fn sample() {
+        let mut tracker = ProcessCpuTracker::new();
+        assert!(tracker.sample().unwrap().is_empty());
+        let usage = tracker.sample().unwrap();
+        assert!(usage.iter().any(|usage| usage.pid == std::process::id()));
+    }
+}
@@ -0,0 +1,155 @@
+//! Process start, exit and state changes, from comparing two scans.
+use std::collections::HashMap;
+
+use super::{ProcessKey, ProcessSnapshot, ProcessStat, ProcessState};
+
+/// The differences between two `ProcessScan`s: processes that started, exited, or changed state.
+///
+/// This is a polling approximation of the process events the kernel reports over netlink, which
+/// needs `CAP_NET_ADMIN`. Processes that start and exit between the two scans aren't seen at all.
+/// Processes are matched by `ProcessKey`, so a reused pid shows up as one process exiting and
+/// another starting. Snapshots without `stat` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessSetDiff {
+    /// Processes only in the later scan, in pid order.
+    pub started: Vec<ProcessEvent>,
+    /// Processes only in the earlier scan, in pid order, as they were then.
+    pub exited: Vec<ProcessEvent>,
+    /// Processes in both scans whose state differs, in pid order.
+    pub state_changed: Vec<StateChange>,
+}
+
+/// A process that started or exited.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessEvent {
+    pub key: ProcessKey,
+    /// The pid of the parent process.
+    pub ppid: u32,
+    /// The filename of the executable, from `ProcessStat::comm`.
+    pub comm: String,
+    pub state: ProcessState,
+}
+
+/// A process whose state changed between the two scans.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StateChange {
+    pub key: ProcessKey,
+    /// The filename of the executable in the later scan.
+    pub comm: String,
+    pub from: ProcessState,
+    pub to: ProcessState,
+}
+
+impl ProcessEvent {
+    fn new(stat: &ProcessStat) -> Self {
+        ProcessEvent {
+            key: stat.key(),
+            ppid: stat.ppid,
+            comm: stat.comm.clone(),
+            state: stat.process_state(),
+        }
+    }
+}
+
+impl ProcessSetDiff {
+    /// Compare the processes in two scans.
+    pub fn between(earlier: &[ProcessSnapshot], later: &[ProcessSnapshot]) -> Self {
+        let before = stats(earlier);
+        let after = stats(later);
+        let mut diff = ProcessSetDiff::default();
+        for (key, stat) in &after {
+            match before.get(key) {
+                None => diff.started.push(ProcessEvent::new(stat)),
+                Some(prev) if prev.state != stat.state => diff.state_changed.push(StateChange {
+                    key: *key,
+                    comm: stat.comm.clone(),
+                    from: prev.process_state(),
+                    to: stat.process_state(),
+                }),
+                Some(_) => (),
+            }
+        }
+        diff.exited = before
+            .iter()
+            .filter(|(key, _)| !after.contains_key(key))
+            .map(|(_, stat)| ProcessEvent::new(stat))
+            .collect();
+        diff.started.sort_by_key(|event| event.key);
+        diff.exited.sort_by_key(|event| event.key);
+        diff.state_changed.sort_by_key(|change| change.key);
+        diff
+    }
+
+    /// Whether nothing started, exited or changed state.
+    pub fn is_empty(&self) -> bool {
+        self.started.is_empty() && self.exited.is_empty() && self.state_changed.is_empty()
+    }
+}
+
+fn stats(scan: &[ProcessSnapshot]) -> HashMap<ProcessKey, &ProcessStat> {
+    scan.iter()
+        .filter_map(|snapshot| snapshot.stat.as_ref())
+        .map(|stat| (stat.key(), stat))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessSetDiff;
+    use crate::process::{stat::test_stat, ProcessScan, ProcessSnapshot, ProcessState};
+
+    fn snapshot(pid: u32, state: char, starttime: u64) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            stat: Some(test_stat(pid, state, 0, starttime)),
+            status: None,
+            io: None,
+        }
+    }
+
+    #[test]
+    fn process_set_diff() {
+        let earlier = vec![
+            snapshot(1, 'S', 5),
+            snapshot(20, 'R', 100),
+            snapshot(30, 'S', 200),
+            snapshot(40, 'S', 300),
+        ];
+        let later = vec![
+            snapshot(1, 'S', 5),
+            snapshot(20, 'Z', 100),
+            snapshot(30, 'R', 900),
+            snapshot(50, 'D', 950),
+        ];
+        let diff = ProcessSetDiff::between(&earlier, &later);
+        let pids = |events: &[super::ProcessEvent]| {
+            events.iter().map(|event| event.key.pid).collect::<Vec<_>>()
+        };
+        // pid 30 was reused, so it both exited and started.
+        assert_eq!(pids(&diff.started), vec![30, 50]);
+        assert_eq!(diff.started[0].key.starttime, 900);
+        assert_eq!(diff.started[1].state, ProcessState::DiskSleep);
+        assert_eq!(pids(&diff.exited), vec![30, 40]);
+        assert_eq!(diff.exited[1].comm, "proc40");
+        assert_eq!(diff.state_changed.len(), 1);
+        let change = &diff.state_changed[0];
+        assert_eq!(change.key.pid, 20);
+        assert_eq!(
+            (change.from, change.to),
+            (ProcessState::Running, ProcessState::Zombie)
+        );
+        assert!(ProcessSetDiff::between(&later, &later).is_empty());
+    }
+
+    #[test]
+    fn scans() {
+        let scan = ProcessScan::new();
+        let earlier = scan.run().unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        // Until it's reaped the child is at least a zombie, so it's in the scan.
+        let later = scan.run().unwrap();
+        child.wait().unwrap();
+        let diff = ProcessSetDiff::between(&earlier, &later);
+        assert!(diff.started.iter().any(|event| event.key.pid == child.id()));
+    }
+}
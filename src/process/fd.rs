@@ -0,0 +1,164 @@
+//! Bindings to `/proc/[pid]/fd`.
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+/// An open file descriptor.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Fd {
+    /// The file descriptor number.
+    pub fd: u32,
+    /// What the file descriptor refers to.
+    pub target: FdTarget,
+}
+
+/// What a file descriptor refers to, decoded from the target of its `/proc/[pid]/fd` symlink.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FdTarget {
+    /// A file on a filesystem.
+    Path(PathBuf),
+    /// A socket, with its inode number (`socket:[inode]`).
+    Socket(u64),
+    /// A pipe, with its inode number (`pipe:[inode]`).
+    Pipe(u64),
+    /// An anonymous inode, with its type, e.g. `eventfd` or `[eventpoll]`.
+    AnonInode(String),
+    /// Anything else.
+    Other(String),
+}
+
+impl FdTarget {
+    fn from_link(link: PathBuf) -> FdTarget {
+        if link.is_absolute() {
+            return FdTarget::Path(link);
+        }
+        let link = link.to_string_lossy();
+        let inode = |prefix: &str| {
+            link.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_prefix('['))
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|inode| inode.parse().ok())
+        };
+        if let Some(inode) = inode("socket:") {
+            FdTarget::Socket(inode)
+        } else if let Some(inode) = inode("pipe:") {
+            FdTarget::Pipe(inode)
+        } else if let Some(kind) = link.strip_prefix("anon_inode:") {
+            FdTarget::AnonInode(kind.to_owned())
+        } else {
+            FdTarget::Other(link.into_owned())
+        }
+    }
+}
+
+/// Read the file descriptors in a `/proc/[pid]/fd` directory.
+///
+/// File descriptors closed while we are reading are skipped.
+pub(crate) fn read_dir(dir: &Path) -> io::Result<Vec<Fd>> {
+    let mut fds = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let fd = match entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        {
+            Some(fd) => fd,
+            None => continue,
+        };
+        let link = match fs::read_link(entry.path()) {
+            Ok(link) => link,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        fds.push(Fd {
+            fd,
+            target: FdTarget::from_link(link),
+        });
+    }
+    fds.sort_by_key(|fd| fd.fd);
+    Ok(fds)
+}
+
+/// An inotify instance held open by a process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct InotifyInstance {
+    /// The file descriptor of the instance.
+    pub fd: u32,
+    /// The number of watches registered on the instance.
+    pub watches: usize,
+}
+
+/// Find the inotify instances in a `/proc/[pid]/fd` directory, counting the watches on each from
+/// the matching `/proc/[pid]/fdinfo` entry.
+pub(crate) fn inotify_instances(root: &Path) -> io::Result<Vec<InotifyInstance>> {
+    let mut instances = Vec::new();
+    for fd in read_dir(&root.join("fd"))? {
+        if fd.target != FdTarget::AnonInode("inotify".into()) {
+            continue;
+        }
+        let info = match fs::File::open(root.join("fdinfo").join(fd.fd.to_string())) {
+            Ok(info) => info,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        instances.push(InotifyInstance {
+            fd: fd.fd,
+            watches: count_inotify_watches(info)?,
+        });
+    }
+    Ok(instances)
+}
+
+/// Count the `inotify wd:...` lines in a `/proc/[pid]/fdinfo/[fd]` file.
+fn count_inotify_watches(reader: impl io::Read) -> io::Result<usize> {
+    let mut count = 0;
+    for line in io::BufReader::new(reader).lines() {
+        if line?.starts_with("inotify ") {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FdTarget;
+    use std::io;
+    use std::path::PathBuf;
+
+    #[test]
+    fn fd_targets() {
+        let target = |link: &str| FdTarget::from_link(PathBuf::from(link));
+        assert_eq!(
+            target("/dev/null"),
+            FdTarget::Path(PathBuf::from("/dev/null"))
+        );
+        assert_eq!(target("socket:[24689]"), FdTarget::Socket(24689));
+        assert_eq!(target("pipe:[1234]"), FdTarget::Pipe(1234));
+        assert_eq!(
+            target("anon_inode:[eventpoll]"),
+            FdTarget::AnonInode("[eventpoll]".into())
+        );
+        assert_eq!(
+            target("net:[4026531840]"),
+            FdTarget::Other("net:[4026531840]".into())
+        );
+    }
+
+    #[test]
+    fn proc_pid_fdinfo_inotify() {
+        let raw = "\
+pos:	0
+flags:	02004000
+mnt_id:	15
+ino:	1057
+inotify wd:2 ino:7ac sdev:800001 mask:fc6 ignored_mask:0 fhandle-bytes:8 fhandle-type:1 f_handle:ac07000000000000
+inotify wd:1 ino:2 sdev:800001 mask:fc6 ignored_mask:0 fhandle-bytes:8 fhandle-type:1 f_handle:0200000000000000
+";
+        assert_eq!(
+            super::count_inotify_watches(io::Cursor::new(raw)).unwrap(),
+            2
+        );
+    }
+}
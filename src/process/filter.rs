@@ -0,0 +1,253 @@
+//! Listing and searching the processes in `/proc`.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{exited, Process};
+use crate::ByteSize;
+
+/// An iterator over the processes in `/proc`, from `Process::all`.
+///
+/// Processes are listed in the order the kernel returns them, which is by pid.
+#[derive(Debug)]
+pub struct Processes {
+    dir: fs::ReadDir,
+}
+
+impl Processes {
+    pub(crate) fn new() -> io::Result<Processes> {
+        Ok(Processes {
+            dir: fs::read_dir("/proc")?,
+        })
+    }
+
+    /// Only yield processes matching `filter`.
+    ///
+    /// Each process's files are only read as far as needed to rule it out. Processes that exit
+    /// while being checked are skipped.
+    pub fn filter_by(self, filter: ProcessFilter) -> FilteredProcesses {
+        FilteredProcesses {
+            processes: self,
+            filter,
+        }
+    }
+}
+
+impl Iterator for Processes {
+    type Item = io::Result<Process>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.dir.next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            let pid = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok());
+            if let Some(pid) = pid {
+                return Some(Ok(Process::from_pid(pid)));
+            }
+        }
+    }
+}
+
+/// An iterator over the processes matching a `ProcessFilter`, from `Processes::filter_by`.
+#[derive(Debug)]
+pub struct FilteredProcesses {
+    processes: Processes,
+    filter: ProcessFilter,
+}
+
+impl Iterator for FilteredProcesses {
+    type Item = io::Result<Process>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let process = match self.processes.next()? {
+                Ok(process) => process,
+                Err(e) => return Some(Err(e)),
+            };
+            match self.filter.matches(&process) {
+                Ok(true) => return Some(Ok(process)),
+                Ok(false) => (),
+                Err(ref e) if exited(e) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Criteria for selecting processes, like the options to `pgrep`.
+///
+/// A process must match every criterion that is set. Criteria are checked from cheapest to
+/// dearest: `/proc/[pid]/status` is read for the name, uid, state, ppid and rss,
+/// `/proc/[pid]/stat` for cpu time and `/proc/[pid]/cgroup` for the cgroup.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessFilter {
+    name: Option<String>,
+    #[cfg(feature = "regex")]
+    name_regex: Option<regex::Regex>,
+    uid: Option<u32>,
+    state: Option<char>,
+    ppid: Option<u32>,
+    cgroup: Option<PathBuf>,
+    min_cpu_ticks: Option<u64>,
+    min_rss: Option<ByteSize>,
+}
+
+impl ProcessFilter {
+    /// A filter matching every process.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only processes whose name (`Status::name`, truncated to 15 bytes by the kernel) is
+    /// exactly `name`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only processes whose name (`Status::name`) matches `regex`.
+    #[cfg(feature = "regex")]
+    pub fn name_regex(mut self, regex: regex::Regex) -> Self {
+        self.name_regex = Some(regex);
+        self
+    }
+
+    /// Only processes with effective user id `uid`.
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Only processes in state `state`, e.g. `'Z'` for zombies.
+    pub fn state(mut self, state: char) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Only children of process `ppid`.
+    pub fn ppid(mut self, ppid: u32) -> Self {
+        self.ppid = Some(ppid);
+        self
+    }
+
+    /// Only processes in the cgroup `path`, or a cgroup below it, in any hierarchy.
+    pub fn cgroup(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cgroup = Some(path.into());
+        self
+    }
+
+    /// Only processes that have used at least `ticks` clock ticks of cpu time (user plus system).
+    pub fn min_cpu_ticks(mut self, ticks: u64) -> Self {
+        self.min_cpu_ticks = Some(ticks);
+        self
+    }
+
+    /// Only processes with a resident set size of at least `rss`. Kernel threads never match.
+    pub fn min_rss(mut self, rss: ByteSize) -> Self {
+        self.min_rss = Some(rss);
+        self
+    }
+
+    fn needs_status(&self) -> bool {
+        #[cfg(feature = "regex")]
+        {
+            if self.name_regex.is_some() {
+                return true;
+            }
+        }
+        self.name.is_some()
+            || self.uid.is_some()
+            || self.state.is_some()
+            || self.ppid.is_some()
+            || self.min_rss.is_some()
+    }
+
+    /// Whether `process` matches, reading only the files needed to decide.
+    pub fn matches(&self, process: &Process) -> io::Result<bool> {
+        if self.needs_status() {
+            let status = process.status()?;
+            let rejected = self.name.as_ref().is_some_and(|name| *name != status.name)
+                || self.uid.is_some_and(|uid| uid != status.uid[1])
+                || self.state.is_some_and(|state| state != status.state)
+                || self.ppid.is_some_and(|ppid| ppid != status.ppid)
+                || self
+                    .min_rss
+                    .is_some_and(|min| status.vm_rss.is_none_or(|rss| rss < min));
+            if rejected {
+                return Ok(false);
+            }
+            #[cfg(feature = "regex")]
+            {
+                if let Some(ref regex) = self.name_regex {
+                    if !regex.is_match(&status.name) {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        if let Some(min) = self.min_cpu_ticks {
+            let stat = process.stat()?;
+            if stat.utime.saturating_add(stat.stime) < min {
+                return Ok(false);
+            }
+        }
+        if let Some(ref path) = self.cgroup {
+            let cgroups = process.cgroups()?;
+            if !cgroups
+                .iter()
+                .any(|cgroup| Path::new(&cgroup.path).starts_with(path))
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessFilter;
+    use crate::process::Process;
+    use crate::ByteSize;
+
+    #[test]
+    fn filter_current_process() {
+        let current = Process::current();
+        let status = current.status().unwrap();
+        let pid = std::process::id();
+        let find = |filter: ProcessFilter| {
+            Process::all()
+                .unwrap()
+                .filter_by(filter)
+                .map(|process| process.unwrap().pid().unwrap())
+                .any(|found| found == pid)
+        };
+        assert!(find(ProcessFilter::new()));
+        assert!(find(
+            ProcessFilter::new()
+                .name(status.name.clone())
+                .ppid(status.ppid)
+                .uid(status.uid[1])
+                .cgroup("/")
+        ));
+        assert!(!find(ProcessFilter::new().ppid(pid)));
+        assert!(!find(ProcessFilter::new().min_rss(ByteSize(u64::MAX))));
+        assert!(!find(ProcessFilter::new().cgroup("/does/not/exist")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn filter_by_regex() {
+        let status = Process::current().status().unwrap();
+        let filter = ProcessFilter::new()
+            .name_regex(regex::Regex::new(&format!("^{}$", regex::escape(&status.name))).unwrap());
+        assert!(filter.matches(&Process::current()).unwrap());
+        let filter = ProcessFilter::new().name_regex(regex::Regex::new("^$").unwrap());
+        assert!(!filter.matches(&Process::current()).unwrap());
+    }
+}
@@ -0,0 +1,199 @@
+//! Resource usage summed over groups of processes, such as cgroups or containers.
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use super::{exited, Cgroup, Process};
+use crate::ByteSize;
+
+/// How to group processes in `ResourceGroups`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GroupBy {
+    /// By cgroup path. The cgroup v2 path is used if there is one, otherwise the path in the
+    /// first v1 hierarchy.
+    Cgroup,
+    /// By container id, extracted from the cgroup path with `container_id`. Processes that aren't
+    /// in a container are left out.
+    Container,
+}
+
+/// The resources used by a group of processes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GroupUsage {
+    /// The number of processes in the group.
+    pub processes: usize,
+    /// Cpu time used (user plus system), in clock ticks.
+    pub cpu_ticks: u64,
+    /// The total resident set size. Memory shared between processes is counted more than once.
+    pub rss: ByteSize,
+    /// Bytes fetched from the storage layer.
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer.
+    pub write_bytes: u64,
+    /// The number of processes whose `/proc/[pid]/io` couldn't be read (it is only readable by
+    /// the process's owner), and so aren't counted in `read_bytes` and `write_bytes`.
+    pub io_unavailable: usize,
+}
+
+/// Resource usage for each group of processes, keyed by cgroup path or container id.
+#[derive(Debug)]
+pub struct ResourceGroups {
+    inner: HashMap<String, GroupUsage>,
+}
+
+impl ResourceGroups {
+    /// Read every process in `/proc`, and sum their usage by group.
+    pub fn from_system(group_by: GroupBy) -> io::Result<Self> {
+        let mut inner = HashMap::new();
+        for process in Process::all()? {
+            match add_process(&mut inner, &process?, group_by) {
+                Ok(()) => (),
+                Err(ref e) if exited(e) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(ResourceGroups { inner })
+    }
+
+    /// The usage of the group `key`, a cgroup path or container id.
+    pub fn get(&self, key: &str) -> Option<&GroupUsage> {
+        self.inner.get(key)
+    }
+
+    /// The number of groups.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether there are no groups.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over the groups in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GroupUsage)> {
+        self.inner.iter().map(|(key, usage)| (key.as_str(), usage))
+    }
+}
+
+impl IntoIterator for ResourceGroups {
+    type IntoIter = std::collections::hash_map::IntoIter<String, GroupUsage>;
+    type Item = (String, GroupUsage);
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+fn add_process(
+    groups: &mut HashMap<String, GroupUsage>,
+    process: &Process,
+    group_by: GroupBy,
+) -> io::Result<()> {
+    let cgroups = process.cgroups()?;
+    let path = match group_path(&cgroups) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let key = match group_by {
+        GroupBy::Cgroup => path.to_string_lossy().into_owned(),
+        GroupBy::Container => match container_id(path) {
+            Some(id) => id.to_owned(),
+            None => return Ok(()),
+        },
+    };
+    let stat = process.stat()?;
+    let rss = process.status()?.vm_rss.unwrap_or_default();
+    let io = match process.io() {
+        Ok(io) => Some(io),
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => None,
+        Err(e) => return Err(e),
+    };
+    let usage = groups.entry(key).or_default();
+    usage.processes += 1;
+    usage.cpu_ticks = usage
+        .cpu_ticks
+        .saturating_add(stat.utime)
+        .saturating_add(stat.stime);
+    usage.rss = ByteSize(usage.rss.bytes().saturating_add(rss.bytes()));
+    match io {
+        Some(io) => {
+            usage.read_bytes = usage.read_bytes.saturating_add(io.read_bytes);
+            usage.write_bytes = usage.write_bytes.saturating_add(io.write_bytes);
+        }
+        None => usage.io_unavailable += 1,
+    }
+    Ok(())
+}
+
+/// The cgroup v2 path if there is one, otherwise the first v1 path.
+fn group_path(cgroups: &[Cgroup]) -> Option<&Path> {
+    cgroups
+        .iter()
+        .find(|cgroup| cgroup.hierarchy == 0)
+        .or_else(|| cgroups.first())
+        .map(|cgroup| cgroup.path.as_path())
+}
+
+/// Extract a container id from a cgroup path.
+///
+/// Recognises the 64 hex digit ids used by Docker, containerd, CRI-O and Podman, in both the
+/// cgroupfs layout (`/docker/<id>`) and the systemd one (`/system.slice/docker-<id>.scope`).
+pub fn container_id(path: &Path) -> Option<&str> {
+    const PREFIXES: [&str; 4] = ["docker-", "cri-containerd-", "crio-", "libpod-"];
+    path.iter().rev().find_map(|component| {
+        let name = component.to_str()?;
+        let name = name.strip_suffix(".scope").unwrap_or(name);
+        let id = PREFIXES
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(name);
+        if id.len() == 64 && id.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            Some(id)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{container_id, GroupBy, ResourceGroups};
+    use crate::process::Process;
+    use std::path::Path;
+
+    const ID: &str = "4f1c3b1fa32c7e5e3d0a8e2b5f6d7c8b9a0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c";
+
+    #[test]
+    fn container_ids() {
+        let id = |path: String| container_id(Path::new(&path)).map(String::from);
+        assert_eq!(id(format!("/docker/{}", ID)).as_deref(), Some(ID));
+        assert_eq!(
+            id(format!("/system.slice/docker-{}.scope", ID)).as_deref(),
+            Some(ID)
+        );
+        assert_eq!(
+            id(format!(
+                "/kubepods.slice/kubepods-besteffort.slice/kubepods-besteffort-pod1234.slice/cri-containerd-{}.scope",
+                ID
+            ))
+            .as_deref(),
+            Some(ID)
+        );
+        assert_eq!(
+            id("/user.slice/user-1000.slice/session-2.scope".into()),
+            None
+        );
+        assert_eq!(id("/".into()), None);
+    }
+
+    #[test]
+    fn resource_groups() {
+        let groups = ResourceGroups::from_system(GroupBy::Cgroup).unwrap();
+        let cgroups = Process::current().cgroups().unwrap();
+        let path = super::group_path(&cgroups).unwrap();
+        let usage = groups.get(&path.to_string_lossy()).unwrap();
+        assert_eq!(groups.iter().count(), groups.len());
+        assert!(usage.processes >= 1);
+        assert!(usage.rss.bytes() > 0);
+    }
+}
@@ -0,0 +1,185 @@
+//! Bindings to `/proc/[pid]/uid_map`, `/proc/[pid]/gid_map` and `/proc/[pid]/setgroups`.
+use std::convert::TryFrom;
+use std::io;
+
+use crate::{util, Error};
+
+/// The mapping of user or group ids between a process's user namespace and the namespace of the
+/// process reading the file (see `user_namespaces(7)`).
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct IdMap {
+    inner: Vec<IdMapRange>,
+}
+
+/// A contiguous range of mapped ids.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IdMapRange {
+    /// The first id in the process's namespace.
+    pub inside: u32,
+    /// The first id as seen from the reading process's namespace.
+    pub outside: u32,
+    /// The number of ids in the range.
+    pub length: u32,
+}
+
+impl IdMapRange {
+    fn translate(from: u32, to: u32, length: u32, id: u32) -> Option<u32> {
+        let offset = id.checked_sub(from)?;
+        if offset < length {
+            to.checked_add(offset)
+        } else {
+            None
+        }
+    }
+}
+
+impl IdMap {
+    /// Parse `/proc/[pid]/uid_map`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut inner = Vec::new();
+        loop {
+            match reader.parse_line(IdMap::parse_range) {
+                Ok(range) => inner.push(range),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(IdMap { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &IdMapRange> {
+        self.inner.iter()
+    }
+
+    /// Translate an id in the process's namespace to the reader's namespace.
+    ///
+    /// `None` if the id isn't mapped, in which case the kernel shows it as the overflow id
+    /// (usually 65534, `nobody`).
+    pub fn to_outside(&self, id: u32) -> Option<u32> {
+        self.iter()
+            .find_map(|range| IdMapRange::translate(range.inside, range.outside, range.length, id))
+    }
+
+    /// Translate an id in the reader's namespace to the process's namespace.
+    pub fn to_inside(&self, id: u32) -> Option<u32> {
+        self.iter()
+            .find_map(|range| IdMapRange::translate(range.outside, range.inside, range.length, id))
+    }
+
+    /// Whether every id maps to itself, as in the initial user namespace: the map is the single
+    /// range `0 0 4294967295`.
+    ///
+    /// An empty map (nothing mapped yet) and partial ranges like `0 0 1` aren't identities, as
+    /// unmapped ids show up as the overflow id.
+    pub fn is_identity(&self) -> bool {
+        matches!(
+            self.inner[..],
+            [IdMapRange {
+                inside: 0,
+                outside: 0,
+                length: u32::MAX,
+            }]
+        )
+    }
+
+    fn parse_range(input: &str) -> Result<IdMapRange, Error> {
+        let id = |input, msg: &'static str| -> Result<(&str, u32), Error> {
+            let (input, id) = util::parse_u64(input).ok_or(msg)?;
+            Ok((input, u32::try_from(id).map_err(|_| msg)?))
+        };
+        let (input, inside) = id(input, "inside id")?;
+        let (input, outside) = id(input, "outside id")?;
+        let (_, length) = id(input, "length")?;
+        Ok(IdMapRange {
+            inside,
+            outside,
+            length,
+        })
+    }
+}
+
+impl std::ops::Deref for IdMap {
+    type Target = Vec<IdMapRange>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for IdMap {
+    type IntoIter = std::vec::IntoIter<IdMapRange>;
+    type Item = IdMapRange;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// Whether a process's user namespace allows `setgroups(2)`, from `/proc/[pid]/setgroups`.
+///
+/// Unprivileged processes must deny `setgroups` before they can write a `gid_map`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SetGroups {
+    Allow,
+    Deny,
+}
+
+impl SetGroups {
+    /// Parse `/proc/[pid]/setgroups`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(|line| match line.trim_end() {
+            "allow" => Ok(SetGroups::Allow),
+            "deny" => Ok(SetGroups::Deny),
+            _ => Err(Error::from("expected \"allow\" or \"deny\"")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdMap, SetGroups};
+    use std::io;
+
+    #[test]
+    fn proc_pid_uid_map() {
+        // A rootless container: root inside is the user outside, and the rest come from
+        // /etc/subuid.
+        let raw = "\
+         0       1000          1
+         1     100000      65536
+";
+        let map = IdMap::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(map.len(), 2);
+        assert!(!map.is_identity());
+        assert_eq!(map.to_outside(0), Some(1000));
+        assert_eq!(map.to_outside(1000), Some(100_999));
+        assert_eq!(map.to_outside(65536), Some(165_535));
+        assert_eq!(map.to_outside(65537), None);
+        assert_eq!(map.to_inside(100_000), Some(1));
+        assert_eq!(map.to_inside(999), None);
+
+        let initial =
+            IdMap::from_reader(io::Cursor::new("         0          0 4294967295\n")).unwrap();
+        assert!(initial.is_identity());
+        assert_eq!(initial.to_outside(u32::MAX - 1), Some(u32::MAX - 1));
+
+        // A new user namespace has nothing mapped until its map is written.
+        let empty = IdMap::from_reader(io::Cursor::new("")).unwrap();
+        assert!(empty.is_empty());
+        assert!(!empty.is_identity());
+        let partial = IdMap::from_reader(io::Cursor::new(
+            "         0          0          1
+",
+        ))
+        .unwrap();
+        assert!(!partial.is_identity());
+        assert_eq!(partial.to_outside(1), None);
+    }
+
+    #[test]
+    fn proc_pid_setgroups() {
+        let setgroups = SetGroups::from_reader(io::Cursor::new("deny\n")).unwrap();
+        assert_eq!(setgroups, SetGroups::Deny);
+        assert!(SetGroups::from_reader(io::Cursor::new("maybe\n")).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Bindings to `/proc/[pid]/io`.
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+use crate::{util, Error, Timestamped};
+
+/// I/O statistics for a process, from `/proc/[pid]/io`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ProcessIo {
+    /// Bytes read using `read` and similar syscalls, whether or not they hit the disk.
+    pub rchar: u64,
+    /// Bytes written using `write` and similar syscalls, whether or not they hit the disk.
+    pub wchar: u64,
+    /// The number of read syscalls.
+    pub syscr: u64,
+    /// The number of write syscalls.
+    pub syscw: u64,
+    /// Bytes actually fetched from the storage layer.
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer.
+    pub write_bytes: u64,
+    /// Bytes that were written but then truncated before reaching storage.
+    pub cancelled_write_bytes: u64,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl ProcessIo {
+    /// Parse `/proc/[pid]/io`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut stats = ProcessIo::default();
+        loop {
+            match reader.parse_line(|line| stats.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// The difference between this sample and an `earlier` one, saturating at zero.
+    pub fn diff(&self, earlier: &ProcessIo) -> ProcessIo {
+        ProcessIo {
+            rchar: self.rchar.saturating_sub(earlier.rchar),
+            wchar: self.wchar.saturating_sub(earlier.wchar),
+            syscr: self.syscr.saturating_sub(earlier.syscr),
+            syscw: self.syscw.saturating_sub(earlier.syscw),
+            read_bytes: self.read_bytes.saturating_sub(earlier.read_bytes),
+            write_bytes: self.write_bytes.saturating_sub(earlier.write_bytes),
+            cancelled_write_bytes: self
+                .cancelled_write_bytes
+                .saturating_sub(earlier.cancelled_write_bytes),
+        }
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        let (key, value) = err_msg!(input.split_once(':'), "expected \":\"")?;
+        let (_, value) = err_msg!(util::parse_u64(value), "value")?;
+        match key {
+            "rchar" => self.rchar = value,
+            "wchar" => self.wchar = value,
+            "syscr" => self.syscr = value,
+            "syscw" => self.syscw = value,
+            "read_bytes" => self.read_bytes = value,
+            "write_bytes" => self.write_bytes = value,
+            "cancelled_write_bytes" => self.cancelled_write_bytes = value,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Calculates the current process's own I/O rates between successive samples of
+/// `/proc/self/io`.
+///
+/// This is for applications that want to report their own I/O in their metrics:
+///
+/// ```no_run
+/// use linux_proc::process::SelfIoTracker;
+///
+/// let mut tracker = SelfIoTracker::new();
+/// tracker.sample()?;
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// if let Some(rates) = tracker.sample()? {
+///     println!("read {:.0} B/s", rates.read_bytes_per_sec());
+///     println!("wrote {:.0} B/s", rates.write_bytes_per_sec());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SelfIoTracker {
+    last: Option<Timestamped<ProcessIo>>,
+}
+
+/// The I/O of the current process between two samples, from `SelfIoTracker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfIoRates {
+    /// The counters' increase over the interval.
+    pub delta: ProcessIo,
+    /// How long the interval really was.
+    pub elapsed: Duration,
+}
+
+impl SelfIoTracker {
+    const PATH: &'static str = "/proc/self/io";
+
+    /// A tracker with no samples.
+    pub fn new() -> Self {
+        SelfIoTracker { last: None }
+    }
+
+    /// Read `/proc/self/io`, and calculate the rates since the last sample.
+    ///
+    /// The first sample returns `None`, since there is nothing to compare it to.
+    pub fn sample(&mut self) -> io::Result<Option<SelfIoRates>> {
+        let io = Timestamped::read(|| ProcessIo::from_reader(File::open(Self::PATH)?))?;
+        Ok(self.update(io))
+    }
+
+    /// Calculate the rates since the last sample, given a sample of `/proc/self/io`.
+    ///
+    /// The first sample returns `None`, since there is nothing to compare it to.
+    pub fn update(&mut self, io: Timestamped<ProcessIo>) -> Option<SelfIoRates> {
+        let last = self.last.replace(io)?;
+        Some(SelfIoRates {
+            delta: io.diff(&last),
+            elapsed: io.elapsed_since(&last),
+        })
+    }
+
+    /// The most recent sample, if any.
+    pub fn last(&self) -> Option<&Timestamped<ProcessIo>> {
+        self.last.as_ref()
+    }
+}
+
+impl SelfIoRates {
+    /// Bytes read using `read` and similar syscalls per second, whether or not they hit the disk.
+    pub fn rchar_per_sec(&self) -> f64 {
+        self.per_sec(self.delta.rchar)
+    }
+
+    /// Bytes written using `write` and similar syscalls per second, whether or not they hit the
+    /// disk.
+    pub fn wchar_per_sec(&self) -> f64 {
+        self.per_sec(self.delta.wchar)
+    }
+
+    /// Bytes fetched from the storage layer per second.
+    pub fn read_bytes_per_sec(&self) -> f64 {
+        self.per_sec(self.delta.read_bytes)
+    }
+
+    /// Bytes sent to the storage layer per second.
+    pub fn write_bytes_per_sec(&self) -> f64 {
+        self.per_sec(self.delta.write_bytes)
+    }
+
+    fn per_sec(&self, count: u64) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            count as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProcessIo, SelfIoTracker};
+    use crate::Timestamped;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_pid_io() {
+        let raw = "\
+rchar: 323934931
+wchar: 323929600
+syscr: 632687
+syscw: 632675
+read_bytes: 0
+write_bytes: 323932160
+cancelled_write_bytes: 0
+";
+        let stats = ProcessIo::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.rchar, 323934931);
+        assert_eq!(stats.syscw, 632675);
+        assert_eq!(stats.write_bytes, 323932160);
+    }
+
+    #[test]
+    fn self_io_tracker() {
+        let mut tracker = SelfIoTracker::new();
+        let first = Timestamped::now(ProcessIo {
+            rchar: 1000,
+            read_bytes: 4096,
+            ..ProcessIo::default()
+        });
+        assert_eq!(tracker.update(first), None);
+        let mut second = first;
+        second.value.rchar = 3000;
+        second.value.read_bytes = 4096 + 8192;
+        second.value.write_bytes = 512;
+        second.taken_at += Duration::from_millis(500);
+        let rates = tracker.update(second).unwrap();
+        assert_eq!(rates.elapsed, Duration::from_millis(500));
+        assert_eq!(rates.rchar_per_sec(), 4000.0);
+        assert_eq!(rates.read_bytes_per_sec(), 16384.0);
+        assert_eq!(rates.write_bytes_per_sec(), 1024.0);
+        assert_eq!(rates.wchar_per_sec(), 0.0);
+
+        let mut tracker = SelfIoTracker::new();
+        assert!(tracker.sample().unwrap().is_none());
+        assert!(tracker.sample().unwrap().is_some());
+    }
+}
@@ -0,0 +1,146 @@
+//! Bindings to `/proc/[pid]/limits`.
+use std::io;
+
+use crate::{util, Error};
+
+/// A resource limit. `None` means unlimited.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Limit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+/// The resource limits of a process, from `/proc/[pid]/limits`.
+///
+/// See `getrlimit(2)` for the meaning of each limit.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Limits {
+    /// In seconds.
+    pub cpu_time: Limit,
+    /// In bytes.
+    pub file_size: Limit,
+    /// In bytes.
+    pub data_size: Limit,
+    /// In bytes.
+    pub stack_size: Limit,
+    /// In bytes.
+    pub core_file_size: Limit,
+    /// In bytes.
+    pub resident_set: Limit,
+    pub processes: Limit,
+    pub open_files: Limit,
+    /// In bytes.
+    pub locked_memory: Limit,
+    /// In bytes.
+    pub address_space: Limit,
+    pub file_locks: Limit,
+    pub pending_signals: Limit,
+    /// In bytes.
+    pub msgqueue_size: Limit,
+    pub nice_priority: Limit,
+    pub realtime_priority: Limit,
+    /// In microseconds.
+    pub realtime_timeout: Limit,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The limit name is left-aligned in a column this wide (including the trailing space).
+const NAME_WIDTH: usize = 26;
+
+fn parse_value(input: &str) -> Result<(&str, Option<u64>), Error> {
+    if let Some(input) = util::expect_bytes("unlimited", input) {
+        return Ok((input, None));
+    }
+    let (input, value) = err_msg!(util::parse_u64(input), "limit value")?;
+    Ok((input, Some(value)))
+}
+
+impl Limits {
+    /// Parse `/proc/[pid]/limits`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // header
+        reader.parse_line(util::parse_dummy)?;
+        let mut limits = Limits::default();
+        loop {
+            match reader.parse_line(|line| limits.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(limits)
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        let name = err_msg!(input.get(..NAME_WIDTH), "limit name")?;
+        let input = &input[NAME_WIDTH..];
+        let (input, soft) = parse_value(input)?;
+        let (_input, hard) = parse_value(input)?;
+        let limit = Limit { soft, hard };
+        match name.trim_end() {
+            "Max cpu time" => self.cpu_time = limit,
+            "Max file size" => self.file_size = limit,
+            "Max data size" => self.data_size = limit,
+            "Max stack size" => self.stack_size = limit,
+            "Max core file size" => self.core_file_size = limit,
+            "Max resident set" => self.resident_set = limit,
+            "Max processes" => self.processes = limit,
+            "Max open files" => self.open_files = limit,
+            "Max locked memory" => self.locked_memory = limit,
+            "Max address space" => self.address_space = limit,
+            "Max file locks" => self.file_locks = limit,
+            "Max pending signals" => self.pending_signals = limit,
+            "Max msgqueue size" => self.msgqueue_size = limit,
+            "Max nice priority" => self.nice_priority = limit,
+            "Max realtime priority" => self.realtime_priority = limit,
+            "Max realtime timeout" => self.realtime_timeout = limit,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limit, Limits};
+    use std::io;
+
+    #[test]
+    fn proc_pid_limits() {
+        let raw = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max cpu time              unlimited            unlimited            seconds
+Max file size             unlimited            unlimited            bytes
+Max data size             unlimited            unlimited            bytes
+Max stack size            8388608              unlimited            bytes
+Max core file size        0                    unlimited            bytes
+Max resident set          unlimited            unlimited            bytes
+Max processes             23961                23961                processes
+Max open files            1024                 524288               files
+Max locked memory         8388608              8388608              bytes
+Max address space         unlimited            unlimited            bytes
+Max file locks            unlimited            unlimited            locks
+Max pending signals       23961                23961                signals
+Max msgqueue size         819200               819200               bytes
+Max nice priority         0                    0
+Max realtime priority     0                    0
+Max realtime timeout      unlimited            unlimited            us
+";
+        let limits = Limits::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(
+            limits.stack_size,
+            Limit {
+                soft: Some(8388608),
+                hard: None
+            }
+        );
+        assert_eq!(limits.open_files.hard, Some(524288));
+        assert_eq!(limits.realtime_timeout.soft, None);
+    }
+}
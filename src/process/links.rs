@@ -0,0 +1,61 @@
+//! Bindings to the `/proc/[pid]/exe`, `/proc/[pid]/cwd` and `/proc/[pid]/root` symlinks.
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// The target of one of the process's symlinks.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LinkTarget {
+    /// The path the link points to, with any ` (deleted)` suffix removed.
+    pub path: PathBuf,
+    /// Whether the target has been unlinked since the process opened it.
+    ///
+    /// A process running a deleted executable is often a sign that it hasn't been restarted
+    /// after an upgrade, or that something is trying to hide.
+    pub deleted: bool,
+}
+
+const DELETED_SUFFIX: &[u8] = b" (deleted)";
+
+impl LinkTarget {
+    fn from_link(link: PathBuf) -> LinkTarget {
+        let bytes = link.as_os_str().as_bytes();
+        match bytes.strip_suffix(DELETED_SUFFIX) {
+            Some(path) => LinkTarget {
+                path: PathBuf::from(OsStr::from_bytes(path)),
+                deleted: true,
+            },
+            None => LinkTarget {
+                path: link,
+                deleted: false,
+            },
+        }
+    }
+}
+
+/// Read one of the process's symlinks.
+///
+/// Reading another user's links requires ptrace access, so expect
+/// `io::ErrorKind::PermissionDenied` if not root. Kernel threads have no executable, so reading
+/// their `exe` link fails with `io::ErrorKind::NotFound`.
+pub(crate) fn read_link(path: &Path) -> io::Result<LinkTarget> {
+    Ok(LinkTarget::from_link(fs::read_link(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkTarget;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn deleted_suffix() {
+        let target = LinkTarget::from_link(PathBuf::from("/usr/bin/my app (deleted)"));
+        assert_eq!(target.path, Path::new("/usr/bin/my app"));
+        assert!(target.deleted);
+        let target = LinkTarget::from_link(PathBuf::from("/usr/bin/cat"));
+        assert_eq!(target.path, Path::new("/usr/bin/cat"));
+        assert!(!target.deleted);
+    }
+}
@@ -0,0 +1,165 @@
+//! Bindings to `/proc/[pid]/maps`.
+use std::io;
+use std::path::PathBuf;
+
+use crate::{
+    parse::{Entries, EntryParser},
+    util, Error,
+};
+
+/// A mapped memory region, from a line of `/proc/[pid]/maps`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MemoryMap {
+    /// The start address of the region.
+    pub start: u64,
+    /// The end address of the region (exclusive).
+    pub end: u64,
+    pub permissions: Permissions,
+    /// The offset into the mapped file.
+    pub offset: u64,
+    /// The major number of the device holding the mapped file.
+    pub dev_major: u32,
+    /// The minor number of the device holding the mapped file.
+    pub dev_minor: u32,
+    /// The inode of the mapped file, `0` for anonymous mappings.
+    pub inode: u64,
+    /// The mapped file, or a pseudo-path like `[heap]` or `[stack]`. `None` for anonymous
+    /// mappings.
+    pub pathname: Option<String>,
+}
+
+impl MemoryMap {
+    /// The size of the region in bytes.
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// The `/dev` path of the device holding the mapped file, from `dev_major:dev_minor`.
+    ///
+    /// `None` for anonymous mappings and files on filesystems without a block device.
+    pub fn device_path(&self) -> io::Result<Option<PathBuf>> {
+        if self.inode == 0 {
+            return Ok(None);
+        }
+        crate::device_name(self.dev_major, self.dev_minor)
+    }
+}
+
+/// Permissions of a mapped memory region.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    /// Whether the mapping is shared (`s`) rather than private copy-on-write (`p`).
+    pub shared: bool,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// An iterator over the regions in `/proc/[pid]/maps`, from `Process::maps_iter`.
+///
+/// The maps of a large process can have hundreds of thousands of regions; this parses them one
+/// at a time.
+pub struct MemoryMaps<R> {
+    entries: Entries<R, EntryParser<MemoryMap>>,
+}
+
+impl<R: io::Read> Iterator for MemoryMaps<R> {
+    type Item = io::Result<MemoryMap>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+/// Iterate over `/proc/[pid]/maps`-formatted data from `reader`.
+pub(crate) fn iter<R: io::Read>(reader: R) -> MemoryMaps<R> {
+    MemoryMaps {
+        entries: util::LineParser::new(reader).entries(MemoryMap::from_str as EntryParser<_>),
+    }
+}
+
+/// Parse `/proc/[pid]/maps`-formatted data from `reader`.
+pub(crate) fn from_reader(reader: impl io::Read) -> io::Result<Vec<MemoryMap>> {
+    iter(reader).collect()
+}
+
+impl MemoryMap {
+    fn from_str(input: &str) -> Result<MemoryMap, Error> {
+        let (input, start) = err_msg!(util::parse_hex_u64(input), "start address")?;
+        let input = err_msg!(util::expect_bytes("-", input), "expected \"-\"")?;
+        let (input, end) = err_msg!(util::parse_hex_u64(input), "end address")?;
+        let (input, perms) = err_msg!(util::parse_token(input), "permissions")?;
+        let perms = perms.as_bytes();
+        if perms.len() != 4 {
+            return Err("permissions".into());
+        }
+        let permissions = Permissions {
+            read: perms[0] == b'r',
+            write: perms[1] == b'w',
+            execute: perms[2] == b'x',
+            shared: perms[3] == b's',
+        };
+        let (input, offset) = err_msg!(util::parse_hex_u64(input), "offset")?;
+        let (input, dev_major) = err_msg!(util::parse_hex_u64(input), "device major")?;
+        let input = err_msg!(util::expect_bytes(":", input), "expected \":\"")?;
+        let (input, dev_minor) = err_msg!(util::parse_hex_u64(input), "device minor")?;
+        let (input, inode) = err_msg!(util::parse_u64(input), "inode")?;
+        // The path is the rest of the line, and may contain spaces.
+        let pathname = util::consume_space(input).trim_end_matches(['\n', '\r']);
+        let pathname = if pathname.is_empty() {
+            None
+        } else {
+            Some(pathname.to_owned())
+        };
+        Ok(MemoryMap {
+            start,
+            end,
+            permissions,
+            offset,
+            dev_major: dev_major as u32,
+            dev_minor: dev_minor as u32,
+            inode,
+            pathname,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    #[test]
+    fn proc_pid_maps() {
+        let raw = "\
+560d668b9000-560d668bb000 r--p 00000000 fe:00 317783                     /usr/bin/head
+560d668bb000-560d668c1000 r-xp 00002000 fe:00 317783                     /usr/bin/head
+560d69b0b000-560d69b2c000 rw-p 00000000 00:00 0                          [heap]
+7f1ea2c00000-7f1ea2e00000 rw-s 00000000 00:05 1234                       /memfd:my region (deleted)
+7f1ea2e00000-7f1ea2f00000 rw-p 00000000 00:00 0
+";
+        let maps = super::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(maps.len(), 5);
+        assert_eq!(maps[0].size(), 0x2000);
+        assert!(maps[1].permissions.execute);
+        assert_eq!(maps[1].offset, 0x2000);
+        assert_eq!((maps[1].dev_major, maps[1].dev_minor), (0xfe, 0));
+        assert_eq!(maps[2].pathname.as_deref(), Some("[heap]"));
+        assert!(maps[3].permissions.shared);
+        assert_eq!(
+            maps[3].pathname.as_deref(),
+            Some("/memfd:my region (deleted)")
+        );
+        assert_eq!(maps[4].pathname, None);
+
+        let mut iter = super::iter(io::Cursor::new(raw.replace("r-xp", "r-x")));
+        assert_eq!(iter.next().unwrap().unwrap(), maps[0]);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+}
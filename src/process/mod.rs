@@ -0,0 +1,393 @@
+//! Bindings to the per-process directories `/proc/[pid]`.
+//!
+//! Use `Process::current()` to inspect the running process through `/proc/self`, or
+//! `Process::from_pid` for any other process. `Thread` does the same for a single thread.
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{
+    latency::LatencyStats, mounts::Mounts, mountstats::MountStats, pagemap::PageMap, util,
+};
+
+mod capabilities;
+pub(crate) mod cgroup;
+mod coredump;
+mod cpu;
+mod diff;
+mod fd;
+mod filter;
+mod groups;
+mod id_map;
+mod io_stats;
+mod limits;
+mod links;
+pub(crate) mod maps;
+pub(crate) mod numa_maps;
+mod personality;
+mod scan;
+mod sched;
+mod signals;
+mod stat;
+mod state;
+mod status;
+mod thread;
+mod top;
+
+pub use self::capabilities::Capabilities;
+pub use self::cgroup::Cgroup;
+pub use self::coredump::CoredumpFilter;
+pub use self::cpu::{ProcessCpuTracker, ProcessCpuUsage};
+pub use self::diff::{ProcessEvent, ProcessSetDiff, StateChange};
+pub use self::fd::{Fd, FdTarget, InotifyInstance};
+pub use self::filter::{FilteredProcesses, ProcessFilter, Processes};
+pub use self::groups::{container_id, GroupBy, GroupUsage, ResourceGroups};
+pub use self::id_map::{IdMap, IdMapRange, SetGroups};
+pub use self::io_stats::{ProcessIo, SelfIoRates, SelfIoTracker};
+pub use self::limits::{Limit, Limits};
+pub use self::links::LinkTarget;
+pub use self::maps::{MemoryMap, MemoryMaps, Permissions};
+pub use self::numa_maps::{numa_bytes_by_node, NumaMap, NumaPolicy, NumaPolicyMode};
+pub use self::personality::{Personality, PersonalityFlags};
+pub use self::scan::{reused_pids, ProcessKey, ProcessScan, ProcessSnapshot};
+pub use self::sched::{Autogroup, ProcessSched};
+pub use self::signals::SignalSet;
+pub use self::stat::ProcessStat;
+pub use self::state::{ProcessState, StateCounts};
+pub use self::status::{SeccompMode, Status};
+pub use self::thread::Thread;
+pub use self::top::{top_by_cpu, top_by_io, top_by_rss, ProcessIoUsage, ProcessMemoryUsage};
+
+/// The number of clock ticks per second (`sysconf(_SC_CLK_TCK)`), the unit of the times in
+/// `ProcessStat`.
+///
+/// This is almost always 100.
+pub fn clock_ticks_per_sec() -> u64 {
+    // Safety: sysconf has no preconditions.
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+/// The size of a memory page in bytes (`sysconf(_SC_PAGESIZE)`), the unit of `ProcessStat::rss`.
+pub fn page_size() -> u64 {
+    // Safety: sysconf has no preconditions.
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+/// Whether an error means the process exited while we were reading it.
+pub(crate) fn exited(e: &io::Error) -> bool {
+    // Reading files of a process that has just exited fails with `ESRCH`.
+    e.kind() == io::ErrorKind::NotFound || e.raw_os_error() == Some(libc::ESRCH)
+}
+
+/// A handle to a process's directory in `/proc`.
+///
+/// Creating a handle doesn't touch the filesystem; each accessor reads the corresponding file
+/// when called, so results are always fresh (and the process may have exited in between).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Process {
+    root: PathBuf,
+}
+
+impl Process {
+    /// The process with the given pid.
+    pub fn from_pid(pid: u32) -> Process {
+        Process {
+            root: Path::new("/proc").join(pid.to_string()),
+        }
+    }
+
+    /// The current process, accessed through `/proc/self`.
+    pub fn current() -> Process {
+        Process {
+            root: PathBuf::from("/proc/self"),
+        }
+    }
+
+    /// List every process in `/proc`.
+    ///
+    /// Use `Processes::filter_by` to search for processes, like `pgrep`.
+    pub fn all() -> io::Result<Processes> {
+        Processes::new()
+    }
+
+    /// The directory this handle reads from, e.g. `/proc/1234` or `/proc/self`.
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// The pid of the process.
+    ///
+    /// For `Process::current()` this resolves the `/proc/self` symlink.
+    pub fn pid(&self) -> io::Result<u32> {
+        let path = if self.root.ends_with("self") {
+            fs::read_link(&self.root)?
+        } else {
+            self.root.clone()
+        };
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "pid is not a number"))
+    }
+
+    /// Parse `/proc/[pid]/stat`.
+    pub fn stat(&self) -> io::Result<ProcessStat> {
+        ProcessStat::from_reader(File::open(self.root.join("stat"))?)
+    }
+
+    /// Parse `/proc/[pid]/status`.
+    pub fn status(&self) -> io::Result<Status> {
+        Status::from_reader(File::open(self.root.join("status"))?)
+    }
+
+    /// Parse `/proc/[pid]/sched`.
+    ///
+    /// Only exists if the kernel was built with `CONFIG_SCHED_DEBUG`.
+    pub fn sched(&self) -> io::Result<ProcessSched> {
+        ProcessSched::from_reader(File::open(self.root.join("sched"))?)
+    }
+
+    /// Parse `/proc/[pid]/latency`, the latencies latencytop recorded for this process.
+    ///
+    /// Only exists if the kernel was built with `CONFIG_LATENCYTOP`.
+    pub fn latency(&self) -> io::Result<LatencyStats> {
+        LatencyStats::from_reader(File::open(self.root.join("latency"))?)
+    }
+
+    /// Parse `/proc/[pid]/autogroup`.
+    ///
+    /// Only exists if the kernel was built with `CONFIG_SCHED_AUTOGROUP`.
+    pub fn autogroup(&self) -> io::Result<Autogroup> {
+        Autogroup::from_reader(File::open(self.root.join("autogroup"))?)
+    }
+
+    /// Parse `/proc/[pid]/uid_map`, the user ids mapped into the process's user namespace.
+    pub fn uid_map(&self) -> io::Result<IdMap> {
+        IdMap::from_reader(File::open(self.root.join("uid_map"))?)
+    }
+
+    /// Parse `/proc/[pid]/gid_map`, the group ids mapped into the process's user namespace.
+    pub fn gid_map(&self) -> io::Result<IdMap> {
+        IdMap::from_reader(File::open(self.root.join("gid_map"))?)
+    }
+
+    /// Parse `/proc/[pid]/setgroups`.
+    pub fn setgroups(&self) -> io::Result<SetGroups> {
+        SetGroups::from_reader(File::open(self.root.join("setgroups"))?)
+    }
+
+    /// Parse `/proc/[pid]/personality`.
+    ///
+    /// Only readable by the owner of the process (or root).
+    pub fn personality(&self) -> io::Result<Personality> {
+        Personality::from_reader(File::open(self.root.join("personality"))?)
+    }
+
+    /// The process's timer slack from `/proc/[pid]/timerslack_ns`: how late the kernel may fire
+    /// its timers, so that nearby wakeups can be coalesced to save power (see `prctl(2)`).
+    ///
+    /// Reading another process's timer slack requires `CAP_SYS_NICE`.
+    pub fn timer_slack(&self) -> io::Result<Duration> {
+        Ok(Duration::from_nanos(util::read_value(
+            self.root.join("timerslack_ns"),
+        )?))
+    }
+
+    /// Set the process's timer slack. A slack of zero resets it to the default (50µs for normal
+    /// processes).
+    ///
+    /// Requires `CAP_SYS_NICE` unless writing to the calling thread.
+    #[cfg(feature = "write")]
+    pub fn set_timer_slack(&self, slack: Duration) -> io::Result<()> {
+        use std::convert::TryFrom;
+
+        let nanos = u64::try_from(slack.as_nanos())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "timer slack is too large"))?;
+        util::write_value(self.root.join("timerslack_ns"), nanos)
+    }
+
+    /// Parse `/proc/[pid]/coredump_filter`, the kinds of memory written to a core dump.
+    pub fn coredump_filter(&self) -> io::Result<CoredumpFilter> {
+        CoredumpFilter::from_reader(File::open(self.root.join("coredump_filter"))?)
+    }
+
+    /// Set which kinds of memory are written to a core dump. The setting is inherited by
+    /// children.
+    #[cfg(feature = "write")]
+    pub fn set_coredump_filter(&self, filter: CoredumpFilter) -> io::Result<()> {
+        util::write_value(
+            self.root.join("coredump_filter"),
+            format_args!("{:#x}", filter.bits()),
+        )
+    }
+
+    /// The process's badness from `/proc/[pid]/oom_score`. When memory runs out, the kernel kills
+    /// the process with the highest score.
+    pub fn oom_score(&self) -> io::Result<u32> {
+        util::read_value(self.root.join("oom_score"))
+    }
+
+    /// The adjustment added to the process's `oom_score`, from `/proc/[pid]/oom_score_adj`.
+    ///
+    /// Ranges from -1000 (never kill) to 1000 (kill first).
+    pub fn oom_score_adj(&self) -> io::Result<i16> {
+        util::read_value(self.root.join("oom_score_adj"))
+    }
+
+    /// Set the process's `oom_score_adj`, which must be between -1000 and 1000.
+    ///
+    /// Lowering it below its previous minimum requires `CAP_SYS_RESOURCE`. The setting is
+    /// inherited by children.
+    #[cfg(feature = "write")]
+    pub fn set_oom_score_adj(&self, adj: i16) -> io::Result<()> {
+        if !(-1000..=1000).contains(&adj) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("oom_score_adj {} is outside -1000..=1000", adj),
+            ));
+        }
+        util::write_value(self.root.join("oom_score_adj"), adj)
+    }
+
+    /// Parse `/proc/[pid]/io`.
+    ///
+    /// Only readable by the owner of the process (or root).
+    pub fn io(&self) -> io::Result<ProcessIo> {
+        ProcessIo::from_reader(File::open(self.root.join("io"))?)
+    }
+
+    /// Parse `/proc/[pid]/limits`.
+    pub fn limits(&self) -> io::Result<Limits> {
+        Limits::from_reader(File::open(self.root.join("limits"))?)
+    }
+
+    /// Parse `/proc/[pid]/maps`.
+    pub fn maps(&self) -> io::Result<Vec<MemoryMap>> {
+        maps::from_reader(File::open(self.root.join("maps"))?)
+    }
+
+    /// Parse `/proc/[pid]/numa_maps`, the NUMA policy and placement of each mapped region.
+    ///
+    /// Only present on kernels built with NUMA support.
+    pub fn numa_maps(&self) -> io::Result<Vec<NumaMap>> {
+        numa_maps::from_reader(File::open(self.root.join("numa_maps"))?)
+    }
+
+    /// Open `/proc/[pid]/pagemap`, to look up where the process's pages are in physical memory.
+    pub fn pagemap(&self) -> io::Result<PageMap> {
+        Ok(PageMap::from_reader(File::open(self.root.join("pagemap"))?))
+    }
+
+    /// Iterate over `/proc/[pid]/maps` one region at a time, without collecting them.
+    pub fn maps_iter(&self) -> io::Result<MemoryMaps<File>> {
+        Ok(maps::iter(File::open(self.root.join("maps"))?))
+    }
+
+    /// Parse `/proc/[pid]/cgroup`, the cgroups the process belongs to in each hierarchy.
+    pub fn cgroups(&self) -> io::Result<Vec<Cgroup>> {
+        cgroup::from_reader(File::open(self.root.join("cgroup"))?)
+    }
+
+    /// Parse `/proc/[pid]/mountinfo`, the mounts in the process's mount namespace.
+    pub fn mounts(&self) -> io::Result<Mounts> {
+        Mounts::from_reader(File::open(self.root.join("mountinfo"))?)
+    }
+
+    /// Parse `/proc/[pid]/mountstats`, the statistics of the mounts visible to the process.
+    pub fn mountstats(&self) -> io::Result<MountStats> {
+        MountStats::from_reader(File::open(self.root.join("mountstats"))?)
+    }
+
+    /// The executable the process is running, from the `/proc/[pid]/exe` symlink.
+    pub fn exe(&self) -> io::Result<LinkTarget> {
+        links::read_link(&self.root.join("exe"))
+    }
+
+    /// The current working directory of the process, from the `/proc/[pid]/cwd` symlink.
+    pub fn cwd(&self) -> io::Result<LinkTarget> {
+        links::read_link(&self.root.join("cwd"))
+    }
+
+    /// The root directory of the process (see `chroot(2)`), from the `/proc/[pid]/root` symlink.
+    pub fn root(&self) -> io::Result<LinkTarget> {
+        links::read_link(&self.root.join("root"))
+    }
+
+    /// List the open file descriptors in `/proc/[pid]/fd`.
+    ///
+    /// Only readable by the owner of the process (or root).
+    pub fn fds(&self) -> io::Result<Vec<Fd>> {
+        fd::read_dir(&self.root.join("fd"))
+    }
+
+    /// List the inotify instances the process holds, with the number of watches on each.
+    ///
+    /// Compare the totals across a user's processes with `sys::fs::WatchLimits` to spot watch
+    /// exhaustion. Only readable by the owner of the process (or root).
+    pub fn inotify_instances(&self) -> io::Result<Vec<InotifyInstance>> {
+        fd::inotify_instances(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Process;
+
+    #[test]
+    fn current_process() {
+        let current = Process::current();
+        assert_eq!(current.pid().unwrap(), std::process::id());
+        let stat = current.stat().unwrap();
+        assert_eq!(stat.pid, std::process::id());
+        assert_eq!(current.status().unwrap().pid, std::process::id());
+        assert!(current.fds().unwrap().iter().any(|fd| fd.fd == 0));
+        assert!(!current.maps().unwrap().is_empty());
+        assert!(current.maps_iter().unwrap().all(|map| map.is_ok()));
+        current.limits().unwrap();
+        assert!(!current.cgroups().unwrap().is_empty());
+        assert!(!current.mounts().unwrap().is_empty());
+        current.io().unwrap();
+        current.personality().unwrap();
+        assert!(!current.uid_map().unwrap().is_empty());
+        current.inotify_instances().unwrap();
+        current.timer_slack().unwrap();
+        current.coredump_filter().unwrap();
+        current.oom_score().unwrap();
+        assert!((-1000..=1000).contains(&current.oom_score_adj().unwrap()));
+        assert_eq!(
+            current.exe().unwrap().path,
+            std::env::current_exe().unwrap()
+        );
+        assert_eq!(
+            current.cwd().unwrap().path,
+            std::env::current_dir().unwrap()
+        );
+        assert!(!current.root().unwrap().deleted);
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn set_coredump_filter() {
+        let current = Process::current();
+        let filter = current.coredump_filter().unwrap();
+        current.set_coredump_filter(filter).unwrap();
+        assert_eq!(current.coredump_filter().unwrap(), filter);
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn set_oom_score_adj() {
+        let current = Process::current();
+        let adj = current.oom_score_adj().unwrap();
+        current.set_oom_score_adj(adj).unwrap();
+        let err = current.set_oom_score_adj(1001).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,307 @@
+//! Bindings to `/proc/[pid]/numa_maps`.
+//!
+//! Each line describes one region of `/proc/[pid]/maps`: its NUMA memory policy, and which nodes
+//! its resident pages are on. Reading the file walks the page tables of every region, so it is
+//! much slower than `maps` for large processes.
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::{mounts::unescape, util, ByteSize, Error};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The NUMA placement of a mapped memory region, from a line of `/proc/[pid]/numa_maps`.
+///
+/// Page counts are in `kernel_page_size` pages. Counts the kernel leaves out because they are
+/// zero are `0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NumaMap {
+    /// The start address of the region, matching `MemoryMap::start`.
+    pub start: u64,
+    pub policy: NumaPolicy,
+    /// The mapped file, if any.
+    pub file: Option<String>,
+    /// Whether this is the heap.
+    pub heap: bool,
+    /// Whether this is the stack of the main thread.
+    pub stack: bool,
+    /// Whether the region is backed by huge pages (`hugetlbfs`).
+    pub huge: bool,
+    /// Anonymous pages, not backed by a file.
+    pub anon: u64,
+    /// Pages modified since they were last written back or swapped out.
+    pub dirty: u64,
+    /// File pages mapped by this region.
+    pub mapped: u64,
+    /// The most processes mapping any one of the pages.
+    pub mapmax: u64,
+    /// Pages in the swap cache.
+    pub swapcache: u64,
+    /// Pages on the active LRU list. `None` when the kernel leaves it out, which it does when
+    /// every page is active, and for huge pages.
+    pub active: Option<u64>,
+    /// Pages being written back.
+    pub writeback: u64,
+    /// The resident pages on each node, as `(node, pages)` in the order listed.
+    pub nodes: Vec<(u32, u64)>,
+    /// The size of the region's pages. `None` if no pages are resident.
+    pub kernel_page_size: Option<ByteSize>,
+}
+
+/// A NUMA memory policy, as set by `set_mempolicy` or `mbind`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NumaPolicy {
+    pub mode: NumaPolicyMode,
+    /// Mode flags, like `static`, `relative` or `balancing`.
+    pub flags: Vec<String>,
+    /// The nodes the policy names. Empty for `Default` and `Local`.
+    pub nodes: Vec<u32>,
+}
+
+/// The mode of a NUMA memory policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum NumaPolicyMode {
+    /// Use the process's policy, or `Local` if it doesn't have one.
+    #[default]
+    Default,
+    /// Allocate on the preferred node, falling back to others.
+    Preferred,
+    /// Only allocate on the given nodes.
+    Bind,
+    /// Spread allocations across the given nodes.
+    Interleave,
+    /// Spread allocations across the given nodes, weighted per node (since 6.9).
+    WeightedInterleave,
+    /// Allocate on the node of the cpu doing the allocating.
+    Local,
+    /// Allocate on any of the preferred nodes, falling back to others (since 5.15).
+    PreferredMany,
+    /// A mode added after this crate was written.
+    Other(String),
+}
+
+impl NumaPolicyMode {
+    /// The names the kernel prints, longest first as some are prefixes of others.
+    const NAMES: &'static [(&'static str, NumaPolicyMode)] = &[
+        ("weighted interleave", NumaPolicyMode::WeightedInterleave),
+        ("prefer (many)", NumaPolicyMode::PreferredMany),
+        ("interleave", NumaPolicyMode::Interleave),
+        ("default", NumaPolicyMode::Default),
+        ("prefer", NumaPolicyMode::Preferred),
+        ("local", NumaPolicyMode::Local),
+        ("bind", NumaPolicyMode::Bind),
+    ];
+}
+
+impl NumaMap {
+    /// The number of resident pages, on all nodes.
+    pub fn pages(&self) -> u64 {
+        self.nodes.iter().map(|&(_, pages)| pages).sum()
+    }
+
+    /// The number of resident pages on `node`.
+    pub fn pages_on(&self, node: u32) -> u64 {
+        self.nodes
+            .iter()
+            .filter(|&&(n, _)| n == node)
+            .map(|&(_, pages)| pages)
+            .sum()
+    }
+
+    /// The resident memory on each node, in node order.
+    pub fn bytes_by_node(&self) -> BTreeMap<u32, ByteSize> {
+        let page_size = self.kernel_page_size.map(ByteSize::bytes).unwrap_or(0);
+        self.nodes
+            .iter()
+            .map(|&(node, pages)| (node, ByteSize(pages.saturating_mul(page_size))))
+            .collect()
+    }
+}
+
+/// The resident memory of all of `maps` on each node, in node order.
+///
+/// A process whose memory is mostly on one node but which runs on cpus of another pays for
+/// every access crossing the interconnect.
+pub fn numa_bytes_by_node(maps: &[NumaMap]) -> BTreeMap<u32, ByteSize> {
+    let mut totals = BTreeMap::new();
+    for map in maps {
+        for (node, bytes) in map.bytes_by_node() {
+            let total: &mut ByteSize = totals.entry(node).or_default();
+            total.0 = total.0.saturating_add(bytes.0);
+        }
+    }
+    totals
+}
+
+/// Parse `/proc/[pid]/numa_maps`-formatted data from `reader`.
+pub(crate) fn from_reader(reader: impl io::Read) -> io::Result<Vec<NumaMap>> {
+    let mut reader = util::LineParser::new(reader);
+    let mut maps = Vec::new();
+    loop {
+        match reader.parse_line(NumaMap::from_str) {
+            Ok(map) => maps.push(map),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(maps)
+}
+
+impl NumaMap {
+    fn from_str(input: &str) -> Result<NumaMap, Error> {
+        let (input, start) = err_msg!(util::parse_hex_u64(input), "start address")?;
+        let (input, policy) = NumaPolicy::from_str(util::consume_space(input))?;
+        let mut map = NumaMap {
+            start,
+            policy,
+            ..NumaMap::default()
+        };
+        for item in input.split_ascii_whitespace() {
+            let (key, value) = match item.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    match item {
+                        "heap" => map.heap = true,
+                        "stack" => map.stack = true,
+                        "huge" => map.huge = true,
+                        // Anything else is from a later kernel.
+                        _ => (),
+                    }
+                    continue;
+                }
+            };
+            if key == "file" {
+                map.file = Some(unescape(value));
+                continue;
+            }
+            let (_, count) = err_msg!(util::parse_u64(value), key.to_owned())?;
+            match key {
+                "anon" => map.anon = count,
+                "dirty" => map.dirty = count,
+                "mapped" => map.mapped = count,
+                "mapmax" => map.mapmax = count,
+                "swapcache" => map.swapcache = count,
+                "active" => map.active = Some(count),
+                "writeback" => map.writeback = count,
+                "kernelpagesize_kB" => map.kernel_page_size = Some(ByteSize::from_kib(count)),
+                _ => {
+                    if let Some(node) = key.strip_prefix('N').and_then(|n| n.parse().ok()) {
+                        map.nodes.push((node, count));
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl NumaPolicy {
+    /// Parses `mode[=flags][:nodes]`, where some modes contain spaces.
+    fn from_str(input: &str) -> Result<(&str, NumaPolicy), Error> {
+        let (input, mode) = match NumaPolicyMode::NAMES
+            .iter()
+            .find(|(name, _)| input.starts_with(name))
+        {
+            Some((name, mode)) => (&input[name.len()..], mode.clone()),
+            None => {
+                let end = err_msg!(
+                    input.find(|c: char| c == '=' || c == ':' || c.is_ascii_whitespace()),
+                    "policy"
+                )?;
+                (
+                    &input[end..],
+                    NumaPolicyMode::Other(input[..end].to_owned()),
+                )
+            }
+        };
+        let end = input
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(input.len());
+        let (rest, input) = (&input[end..], &input[..end]);
+        let (flags, nodes) = match input.split_once(':') {
+            Some((flags, nodes)) => (flags, nodes),
+            None => (input, ""),
+        };
+        let flags = match flags.strip_prefix('=') {
+            Some(flags) => flags.split('|').map(str::to_owned).collect(),
+            None if flags.is_empty() => Vec::new(),
+            None => return Err("policy".into()),
+        };
+        let nodes = err_msg!(util::parse_cpu_list(nodes), "policy nodes")?;
+        Ok((rest, NumaPolicy { mode, flags, nodes }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{numa_bytes_by_node, NumaPolicy, NumaPolicyMode};
+    use crate::{process::Process, ByteSize};
+    use std::io;
+
+    #[test]
+    fn proc_pid_numa_maps() {
+        let raw = "\
+559118fba000 default file=/usr/bin/my\\040app mapped=2 mapmax=3 N0=2 kernelpagesize_kB=4
+559119b1d000 bind=static:0-1 heap anon=300 dirty=300 active=120 N0=100 N1=200 kernelpagesize_kB=4
+7f3c00000000 weighted interleave:0,1 anon=4 dirty=4 N0=2 N1=2 kernelpagesize_kB=2048
+7ffd6d9a5000 default stack anon=3 dirty=3 N1=3 kernelpagesize_kB=4
+7ffd6d9f2000 default
+";
+        let maps = super::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(maps.len(), 5);
+        let exe = &maps[0];
+        assert_eq!(exe.start, 0x559118fba000);
+        assert_eq!(exe.policy, NumaPolicy::default());
+        assert_eq!(exe.file.as_deref(), Some("/usr/bin/my app"));
+        assert_eq!((exe.mapped, exe.mapmax, exe.active), (2, 3, None));
+        let heap = &maps[1];
+        assert!(heap.heap && !heap.stack);
+        assert_eq!(heap.policy.mode, NumaPolicyMode::Bind);
+        assert_eq!(heap.policy.flags, vec!["static"]);
+        assert_eq!(heap.policy.nodes, vec![0, 1]);
+        assert_eq!((heap.anon, heap.dirty, heap.active), (300, 300, Some(120)));
+        assert_eq!(heap.pages(), 300);
+        assert_eq!(heap.pages_on(1), 200);
+        let huge = &maps[2];
+        assert_eq!(huge.policy.mode, NumaPolicyMode::WeightedInterleave);
+        assert_eq!(huge.kernel_page_size, Some(ByteSize::from_kib(2048)));
+        assert!(maps[3].stack);
+        assert_eq!(maps[4].pages(), 0);
+        assert_eq!(maps[4].kernel_page_size, None);
+
+        let totals = numa_bytes_by_node(&maps);
+        assert_eq!(totals[&0], ByteSize::from_kib(4 * 102 + 2048 * 2));
+        assert_eq!(totals[&1], ByteSize::from_kib(4 * 203 + 2048 * 2));
+    }
+
+    #[test]
+    fn numa_policy() {
+        let (rest, policy) =
+            NumaPolicy::from_str("prefer (many)=relative|balancing:2 anon=1").unwrap();
+        assert_eq!(rest, " anon=1");
+        assert_eq!(policy.mode, NumaPolicyMode::PreferredMany);
+        assert_eq!(policy.flags, vec!["relative", "balancing"]);
+        assert_eq!(policy.nodes, vec![2]);
+        let (_, policy) = NumaPolicy::from_str("prefer:1").unwrap();
+        assert_eq!(policy.mode, NumaPolicyMode::Preferred);
+        let (_, policy) = NumaPolicy::from_str("local").unwrap();
+        assert_eq!(policy.mode, NumaPolicyMode::Local);
+        let (_, policy) = NumaPolicy::from_str("future:3 anon=1").unwrap();
+        assert_eq!(policy.mode, NumaPolicyMode::Other("future".into()));
+        assert!(NumaPolicy::from_str("bind:x").is_err());
+    }
+
+    #[test]
+    fn numa_maps() {
+        // The file is missing on kernels built without NUMA support.
+        match Process::current().numa_maps() {
+            Ok(maps) => assert!(maps.iter().any(|map| map.pages() > 0)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
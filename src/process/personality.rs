@@ -0,0 +1,76 @@
+//! Bindings to `/proc/[pid]/personality`.
+use std::io;
+
+use bitflags::bitflags;
+
+use crate::{util, Error};
+
+/// The execution domain and flags of a process (see `personality(2)`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Personality {
+    /// The execution domain, e.g. `0` (`PER_LINUX`) or `8` (`PER_LINUX32`).
+    pub domain: u8,
+    pub flags: PersonalityFlags,
+}
+
+bitflags! {
+    /// Flags modifying how the kernel treats a process, from `personality(2)`.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct PersonalityFlags: u32 {
+        /// Report the kernel version as 2.6.40+x (for old software that parses it).
+        const UNAME26 = 0x0002_0000;
+        /// Disable address space layout randomization.
+        const ADDR_NO_RANDOMIZE = 0x0004_0000;
+        const FDPIC_FUNCPTRS = 0x0008_0000;
+        /// Map page 0 read-only, for SVr4 compatibility.
+        const MMAP_PAGE_ZERO = 0x0010_0000;
+        /// Use the legacy virtual address space layout.
+        const ADDR_COMPAT_LAYOUT = 0x0020_0000;
+        /// Make every readable mapping executable.
+        const READ_IMPLIES_EXEC = 0x0040_0000;
+        const ADDR_LIMIT_32BIT = 0x0080_0000;
+        const SHORT_INODE = 0x0100_0000;
+        const WHOLE_SECONDS = 0x0200_0000;
+        const STICKY_TIMEOUTS = 0x0400_0000;
+        const ADDR_LIMIT_3GB = 0x0800_0000;
+
+        // Unknown bits are kept.
+        const _ = !0;
+    }
+}
+
+impl Personality {
+    /// Parse `/proc/[pid]/personality`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(Self::from_str)
+    }
+
+    fn from_str(input: &str) -> Result<Personality, Error> {
+        let (_, value) = util::parse_hex_u64(input).ok_or("personality")?;
+        let value = value as u32;
+        Ok(Personality {
+            domain: (value & 0xff) as u8,
+            flags: PersonalityFlags::from_bits_retain(value & !0xff),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Personality, PersonalityFlags};
+    use std::io;
+
+    #[test]
+    fn proc_pid_personality() {
+        let personality = Personality::from_reader(io::Cursor::new("00440008\n")).unwrap();
+        assert_eq!(personality.domain, 8);
+        assert_eq!(
+            personality.flags,
+            PersonalityFlags::ADDR_NO_RANDOMIZE | PersonalityFlags::READ_IMPLIES_EXEC
+        );
+        let linux = Personality::from_reader(io::Cursor::new("00000000\n")).unwrap();
+        assert_eq!(linux.domain, 0);
+        assert!(linux.flags.is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+//! Reading the same files from every process at once.
+use std::collections::HashMap;
+use std::io;
+#[cfg(feature = "rayon")]
+use std::sync::{Arc, OnceLock};
+
+use super::{exited, Process, ProcessIo, ProcessStat, Status};
+
+/// Options for reading files from every process with `ProcessScan::run`, like `top` or `ps aux`.
+///
+/// With the `rayon` feature the processes are read in parallel, which on a server with thousands
+/// of processes is many times faster than reading them one by one. Only `stat` is read by
+/// default. The thread pool is built by the first `run` and reused by later ones, so keep the
+/// `ProcessScan` around when scanning repeatedly.
+#[derive(Debug, Clone)]
+pub struct ProcessScan {
+    stat: bool,
+    status: bool,
+    io: bool,
+    concurrency: usize,
+    #[cfg(feature = "rayon")]
+    pool: OnceLock<Arc<rayon::ThreadPool>>,
+}
+
+impl Default for ProcessScan {
+    fn default() -> Self {
+        ProcessScan {
+            stat: true,
+            status: false,
+            io: false,
+            concurrency: 0,
+            #[cfg(feature = "rayon")]
+            pool: OnceLock::new(),
+        }
+    }
+}
+
+/// The files read from one process by `ProcessScan::run`.
+///
+/// Files that weren't requested are `None`, as is `io` if we aren't allowed to read it.
+#[derive(Debug, Clone)]
+pub struct ProcessSnapshot {
+    pub pid: u32,
+    pub stat: Option<ProcessStat>,
+    pub status: Option<Status>,
+    pub io: Option<ProcessIo>,
+}
+
+/// The identity of a process: its pid and start time.
+///
+/// Pids are reused once they wrap around `pid_max`, which on a busy machine can take seconds, so
+/// trackers that compare snapshots should key processes by this rather than the pid alone. Two
+/// processes can only share a key if the same pid is reused within one clock tick.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProcessKey {
+    pub pid: u32,
+    /// The time the process started after boot, in clock ticks (`ProcessStat::starttime`).
+    pub starttime: u64,
+}
+
+impl ProcessSnapshot {
+    /// The process's identity, or `None` if `stat` wasn't read.
+    pub fn key(&self) -> Option<ProcessKey> {
+        self.stat.as_ref().map(ProcessStat::key)
+    }
+}
+
+/// The pids that belong to a different process in `later` than in `earlier`, in increasing
+/// order.
+///
+/// Snapshots without `stat` are ignored, since their start time isn't known.
+pub fn reused_pids(earlier: &[ProcessSnapshot], later: &[ProcessSnapshot]) -> Vec<u32> {
+    let earlier: HashMap<u32, u64> = earlier
+        .iter()
+        .filter_map(ProcessSnapshot::key)
+        .map(|key| (key.pid, key.starttime))
+        .collect();
+    let mut reused: Vec<u32> = later
+        .iter()
+        .filter_map(ProcessSnapshot::key)
+        .filter(
+            |key| matches!(earlier.get(&key.pid), Some(&starttime) if starttime != key.starttime),
+        )
+        .map(|key| key.pid)
+        .collect();
+    reused.sort_unstable();
+    reused
+}
+
+impl ProcessScan {
+    /// A scan reading `/proc/[pid]/stat` only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to read `/proc/[pid]/stat`.
+    pub fn stat(mut self, stat: bool) -> Self {
+        self.stat = stat;
+        self
+    }
+
+    /// Whether to read `/proc/[pid]/status`.
+    pub fn status(mut self, status: bool) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Whether to read `/proc/[pid]/io`. Only the owner of a process (or root) can read this.
+    pub fn io(mut self, io: bool) -> Self {
+        self.io = io;
+        self
+    }
+
+    /// The most threads to read with at once, or 0 (the default) for one per cpu.
+    ///
+    /// Without the `rayon` feature processes are always read one at a time.
+    pub fn concurrency(mut self, threads: usize) -> Self {
+        self.concurrency = threads;
+        #[cfg(feature = "rayon")]
+        {
+            self.pool = OnceLock::new();
+        }
+        self
+    }
+
+    /// Read the requested files from every process, sorted by pid.
+    ///
+    /// Processes that exit during the scan are left out.
+    pub fn run(&self) -> io::Result<Vec<ProcessSnapshot>> {
+        let pids = Process::all()?
+            .map(|process| process?.pid())
+            .collect::<io::Result<Vec<u32>>>()?;
+        let mut snapshots = self.read_all(&pids)?;
+        snapshots.sort_by_key(|snapshot| snapshot.pid);
+        Ok(snapshots)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn read_all(&self, pids: &[u32]) -> io::Result<Vec<ProcessSnapshot>> {
+        use rayon::prelude::*;
+
+        self.pool()?.install(|| {
+            pids.par_iter()
+                .filter_map(|&pid| self.read(pid).transpose())
+                .collect()
+        })
+    }
+
+    /// The thread pool to read with, built on first use.
+    #[cfg(feature = "rayon")]
+    fn pool(&self) -> io::Result<&rayon::ThreadPool> {
+        if let Some(pool) = self.pool.get() {
+            return Ok(pool);
+        }
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.concurrency)
+            .build()
+            .map_err(io::Error::other)?;
+        Ok(self.pool.get_or_init(|| Arc::new(pool)))
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    fn read_all(&self, pids: &[u32]) -> io::Result<Vec<ProcessSnapshot>> {
+        pids.iter()
+            .filter_map(|&pid| self.read(pid).transpose())
+            .collect()
+    }
+
+    /// Read the requested files from process `pid`, or `None` if it has exited.
+    fn read(&self, pid: u32) -> io::Result<Option<ProcessSnapshot>> {
+        let process = Process::from_pid(pid);
+        let read = || -> io::Result<ProcessSnapshot> {
+            Ok(ProcessSnapshot {
+                pid,
+                stat: if self.stat {
+                    Some(process.stat()?)
+                } else {
+                    None
+                },
+                status: if self.status {
+                    Some(process.status()?)
+                } else {
+                    None
+                },
+                io: if self.io {
+                    match process.io() {
+                        Ok(io) => Some(io),
+                        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => None,
+                        Err(e) => return Err(e),
+                    }
+                } else {
+                    None
+                },
+            })
+        };
+        match read() {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(ref e) if exited(e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reused_pids, ProcessScan, ProcessSnapshot};
+    use crate::process::stat::test_stat;
+
+    fn snapshot(pid: u32, starttime: u64) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            stat: Some(test_stat(pid, 'S', 0, starttime)),
+            status: None,
+            io: None,
+        }
+    }
+
+    #[test]
+    fn pid_reuse() {
+        let earlier = vec![snapshot(1, 5), snapshot(20, 100), snapshot(30, 200)];
+        let mut unknown = snapshot(40, 300);
+        unknown.stat = None;
+        let later = vec![
+            snapshot(1, 5),
+            snapshot(30, 900),
+            snapshot(20, 800),
+            snapshot(35, 850),
+            unknown,
+        ];
+        assert_eq!(reused_pids(&earlier, &later), vec![20, 30]);
+        assert_eq!(later[1].key().unwrap().starttime, 900);
+        assert!(reused_pids(&earlier, &earlier).is_empty());
+    }
+
+    #[test]
+    fn scan() {
+        let scan = ProcessScan::new().status(true).io(true).concurrency(2);
+        let snapshots = scan.run().unwrap();
+        assert!(snapshots.windows(2).all(|pair| pair[0].pid < pair[1].pid));
+        let me = snapshots
+            .iter()
+            .find(|snapshot| snapshot.pid == std::process::id())
+            .unwrap();
+        assert_eq!(me.stat.as_ref().unwrap().pid, me.pid);
+        assert_eq!(me.status.as_ref().unwrap().pid, me.pid);
+        assert!(me.io.is_some());
+
+        #[cfg(feature = "rayon")]
+        {
+            let pool: *const _ = scan.pool().unwrap();
+            scan.run().unwrap();
+            assert!(std::ptr::eq(pool, scan.pool().unwrap()));
+            assert_eq!(scan.pool().unwrap().current_num_threads(), 2);
+        }
+        let without_io = ProcessScan::new().run().unwrap();
+        assert!(without_io.iter().all(|snapshot| snapshot.io.is_none()));
+    }
+}
@@ -0,0 +1,168 @@
+//! Bindings to `/proc/[pid]/sched` and `/proc/[pid]/autogroup`.
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use crate::{util, Error};
+
+/// Scheduler statistics for a thread, from `/proc/[pid]/sched`.
+///
+/// The file is a debugging interface whose contents depend on the kernel version and config
+/// (`CONFIG_SCHED_DEBUG`, and `schedstats` for the wait times), so only the most useful values
+/// are typed. Everything is available as text in `values`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessSched {
+    /// The total time spent running on a cpu (`se.sum_exec_runtime`).
+    pub exec_runtime: Option<Duration>,
+    /// The total time spent runnable but waiting for a cpu (`wait_sum`, only with schedstats).
+    pub wait_sum: Option<Duration>,
+    /// The number of times the thread was migrated between cpus (`se.nr_migrations`).
+    pub nr_migrations: Option<u64>,
+    /// The number of context switches (`nr_switches`).
+    pub nr_switches: Option<u64>,
+    /// The number of times the thread gave up the cpu, e.g. to wait for I/O.
+    pub nr_voluntary_switches: Option<u64>,
+    /// The number of times the thread was preempted.
+    pub nr_involuntary_switches: Option<u64>,
+    /// Every `key : value` line, with the value as text.
+    pub values: HashMap<String, String>,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Parse a time printed as milliseconds with 6 decimal places, e.g. `0.483966`.
+fn parse_millis(input: &str) -> Result<Duration, Error> {
+    let (input, millis) = err_msg!(util::parse_u64(input), "milliseconds")?;
+    let input = err_msg!(util::expect_bytes(".", input), "expected \".\"")?;
+    let (_, nanos) = err_msg!(util::parse_fraction(input, 1_000_000), "nanoseconds")?;
+    Ok(Duration::from_millis(millis) + Duration::from_nanos(nanos))
+}
+
+fn parse_count(input: &str, msg: &str) -> Result<u64, Error> {
+    Ok(err_msg!(util::parse_u64(input), msg)?.1)
+}
+
+impl ProcessSched {
+    /// Parse `/proc/[pid]/sched`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        // The thread name and pid, and a line of dashes.
+        reader.skip_line()?;
+        reader.skip_line()?;
+        let mut sched = ProcessSched::default();
+        loop {
+            match reader.parse_line(|line| sched.parse_line(line)) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(sched)
+    }
+
+    fn parse_line(&mut self, input: &str) -> Result<(), Error> {
+        // Some lines, like the numa fault counts, aren't `key : value` pairs.
+        let (key, value) = match util::parse_key_value(input) {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+        let key = key.trim_end();
+        match key {
+            "se.sum_exec_runtime" => self.exec_runtime = Some(parse_millis(value)?),
+            // Renamed from `se.statistics.wait_sum` in 5.10.
+            "wait_sum" | "se.statistics.wait_sum" => self.wait_sum = Some(parse_millis(value)?),
+            "se.nr_migrations" => self.nr_migrations = Some(parse_count(value, key)?),
+            "nr_switches" => self.nr_switches = Some(parse_count(value, key)?),
+            "nr_voluntary_switches" => self.nr_voluntary_switches = Some(parse_count(value, key)?),
+            "nr_involuntary_switches" => {
+                self.nr_involuntary_switches = Some(parse_count(value, key)?)
+            }
+            _ => (),
+        }
+        self.values.insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+}
+
+/// The autogroup a process belongs to, from `/proc/[pid]/autogroup`.
+///
+/// With `CONFIG_SCHED_AUTOGROUP`, each session gets its own task group so that cpu time is shared
+/// fairly between sessions rather than threads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Autogroup {
+    /// The autogroup id.
+    pub id: u64,
+    /// The nice value of the group as a whole, from 19 (low priority) to -20 (high priority).
+    pub nice: i64,
+}
+
+impl Autogroup {
+    /// Parse `/proc/[pid]/autogroup`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(Self::from_str)
+    }
+
+    fn from_str(input: &str) -> Result<Autogroup, Error> {
+        let input = err_msg!(util::expect_bytes("/autogroup-", input), "autogroup")?;
+        let (input, id) = err_msg!(util::parse_u64(input), "autogroup id")?;
+        let (input, _) = err_msg!(util::parse_token(input), "expected \"nice\"")?;
+        let (_, nice) = err_msg!(util::parse_i64(input), "nice")?;
+        Ok(Autogroup { id, nice })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Autogroup, ProcessSched};
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_pid_sched() {
+        let raw = "\
+head (31333, #threads: 1)
+-------------------------------------------------------------------
+se.exec_start                                :       2541052.640429
+se.vruntime                                  :          2255.512010
+se.sum_exec_runtime                          :             0.483966
+se.nr_migrations                             :                    2
+sum_sleep_runtime                            :             0.000000
+wait_start                                   :             0.000000
+wait_sum                                     :            12.000500
+nr_switches                                  :                    7
+nr_voluntary_switches                        :                    5
+nr_involuntary_switches                      :                    2
+se.load.weight                               :              1048576
+policy                                       :                    0
+prio                                         :                  120
+mm->numa_scan_seq                            :                    0
+current_node=0, numa_group_id=0
+numa_faults node=0 task_private=0 task_shared=0 group_private=0 group_shared=0
+";
+        let sched = ProcessSched::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(sched.exec_runtime, Some(Duration::from_nanos(483_966)));
+        assert_eq!(
+            sched.wait_sum,
+            Some(Duration::from_millis(12) + Duration::from_nanos(500))
+        );
+        assert_eq!(sched.nr_migrations, Some(2));
+        assert_eq!(
+            (sched.nr_voluntary_switches, sched.nr_involuntary_switches),
+            (Some(5), Some(2))
+        );
+        assert_eq!(sched.values["prio"], "120");
+        assert_eq!(sched.values["mm->numa_scan_seq"], "0");
+    }
+
+    #[test]
+    fn proc_pid_autogroup() {
+        let autogroup =
+            Autogroup::from_reader(io::Cursor::new("/autogroup-126 nice -5\n")).unwrap();
+        assert_eq!(autogroup, Autogroup { id: 126, nice: -5 });
+    }
+}
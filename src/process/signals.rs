@@ -0,0 +1,198 @@
+//! Decoding the signal masks in `/proc/[pid]/status`.
+use std::fmt;
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// A set of signals, as in the `Sig*` and `ShdPnd` fields of `/proc/[pid]/status`, where
+    /// bit `n - 1` is signal `n`.
+    ///
+    /// The named signals use the numbering of x86 and arm, which most architectures share (alpha,
+    /// mips and sparc differ). Signals 32 to 64 are realtime signals.
+    ///
+    /// Displays as a comma-separated list of names, like `SIGINT,SIGTERM`, with realtime signals
+    /// named as `kill -l` does, relative to glibc's `SIGRTMIN` (34).
+    ///
+    /// ```no_run
+    /// use linux_proc::process::{Process, SignalSet};
+    ///
+    /// let status = Process::from_pid(1234).status()?;
+    /// if status.sig_ign.contains(SignalSet::TERM) {
+    ///     println!("SIGTERM is ignored");
+    /// } else if status.sig_blk.contains(SignalSet::TERM) {
+    ///     println!("SIGTERM is blocked, and {}", if status.sig_cgt.contains(SignalSet::TERM) {
+    ///         "will be handled once unblocked"
+    ///     } else {
+    ///         "will terminate the process once unblocked"
+    ///     });
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+    pub struct SignalSet: u64 {
+        const HUP = 1 << 0;
+        const INT = 1 << 1;
+        const QUIT = 1 << 2;
+        const ILL = 1 << 3;
+        const TRAP = 1 << 4;
+        const ABRT = 1 << 5;
+        const BUS = 1 << 6;
+        const FPE = 1 << 7;
+        /// Can't be blocked, ignored or caught.
+        const KILL = 1 << 8;
+        const USR1 = 1 << 9;
+        const SEGV = 1 << 10;
+        const USR2 = 1 << 11;
+        const PIPE = 1 << 12;
+        const ALRM = 1 << 13;
+        const TERM = 1 << 14;
+        const STKFLT = 1 << 15;
+        const CHLD = 1 << 16;
+        const CONT = 1 << 17;
+        /// Can't be blocked, ignored or caught.
+        const STOP = 1 << 18;
+        const TSTP = 1 << 19;
+        const TTIN = 1 << 20;
+        const TTOU = 1 << 21;
+        const URG = 1 << 22;
+        const XCPU = 1 << 23;
+        const XFSZ = 1 << 24;
+        const VTALRM = 1 << 25;
+        const PROF = 1 << 26;
+        const WINCH = 1 << 27;
+        const IO = 1 << 28;
+        const PWR = 1 << 29;
+        const SYS = 1 << 30;
+
+        // Realtime signals, and unknown bits, are kept.
+        const _ = !0;
+    }
+}
+
+/// The first realtime signal available to applications, after the two glibc reserves for its
+/// threads implementation.
+const SIGRTMIN: u32 = 34;
+/// The last realtime signal.
+const SIGRTMAX: u32 = 64;
+
+impl SignalSet {
+    /// The set containing just `signal`, or `None` if it isn't from 1 to 64.
+    pub fn from_signal(signal: u32) -> Option<Self> {
+        match signal {
+            1..=64 => Some(SignalSet::from_bits_retain(1 << (signal - 1))),
+            _ => None,
+        }
+    }
+
+    /// Whether `signal` is in the set.
+    pub fn contains_signal(self, signal: u32) -> bool {
+        SignalSet::from_signal(signal).is_some_and(|set| self.contains(set))
+    }
+
+    /// The signal numbers in the set, in increasing order.
+    pub fn signals(self) -> impl Iterator<Item = u32> {
+        (1..=64).filter(move |&signal| self.contains_signal(signal))
+    }
+
+    /// The name of signal `signal`, like `SIGTERM` or `SIGRTMIN+2`. `None` if it isn't from 1
+    /// to 64.
+    pub fn name(signal: u32) -> Option<String> {
+        let name = match signal {
+            1..=31 => SIGNAL_NAMES[signal as usize - 1].to_owned(),
+            SIGRTMIN => "SIGRTMIN".to_owned(),
+            SIGRTMAX => "SIGRTMAX".to_owned(),
+            // `kill -l` counts up from SIGRTMIN for the first half, and down from SIGRTMAX for
+            // the second.
+            35..=49 => format!("SIGRTMIN+{}", signal - SIGRTMIN),
+            50..=63 => format!("SIGRTMAX-{}", SIGRTMAX - signal),
+            32 | 33 => format!("SIG{}", signal),
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// The names of signals 1 to 31.
+const SIGNAL_NAMES: [&str; 31] = [
+    "SIGHUP",
+    "SIGINT",
+    "SIGQUIT",
+    "SIGILL",
+    "SIGTRAP",
+    "SIGABRT",
+    "SIGBUS",
+    "SIGFPE",
+    "SIGKILL",
+    "SIGUSR1",
+    "SIGSEGV",
+    "SIGUSR2",
+    "SIGPIPE",
+    "SIGALRM",
+    "SIGTERM",
+    "SIGSTKFLT",
+    "SIGCHLD",
+    "SIGCONT",
+    "SIGSTOP",
+    "SIGTSTP",
+    "SIGTTIN",
+    "SIGTTOU",
+    "SIGURG",
+    "SIGXCPU",
+    "SIGXFSZ",
+    "SIGVTALRM",
+    "SIGPROF",
+    "SIGWINCH",
+    "SIGIO",
+    "SIGPWR",
+    "SIGSYS",
+];
+
+impl fmt::Display for SignalSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, signal) in self.signals().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            // Every signal in the set is from 1 to 64, so has a name.
+            f.write_str(&SignalSet::name(signal).unwrap_or_default())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignalSet, SIGNAL_NAMES};
+
+    #[test]
+    fn signal_set() {
+        // What a shell typically catches.
+        let set = SignalSet::from_bits_retain(0x0000_0000_4b81_7efb);
+        assert!(set.contains(SignalSet::INT | SignalSet::TERM | SignalSet::CHLD));
+        assert!(set.contains_signal(2));
+        assert!(!set.contains_signal(9));
+        assert_eq!(
+            set.signals().collect::<Vec<_>>(),
+            vec![1, 2, 4, 5, 6, 7, 8, 10, 11, 12, 13, 14, 15, 17, 24, 25, 26, 28, 31]
+        );
+        assert_eq!(
+            (SignalSet::TERM | SignalSet::USR1).to_string(),
+            "SIGUSR1,SIGTERM"
+        );
+        let realtime = SignalSet::from_bits_retain(0xc000_000c_0000_0000);
+        assert_eq!(
+            realtime.to_string(),
+            "SIGRTMIN+1,SIGRTMIN+2,SIGRTMAX-1,SIGRTMAX"
+        );
+        assert_eq!(SignalSet::empty().to_string(), "");
+        assert_eq!(SignalSet::from_signal(0), None);
+        assert_eq!(SignalSet::from_signal(65), None);
+        assert_eq!(SignalSet::name(32).as_deref(), Some("SIG32"));
+        assert_eq!(SignalSet::name(34).as_deref(), Some("SIGRTMIN"));
+        assert_eq!(SignalSet::name(0), None);
+        for (idx, name) in SIGNAL_NAMES.iter().enumerate() {
+            let flag = SignalSet::from_signal(idx as u32 + 1).unwrap();
+            assert_eq!(flag.iter_names().next().unwrap().0, &name[3..]);
+        }
+    }
+}
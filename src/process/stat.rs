@@ -0,0 +1,278 @@
+//! Bindings to `/proc/[pid]/stat`.
+use std::io;
+use std::time::Duration;
+
+use super::{ProcessKey, ProcessState};
+use crate::{util, Error, Pages};
+
+/// The status information from `/proc/[pid]/stat`.
+///
+/// Times are in clock ticks (see `sysconf(_SC_CLK_TCK)`, usually 100 per second). Fields that
+/// were added in later kernel versions are optional.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ProcessStat {
+    pub pid: u32,
+    /// The filename of the executable, without the surrounding parentheses. This is truncated to
+    /// 15 bytes by the kernel.
+    pub comm: String,
+    /// The state of the process, one of "RSDZTtWXxKWPI" depending on kernel version.
+    pub state: char,
+    pub ppid: u32,
+    /// The process group id.
+    pub pgrp: u32,
+    /// The session id.
+    pub session: u32,
+    /// The controlling terminal of the process.
+    pub tty_nr: i64,
+    /// The id of the foreground process group of the controlling terminal.
+    pub tpgid: i64,
+    /// The kernel flags word of the process.
+    pub flags: u64,
+    /// The number of minor faults the process has made.
+    pub minflt: u64,
+    /// The number of minor faults the process's waited-for children have made.
+    pub cminflt: u64,
+    /// The number of major faults the process has made.
+    pub majflt: u64,
+    /// The number of major faults the process's waited-for children have made.
+    pub cmajflt: u64,
+    /// Time the process has been scheduled in user mode, in clock ticks.
+    pub utime: u64,
+    /// Time the process has been scheduled in kernel mode, in clock ticks.
+    pub stime: u64,
+    /// Time waited-for children have been scheduled in user mode, in clock ticks.
+    pub cutime: i64,
+    /// Time waited-for children have been scheduled in kernel mode, in clock ticks.
+    pub cstime: i64,
+    pub priority: i64,
+    /// The nice value, from 19 (low priority) to -20 (high priority).
+    pub nice: i64,
+    pub num_threads: u64,
+    /// The time the process started after system boot, in clock ticks.
+    pub starttime: u64,
+    /// Virtual memory size in bytes.
+    pub vsize: u64,
+    /// Resident set size.
+    pub rss: Pages,
+    /// Soft limit on the resident set size, in bytes.
+    pub rsslim: u64,
+    /// The signal sent to the parent when the process dies (since 2.1.22).
+    pub exit_signal: Option<i64>,
+    /// The cpu the process last ran on (since 2.2.8).
+    pub processor: Option<u64>,
+    /// The realtime scheduling priority (since 2.5.19).
+    pub rt_priority: Option<u64>,
+    /// The scheduling policy (since 2.5.19).
+    pub policy: Option<u64>,
+    /// Aggregated block I/O delays, in clock ticks (since 2.6.18).
+    pub delayacct_blkio_ticks: Option<u64>,
+    /// Time spent running a virtual cpu for a guest OS, in clock ticks (since 2.6.24).
+    pub guest_time: Option<u64>,
+    /// Guest time of the process's children, in clock ticks (since 2.6.24).
+    pub cguest_time: Option<i64>,
+    /// The thread's exit status, as reported by `waitpid` (since 3.5).
+    pub exit_code: Option<i64>,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Parse an optional trailing field, leaving the input untouched if it's missing.
+fn optional<'a, T>(
+    input: &'a str,
+    parser: fn(&'a str) -> Option<(&'a str, T)>,
+) -> (&'a str, Option<T>) {
+    match parser(input) {
+        Some((input, value)) => (input, Some(value)),
+        None => (input, None),
+    }
+}
+
+/// Skip `count` unsigned fields that we don't expose.
+fn skip_fields(mut input: &str, count: usize) -> Option<&str> {
+    for _ in 0..count {
+        input = util::parse_u64(input)?.0;
+    }
+    Some(input)
+}
+
+impl ProcessStat {
+    /// Parse `/proc/[pid]/stat`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(Self::from_str)
+    }
+
+    /// The process's identity, which unlike the pid isn't reused.
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid,
+            starttime: self.starttime,
+        }
+    }
+
+    /// `state` decoded.
+    pub fn process_state(&self) -> ProcessState {
+        ProcessState::from_char(self.state)
+    }
+
+    /// `utime` as a `Duration`, given the number of clock ticks per second. `None` if
+    /// `ticks_per_sec` is 0.
+    pub fn user_time(&self, ticks_per_sec: u64) -> Option<Duration> {
+        util::ticks_to_duration(self.utime, ticks_per_sec)
+    }
+
+    /// `stime` as a `Duration`, given the number of clock ticks per second. `None` if
+    /// `ticks_per_sec` is 0.
+    pub fn system_time(&self, ticks_per_sec: u64) -> Option<Duration> {
+        util::ticks_to_duration(self.stime, ticks_per_sec)
+    }
+
+    /// `starttime` as a `Duration` after boot, given the number of clock ticks per second. `None` if
+    /// `ticks_per_sec` is 0.
+    pub fn start_time(&self, ticks_per_sec: u64) -> Option<Duration> {
+        util::ticks_to_duration(self.starttime, ticks_per_sec)
+    }
+
+    fn from_str(input: &str) -> Result<ProcessStat, Error> {
+        let (input, pid) = err_msg!(util::parse_u64(input), "pid")?;
+        // The command can contain anything, including spaces and parentheses, so we look for the
+        // last closing paren.
+        let input = err_msg!(util::expect_bytes("(", input), "expected \"(\"")?;
+        let end = err_msg!(input.rfind(')'), "expected \")\"")?;
+        let comm = input[..end].to_owned();
+        let input = &input[end + 1..];
+        let (input, state) = err_msg!(util::parse_token(input), "state")?;
+        let state = err_msg!(state.chars().next(), "state")?;
+        let (input, ppid) = err_msg!(util::parse_u64(input), "ppid")?;
+        let (input, pgrp) = err_msg!(util::parse_u64(input), "pgrp")?;
+        let (input, session) = err_msg!(util::parse_u64(input), "session")?;
+        let (input, tty_nr) = err_msg!(util::parse_i64(input), "tty_nr")?;
+        let (input, tpgid) = err_msg!(util::parse_i64(input), "tpgid")?;
+        let (input, flags) = err_msg!(util::parse_u64(input), "flags")?;
+        let (input, minflt) = err_msg!(util::parse_u64(input), "minflt")?;
+        let (input, cminflt) = err_msg!(util::parse_u64(input), "cminflt")?;
+        let (input, majflt) = err_msg!(util::parse_u64(input), "majflt")?;
+        let (input, cmajflt) = err_msg!(util::parse_u64(input), "cmajflt")?;
+        let (input, utime) = err_msg!(util::parse_u64(input), "utime")?;
+        let (input, stime) = err_msg!(util::parse_u64(input), "stime")?;
+        let (input, cutime) = err_msg!(util::parse_i64(input), "cutime")?;
+        let (input, cstime) = err_msg!(util::parse_i64(input), "cstime")?;
+        let (input, priority) = err_msg!(util::parse_i64(input), "priority")?;
+        let (input, nice) = err_msg!(util::parse_i64(input), "nice")?;
+        let (input, num_threads) = err_msg!(util::parse_u64(input), "num_threads")?;
+        let (input, _itrealvalue) = err_msg!(util::parse_i64(input), "itrealvalue")?;
+        let (input, starttime) = err_msg!(util::parse_u64(input), "starttime")?;
+        let (input, vsize) = err_msg!(util::parse_u64(input), "vsize")?;
+        let (input, rss) = err_msg!(util::parse_u64(input), "rss")?;
+        let (input, rsslim) = err_msg!(util::parse_u64(input), "rsslim")?;
+        // startcode to cnswap: addresses, obsolete signal fields, wchan and swap counts.
+        let input = err_msg!(skip_fields(input, 12), "startcode to cnswap")?;
+        // Following are optional fields
+        let (input, exit_signal) = optional(input, util::parse_i64);
+        let (input, processor) = optional(input, util::parse_u64);
+        let (input, rt_priority) = optional(input, util::parse_u64);
+        let (input, policy) = optional(input, util::parse_u64);
+        let (input, delayacct_blkio_ticks) = optional(input, util::parse_u64);
+        let (input, guest_time) = optional(input, util::parse_u64);
+        let (input, cguest_time) = optional(input, util::parse_i64);
+        // start_data to env_end: addresses.
+        let exit_code = skip_fields(input, 7)
+            .and_then(util::parse_i64)
+            .map(|(_, exit_code)| exit_code);
+        // We don't check remaining content as future linux may add extra columns.
+        Ok(ProcessStat {
+            pid: util::to_u32(pid, "pid")?,
+            comm,
+            state,
+            ppid: util::to_u32(ppid, "ppid")?,
+            pgrp: util::to_u32(pgrp, "pgrp")?,
+            session: util::to_u32(session, "session")?,
+            tty_nr,
+            tpgid,
+            flags,
+            minflt,
+            cminflt,
+            majflt,
+            cmajflt,
+            utime,
+            stime,
+            cutime,
+            cstime,
+            priority,
+            nice,
+            num_threads,
+            starttime,
+            vsize,
+            rss: Pages(rss),
+            rsslim,
+            exit_signal,
+            processor,
+            rt_priority,
+            policy,
+            delayacct_blkio_ticks,
+            guest_time,
+            cguest_time,
+            exit_code,
+        })
+    }
+}
+
+/// A `ProcessStat` for pid `pid`, with command `proc[pid]`, for tests of code built on it.
+///
+/// It is parsed from a generated line, so it always matches what the parser produces.
+#[cfg(test)]
+pub(crate) fn test_stat(pid: u32, state: char, utime: u64, starttime: u64) -> ProcessStat {
+    let raw = format!(
+        "{0} (proc{0}) {1} 1 1 1 0 -1 0 0 0 0 0 {2} 0 0 0 20 0 1 0 {3} 0 0 0 0 0 0 0 0 0 0 0 0 0 0 17 0\n",
+        pid, state, utime, starttime
+    );
+    ProcessStat::from_reader(io::Cursor::new(raw)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessStat;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_pid_stat() {
+        let raw = "\
+11082 (tmux: server (1)) S 10972 10972 10972 0 -1 4194304 78 0 0 0 3 5 0 0 20 0 1 0 97004 2703360 285 18446744073709551615 94608566149120 94608566169001 140723992948848 0 0 0 0 0 0 0 0 0 17 3 0 0 0 0 0 94608566185008 94608566186624 94608880726016 140723992949905 140723992949925 140723992949925 140723992952811 0
+";
+        let stat = ProcessStat::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stat.pid, 11082);
+        assert_eq!(stat.comm, "tmux: server (1)");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.tpgid, -1);
+        assert_eq!((stat.utime, stat.stime), (3, 5));
+        assert_eq!(stat.starttime, 97004);
+        assert_eq!(stat.start_time(100), Some(Duration::from_millis(970_040)));
+        assert_eq!(stat.system_time(100), Some(Duration::from_millis(50)));
+        assert_eq!(stat.user_time(0), None);
+        assert_eq!(stat.rsslim, u64::MAX);
+        assert_eq!(stat.exit_signal, Some(17));
+        assert_eq!(stat.processor, Some(3));
+        assert_eq!(stat.exit_code, Some(0));
+
+        let huge_pid = raw.replacen("11082", "4294978378", 1);
+        let err = ProcessStat::from_reader(io::Cursor::new(huge_pid)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("pid out of range"));
+    }
+
+    #[test]
+    fn proc_pid_stat_old_kernel() {
+        let raw = "\
+1 (init) S 0 1 1 0 -1 8388864 1127 1306745 15 1042 2 34 24367 8203 15 0 1 0 6 1601536 163 4294967295 134512640 134544996 3217223936 3217223196 3077743622 0 0 1475812603 675036815 3222393893 0 0 17 0
+";
+        let stat = ProcessStat::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stat.processor, Some(0));
+        assert_eq!(stat.rt_priority, None);
+        assert_eq!(stat.exit_code, None);
+    }
+}
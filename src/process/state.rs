@@ -0,0 +1,211 @@
+//! Decoding the state of a process, and counting processes by state.
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::iter::FromIterator;
+
+use super::ProcessScan;
+
+/// The scheduler state of a process, from the state letter in `/proc/[pid]/stat` or
+/// `/proc/[pid]/status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProcessState {
+    /// Running or runnable (`R`).
+    Running,
+    /// Sleeping until woken, interruptibly (`S`).
+    Sleeping,
+    /// Sleeping uninterruptibly, usually waiting for disk I/O (`D`).
+    DiskSleep,
+    /// Exited but not yet reaped by its parent (`Z`).
+    Zombie,
+    /// Stopped by a signal (`T`).
+    Stopped,
+    /// Stopped by a debugger (`t`, since 2.6.33).
+    TracingStop,
+    /// Dead, which should never be seen (`X` or `x`).
+    Dead,
+    /// An idle kernel thread (`I`, since 4.14).
+    Idle,
+    /// Parked, a kernel thread waiting to be unparked (`P`, 3.9 to 3.13).
+    Parked,
+    /// Waking (`W`, 2.6.33 to 3.13) or paging (before 2.6.0).
+    Waking,
+    /// Killed while sleeping (`K`, 2.6.33 to 3.13).
+    WakeKill,
+    /// A letter this crate doesn't know about.
+    Other(char),
+}
+
+impl ProcessState {
+    /// Decode the state letter.
+    pub fn from_char(state: char) -> Self {
+        match state {
+            'R' => ProcessState::Running,
+            'S' => ProcessState::Sleeping,
+            'D' => ProcessState::DiskSleep,
+            'Z' => ProcessState::Zombie,
+            'T' => ProcessState::Stopped,
+            't' => ProcessState::TracingStop,
+            'X' | 'x' => ProcessState::Dead,
+            'I' => ProcessState::Idle,
+            'P' => ProcessState::Parked,
+            'W' => ProcessState::Waking,
+            'K' => ProcessState::WakeKill,
+            other => ProcessState::Other(other),
+        }
+    }
+
+    /// The state letter, as the kernel prints it.
+    pub fn as_char(self) -> char {
+        match self {
+            ProcessState::Running => 'R',
+            ProcessState::Sleeping => 'S',
+            ProcessState::DiskSleep => 'D',
+            ProcessState::Zombie => 'Z',
+            ProcessState::Stopped => 'T',
+            ProcessState::TracingStop => 't',
+            ProcessState::Dead => 'X',
+            ProcessState::Idle => 'I',
+            ProcessState::Parked => 'P',
+            ProcessState::Waking => 'W',
+            ProcessState::WakeKill => 'K',
+            ProcessState::Other(other) => other,
+        }
+    }
+
+    /// Whether the process is running or waiting for a cpu.
+    pub fn is_running(self) -> bool {
+        self == ProcessState::Running
+    }
+
+    /// Whether the process is in uninterruptible sleep, so can't even be killed until it wakes.
+    ///
+    /// These processes count towards the load average. Many of them usually means slow or hung
+    /// storage.
+    pub fn is_uninterruptible(self) -> bool {
+        self == ProcessState::DiskSleep
+    }
+
+    /// Whether the process has exited but hasn't been reaped by its parent.
+    pub fn is_zombie(self) -> bool {
+        self == ProcessState::Zombie
+    }
+
+    /// Whether the process is stopped by a signal or a debugger.
+    pub fn is_stopped(self) -> bool {
+        matches!(self, ProcessState::Stopped | ProcessState::TracingStop)
+    }
+}
+
+impl From<char> for ProcessState {
+    fn from(state: char) -> Self {
+        ProcessState::from_char(state)
+    }
+}
+
+impl fmt::Display for ProcessState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ProcessState::Running => "running",
+            ProcessState::Sleeping => "sleeping",
+            ProcessState::DiskSleep => "disk sleep",
+            ProcessState::Zombie => "zombie",
+            ProcessState::Stopped => "stopped",
+            ProcessState::TracingStop => "tracing stop",
+            ProcessState::Dead => "dead",
+            ProcessState::Idle => "idle",
+            ProcessState::Parked => "parked",
+            ProcessState::Waking => "waking",
+            ProcessState::WakeKill => "wakekill",
+            ProcessState::Other(other) => return write!(f, "unknown ({})", other),
+        };
+        f.pad(name)
+    }
+}
+
+/// The number of processes in each state, a standard health indicator.
+///
+/// Threads aren't counted separately; each process is counted in the state of its main thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateCounts {
+    inner: HashMap<ProcessState, usize>,
+}
+
+impl StateCounts {
+    /// Count the states of every process in `/proc`.
+    pub fn from_system() -> io::Result<Self> {
+        let processes = ProcessScan::new().run()?;
+        Ok(processes
+            .iter()
+            .filter_map(|process| process.stat.as_ref())
+            .map(|stat| ProcessState::from_char(stat.state))
+            .collect())
+    }
+
+    /// The number of processes in `state`.
+    pub fn get(&self, state: ProcessState) -> usize {
+        self.inner.get(&state).copied().unwrap_or(0)
+    }
+
+    /// The number of processes counted.
+    pub fn total(&self) -> usize {
+        self.inner.values().sum()
+    }
+
+    /// The number of different states seen.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether no processes were counted.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over the states seen and their counts, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (ProcessState, usize)> + '_ {
+        self.inner.iter().map(|(&state, &count)| (state, count))
+    }
+}
+
+impl FromIterator<ProcessState> for StateCounts {
+    fn from_iter<I: IntoIterator<Item = ProcessState>>(states: I) -> Self {
+        let mut inner = HashMap::new();
+        for state in states {
+            *inner.entry(state).or_insert(0) += 1;
+        }
+        StateCounts { inner }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProcessState, StateCounts};
+
+    #[test]
+    fn process_state() {
+        for letter in "RSDZTtXIPWK?".chars() {
+            let state = ProcessState::from_char(letter);
+            assert_eq!(state.as_char(), letter);
+        }
+        assert!(ProcessState::from('D').is_uninterruptible());
+        assert!(ProcessState::from('Z').is_zombie());
+        assert!(ProcessState::from('t').is_stopped());
+        assert_eq!(ProcessState::from('x'), ProcessState::Dead);
+        assert_eq!(ProcessState::DiskSleep.to_string(), "disk sleep");
+
+        let counts: StateCounts = "RSSZ".chars().map(ProcessState::from).collect();
+        assert_eq!(counts.get(ProcessState::Sleeping), 2);
+        assert_eq!(counts.get(ProcessState::DiskSleep), 0);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(
+            counts
+                .iter()
+                .find(|&(state, _)| state == ProcessState::Zombie),
+            Some((ProcessState::Zombie, 1))
+        );
+
+        assert!(StateCounts::from_system().unwrap().total() > 0);
+    }
+}
@@ -0,0 +1,401 @@
+//! Bindings to `/proc/[pid]/status`.
+use std::convert::TryFrom;
+use std::io;
+
+use super::{Capabilities, ProcessState, SignalSet};
+use crate::{parse::ParseMode, util, ByteSize, Error};
+
+/// The human-readable process information from `/proc/[pid]/status`.
+///
+/// Fields that are missing on older kernels, or for kernel threads (which
+/// have no memory map), are optional.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Status {
+    /// The command run by this process.
+    pub name: String,
+    /// The process umask (since 4.7).
+    pub umask: Option<u32>,
+    /// The state of the process, e.g. `'R'` for running.
+    pub state: char,
+    /// The thread group id (the process id of the process containing this thread).
+    pub tgid: u32,
+    /// The NUMA group id (since 3.13).
+    pub ngid: Option<u32>,
+    /// The thread id.
+    pub pid: u32,
+    pub ppid: u32,
+    /// The pid of the process tracing this process, `0` if it isn't being traced.
+    pub tracer_pid: u32,
+    /// Real, effective, saved set and filesystem uids.
+    pub uid: [u32; 4],
+    /// Real, effective, saved set and filesystem gids.
+    pub gid: [u32; 4],
+    /// The number of file descriptor slots currently allocated.
+    pub fd_size: u64,
+    /// Supplementary group list.
+    pub groups: Vec<u32>,
+    /// Peak virtual memory size.
+    pub vm_peak: Option<ByteSize>,
+    /// Virtual memory size.
+    pub vm_size: Option<ByteSize>,
+    /// Locked memory size.
+    pub vm_lck: Option<ByteSize>,
+    /// Pinned memory size (since 3.2).
+    pub vm_pin: Option<ByteSize>,
+    /// Peak resident set size ("high water mark").
+    pub vm_hwm: Option<ByteSize>,
+    /// Resident set size.
+    pub vm_rss: Option<ByteSize>,
+    /// Size of resident anonymous memory (since 4.5).
+    pub rss_anon: Option<ByteSize>,
+    /// Size of resident file mappings (since 4.5).
+    pub rss_file: Option<ByteSize>,
+    /// Size of resident shared memory (since 4.5).
+    pub rss_shmem: Option<ByteSize>,
+    /// Size of the data segment.
+    pub vm_data: Option<ByteSize>,
+    /// Size of the stack segment.
+    pub vm_stk: Option<ByteSize>,
+    /// Size of the text segment.
+    pub vm_exe: Option<ByteSize>,
+    /// Shared library code size.
+    pub vm_lib: Option<ByteSize>,
+    /// Page table entries size (since 2.6.10).
+    pub vm_pte: Option<ByteSize>,
+    /// Swapped-out virtual memory size (since 2.6.34).
+    pub vm_swap: Option<ByteSize>,
+    /// The number of threads in the process.
+    pub threads: u64,
+    /// Signals pending for the thread.
+    pub sig_pnd: SignalSet,
+    /// Signals pending for the process as a whole.
+    pub shd_pnd: SignalSet,
+    /// Blocked signals, which stay pending until unblocked.
+    pub sig_blk: SignalSet,
+    /// Ignored signals, which are discarded.
+    pub sig_ign: SignalSet,
+    /// Caught signals, which run a handler.
+    pub sig_cgt: SignalSet,
+    /// Inheritable capabilities.
+    pub cap_inh: Capabilities,
+    /// Permitted capabilities.
+    pub cap_prm: Capabilities,
+    /// Effective capabilities.
+    pub cap_eff: Capabilities,
+    /// Capability bounding set (since 2.6.26).
+    pub cap_bnd: Option<Capabilities>,
+    /// Ambient capability set (since 4.3).
+    pub cap_amb: Option<Capabilities>,
+    /// Whether the process can't gain privileges through `execve` (see `PR_SET_NO_NEW_PRIVS`,
+    /// since 4.10).
+    pub no_new_privs: Option<bool>,
+    /// The seccomp mode of the process (since 3.8).
+    pub seccomp: Option<SeccompMode>,
+    /// The number of seccomp filters attached to the process (since 5.9).
+    pub seccomp_filters: Option<u64>,
+    /// The number of voluntary context switches (since 2.6.23).
+    pub voluntary_ctxt_switches: Option<u64>,
+    /// The number of involuntary context switches (since 2.6.23).
+    pub nonvoluntary_ctxt_switches: Option<u64>,
+    /// Lines with unknown keys, or values that couldn't be parsed.
+    ///
+    /// Only filled in when parsed with `ParseMode::Lenient`. Otherwise unknown keys are ignored,
+    /// and bad values are an error.
+    pub extras: Vec<String>,
+}
+
+/// The seccomp mode of a process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SeccompMode {
+    /// No restrictions.
+    Disabled,
+    /// Only `read`, `write`, `_exit` and `sigreturn` are allowed.
+    Strict,
+    /// Syscalls are checked by BPF filters.
+    Filter,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+fn parse_u32(input: &str, msg: &str) -> Result<u32, Error> {
+    let (_, value) = err_msg!(util::parse_u64(input), msg)?;
+    u32::try_from(value).map_err(|_| Error::from(msg))
+}
+
+fn parse_hex(input: &str, msg: &str) -> Result<u64, Error> {
+    u64::from_str_radix(input.trim(), 16).map_err(|_| Error::from(msg))
+}
+
+fn parse_signals(input: &str, msg: &str) -> Result<SignalSet, Error> {
+    Ok(SignalSet::from_bits_retain(parse_hex(input, msg)?))
+}
+
+fn parse_caps(input: &str, msg: &str) -> Result<Capabilities, Error> {
+    Ok(Capabilities::from_bits_retain(parse_hex(input, msg)?))
+}
+
+fn parse_size(input: &str, msg: &str) -> Result<ByteSize, Error> {
+    Ok(err_msg!(util::parse_size(input), msg)?.1)
+}
+
+fn parse_ids(input: &str, msg: &str) -> Result<[u32; 4], Error> {
+    let mut ids = [0; 4];
+    let mut input = input;
+    for id in ids.iter_mut() {
+        let (rest, value) = err_msg!(util::parse_u64(input), msg)?;
+        *id = u32::try_from(value).map_err(|_| Error::from(msg))?;
+        input = rest;
+    }
+    Ok(ids)
+}
+
+impl Status {
+    /// Parse `/proc/[pid]/status`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        Status::from_reader_with(reader, ParseMode::Strict)
+    }
+
+    /// Parse `/proc/[pid]/status`-formatted data from `reader`, treating lines that aren't
+    /// understood according to `mode`.
+    pub fn from_reader_with(reader: impl io::Read, mode: ParseMode) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let mut status = Status::default();
+        let mut extras = Vec::new();
+        loop {
+            match reader
+                .parse_line_with_mode(mode, &mut extras, |line| status.parse_line(line, mode))
+            {
+                Ok(_) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        status.extras = extras;
+        Ok(status)
+    }
+
+    /// `state` decoded.
+    pub fn process_state(&self) -> ProcessState {
+        ProcessState::from_char(self.state)
+    }
+
+    fn parse_line(&mut self, input: &str, mode: ParseMode) -> Result<(), Error> {
+        let (key, value) = err_msg!(util::parse_key_value(input), "expected \":\"")?;
+        match key {
+            "Name" => self.name = value.trim().to_owned(),
+            "Umask" => {
+                let umask = u32::from_str_radix(value.trim(), 8).map_err(|_| "umask")?;
+                self.umask = Some(umask);
+            }
+            "State" => {
+                let (_, state) = err_msg!(util::parse_token(value), "state")?;
+                self.state = err_msg!(state.chars().next(), "state")?;
+            }
+            "Tgid" => self.tgid = parse_u32(value, "tgid")?,
+            "Ngid" => self.ngid = Some(parse_u32(value, "ngid")?),
+            "Pid" => self.pid = parse_u32(value, "pid")?,
+            "PPid" => self.ppid = parse_u32(value, "ppid")?,
+            "TracerPid" => self.tracer_pid = parse_u32(value, "tracer pid")?,
+            "Uid" => self.uid = parse_ids(value, "uid")?,
+            "Gid" => self.gid = parse_ids(value, "gid")?,
+            "FDSize" => self.fd_size = err_msg!(util::parse_u64(value), "fd size")?.1,
+            "Groups" => {
+                self.groups = value
+                    .split_whitespace()
+                    .map(|group| group.parse().map_err(|_| Error::from("group")))
+                    .collect::<Result<_, _>>()?
+            }
+            "VmPeak" => self.vm_peak = Some(parse_size(value, "vm peak")?),
+            "VmSize" => self.vm_size = Some(parse_size(value, "vm size")?),
+            "VmLck" => self.vm_lck = Some(parse_size(value, "vm locked")?),
+            "VmPin" => self.vm_pin = Some(parse_size(value, "vm pinned")?),
+            "VmHWM" => self.vm_hwm = Some(parse_size(value, "vm hwm")?),
+            "VmRSS" => self.vm_rss = Some(parse_size(value, "vm rss")?),
+            "RssAnon" => self.rss_anon = Some(parse_size(value, "rss anon")?),
+            "RssFile" => self.rss_file = Some(parse_size(value, "rss file")?),
+            "RssShmem" => self.rss_shmem = Some(parse_size(value, "rss shmem")?),
+            "VmData" => self.vm_data = Some(parse_size(value, "vm data")?),
+            "VmStk" => self.vm_stk = Some(parse_size(value, "vm stack")?),
+            "VmExe" => self.vm_exe = Some(parse_size(value, "vm exe")?),
+            "VmLib" => self.vm_lib = Some(parse_size(value, "vm lib")?),
+            "VmPTE" => self.vm_pte = Some(parse_size(value, "vm pte")?),
+            "VmSwap" => self.vm_swap = Some(parse_size(value, "vm swap")?),
+            "Threads" => self.threads = err_msg!(util::parse_u64(value), "threads")?.1,
+            "SigPnd" => self.sig_pnd = parse_signals(value, "sig pending")?,
+            "ShdPnd" => self.shd_pnd = parse_signals(value, "shared pending")?,
+            "SigBlk" => self.sig_blk = parse_signals(value, "sig blocked")?,
+            "SigIgn" => self.sig_ign = parse_signals(value, "sig ignored")?,
+            "SigCgt" => self.sig_cgt = parse_signals(value, "sig caught")?,
+            "CapInh" => self.cap_inh = parse_caps(value, "cap inheritable")?,
+            "CapPrm" => self.cap_prm = parse_caps(value, "cap permitted")?,
+            "CapEff" => self.cap_eff = parse_caps(value, "cap effective")?,
+            "CapBnd" => self.cap_bnd = Some(parse_caps(value, "cap bounding")?),
+            "CapAmb" => self.cap_amb = Some(parse_caps(value, "cap ambient")?),
+            "NoNewPrivs" => {
+                let (_, flag) = err_msg!(util::parse_u64(value), "no new privs")?;
+                self.no_new_privs = Some(flag != 0);
+            }
+            "Seccomp" => {
+                self.seccomp = Some(match err_msg!(util::parse_u64(value), "seccomp")?.1 {
+                    0 => SeccompMode::Disabled,
+                    1 => SeccompMode::Strict,
+                    2 => SeccompMode::Filter,
+                    _ => return Err("unknown seccomp mode".into()),
+                })
+            }
+            "Seccomp_filters" => {
+                let (_, filters) = err_msg!(util::parse_u64(value), "seccomp filters")?;
+                self.seccomp_filters = Some(filters);
+            }
+            "voluntary_ctxt_switches" => {
+                let (_, switches) = err_msg!(util::parse_u64(value), "voluntary switches")?;
+                self.voluntary_ctxt_switches = Some(switches);
+            }
+            "nonvoluntary_ctxt_switches" => {
+                let (_, switches) = err_msg!(util::parse_u64(value), "nonvoluntary switches")?;
+                self.nonvoluntary_ctxt_switches = Some(switches);
+            }
+            _ if mode == ParseMode::Lenient => return Err("unknown key".into()),
+            // Ignore anything we don't know about: new fields get added regularly.
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SeccompMode, Status};
+    use crate::{
+        parse::ParseMode,
+        process::{Capabilities, SignalSet},
+        ByteSize,
+    };
+    use std::io;
+
+    #[test]
+    fn proc_pid_status() {
+        let raw = "\
+Name:\tcat
+Umask:\t0022
+State:\tR (running)
+Tgid:\t11083
+Ngid:\t0
+Pid:\t11083
+PPid:\t10972
+TracerPid:\t0
+Uid:\t1000\t1000\t1000\t1000
+Gid:\t100\t100\t100\t100
+FDSize:\t64
+Groups:\t10 100
+NStgid:\t11083
+NSpid:\t11083
+VmPeak:\t    2640 kB
+VmSize:\t    2640 kB
+VmLck:\t       0 kB
+VmPin:\t       0 kB
+VmHWM:\t    1304 kB
+VmRSS:\t    1304 kB
+RssAnon:\t     104 kB
+RssFile:\t    1200 kB
+RssShmem:\t       0 kB
+VmData:\t     360 kB
+VmStk:\t     132 kB
+VmExe:\t      20 kB
+VmLib:\t    1528 kB
+VmPTE:\t      44 kB
+VmSwap:\t       0 kB
+HugetlbPages:\t       0 kB
+Threads:\t1
+SigQ:\t0/23961
+SigPnd:\t0000000000000000
+ShdPnd:\t0000000000000000
+SigBlk:\t0000000000010000
+SigIgn:\t0000000000000006
+SigCgt:\t0000000180000000
+CapInh:\t0000000000000000
+CapPrm:\t0000000000000000
+CapEff:\t0000000000000000
+CapBnd:\t000001ffffffffff
+CapAmb:\t0000000000000000
+NoNewPrivs:\t1
+Seccomp:\t2
+Seccomp_filters:\t3
+Cpus_allowed_list:\t0-3
+voluntary_ctxt_switches:\t3
+nonvoluntary_ctxt_switches:\t1
+";
+        let status = Status::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(status.name, "cat");
+        assert_eq!(status.umask, Some(0o022));
+        assert_eq!(status.state, 'R');
+        assert!(status.process_state().is_running());
+        assert_eq!(status.uid, [1000; 4]);
+        assert_eq!(status.groups, vec![10, 100]);
+        assert_eq!(status.vm_rss, Some(ByteSize::from_kib(1304)));
+        assert_eq!(status.sig_blk, SignalSet::CHLD);
+        assert_eq!(
+            status.cap_bnd,
+            Some(Capabilities::from_bits_retain(0x1ff_ffff_ffff))
+        );
+        assert!(status.cap_eff.is_empty());
+        assert_eq!(status.nonvoluntary_ctxt_switches, Some(1));
+        assert_eq!(status.no_new_privs, Some(true));
+        assert_eq!(status.seccomp, Some(SeccompMode::Filter));
+        assert_eq!(status.seccomp_filters, Some(3));
+        assert!(status.extras.is_empty());
+
+        let status = Status::from_reader_with(io::Cursor::new(raw), ParseMode::Lenient).unwrap();
+        assert_eq!(status.name, "cat");
+        assert_eq!(
+            status.extras,
+            vec![
+                "NStgid:\t11083",
+                "NSpid:\t11083",
+                "HugetlbPages:\t       0 kB",
+                "SigQ:\t0/23961",
+                "Cpus_allowed_list:\t0-3",
+            ]
+        );
+        let newer = "Name:\tcat\nSeccomp:\t3\n";
+        assert!(Status::from_reader(io::Cursor::new(newer)).is_err());
+        let status = Status::from_reader_with(io::Cursor::new(newer), ParseMode::Lenient).unwrap();
+        assert_eq!(status.seccomp, None);
+        assert_eq!(status.extras, vec!["Seccomp:\t3"]);
+    }
+
+    #[test]
+    fn proc_pid_status_kernel_thread() {
+        let raw = "\
+Name:\tkthreadd
+State:\tS (sleeping)
+Tgid:\t2
+Pid:\t2
+PPid:\t0
+TracerPid:\t0
+Uid:\t0\t0\t0\t0
+Gid:\t0\t0\t0\t0
+FDSize:\t64
+Groups:\t
+Threads:\t1
+SigPnd:\t0000000000000000
+ShdPnd:\t0000000000000000
+SigBlk:\t0000000000000000
+SigIgn:\tffffffffffffffff
+SigCgt:\t0000000000000000
+CapInh:\t0000000000000000
+CapPrm:\tffffffffffffffff
+CapEff:\tffffffffffffffff
+";
+        let status = Status::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(status.vm_rss, None);
+        assert!(status.groups.is_empty());
+        assert_eq!(status.sig_ign, SignalSet::all());
+        assert_eq!(status.cap_amb, None);
+        assert_eq!(status.seccomp, None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Per-thread directories, `/proc/[pid]/task/[tid]`.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{Process, ProcessIo, ProcessSched, ProcessStat, Status};
+
+/// A handle to a thread's directory in `/proc`.
+///
+/// The files in `/proc/[pid]` describe the whole process, summed over its threads (or, for some
+/// like `stat`'s `state`, just the main thread). A thread's own directory has the same files
+/// scoped to that thread. Like `Process`, creating a handle doesn't touch the filesystem.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Thread {
+    root: PathBuf,
+}
+
+impl Thread {
+    /// The thread `tid` of process `pid`.
+    pub fn from_ids(pid: u32, tid: u32) -> Thread {
+        Thread {
+            root: Path::new("/proc")
+                .join(pid.to_string())
+                .join("task")
+                .join(tid.to_string()),
+        }
+    }
+
+    /// The calling thread, accessed through `/proc/thread-self` (Linux 3.17 and later).
+    ///
+    /// The handle always refers to whichever thread uses it, so don't send it to another thread
+    /// expecting it to describe this one; use `Thread::from_ids` with this thread's `tid` instead.
+    pub fn current() -> Thread {
+        Thread {
+            root: PathBuf::from("/proc/thread-self"),
+        }
+    }
+
+    /// The directory this handle reads from, e.g. `/proc/1234/task/1240` or `/proc/thread-self`.
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// The `(pid, tid)` of the thread.
+    ///
+    /// For `Thread::current()`, and threads of `Process::current()`, this resolves the
+    /// `/proc/thread-self` or `/proc/self` symlink.
+    pub fn ids(&self) -> io::Result<(u32, u32)> {
+        let path = if self.root.ends_with("thread-self") || self.root.starts_with("/proc/self") {
+            fs::canonicalize(&self.root)?
+        } else {
+            self.root.clone()
+        };
+        let id = |path: Option<&Path>| {
+            path.and_then(|path| path.file_name())
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "id is not a number"))
+        };
+        let tid = id(Some(&path))?;
+        let pid = id(path.parent().and_then(Path::parent))?;
+        Ok((pid, tid))
+    }
+
+    /// The thread id.
+    pub fn tid(&self) -> io::Result<u32> {
+        self.ids().map(|(_, tid)| tid)
+    }
+
+    /// The process the thread belongs to.
+    pub fn process(&self) -> io::Result<Process> {
+        self.ids().map(|(pid, _)| Process::from_pid(pid))
+    }
+
+    /// Parse `/proc/[pid]/task/[tid]/stat`. The `pid` field is the thread id.
+    pub fn stat(&self) -> io::Result<ProcessStat> {
+        self.as_process().stat()
+    }
+
+    /// Parse `/proc/[pid]/task/[tid]/status`. The `pid` field is the thread id.
+    pub fn status(&self) -> io::Result<Status> {
+        self.as_process().status()
+    }
+
+    /// Parse `/proc/[pid]/task/[tid]/sched`.
+    ///
+    /// Only exists if the kernel was built with `CONFIG_SCHED_DEBUG`.
+    pub fn sched(&self) -> io::Result<ProcessSched> {
+        self.as_process().sched()
+    }
+
+    /// Parse `/proc/[pid]/task/[tid]/io`, the I/O done by this thread alone.
+    pub fn io(&self) -> io::Result<ProcessIo> {
+        self.as_process().io()
+    }
+
+    /// The thread's directory has the same layout as a process's, so read it as one.
+    fn as_process(&self) -> Process {
+        Process {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl Process {
+    /// The threads of the process, from `/proc/[pid]/task`, in order of thread id.
+    pub fn threads(&self) -> io::Result<Vec<Thread>> {
+        let mut tids = Vec::new();
+        for entry in fs::read_dir(self.root.join("task"))? {
+            if let Some(tid) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+            {
+                tids.push(tid);
+            }
+        }
+        tids.sort_unstable();
+        Ok(tids
+            .into_iter()
+            .map(|tid| Thread {
+                root: self.root.join("task").join(tid.to_string()),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Thread;
+    use crate::process::Process;
+
+    #[test]
+    fn thread_self() {
+        let pid = std::process::id();
+        // Tests run on their own threads, not the main one.
+        let (test_pid, test_tid) = Thread::current().ids().unwrap();
+        assert_eq!(test_pid, pid);
+        let (ids, stat, status) = std::thread::spawn(|| {
+            let thread = Thread::current();
+            (
+                thread.ids().unwrap(),
+                thread.stat().unwrap(),
+                thread.status().unwrap(),
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(ids.0, pid);
+        assert_ne!(ids.1, pid);
+        assert_ne!(ids.1, test_tid);
+        assert_eq!(stat.pid, ids.1);
+        assert_eq!(status.pid, ids.1);
+        assert_eq!(status.tgid, pid);
+
+        let threads = Process::current().threads().unwrap();
+        assert!(threads.iter().any(|thread| thread.tid().unwrap() == pid));
+        let thread = Thread::from_ids(pid, pid);
+        assert_eq!(thread.stat().unwrap().pid, pid);
+        assert_eq!(thread.process().unwrap(), Process::from_pid(pid));
+    }
+}
@@ -0,0 +1,142 @@
+//! The processes using the most of a resource, like the sorted views in `top`.
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use super::{page_size, ProcessCpuTracker, ProcessCpuUsage, ProcessScan};
+use crate::{ByteSize, Timestamped};
+
+/// The resident memory of a process, from `top_by_rss`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessMemoryUsage {
+    pub pid: u32,
+    /// The filename of the executable, from `ProcessStat::comm`.
+    pub comm: String,
+    /// The resident set size.
+    pub rss: ByteSize,
+}
+
+/// The storage I/O of a process over an interval, from `top_by_io`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessIoUsage {
+    pub pid: u32,
+    /// The filename of the executable, from `ProcessStat::comm`.
+    pub comm: String,
+    /// Bytes fetched from the storage layer during the interval.
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer during the interval.
+    pub write_bytes: u64,
+    /// How long the interval really was.
+    pub elapsed: Duration,
+}
+
+impl ProcessIoUsage {
+    /// Bytes read and written per second.
+    pub fn bytes_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.read_bytes.saturating_add(self.write_bytes) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The `n` processes that used the most cpu over `interval`, busiest first.
+///
+/// This blocks for `interval`, sampling every process before and after.
+pub fn top_by_cpu(n: usize, interval: Duration) -> io::Result<Vec<ProcessCpuUsage>> {
+    let mut tracker = ProcessCpuTracker::new();
+    tracker.sample()?;
+    thread::sleep(interval);
+    let mut usage = tracker.sample()?;
+    usage.sort_by(|a, b| b.percent.partial_cmp(&a.percent).unwrap_or(Ordering::Equal));
+    usage.truncate(n);
+    Ok(usage)
+}
+
+/// The `n` processes with the largest resident set, largest first.
+///
+/// Kernel threads have no memory of their own, so are never included.
+pub fn top_by_rss(n: usize) -> io::Result<Vec<ProcessMemoryUsage>> {
+    let page_size = page_size();
+    let mut usage: Vec<ProcessMemoryUsage> = ProcessScan::new()
+        .run()?
+        .into_iter()
+        .filter_map(|process| process.stat)
+        .filter(|stat| stat.rss.get() > 0)
+        .map(|stat| ProcessMemoryUsage {
+            pid: stat.pid,
+            comm: stat.comm,
+            rss: stat.rss.bytes(page_size),
+        })
+        .collect();
+    usage.sort_by_key(|usage| Reverse(usage.rss));
+    usage.truncate(n);
+    Ok(usage)
+}
+
+/// The `n` processes that read and wrote the most to storage over `interval`, busiest first.
+///
+/// This blocks for `interval`. Processes whose `/proc/[pid]/io` we can't read (those of other
+/// users, unless we're root) are left out, as are processes that did no I/O.
+pub fn top_by_io(n: usize, interval: Duration) -> io::Result<Vec<ProcessIoUsage>> {
+    let scan = ProcessScan::new().io(true);
+    let before = Timestamped::read(|| scan.run())?;
+    thread::sleep(interval);
+    let after = Timestamped::read(|| scan.run())?;
+    let elapsed = after.elapsed_since(&before);
+    // Keyed by start time as well as pid, so a reused pid isn't compared with its predecessor.
+    let earlier: HashMap<_, _> = before
+        .into_inner()
+        .into_iter()
+        .filter_map(|process| Some((process.key()?, process.io?)))
+        .collect();
+    let mut usage: Vec<ProcessIoUsage> = after
+        .into_inner()
+        .into_iter()
+        .filter_map(|process| {
+            let stat = process.stat?;
+            let io = process.io?;
+            let prev = earlier.get(&stat.key())?;
+            Some(ProcessIoUsage {
+                pid: process.pid,
+                comm: stat.comm,
+                read_bytes: io.read_bytes.saturating_sub(prev.read_bytes),
+                write_bytes: io.write_bytes.saturating_sub(prev.write_bytes),
+                elapsed,
+            })
+        })
+        .filter(|usage| usage.read_bytes > 0 || usage.write_bytes > 0)
+        .collect();
+    usage.sort_by_key(|usage| Reverse(usage.read_bytes.saturating_add(usage.write_bytes)));
+    usage.truncate(n);
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{top_by_cpu, top_by_io, top_by_rss};
+    use std::time::Duration;
+
+    #[test]
+    fn top() {
+        let rss = top_by_rss(3).unwrap();
+        assert!(!rss.is_empty() && rss.len() <= 3);
+        assert!(rss.windows(2).all(|pair| pair[0].rss >= pair[1].rss));
+
+        let cpu = top_by_cpu(5, Duration::from_millis(50)).unwrap();
+        assert!(cpu.len() <= 5);
+        assert!(cpu
+            .windows(2)
+            .all(|pair| pair[0].percent >= pair[1].percent));
+
+        let io = top_by_io(5, Duration::from_millis(50)).unwrap();
+        assert!(io.len() <= 5);
+        assert!(io
+            .iter()
+            .all(|usage| usage.elapsed >= Duration::from_millis(50)));
+    }
+}
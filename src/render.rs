@@ -0,0 +1,120 @@
+//! Rendering snapshots for people and programs.
+//!
+//! Every snapshot type implements `Display`, printing an aligned table like the original file
+//! but with units, and `write` prints it. With the `serde` feature they also implement
+//! `serde::Serialize`, and `write_json` prints JSON. The two are separate so that enabling
+//! `serde` never changes which types `write` accepts.
+//!
+//! ```no_run
+//! use linux_proc::{loadavg::LoadAvg, render};
+//!
+//! let loadavg = LoadAvg::from_system()?;
+//! render::write(std::io::stdout(), &loadavg)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// Write the `Display` output of `value` to `writer`, followed by a newline.
+pub fn write(mut writer: impl io::Write, value: &impl fmt::Display) -> io::Result<()> {
+    writeln!(writer, "{}", value)
+}
+
+/// The layout of the JSON from `write_json`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum JsonStyle {
+    /// A single line.
+    Compact,
+    /// Indented over several lines.
+    Pretty,
+}
+
+/// Write `value` to `writer` as JSON, followed by a newline.
+#[cfg(feature = "serde")]
+pub fn write_json(
+    mut writer: impl io::Write,
+    value: &impl serde::Serialize,
+    style: JsonStyle,
+) -> io::Result<()> {
+    match style {
+        JsonStyle::Compact => serde_json::to_writer(&mut writer, value)?,
+        JsonStyle::Pretty => serde_json::to_writer_pretty(&mut writer, value)?,
+    }
+    writeln!(writer)
+}
+
+/// Render `value` as JSON, followed by a newline.
+#[cfg(feature = "serde")]
+pub fn to_json(value: &impl serde::Serialize, style: JsonStyle) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_json(&mut buf, value, style)?;
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Displays a duration as seconds with two decimal places, e.g. `20.16s`, respecting width.
+pub(crate) struct Seconds(pub Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{:.2}s", self.0.as_secs_f64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write, Seconds};
+    use crate::{loadavg::LoadAvg, swaps::Swaps, uptime::Uptime};
+    use std::{io, time::Duration};
+
+    fn loadavg() -> LoadAvg {
+        LoadAvg::from_reader(io::Cursor::new("0.50 0.42 0.30 2/815 40960\n")).unwrap()
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            format!("{:>8}", Seconds(Duration::from_millis(20160))),
+            "  20.16s"
+        );
+        let mut buf = Vec::new();
+        write(&mut buf, &loadavg()).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "load average: 0.50, 0.42, 0.30 (2 running of 815 tasks, last pid 40960)\n"
+        );
+        // Anything `Display` can be written, whatever features are enabled.
+        let mut buf = Vec::new();
+        write(&mut buf, &crate::process::ProcessState::Running).unwrap();
+        let uptime = Uptime::from_str("1640919.14 2328903.47\n").unwrap();
+        assert_eq!(
+            uptime.to_string(),
+            "up 18 days, 23:48:39 (1640919.14s), idle 2328903.47s"
+        );
+        let raw = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/dm-1                               partition\t8388604\t\t262144\t\t-2
+/swapfile                               file\t\t1048576\t\t0\t\t-3
+";
+        let swaps = Swaps::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(
+            swaps.to_string(),
+            "\
+Filename  Type          Size       Used Priority
+/dev/dm-1 partition 8.00 GiB 256.00 MiB       -2
+/swapfile file      1.00 GiB        0 B       -3"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        use super::{to_json, JsonStyle};
+        assert_eq!(
+            to_json(&loadavg(), JsonStyle::Compact).unwrap(),
+            "{\"one\":0.5,\"five\":0.42,\"fifteen\":0.3,\"running\":2,\"total\":815,\
+             \"last_pid\":40960}\n"
+        );
+    }
+}
@@ -0,0 +1,404 @@
+//! Bindings to `/proc/schedstat`, the scheduler statistics for each cpu and scheduling domain.
+//!
+//! The file only exists if the kernel was built with `CONFIG_SCHEDSTATS`, and the counters only
+//! move while `/proc/sys/kernel/sched_schedstats` is 1. The layout of the `domain` lines has
+//! changed between versions; versions 15 (Linux 4.7) to 17 (Linux 6.14) are understood. See
+//! `Documentation/scheduler/sched-stats.rst` in the kernel source for what each counter means.
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+use crate::{util, Error, Jiffies};
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The scheduler statistics, from `/proc/schedstat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedStat {
+    /// The version of the file's layout.
+    pub version: u32,
+    /// When the statistics were printed, in jiffies.
+    pub timestamp: Jiffies,
+    pub cpus: Vec<CpuSchedStat>,
+}
+
+/// The statistics for one cpu's run queue, from a `cpuN` line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuSchedStat {
+    pub cpu: u32,
+    /// The number of times `sched_yield()` was called.
+    pub yield_count: u64,
+    /// The number of times `schedule()` was called.
+    pub schedule_count: u64,
+    /// The number of times `schedule()` left the cpu idle.
+    pub schedule_idle: u64,
+    /// The number of times `try_to_wake_up()` was called.
+    pub wakeups: u64,
+    /// The number of those wakeups that woke a task on this cpu.
+    pub wakeups_local: u64,
+    /// The total time tasks have run on this cpu.
+    pub run_time: Duration,
+    /// The total time tasks have spent waiting to run on this cpu.
+    pub run_delay: Duration,
+    /// The number of timeslices run on this cpu.
+    pub timeslices: u64,
+    /// The scheduling domains this cpu belongs to, from the `domainN` lines following it.
+    pub domains: Vec<SchedDomainStat>,
+}
+
+/// The load balancing statistics for one cpu in one scheduling domain, from a `domainN` line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedDomainStat {
+    /// The level of the domain, `N` in `domainN`. Level 0 is the smallest, like SMT siblings.
+    pub level: u32,
+    /// The name of the domain, like `SMT`, `MC` or `PKG`. Only printed since version 17.
+    pub name: Option<String>,
+    /// The cpus in the domain, as a hex mask.
+    pub cpumask: String,
+    /// Balancing when the cpu was idle.
+    pub idle: LoadBalanceStat,
+    /// Balancing when the cpu was busy.
+    pub busy: LoadBalanceStat,
+    /// Balancing when the cpu was just becoming idle.
+    pub newly_idle: LoadBalanceStat,
+    /// The number of times active load balancing was tried.
+    pub alb_count: u64,
+    /// The number of times active load balancing failed to move a task.
+    pub alb_failed: u64,
+    /// The number of times active load balancing moved a task.
+    pub alb_pushed: u64,
+    /// Balancing on `exec()`. Always zero since Linux 2.6.26.
+    pub sbe_count: u64,
+    pub sbe_balanced: u64,
+    pub sbe_pushed: u64,
+    /// Balancing on `fork()`. Always zero since Linux 2.6.26.
+    pub sbf_count: u64,
+    pub sbf_balanced: u64,
+    pub sbf_pushed: u64,
+    /// Wakeups of a task that last ran on a different cpu in this domain.
+    pub ttwu_wake_remote: u64,
+    /// Wakeups that moved a task to the waking cpu because it was cache-cold.
+    pub ttwu_move_affine: u64,
+    /// Wakeups that moved a task to the waking cpu for load balancing.
+    pub ttwu_move_balance: u64,
+}
+
+/// The counters from `load_balance()` for one kind of cpu idleness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LoadBalanceStat {
+    /// The number of times `load_balance()` was called.
+    pub count: u64,
+    /// The number of times the domain was found to be balanced already.
+    pub balanced: u64,
+    /// The number of times balancing failed to move any tasks.
+    pub failed: u64,
+    /// The sum of the imbalances found. Replaced by the `imbalance_*` counters in version 17.
+    pub imbalance: Option<u64>,
+    /// The sum of the load imbalances found. Since version 17.
+    pub imbalance_load: Option<u64>,
+    /// The sum of the utilization imbalances found. Since version 17.
+    pub imbalance_util: Option<u64>,
+    /// The sum of the task count imbalances found. Since version 17.
+    pub imbalance_task: Option<u64>,
+    /// The number of misfit task imbalances found. Since version 17.
+    pub imbalance_misfit: Option<u64>,
+    /// The number of tasks moved to this cpu.
+    pub gained: u64,
+    /// The number of tasks moved to this cpu that were cache-hot on their old one.
+    pub hot_gained: u64,
+    /// The number of times no busier run queue was found.
+    pub no_busy_queue: u64,
+    /// The number of times no busier group was found.
+    pub no_busy_group: u64,
+}
+
+impl SchedStat {
+    const PATH: &'static str = "/proc/schedstat";
+    /// Parse the contents of `/proc/schedstat`.
+    pub fn from_system() -> io::Result<Self> {
+        SchedStat::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/schedstat`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        let version = reader.parse_line(|line| {
+            parse_header("version", line).and_then(|version| util::to_u32(version, "version"))
+        })?;
+        if !(15..=17).contains(&version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported /proc/schedstat version {}", version),
+            ));
+        }
+        let timestamp = Jiffies(reader.parse_line(|line| parse_header("timestamp", line))?);
+        let mut cpus: Vec<CpuSchedStat> = Vec::new();
+        loop {
+            match reader.parse_line(|line| Line::from_str(line, version)) {
+                Ok(Line::Cpu(cpu)) => cpus.push(cpu),
+                Ok(Line::Domain(domain)) => match cpus.last_mut() {
+                    Some(cpu) => cpu.domains.push(*domain),
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "domain line before any cpu line in /proc/schedstat",
+                        ))
+                    }
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(SchedStat {
+            version,
+            timestamp,
+            cpus,
+        })
+    }
+}
+
+/// Parse a `name value` line at the top of the file.
+fn parse_header(name: &str, input: &str) -> Result<u64, Error> {
+    let input = err_msg!(util::expect_bytes(name, input), name)?;
+    let (_, value) = err_msg!(util::parse_u64(input), name)?;
+    Ok(value)
+}
+
+/// Parse `N` space-separated counters.
+fn parse_counters<const N: usize>(mut input: &str) -> Option<(&str, [u64; N])> {
+    let mut counters = [0; N];
+    for counter in counters.iter_mut() {
+        let (rest, value) = util::parse_u64(input)?;
+        *counter = value;
+        input = rest;
+    }
+    Some((input, counters))
+}
+
+enum Line {
+    Cpu(CpuSchedStat),
+    Domain(Box<SchedDomainStat>),
+}
+
+impl Line {
+    fn from_str(input: &str, version: u32) -> Result<Line, Error> {
+        let (_, name) = err_msg!(util::parse_token(input), "first token")?;
+        if name.starts_with("cpu") {
+            CpuSchedStat::from_str(input).map(Line::Cpu)
+        } else if name.starts_with("domain") {
+            SchedDomainStat::from_str(input, version)
+                .map(Box::new)
+                .map(Line::Domain)
+        } else {
+            Err(Error::from(format!("unknown line \"{}\"", name)))
+        }
+    }
+}
+
+impl CpuSchedStat {
+    fn from_str(input: &str) -> Result<CpuSchedStat, Error> {
+        let input = err_msg!(util::expect_bytes("cpu", input), "cpu")?;
+        let (input, cpu) = err_msg!(util::parse_u64(input), "cpu number")?;
+        let (_, counters) = err_msg!(parse_counters::<9>(input), "cpu counters")?;
+        // The second counter is a legacy field, always zero.
+        Ok(CpuSchedStat {
+            cpu: util::to_u32(cpu, "cpu number")?,
+            yield_count: counters[0],
+            schedule_count: counters[2],
+            schedule_idle: counters[3],
+            wakeups: counters[4],
+            wakeups_local: counters[5],
+            run_time: Duration::from_nanos(counters[6]),
+            run_delay: Duration::from_nanos(counters[7]),
+            timeslices: counters[8],
+            domains: Vec::new(),
+        })
+    }
+}
+
+impl SchedDomainStat {
+    fn from_str(input: &str, version: u32) -> Result<SchedDomainStat, Error> {
+        let input = err_msg!(util::expect_bytes("domain", input), "domain")?;
+        let (input, level) = err_msg!(util::parse_u64(input), "domain level")?;
+        let (input, name) = if version >= 17 {
+            let (input, name) = err_msg!(util::parse_token(input), "domain name")?;
+            (input, Some(name.to_owned()))
+        } else {
+            (input, None)
+        };
+        let (mut input, cpumask) = err_msg!(util::parse_token(input), "cpumask")?;
+        let mut stats = [LoadBalanceStat::default(); 3];
+        for stat in stats.iter_mut() {
+            let (rest, parsed) = err_msg!(
+                LoadBalanceStat::parse(input, version),
+                "load balance counters"
+            )?;
+            *stat = parsed;
+            input = rest;
+        }
+        // Version 16 swapped the order of the idle and busy counters.
+        let (idle, busy, newly_idle) = if version >= 16 {
+            (stats[1], stats[0], stats[2])
+        } else {
+            (stats[0], stats[1], stats[2])
+        };
+        let (_, counters) = err_msg!(parse_counters::<12>(input), "domain counters")?;
+        Ok(SchedDomainStat {
+            level: util::to_u32(level, "domain level")?,
+            name,
+            cpumask: cpumask.to_owned(),
+            idle,
+            busy,
+            newly_idle,
+            alb_count: counters[0],
+            alb_failed: counters[1],
+            alb_pushed: counters[2],
+            sbe_count: counters[3],
+            sbe_balanced: counters[4],
+            sbe_pushed: counters[5],
+            sbf_count: counters[6],
+            sbf_balanced: counters[7],
+            sbf_pushed: counters[8],
+            ttwu_wake_remote: counters[9],
+            ttwu_move_affine: counters[10],
+            ttwu_move_balance: counters[11],
+        })
+    }
+
+    /// The cpus in the domain, decoded from `cpumask`.
+    pub fn cpus(&self) -> Vec<u32> {
+        // The mask is printed as comma-separated 32 bit words, most significant first.
+        let mut cpus = Vec::new();
+        for (word_idx, word) in self.cpumask.rsplit(',').enumerate() {
+            let word = u32::from_str_radix(word, 16).unwrap_or(0);
+            for bit in 0..32 {
+                if word & (1 << bit) != 0 {
+                    cpus.push(word_idx as u32 * 32 + bit);
+                }
+            }
+        }
+        cpus
+    }
+}
+
+impl LoadBalanceStat {
+    fn parse(input: &str, version: u32) -> Option<(&str, LoadBalanceStat)> {
+        if version >= 17 {
+            let (input, counters) = parse_counters::<11>(input)?;
+            Some((
+                input,
+                LoadBalanceStat {
+                    count: counters[0],
+                    balanced: counters[1],
+                    failed: counters[2],
+                    imbalance: None,
+                    imbalance_load: Some(counters[3]),
+                    imbalance_util: Some(counters[4]),
+                    imbalance_task: Some(counters[5]),
+                    imbalance_misfit: Some(counters[6]),
+                    gained: counters[7],
+                    hot_gained: counters[8],
+                    no_busy_queue: counters[9],
+                    no_busy_group: counters[10],
+                },
+            ))
+        } else {
+            let (input, counters) = parse_counters::<8>(input)?;
+            Some((
+                input,
+                LoadBalanceStat {
+                    count: counters[0],
+                    balanced: counters[1],
+                    failed: counters[2],
+                    imbalance: Some(counters[3]),
+                    gained: counters[4],
+                    hot_gained: counters[5],
+                    no_busy_queue: counters[6],
+                    no_busy_group: counters[7],
+                    ..LoadBalanceStat::default()
+                },
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SchedStat;
+    use crate::Jiffies;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn proc_schedstat_v15() {
+        let raw = "\
+version 15
+timestamp 4295892818
+cpu0 0 0 1136268 448126 661218 313394 85463290512 9817282203 689291
+domain0 00000003 2001 1990 7 8 5 0 0 4 1322 1280 38 52 51 0 0 4 1040 990 47 54 6 0 0 44 0 0 0 0 0 0 0 0 0 9731 4436 0
+domain1 0000000f 1000 990 3 4 8 1 0 2 20 19 0 1 1 0 0 0 300 290 10 12 5 0 0 5 1 0 1 0 0 0 0 0 0 3000 800 0
+cpu1 0 0 1 1 1 1 1000 2000 1
+";
+        let stat = SchedStat::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stat.version, 15);
+        assert_eq!(stat.timestamp, Jiffies(4295892818));
+        assert_eq!(stat.cpus.len(), 2);
+        let cpu0 = &stat.cpus[0];
+        assert_eq!(cpu0.schedule_count, 1136268);
+        assert_eq!(cpu0.run_delay, Duration::from_nanos(9817282203));
+        assert_eq!(cpu0.domains.len(), 2);
+        let domain = &cpu0.domains[0];
+        assert_eq!(domain.name, None);
+        assert_eq!(domain.cpus(), vec![0, 1]);
+        assert_eq!(domain.idle.count, 2001);
+        assert_eq!(domain.idle.imbalance, Some(8));
+        assert_eq!(domain.busy.count, 1322);
+        assert_eq!(domain.newly_idle.no_busy_group, 44);
+        assert_eq!(domain.ttwu_wake_remote, 9731);
+        assert_eq!(cpu0.domains[1].alb_count, 1);
+        assert!(stat.cpus[1].domains.is_empty());
+    }
+
+    #[test]
+    fn proc_schedstat_v17() {
+        let raw = "\
+version 17
+timestamp 4295892818
+cpu0 0 0 10 5 6 3 1000 2000 7
+domain0 SMT 00000000,00000003 1 2 3 4 5 6 7 8 9 10 11 21 22 23 24 25 26 27 28 29 30 31 41 42 43 44 45 46 47 48 49 50 51 0 0 0 0 0 0 0 0 0 61 62 63
+";
+        let stat = SchedStat::from_reader(io::Cursor::new(raw)).unwrap();
+        let domain = &stat.cpus[0].domains[0];
+        assert_eq!(domain.name.as_deref(), Some("SMT"));
+        assert_eq!(domain.cpus(), vec![0, 1]);
+        // busy comes first since version 16
+        assert_eq!(domain.busy.count, 1);
+        assert_eq!(domain.busy.imbalance, None);
+        assert_eq!(domain.busy.imbalance_misfit, Some(7));
+        assert_eq!(domain.idle.count, 21);
+        assert_eq!(domain.newly_idle.no_busy_group, 51);
+        assert_eq!(domain.ttwu_move_balance, 63);
+    }
+
+    #[test]
+    fn proc_schedstat_errors() {
+        for raw in &[
+            "version 14\ntimestamp 1\n",
+            "version 15\ntimestamp 1\ndomain0 3 0\n",
+            "version 15\ntimestamp 1\ncpu0 1 2 3\n",
+        ] {
+            assert!(SchedStat::from_reader(io::Cursor::new(raw)).is_err());
+        }
+    }
+
+    #[test]
+    fn from_system() {
+        if let crate::ReadOutcome::Ok(stat) = crate::ReadOutcome::from(SchedStat::from_system()) {
+            assert!(!stat.cpus.is_empty());
+        }
+    }
+}
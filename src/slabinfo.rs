@@ -0,0 +1,155 @@
+//! Bindings to `/proc/slabinfo`, the kernel's slab allocator caches.
+//!
+//! Only root can read `/proc/slabinfo`. There can be hundreds of caches, so they're read one at
+//! a time.
+use std::fs::File;
+use std::io;
+
+use crate::{
+    parse::{Entries, EntryParser},
+    util, ByteSize, Error,
+};
+
+/// A slab cache, from a line of `/proc/slabinfo`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SlabCache {
+    pub name: String,
+    /// The number of objects in use.
+    pub active_objs: u64,
+    /// The number of objects allocated, in use or not.
+    pub num_objs: u64,
+    /// The size of each object.
+    pub object_size: ByteSize,
+    pub objects_per_slab: u64,
+    pub pages_per_slab: u64,
+    /// The number of slabs with at least one object in use.
+    pub active_slabs: u64,
+    pub num_slabs: u64,
+}
+
+impl SlabCache {
+    /// The memory used by the objects in use (not counting the rest of their slabs).
+    pub fn active_size(&self) -> ByteSize {
+        ByteSize(self.active_objs.saturating_mul(self.object_size.bytes()))
+    }
+}
+
+/// An iterator over the caches in `/proc/slabinfo`.
+///
+/// Collect it into an `io::Result<Vec<_>>` to read every cache at once.
+pub struct SlabCaches<R> {
+    entries: Entries<R, EntryParser<SlabCache>>,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl SlabCaches<File> {
+    const PATH: &'static str = "/proc/slabinfo";
+    /// Iterate over the caches in `/proc/slabinfo`.
+    pub fn from_system() -> io::Result<Self> {
+        SlabCaches::from_reader(File::open(Self::PATH)?)
+    }
+}
+
+impl<R: io::Read> SlabCaches<R> {
+    /// Iterate over `/proc/slabinfo`-formatted data from `reader`.
+    ///
+    /// The two header lines are read straight away, and only version 2 of the format is
+    /// understood.
+    pub fn from_reader(reader: R) -> io::Result<Self> {
+        let mut reader = util::LineParser::new(reader);
+        reader.parse_line(
+            |line| match line.trim_end().strip_prefix("slabinfo - version: ") {
+                Some(version) if version.starts_with("2.") => Ok(()),
+                _ => Err(Error::from("expected \"slabinfo - version: 2.x\"")),
+            },
+        )?;
+        // The column names.
+        reader.parse_line(util::parse_dummy)?;
+        Ok(SlabCaches {
+            entries: reader.entries(SlabCache::from_str as EntryParser<_>),
+        })
+    }
+}
+
+impl<R: io::Read> Iterator for SlabCaches<R> {
+    type Item = io::Result<SlabCache>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+impl SlabCache {
+    // e.g. `kmalloc-64  3072  3072  64  64  1 : tunables  0  0  0 : slabdata  48  48  0`
+    fn from_str(input: &str) -> Result<SlabCache, Error> {
+        let (input, name) = err_msg!(util::parse_token(input), "name")?;
+        let (input, active_objs) = err_msg!(util::parse_u64(input), "active objects")?;
+        let (input, num_objs) = err_msg!(util::parse_u64(input), "number of objects")?;
+        let (input, object_size) = err_msg!(util::parse_u64(input), "object size")?;
+        let (input, objects_per_slab) = err_msg!(util::parse_u64(input), "objects per slab")?;
+        let (input, pages_per_slab) = err_msg!(util::parse_u64(input), "pages per slab")?;
+        let input = err_msg!(
+            util::expect_bytes(":", util::consume_space(input)),
+            "expected \":\""
+        )?;
+        let (input, _) = err_msg!(util::parse_token(input), "tunables")?;
+        let (input, _limit) = err_msg!(util::parse_u64(input), "limit")?;
+        let (input, _batch_count) = err_msg!(util::parse_u64(input), "batch count")?;
+        let (input, _shared_factor) = err_msg!(util::parse_u64(input), "shared factor")?;
+        let input = err_msg!(
+            util::expect_bytes(":", util::consume_space(input)),
+            "expected \":\""
+        )?;
+        let (input, _) = err_msg!(util::parse_token(input), "slabdata")?;
+        let (input, active_slabs) = err_msg!(util::parse_u64(input), "active slabs")?;
+        let (_input, num_slabs) = err_msg!(util::parse_u64(input), "number of slabs")?;
+        Ok(SlabCache {
+            name: name.to_owned(),
+            active_objs,
+            num_objs,
+            object_size: ByteSize(object_size),
+            objects_per_slab,
+            pages_per_slab,
+            active_slabs,
+            num_slabs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlabCaches;
+    use crate::{ByteSize, ReadOutcome};
+    use std::io;
+
+    #[test]
+    fn proc_slabinfo() {
+        let raw = "\
+slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+ext4_groupinfo_4k   2054   2054    152   26    1 : tunables    0    0    0 : slabdata     79     79      0
+kmalloc-64          3008   3072     64   64    1 : tunables    0    0    0 : slabdata     48     48      0
+";
+        let caches = SlabCaches::from_reader(io::Cursor::new(raw))
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(caches.len(), 2);
+        assert_eq!(caches[0].name, "ext4_groupinfo_4k");
+        assert_eq!(caches[0].objects_per_slab, 26);
+        assert_eq!(caches[1].active_size(), ByteSize(3008 * 64));
+        assert_eq!(caches[1].num_slabs, 48);
+        assert!(SlabCaches::from_reader(io::Cursor::new("slabinfo - version: 1.1\n")).is_err());
+
+        if let ReadOutcome::Ok(caches) = ReadOutcome::from(SlabCaches::from_system()) {
+            for cache in caches {
+                cache.unwrap();
+            }
+        }
+    }
+}
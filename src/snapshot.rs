@@ -0,0 +1,151 @@
+//! Reading several `/proc` files together, as one point-in-time snapshot of the system.
+use std::io;
+
+use crate::{
+    diskstats::DiskStats, loadavg::LoadAvg, meminfo::MemInfo, net::dev::NetDev, stat::Stat,
+    uptime::Uptime, Timestamped,
+};
+
+/// Which files `SystemSnapshot::capture` reads. Everything is read by default.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SnapshotOptions {
+    stat: bool,
+    meminfo: bool,
+    loadavg: bool,
+    uptime: bool,
+    diskstats: bool,
+    net_dev: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        SnapshotOptions {
+            stat: true,
+            meminfo: true,
+            loadavg: true,
+            uptime: true,
+            diskstats: true,
+            net_dev: true,
+        }
+    }
+}
+
+impl SnapshotOptions {
+    /// Options to read everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options to read nothing, for enabling just the files you need.
+    pub fn none() -> Self {
+        SnapshotOptions {
+            stat: false,
+            meminfo: false,
+            loadavg: false,
+            uptime: false,
+            diskstats: false,
+            net_dev: false,
+        }
+    }
+
+    /// Whether to read `/proc/stat`.
+    pub fn stat(mut self, stat: bool) -> Self {
+        self.stat = stat;
+        self
+    }
+
+    /// Whether to read `/proc/meminfo`.
+    pub fn meminfo(mut self, meminfo: bool) -> Self {
+        self.meminfo = meminfo;
+        self
+    }
+
+    /// Whether to read `/proc/loadavg`.
+    pub fn loadavg(mut self, loadavg: bool) -> Self {
+        self.loadavg = loadavg;
+        self
+    }
+
+    /// Whether to read `/proc/uptime`.
+    pub fn uptime(mut self, uptime: bool) -> Self {
+        self.uptime = uptime;
+        self
+    }
+
+    /// Whether to read `/proc/diskstats`.
+    pub fn diskstats(mut self, diskstats: bool) -> Self {
+        self.diskstats = diskstats;
+        self
+    }
+
+    /// Whether to read `/proc/net/dev`.
+    pub fn net_dev(mut self, net_dev: bool) -> Self {
+        self.net_dev = net_dev;
+        self
+    }
+}
+
+/// The system-wide statistics, read one after another as close together as possible.
+///
+/// Files that weren't enabled in the `SnapshotOptions` are `None`.
+#[derive(Debug, Default)]
+pub struct SystemSnapshot {
+    pub stat: Option<Stat>,
+    pub meminfo: Option<MemInfo>,
+    pub loadavg: Option<LoadAvg>,
+    pub uptime: Option<Uptime>,
+    pub diskstats: Option<DiskStats>,
+    pub net_dev: Option<NetDev>,
+}
+
+/// Call `read` if `enabled`, adding `path` to any error.
+fn read_if<T>(
+    enabled: bool,
+    path: &str,
+    read: impl FnOnce() -> io::Result<T>,
+) -> io::Result<Option<T>> {
+    if !enabled {
+        return Ok(None);
+    }
+    read()
+        .map(Some)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+impl SystemSnapshot {
+    /// Read the files enabled in `options`, stamped with the time reading started.
+    ///
+    /// Fails if any enabled file can't be read. The error keeps the original `io::ErrorKind`, and
+    /// its message names the file.
+    pub fn capture(options: SnapshotOptions) -> io::Result<Timestamped<Self>> {
+        Timestamped::read(|| {
+            Ok(SystemSnapshot {
+                stat: read_if(options.stat, "/proc/stat", Stat::from_system)?,
+                meminfo: read_if(options.meminfo, "/proc/meminfo", MemInfo::from_system)?,
+                loadavg: read_if(options.loadavg, "/proc/loadavg", LoadAvg::from_system)?,
+                uptime: read_if(options.uptime, "/proc/uptime", Uptime::from_system)?,
+                diskstats: read_if(options.diskstats, "/proc/diskstats", DiskStats::from_system)?,
+                net_dev: read_if(options.net_dev, "/proc/net/dev", NetDev::from_system)?,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SnapshotOptions, SystemSnapshot};
+
+    #[test]
+    fn capture() {
+        let snapshot = SystemSnapshot::capture(SnapshotOptions::new()).unwrap();
+        assert!(snapshot.stat.is_some());
+        assert!(snapshot.meminfo.is_some());
+        assert!(snapshot.net_dev.is_some());
+
+        let options = SnapshotOptions::none().loadavg(true);
+        let snapshot = SystemSnapshot::capture(options).unwrap();
+        assert!(snapshot.loadavg.is_some());
+        assert!(snapshot.stat.is_none());
+        assert!(snapshot.diskstats.is_none());
+    }
+}
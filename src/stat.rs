@@ -1,6 +1,10 @@
 //! Bindings to `/proc/stat`.
-use crate::{util, Error};
-use std::{fs::File, io};
+use crate::{
+    parse::ParseMode, process::clock_ticks_per_sec, render::Seconds, util, Error, Jiffies,
+    RatioFormat, RatioUnit,
+};
+use bitflags::bitflags;
+use std::{fmt, fs::File, io, time::Duration};
 
 macro_rules! parse_single {
     ($name:expr) => {
@@ -22,8 +26,15 @@ macro_rules! parse_single {
     };
 }
 
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
 /// The stats from `/proc/stat`.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Stat {
     /// Total stats, sum of all cpus.
     pub cpu_totals: StatCpu,
@@ -39,7 +50,108 @@ pub struct Stat {
     pub procs_running: u64,
     /// The total number of processes waiting to run on the cpu.
     pub procs_blocked: u64,
-    // todo `softirq`
+    /// The number of interrupts serviced, in total and for each interrupt number.
+    ///
+    /// `None` if skipped using [`StatOptions::interrupts`].
+    pub interrupts: Option<StatCounts>,
+    /// The number of softirqs serviced, in total and for each softirq type.
+    ///
+    /// `None` if skipped using [`StatOptions::softirqs`], or if the kernel doesn't print the
+    /// `softirq` line (before 2.6.31).
+    pub softirqs: Option<StatCounts>,
+    /// Lines that weren't understood.
+    ///
+    /// Only filled in when parsed with [`ParseMode::Lenient`] (see [`StatOptions::mode`]).
+    pub extras: Vec<String>,
+    /// How lines that weren't understood were treated, which `refresh` does again.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub mode: ParseMode,
+}
+
+/// Options controlling which parts of `/proc/stat` are parsed.
+///
+/// The `intr` line can contain hundreds of numbers, so skipping the parts you don't need makes a
+/// noticeable difference when sampling in a tight loop. Everything is parsed by default.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct StatOptions {
+    per_cpu: bool,
+    interrupts: bool,
+    softirqs: bool,
+    mode: ParseMode,
+}
+
+impl Default for StatOptions {
+    fn default() -> Self {
+        StatOptions {
+            per_cpu: true,
+            interrupts: true,
+            softirqs: true,
+            mode: ParseMode::Strict,
+        }
+    }
+}
+
+impl StatOptions {
+    /// Options to parse everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to parse the per-cpu lines. If not, `Stat::cpus` will be empty.
+    pub fn per_cpu(mut self, per_cpu: bool) -> Self {
+        self.per_cpu = per_cpu;
+        self
+    }
+
+    /// Whether to parse the `intr` line.
+    pub fn interrupts(mut self, interrupts: bool) -> Self {
+        self.interrupts = interrupts;
+        self
+    }
+
+    /// Whether to parse the `softirq` line.
+    pub fn softirqs(mut self, softirqs: bool) -> Self {
+        self.softirqs = softirqs;
+        self
+    }
+
+    /// How to treat lines that aren't understood.
+    ///
+    /// In lenient mode, the lines may come in any order, and unknown ones are collected in
+    /// `Stat::extras`.
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// A total count, followed by a count for each individual source.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StatCounts {
+    pub total: u64,
+    pub counts: Vec<u64>,
+}
+
+impl StatCounts {
+    /// Parse a line into `self`, reusing the allocation for `counts`.
+    fn parse_into(&mut self, name: &str, input: &str) -> Result<(), Error> {
+        let (input, actual) = err_msg!(util::parse_token(input), "cannot read name")?;
+        if actual != name {
+            return Err(Error::from(format!(
+                "incorrect name, expected: {}, actual: {}",
+                name, actual
+            )));
+        }
+        let (mut input, total) = err_msg!(util::parse_u64(input), "total")?;
+        self.total = total;
+        self.counts.clear();
+        while let Some((rest, count)) = util::parse_u64(input) {
+            self.counts.push(count);
+            input = rest;
+        }
+        Ok(())
+    }
 }
 
 impl Stat {
@@ -47,87 +159,351 @@ impl Stat {
 
     /// Parse the contents of `/proc/stat`.
     pub fn from_system() -> io::Result<Self> {
-        Stat::from_reader(File::open(Self::PATH)?)
+        Stat::parse_with(StatOptions::default())
+    }
+
+    /// Parse the parts of `/proc/stat` selected by `options`.
+    pub fn parse_with(options: StatOptions) -> io::Result<Self> {
+        Stat::from_reader_with(File::open(Self::PATH)?, options)
+    }
+
+    /// Parse `/proc/stat`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        Stat::from_reader_with(reader, StatOptions::default())
+    }
+
+    /// Parse the parts of `/proc/stat`-formatted data from `reader` selected by `options`.
+    pub fn from_reader_with(reader: impl io::Read, options: StatOptions) -> io::Result<Self> {
+        let mut stat = Stat {
+            interrupts: if options.interrupts {
+                Some(StatCounts::default())
+            } else {
+                None
+            },
+            softirqs: if options.softirqs {
+                Some(StatCounts::default())
+            } else {
+                None
+            },
+            ..Stat::default()
+        };
+        stat.parse_into(reader, options)?;
+        Ok(stat)
+    }
+
+    /// Re-read `/proc/stat`, updating `self` in place.
+    ///
+    /// The same parts of the file are parsed as when `self` was created, and the allocations for
+    /// `cpus` and the interrupt counts are reused, which avoids heap churn when sampling in a
+    /// loop.
+    ///
+    /// If an error is returned, the contents of `self` are unspecified.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        self.refresh_from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Like `refresh`, but reading `/proc/stat`-formatted data from `reader`.
+    pub fn refresh_from_reader(&mut self, reader: impl io::Read) -> io::Result<()> {
+        let options = StatOptions {
+            per_cpu: !self.cpus.is_empty(),
+            interrupts: self.interrupts.is_some(),
+            softirqs: self.softirqs.is_some(),
+            mode: self.mode,
+        };
+        self.parse_into(reader, options)
     }
 
-    fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+    fn parse_into(&mut self, reader: impl io::Read, options: StatOptions) -> io::Result<()> {
         let mut reader = util::LineParser::new(reader);
-        let cpu_totals = reader.parse_line(StatCpu::from_str)?;
-        let mut cpus = Vec::new();
+        self.mode = options.mode;
+        if options.mode == ParseMode::Lenient {
+            return self.parse_lenient(reader, options);
+        }
+        self.cpu_totals = reader.parse_line(StatCpu::from_str)?;
+        if options.per_cpu {
+            let mut count = 0;
+            while let Ok(cpu_info) = reader.parse_line(StatCpu::from_str) {
+                match self.cpus.get_mut(count) {
+                    Some(cpu) => *cpu = cpu_info,
+                    None => self.cpus.push(cpu_info),
+                }
+                count += 1;
+            }
+            self.cpus.truncate(count);
+        } else {
+            while reader.parse_line(skip_cpu).is_ok() {}
+        }
+        match self.interrupts {
+            Some(ref mut interrupts) => {
+                reader.parse_line(|line| interrupts.parse_into("intr", line))?
+            }
+            None => reader.parse_line(util::parse_dummy)?,
+        }
+        self.context_switches = reader.parse_line(parse_single!("ctxt"))?;
+        self.boot_time = reader.parse_line(parse_single!("btime"))?;
+        self.processes = reader.parse_line(parse_single!("processes"))?;
+        self.procs_running = reader.parse_line(parse_single!("procs_running"))?;
+        self.procs_blocked = reader.parse_line(parse_single!("procs_blocked"))?;
+        if let Some(ref mut softirqs) = self.softirqs {
+            match reader.parse_line(|line| softirqs.parse_into("softirq", line)) {
+                Ok(()) => (),
+                // Kernels before 2.6.31 stop after `procs_blocked`.
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => self.softirqs = None,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Stat {
+    /// Parse lines in any order, collecting the ones we don't understand.
+    fn parse_lenient(
+        &mut self,
+        mut reader: util::LineParser<impl io::Read>,
+        options: StatOptions,
+    ) -> io::Result<()> {
+        let mut extras = std::mem::take(&mut self.extras);
+        extras.clear();
+        let mut count = 0;
         loop {
-            if let Ok(cpu_info) = reader.parse_line(StatCpu::from_str) {
-                cpus.push(cpu_info);
-            } else {
-                break;
+            match reader.parse_line_with_mode(ParseMode::Lenient, &mut extras, |line| {
+                self.parse_any_line(line, options, &mut count)
+            }) {
+                Ok(_) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
             }
         }
-        reader.parse_line(util::parse_dummy)?;
-        let context_switches = reader.parse_line(parse_single!("ctxt"))?;
-        let boot_time = reader.parse_line(parse_single!("btime"))?;
-        let processes = reader.parse_line(parse_single!("processes"))?;
-        let procs_running = reader.parse_line(parse_single!("procs_running"))?;
-        let procs_blocked = reader.parse_line(parse_single!("procs_blocked"))?;
-        // todo softirq
-        Ok(Stat {
-            cpu_totals,
-            cpus,
-            context_switches,
-            boot_time,
-            processes,
-            procs_running,
-            procs_blocked,
-        })
+        self.cpus.truncate(count);
+        self.extras = extras;
+        Ok(())
+    }
+
+    fn parse_any_line(
+        &mut self,
+        line: &str,
+        options: StatOptions,
+        count: &mut usize,
+    ) -> Result<(), Error> {
+        let (_, name) = err_msg!(util::parse_token(line), "cannot read name")?;
+        match name {
+            "cpu" => self.cpu_totals = StatCpu::from_str(line)?,
+            _ if name.starts_with("cpu") => {
+                if options.per_cpu {
+                    let cpu_info = StatCpu::from_str(line)?;
+                    match self.cpus.get_mut(*count) {
+                        Some(cpu) => *cpu = cpu_info,
+                        None => self.cpus.push(cpu_info),
+                    }
+                    *count += 1;
+                }
+            }
+            "intr" => {
+                if let Some(ref mut interrupts) = self.interrupts {
+                    interrupts.parse_into("intr", line)?
+                }
+            }
+            "softirq" => {
+                if let Some(ref mut softirqs) = self.softirqs {
+                    softirqs.parse_into("softirq", line)?
+                }
+            }
+            "ctxt" => self.context_switches = (parse_single!("ctxt"))(line)?,
+            "btime" => self.boot_time = (parse_single!("btime"))(line)?,
+            "processes" => self.processes = (parse_single!("processes"))(line)?,
+            "procs_running" => self.procs_running = (parse_single!("procs_running"))(line)?,
+            "procs_blocked" => self.procs_blocked = (parse_single!("procs_blocked"))(line)?,
+            _ => return Err(format!("unknown line {:?}", name).into()),
+        }
+        Ok(())
     }
 }
 
-/// Info about the number of *units* in the various cpu contexts.
-///
-/// *units* could be anything, for example cpu cycles, or hundredths of a second. The numbers only
-/// really make sense as a proportion of the total.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct StatCpu {
-    pub user: u64,
-    pub nice: u64,
-    pub system: u64,
-    pub idle: u64,
-    pub iowait: u64,
-    pub irq: u64,
-    pub softirq: u64,
-    pub steal: Option<u64>,
-    pub guest: Option<u64>,
-    pub guest_nice: Option<u64>,
+/// Consume a per-cpu line without parsing the numbers.
+fn skip_cpu(input: &str) -> Result<(), Error> {
+    if input.starts_with("cpu") {
+        Ok(())
+    } else {
+        Err("starts with cpu<x>".into())
+    }
 }
 
-macro_rules! err_msg {
-    ($inner:expr, $msg:expr) => {
-        $inner.ok_or_else(|| Error::from($msg))
-    };
+impl fmt::Display for Stat {
+    /// Cpu times are shown in seconds, assuming the snapshot came from this machine.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ticks_per_sec = clock_ticks_per_sec();
+        let label = format!("cpu{}", self.cpus.len().saturating_sub(1)).len();
+        let columns = [
+            "user",
+            "nice",
+            "system",
+            "idle",
+            "iowait",
+            "irq",
+            "softirq",
+            "steal",
+            "guest",
+            "guest_nice",
+        ];
+        write!(f, "{:label$}", "")?;
+        for column in &columns {
+            write!(f, " {:>11}", column)?;
+        }
+        let cpus = std::iter::once(("cpu".to_owned(), &self.cpu_totals)).chain(
+            self.cpus
+                .iter()
+                .enumerate()
+                .map(|(idx, cpu)| (format!("cpu{}", idx), cpu)),
+        );
+        for (name, cpu) in cpus {
+            write!(f, "\n{:label$}", name)?;
+            let times = [
+                Some(cpu.user),
+                Some(cpu.nice),
+                Some(cpu.system),
+                Some(cpu.idle),
+                Some(cpu.iowait),
+                Some(cpu.irq),
+                Some(cpu.softirq),
+                cpu.steal,
+                cpu.guest,
+                cpu.guest_nice,
+            ];
+            for time in &times {
+                match time.and_then(|ticks| ticks.to_duration(ticks_per_sec)) {
+                    Some(time) => write!(f, " {:>11}", Seconds(time))?,
+                    None => write!(f, " {:>11}", "-")?,
+                }
+            }
+        }
+        write!(f, "\ncontext switches  {}", self.context_switches)?;
+        write!(f, "\nboot time         {} (unix time)", self.boot_time)?;
+        write!(f, "\nprocesses created {}", self.processes)?;
+        write!(f, "\nprocs running     {}", self.procs_running)?;
+        write!(f, "\nprocs blocked     {}", self.procs_blocked)?;
+        if let Some(interrupts) = &self.interrupts {
+            write!(f, "\ninterrupts        {}", interrupts.total)?;
+        }
+        if let Some(softirqs) = &self.softirqs {
+            write!(f, "\nsoftirqs          {}", softirqs.total)?;
+        }
+        Ok(())
+    }
+}
+
+bitflags! {
+    /// A set of the columns of a `cpu` line in `/proc/stat`, as returned by
+    /// `StatCpu::fields_present`.
+    ///
+    /// Each column was added in a particular kernel version, noted on its flag.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct StatCpuFields: u16 {
+        /// Present since the first `/proc/stat`.
+        const USER = 1 << 0;
+        /// Present since the first `/proc/stat`.
+        const NICE = 1 << 1;
+        /// Present since the first `/proc/stat`.
+        const SYSTEM = 1 << 2;
+        /// Present since the first `/proc/stat`.
+        const IDLE = 1 << 3;
+        /// Added in 2.5.41.
+        const IOWAIT = 1 << 4;
+        /// Added in 2.6.0.
+        const IRQ = 1 << 5;
+        /// Added in 2.6.0.
+        const SOFTIRQ = 1 << 6;
+        /// Added in 2.6.11.
+        const STEAL = 1 << 7;
+        /// Added in 2.6.24.
+        const GUEST = 1 << 8;
+        /// Added in 2.6.33.
+        const GUEST_NICE = 1 << 9;
+    }
+}
+
+impl StatCpuFields {
+    /// The kernel version that added each column.
+    const INTRODUCED: [(StatCpuFields, (u32, u32, u32)); 10] = [
+        (StatCpuFields::USER, (0, 0, 0)),
+        (StatCpuFields::NICE, (0, 0, 0)),
+        (StatCpuFields::SYSTEM, (0, 0, 0)),
+        (StatCpuFields::IDLE, (0, 0, 0)),
+        (StatCpuFields::IOWAIT, (2, 5, 41)),
+        (StatCpuFields::IRQ, (2, 6, 0)),
+        (StatCpuFields::SOFTIRQ, (2, 6, 0)),
+        (StatCpuFields::STEAL, (2, 6, 11)),
+        (StatCpuFields::GUEST, (2, 6, 24)),
+        (StatCpuFields::GUEST_NICE, (2, 6, 33)),
+    ];
+
+    /// The columns a kernel of version `(major, minor, patch)` reports, for example `(5, 10, 0)`.
+    ///
+    /// The version can be parsed from `sys::kernel::KernelIdentity::osrelease`.
+    pub fn expected_for(version: (u32, u32, u32)) -> Self {
+        Self::INTRODUCED
+            .iter()
+            .filter(|&&(_, introduced)| version >= introduced)
+            .fold(StatCpuFields::empty(), |fields, &(field, _)| fields | field)
+    }
+
+    /// The kernel version that added `field`, as `(major, minor, patch)`, or `None` if `field`
+    /// isn't a single column.
+    pub fn introduced_in(field: StatCpuFields) -> Option<(u32, u32, u32)> {
+        Self::INTRODUCED
+            .iter()
+            .find(|&&(f, _)| f == field)
+            .map(|&(_, introduced)| introduced)
+    }
+}
+
+/// The time spent in the various cpu contexts, in clock ticks.
+///
+/// The numbers are cumulative since boot, so usually only make sense as a proportion of the
+/// total, or as the change between two snapshots.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StatCpu {
+    pub user: Jiffies,
+    pub nice: Jiffies,
+    pub system: Jiffies,
+    pub idle: Jiffies,
+    pub iowait: Jiffies,
+    pub irq: Jiffies,
+    pub softirq: Jiffies,
+    pub steal: Option<Jiffies>,
+    pub guest: Option<Jiffies>,
+    pub guest_nice: Option<Jiffies>,
 }
 
 impl StatCpu {
     fn from_str(input: &str) -> Result<StatCpu, Error> {
+        fn parse_jiffies(input: &str) -> Option<(&str, Jiffies)> {
+            util::parse_u64(input).map(|(input, ticks)| (input, Jiffies(ticks)))
+        }
+
         let (input, cpunum) = err_msg!(util::parse_token(input), "first token")?;
         if !cpunum.starts_with("cpu") {
             return Err("starts with cpu<x>".into());
         }
 
-        let (input, user) = err_msg!(util::parse_u64(input), "user")?;
-        let (input, nice) = err_msg!(util::parse_u64(input), "nice")?;
-        let (input, system) = err_msg!(util::parse_u64(input), "system")?;
-        let (input, idle) = err_msg!(util::parse_u64(input), "idle")?;
-        let (input, iowait) = err_msg!(util::parse_u64(input), "iowait")?;
-        let (input, irq) = err_msg!(util::parse_u64(input), "irq")?;
-        let (input, softirq) = err_msg!(util::parse_u64(input), "softirq")?;
+        let (input, user) = err_msg!(parse_jiffies(input), "user")?;
+        let (input, nice) = err_msg!(parse_jiffies(input), "nice")?;
+        let (input, system) = err_msg!(parse_jiffies(input), "system")?;
+        let (input, idle) = err_msg!(parse_jiffies(input), "idle")?;
+        let (input, iowait) = err_msg!(parse_jiffies(input), "iowait")?;
+        let (input, irq) = err_msg!(parse_jiffies(input), "irq")?;
+        let (input, softirq) = err_msg!(parse_jiffies(input), "softirq")?;
         // Following are optional fields
-        let (input, steal) = match util::parse_u64(input) {
+        let (input, steal) = match parse_jiffies(input) {
             Some((i, steal)) => (i, Some(steal)),
             None => (input, None),
         };
-        let (input, guest) = match util::parse_u64(input) {
+        let (input, guest) = match parse_jiffies(input) {
             Some((i, guest)) => (i, Some(guest)),
             None => (input, None),
         };
-        let (_, guest_nice) = match util::parse_u64(input) {
+        let (_, guest_nice) = match parse_jiffies(input) {
             Some((i, guest_nice)) => (i, Some(guest_nice)),
             None => (input, None),
         };
@@ -146,30 +522,268 @@ impl StatCpu {
         })
     }
 
-    /// Convenience function to add up all cpu values.
-    pub fn total(&self) -> u64 {
-        self.user
-            .checked_add(self.nice)
-            .unwrap()
-            .checked_add(self.system)
-            .unwrap()
-            .checked_add(self.idle)
-            .unwrap()
-            .checked_add(self.iowait)
-            .unwrap()
-            .checked_add(self.irq)
-            .unwrap()
-            .checked_add(self.softirq)
-            .unwrap()
-            .checked_add(self.steal.unwrap_or(0))
-            .unwrap()
-            .checked_add(self.guest.unwrap_or(0))
-            .unwrap()
-            .checked_add(self.guest_nice.unwrap_or(0))
-            .unwrap()
+    /// Which columns the kernel reported.
+    ///
+    /// The columns up to `softirq` are present on every 2.6 or later kernel, so are always set.
+    /// Compare with `StatCpuFields::expected_for` to tell a column that's missing because the
+    /// kernel is too old from one that's zero, like `steal` on bare metal.
+    pub fn fields_present(&self) -> StatCpuFields {
+        let mut fields = StatCpuFields::USER
+            | StatCpuFields::NICE
+            | StatCpuFields::SYSTEM
+            | StatCpuFields::IDLE
+            | StatCpuFields::IOWAIT
+            | StatCpuFields::IRQ
+            | StatCpuFields::SOFTIRQ;
+        fields.set(StatCpuFields::STEAL, self.steal.is_some());
+        fields.set(StatCpuFields::GUEST, self.guest.is_some());
+        fields.set(StatCpuFields::GUEST_NICE, self.guest_nice.is_some());
+        fields
+    }
+
+    /// The change in each column since `earlier`.
+    ///
+    /// Columns that went backwards count as zero. This happens to iowait on tickless kernels,
+    /// and subtracting naively would underflow or, with the other columns, add up to more time
+    /// than passed.
+    pub fn diff(&self, earlier: &StatCpu) -> StatCpu {
+        let diff = |later: Option<Jiffies>, earlier: Option<Jiffies>| {
+            later.map(|later| later.saturating_sub(earlier.unwrap_or_default()))
+        };
+        StatCpu {
+            user: self.user.saturating_sub(earlier.user),
+            nice: self.nice.saturating_sub(earlier.nice),
+            system: self.system.saturating_sub(earlier.system),
+            idle: self.idle.saturating_sub(earlier.idle),
+            iowait: self.iowait.saturating_sub(earlier.iowait),
+            irq: self.irq.saturating_sub(earlier.irq),
+            softirq: self.softirq.saturating_sub(earlier.softirq),
+            steal: diff(self.steal, earlier.steal),
+            guest: diff(self.guest, earlier.guest),
+            guest_nice: diff(self.guest_nice, earlier.guest_nice),
+        }
+    }
+
+    /// The proportion of time spent in each state since `earlier`.
+    ///
+    /// Each column's change is clamped at zero (see `diff`) and the proportions are of the sum of
+    /// the clamped changes, so they never add up to more than 100%.
+    pub fn usage_since(&self, earlier: &StatCpu, options: CpuUsageOptions) -> CpuUsage {
+        let delta = self.diff(earlier);
+        let (idle, iowait) = if options.iowait_as_idle {
+            (delta.idle + delta.iowait, Jiffies(0))
+        } else {
+            (delta.idle, delta.iowait)
+        };
+        let steal = delta.steal.unwrap_or_default();
+        let total = [
+            delta.user,
+            delta.nice,
+            delta.system,
+            idle,
+            iowait,
+            delta.irq,
+            delta.softirq,
+            steal,
+        ]
+        .iter()
+        .map(|time| time.get() as f64)
+        .sum::<f64>();
+        let share = |time: Jiffies| {
+            if total > 0.0 {
+                time.get() as f64 / total
+            } else {
+                0.0
+            }
+        };
+        CpuUsage {
+            user: share(delta.user),
+            nice: share(delta.nice),
+            system: share(delta.system),
+            idle: share(idle),
+            iowait: share(iowait),
+            irq: share(delta.irq),
+            softirq: share(delta.softirq),
+            steal: share(steal),
+        }
+    }
+
+    /// Convenience function to add up all cpu values, saturating at `u64::MAX` ticks.
+    pub fn total(&self) -> Jiffies {
+        [
+            self.user,
+            self.nice,
+            self.system,
+            self.idle,
+            self.iowait,
+            self.irq,
+            self.softirq,
+            self.steal.unwrap_or_default(),
+            self.guest.unwrap_or_default(),
+            self.guest_nice.unwrap_or_default(),
+        ]
+        .iter()
+        .fold(Jiffies(0), |total, &time| total.saturating_add(time))
+    }
+}
+
+/// Options controlling how `StatCpu::usage_since` turns two samples into proportions.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CpuUsageOptions {
+    iowait_as_idle: bool,
+}
+
+impl CpuUsageOptions {
+    /// Options that keep iowait separate from idle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to count iowait as idle time, leaving `CpuUsage::iowait` at zero.
+    ///
+    /// A cpu waiting for I/O is idle, and could be running something else. The kernel only
+    /// attributes iowait approximately (and, with `NO_HZ`, the per-cpu counter can even go
+    /// backwards), so monitors that just want "how busy" are often better off merging it.
+    pub fn iowait_as_idle(mut self, iowait_as_idle: bool) -> Self {
+        self.iowait_as_idle = iowait_as_idle;
+        self
+    }
+}
+
+/// The proportion of time a cpu spent in each state between two samples, from
+/// `StatCpu::usage_since`.
+///
+/// The proportions are between 0 and 1 and add up to 1, or are all 0 if no time passed. Guest
+/// time is already included in `user` and `nice`, so isn't counted separately.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CpuUsage {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+}
+
+impl CpuUsage {
+    /// The proportion of time the cpu was doing something other than idling or waiting for I/O.
+    pub fn busy(&self) -> f64 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+
+    /// `busy`, expressed in `format`.
+    pub fn busy_in(&self, format: RatioFormat) -> f64 {
+        format.apply(self.busy())
+    }
+
+    /// `busy` in thousandths of `unit` (see `RatioUnit::milli`).
+    pub fn busy_milli(&self, unit: RatioUnit) -> u64 {
+        unit.milli(self.busy())
+    }
+
+    /// Every proportion expressed in `format`.
+    pub fn scaled(&self, format: RatioFormat) -> CpuUsage {
+        CpuUsage {
+            user: format.apply(self.user),
+            nice: format.apply(self.nice),
+            system: format.apply(self.system),
+            idle: format.apply(self.idle),
+            iowait: format.apply(self.iowait),
+            irq: format.apply(self.irq),
+            softirq: format.apply(self.softirq),
+            steal: format.apply(self.steal),
+        }
+    }
+}
+
+/// The rates of process creation and context switching between two `Stat` snapshots.
+///
+/// A sustained high fork rate usually means something is spawning short-lived processes in a
+/// loop, like a crashing service being restarted or a shell script calling many tools.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ForkRate {
+    /// Processes and threads created per second.
+    pub forks_per_sec: f64,
+    /// Context switches per second, across all cpus.
+    pub context_switches_per_sec: f64,
+}
+
+impl ForkRate {
+    /// The rates between `earlier` and `later`, taken `elapsed` apart.
+    ///
+    /// Counters that went backwards (which only happens if the snapshots came from different
+    /// boots) count as zero, as does a zero `elapsed`.
+    pub fn between(earlier: &Stat, later: &Stat, elapsed: Duration) -> ForkRate {
+        let secs = elapsed.as_secs_f64();
+        let rate = |before: u64, after: u64| {
+            if secs > 0.0 {
+                after.saturating_sub(before) as f64 / secs
+            } else {
+                0.0
+            }
+        };
+        ForkRate {
+            forks_per_sec: rate(earlier.processes, later.processes),
+            context_switches_per_sec: rate(earlier.context_switches, later.context_switches),
+        }
     }
 }
 
+#[test]
+fn test_cpu_usage() {
+    let cpu = |line: &str| StatCpu::from_str(line).unwrap();
+    let earlier = cpu("cpu0 100 0 50 800 40 5 5 0 0 0");
+    // iowait went backwards, which would otherwise make the total less than the busy time.
+    let later = cpu("cpu0 160 0 70 900 30 15 15 0 0 0");
+    assert_eq!(later.diff(&earlier).iowait, Jiffies(0));
+    let usage = later.usage_since(&earlier, CpuUsageOptions::new());
+    assert_eq!(usage.user, 0.3);
+    assert_eq!(usage.idle, 0.5);
+    assert_eq!(usage.iowait, 0.0);
+    assert!((usage.busy() - 0.5).abs() < 1e-9);
+
+    let later = cpu("cpu0 100 0 50 900 140 5 5 0 0 0");
+    let usage = later.usage_since(&earlier, CpuUsageOptions::new());
+    assert_eq!((usage.idle, usage.iowait, usage.busy()), (0.5, 0.5, 0.0));
+    let usage = later.usage_since(&earlier, CpuUsageOptions::new().iowait_as_idle(true));
+    assert_eq!((usage.idle, usage.iowait, usage.busy()), (1.0, 0.0, 0.0));
+    assert_eq!(
+        earlier.usage_since(&earlier, CpuUsageOptions::new()),
+        CpuUsage::default()
+    );
+    assert_eq!(usage.scaled(RatioFormat::percent()).idle, 100.0);
+    assert_eq!(usage.busy_milli(RatioUnit::Percent), 0);
+
+    let huge = cpu("cpu0 18446744073709551615 1 0 0 0 0 0 0 0 0");
+    assert_eq!(huge.total(), Jiffies(u64::MAX));
+}
+
+#[test]
+fn test_fork_rate() {
+    let earlier = Stat {
+        processes: 2453,
+        context_switches: 2_238_717,
+        ..Stat::default()
+    };
+    let later = Stat {
+        processes: 2553,
+        context_switches: 2_258_717,
+        ..Stat::default()
+    };
+    let rate = ForkRate::between(&earlier, &later, Duration::from_secs(2));
+    assert_eq!(rate.forks_per_sec, 50.0);
+    assert_eq!(rate.context_switches_per_sec, 10_000.0);
+    let rate = ForkRate::between(&later, &earlier, Duration::from_secs(2));
+    assert_eq!(rate.forks_per_sec, 0.0);
+    assert_eq!(
+        ForkRate::between(&earlier, &later, Duration::from_secs(0)),
+        ForkRate::default()
+    );
+}
+
 #[test]
 fn test_stat() {
     let raw = "\
@@ -186,5 +800,104 @@ procs_running 1
 procs_blocked 0
 softirq 4257581 64 299604 69 2986 36581 0 3497229 283111 0 137937
 ";
-    let _stat = Stat::from_reader(io::Cursor::new(raw)).unwrap();
+    let stat = Stat::from_reader(io::Cursor::new(raw)).unwrap();
+    assert_eq!(stat.cpus.len(), 4);
+    assert_eq!(stat.interrupts.as_ref().unwrap().total, 1015182);
+    let softirqs = stat.softirqs.unwrap();
+    assert_eq!(softirqs.total, 4257581);
+    assert_eq!(softirqs.counts.len(), 10);
+
+    let options = StatOptions::new()
+        .per_cpu(false)
+        .interrupts(false)
+        .softirqs(false);
+    let stat = Stat::from_reader_with(io::Cursor::new(raw), options).unwrap();
+    assert!(stat.cpus.is_empty());
+    assert_eq!(stat.interrupts, None);
+    assert_eq!(stat.softirqs, None);
+    assert_eq!(stat.procs_running, 1);
+
+    let mut stat = Stat::from_reader(io::Cursor::new(raw)).unwrap();
+    let two_cpus = raw
+        .replace("cpu2 4966 1 1988 2051243 5596 516 141 0 0 0\n", "")
+        .replace("cpu3 3955 0 1258 2056922 2835 250 100 0 0 0\n", "")
+        .replace("procs_running 1", "procs_running 5");
+    stat.refresh_from_reader(io::Cursor::new(two_cpus)).unwrap();
+    assert_eq!(stat.cpus.len(), 2);
+    assert_eq!(stat.procs_running, 5);
+    assert_eq!(stat, {
+        let mut fresh = Stat::from_reader(io::Cursor::new(raw)).unwrap();
+        fresh.cpus.truncate(2);
+        fresh.procs_running = 5;
+        fresh
+    });
+}
+
+#[test]
+fn test_stat_without_softirq() {
+    // 2.6.18, before the softirq line was added
+    let raw = "\
+cpu  17501 2 6293 8212469 20141 1955 805 0
+cpu0 17501 2 6293 8212469 20141 1955 805 0
+intr 1015182 8 8252
+ctxt 2238717
+btime 1535128607
+processes 2453
+procs_running 1
+procs_blocked 0
+";
+    let mut stat = Stat::from_reader(io::Cursor::new(raw)).unwrap();
+    assert_eq!(stat.softirqs, None);
+    assert_eq!(stat.procs_blocked, 0);
+    stat.refresh_from_reader(io::Cursor::new(raw)).unwrap();
+    assert_eq!(stat.softirqs, None);
+
+    let truncated = "cpu  17501 2 6293 8212469 20141 1955 805 0\nintr 1015182\nctxt 2238717\n";
+    assert!(Stat::from_reader(io::Cursor::new(truncated)).is_err());
+}
+
+#[test]
+fn test_stat_cpu_fields() {
+    let cpu = |line: &str| StatCpu::from_str(line).unwrap();
+    // 2.6.18, before guest time was accounted
+    let old = cpu("cpu  17501 2 6293 8212469 20141 1955 805 0");
+    assert!(old.fields_present().contains(StatCpuFields::STEAL));
+    assert!(!old.fields_present().contains(StatCpuFields::GUEST));
+    assert_eq!(
+        old.fields_present(),
+        StatCpuFields::expected_for((2, 6, 18))
+    );
+    let new = cpu("cpu  17501 2 6293 8212469 20141 1955 805 0 0 0");
+    assert_eq!(new.fields_present(), StatCpuFields::all());
+    assert_eq!(StatCpuFields::expected_for((6, 1, 0)), StatCpuFields::all());
+    assert_eq!(
+        StatCpuFields::introduced_in(StatCpuFields::GUEST_NICE),
+        Some((2, 6, 33))
+    );
+    assert_eq!(StatCpuFields::introduced_in(StatCpuFields::all()), None);
+}
+
+#[test]
+fn test_stat_lenient() {
+    let raw = "\
+cpu  17501 2 6293 8212469 20141 1955 805 0 0 0
+cpu0 4713 0 1720 2049410 8036 260 255 0 0 0
+ctxt 2238717
+newfield 42
+btime 1535128607
+processes 2453
+procs_running 1
+procs_blocked 0
+intr 1015182 8 8252
+";
+    assert!(Stat::from_reader(io::Cursor::new(raw)).is_err());
+    let options = StatOptions::new().mode(ParseMode::Lenient).softirqs(false);
+    let mut stat = Stat::from_reader_with(io::Cursor::new(raw), options).unwrap();
+    assert_eq!(stat.cpus.len(), 1);
+    assert_eq!(stat.boot_time, 1535128607);
+    assert_eq!(stat.interrupts.as_ref().unwrap().counts, vec![8, 8252]);
+    assert_eq!(stat.extras, vec!["newfield 42"]);
+    stat.refresh_from_reader(io::Cursor::new(raw.replace("newfield 42\n", "")))
+        .unwrap();
+    assert!(stat.extras.is_empty());
 }
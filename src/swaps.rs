@@ -0,0 +1,157 @@
+//! Bindings to `/proc/swaps`.
+use std::fs::File;
+use std::{fmt, io};
+
+use crate::{util, ByteSize, Error};
+
+/// The swap areas in use, from `/proc/swaps`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct Swaps {
+    inner: Vec<Swap>,
+}
+
+impl Swaps {
+    const PATH: &'static str = "/proc/swaps";
+    /// Parse the contents of `/proc/swaps`.
+    pub fn from_system() -> io::Result<Self> {
+        Swaps::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/swaps`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let mut table = util::TableParser::new(reader)?;
+        let mut inner = Vec::new();
+        loop {
+            match table.parse_row(Swap::from_row) {
+                Ok(swap) => inner.push(swap),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Swaps { inner })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Swap> {
+        self.inner.iter()
+    }
+
+    /// The total size of all swap areas.
+    pub fn total_size(&self) -> ByteSize {
+        ByteSize(self.iter().map(|swap| swap.size.bytes()).sum())
+    }
+
+    /// The total amount of swap in use.
+    pub fn total_used(&self) -> ByteSize {
+        ByteSize(self.iter().map(|swap| swap.used.bytes()).sum())
+    }
+}
+
+impl fmt::Display for Swaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sizes: Vec<_> = self
+            .iter()
+            .map(|swap| (swap.size.to_string(), swap.used.to_string()))
+            .collect();
+        let width = |header: &str, lens: &mut dyn Iterator<Item = usize>| {
+            lens.fold(header.len(), usize::max)
+        };
+        let filename = width("Filename", &mut self.iter().map(|swap| swap.filename.len()));
+        let kind = width("Type", &mut self.iter().map(|swap| swap.kind.len()));
+        let size = width("Size", &mut sizes.iter().map(|(size, _)| size.len()));
+        let used = width("Used", &mut sizes.iter().map(|(_, used)| used.len()));
+        write!(
+            f,
+            "{:<filename$} {:<kind$} {:>size$} {:>used$} Priority",
+            "Filename", "Type", "Size", "Used",
+        )?;
+        for (swap, (size_text, used_text)) in self.iter().zip(&sizes) {
+            write!(
+                f,
+                "\n{:<filename$} {:<kind$} {:>size$} {:>used$} {:>8}",
+                swap.filename, swap.kind, size_text, used_text, swap.priority,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for Swaps {
+    type Target = Vec<Swap>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl IntoIterator for Swaps {
+    type IntoIter = std::vec::IntoIter<Swap>;
+    type Item = Swap;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+/// A swap area.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Swap {
+    /// The swap partition or file.
+    pub filename: String,
+    /// `partition` or `file`.
+    pub kind: String,
+    pub size: ByteSize,
+    pub used: ByteSize,
+    /// Areas with higher priority are used first.
+    pub priority: i64,
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+impl Swap {
+    fn from_row(row: &util::TableRow) -> Result<Swap, Error> {
+        let column = |name: &'static str| err_msg!(row.get_by_name(name), name);
+        let kib = |name: &'static str| -> Result<ByteSize, Error> {
+            let (_, value) = err_msg!(util::parse_u64(column(name)?), name)?;
+            Ok(ByteSize::from_kib(value))
+        };
+        let (_, priority) = err_msg!(util::parse_i64(column("Priority")?), "Priority")?;
+        Ok(Swap {
+            filename: column("Filename")?.to_owned(),
+            kind: column("Type")?.to_owned(),
+            size: kib("Size")?,
+            used: kib("Used")?,
+            priority,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Swaps;
+    use crate::ByteSize;
+    use std::io;
+
+    #[test]
+    fn proc_swaps() {
+        let raw = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/nvme0n1p3                          partition\t16777212\t1048576\t\t-2
+/swapfile                               file\t\t2097148\t\t0\t\t-3
+";
+        let swaps = Swaps::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].kind, "partition");
+        assert_eq!(swaps[0].used, ByteSize::from_kib(1048576));
+        assert_eq!(swaps[1].filename, "/swapfile");
+        assert_eq!(swaps[1].priority, -3);
+        assert_eq!(swaps.total_size(), ByteSize::from_kib(16777212 + 2097148));
+
+        let none = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n";
+        assert!(Swaps::from_reader(io::Cursor::new(none))
+            .unwrap()
+            .is_empty());
+    }
+}
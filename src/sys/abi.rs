@@ -0,0 +1,101 @@
+//! Bindings to `/proc/sys/abi`, the settings for running binaries built for another ABI.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::util;
+
+const ROOT: &str = "/proc/sys/abi";
+
+/// The ABI emulation settings, from the files in `/proc/sys/abi`.
+///
+/// Which files exist depends on the architecture and kernel config. The directory is missing
+/// entirely when nothing needs configuring, which reads as settings with nothing set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AbiSettings {
+    /// Whether 32-bit processes get a vDSO, from `/proc/sys/abi/vsyscall32`.
+    ///
+    /// Only x86-64 kernels that can run 32-bit binaries (`CONFIG_IA32_EMULATION`) have this file.
+    pub vsyscall32: Option<bool>,
+    /// Every file in the directory, by name, with its contents trimmed. On arm64 these include
+    /// the emulation modes of deprecated 32-bit instructions, like `swp` and `setend`.
+    pub values: BTreeMap<String, String>,
+}
+
+impl AbiSettings {
+    /// Read the settings in `/proc/sys/abi`.
+    pub fn from_system() -> io::Result<Self> {
+        AbiSettings::from_dir(Path::new(ROOT))
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let mut values = BTreeMap::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let value = match util::read_value::<String>(entry.path()) {
+                Ok(value) => value,
+                // Some settings are write-only, or only readable by root.
+                Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => continue,
+                Err(e) => return Err(e),
+            };
+            values.insert(entry.file_name().to_string_lossy().into_owned(), value);
+        }
+        let vsyscall32 = match values.get("vsyscall32").map(String::as_str) {
+            Some("0") => Some(false),
+            Some("1") => Some(true),
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "cannot parse contents of /proc/sys/abi/vsyscall32",
+                ))
+            }
+            None => None,
+        };
+        Ok(AbiSettings { vsyscall32, values })
+    }
+
+    /// Whether the kernel can run 32-bit binaries on a 64-bit system, as far as `/proc/sys/abi`
+    /// shows: on x86-64 this is the presence of `vsyscall32`, and on arm64 of any instruction
+    /// emulation setting.
+    ///
+    /// A `false` isn't proof, since a kernel can support 32-bit binaries without any settings
+    /// here. Check `/proc/config.gz` where available.
+    pub fn compat_32bit(&self) -> bool {
+        !self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AbiSettings;
+    use crate::util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn proc_sys_abi() {
+        let dir = TempDir::new("abi");
+        fs::write(dir.join("vsyscall32"), "1\n").unwrap();
+        fs::write(dir.join("swp"), "0\n").unwrap();
+        let settings = AbiSettings::from_dir(&dir).unwrap();
+        assert_eq!(settings.vsyscall32, Some(true));
+        assert_eq!(settings.values["swp"], "0");
+        assert!(settings.compat_32bit());
+        fs::write(dir.join("vsyscall32"), "maybe\n").unwrap();
+        assert!(AbiSettings::from_dir(&dir).is_err());
+
+        let missing = AbiSettings::from_dir(&dir.join("missing")).unwrap();
+        assert_eq!(missing, AbiSettings::default());
+        assert!(!missing.compat_32bit());
+
+        AbiSettings::from_system().unwrap();
+    }
+}
@@ -0,0 +1,360 @@
+//! Bindings to `/proc/sys/fs/binfmt_misc`, the registered handlers for extra binary formats.
+//!
+//! `binfmt_misc` lets the kernel run binaries it doesn't understand itself by passing them to an
+//! interpreter, e.g. `qemu-aarch64` for foreign ELF files or `wine` for `.exe`s. It is a separate
+//! filesystem, usually mounted (or automounted) by systemd. When it isn't mounted, reading fails
+//! with `io::ErrorKind::NotFound`.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bitflags::bitflags;
+
+use crate::{util, Error};
+
+const ROOT: &str = "/proc/sys/fs/binfmt_misc";
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// The registered `binfmt_misc` handlers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BinfmtHandlers {
+    /// Whether `binfmt_misc` is enabled as a whole, from the `status` file. When it isn't, no
+    /// handler is used, whether or not it is enabled itself.
+    pub enabled: bool,
+    inner: Vec<BinfmtHandler>,
+}
+
+/// A registered `binfmt_misc` handler, from `/proc/sys/fs/binfmt_misc/[name]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BinfmtHandler {
+    /// The name it was registered under, e.g. `qemu-aarch64`.
+    pub name: String,
+    /// Whether the handler is used. Disabled handlers stay registered.
+    pub enabled: bool,
+    /// The program binaries are passed to.
+    pub interpreter: PathBuf,
+    pub flags: BinfmtFlags,
+    /// How binaries for the handler are recognised.
+    pub matcher: BinfmtMatch,
+}
+
+/// How a `binfmt_misc` handler recognises its binaries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BinfmtMatch {
+    /// The file contains `magic` at byte `offset`, after applying `mask` if there is one.
+    Magic {
+        offset: u64,
+        magic: Vec<u8>,
+        /// Bits of the file to compare, the same length as `magic`.
+        mask: Option<Vec<u8>>,
+    },
+    /// The file name ends in `.` and this extension, e.g. `exe`.
+    Extension(String),
+}
+
+bitflags! {
+    /// The flags a `binfmt_misc` handler was registered with.
+    ///
+    /// Displays as the letters used to register them, like `OCF`.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+    pub struct BinfmtFlags: u8 {
+        /// `P`: pass the original `argv[0]` to the interpreter, instead of the full path.
+        const PRESERVE_ARGV0 = 1 << 0;
+        /// `O`: pass the interpreter an open file descriptor, so it can run binaries it can't
+        /// read itself.
+        const OPEN_BINARY = 1 << 1;
+        /// `C`: use the credentials and security context of the binary, not the interpreter, so
+        /// setuid binaries work. Implies `O`.
+        const CREDENTIALS = 1 << 2;
+        /// `F`: open the interpreter when registering, so it works inside containers and chroots
+        /// where its path doesn't exist.
+        const FIX_BINARY = 1 << 3;
+    }
+}
+
+/// The flag letters, in the order the kernel prints them.
+const FLAG_LETTERS: [(char, BinfmtFlags); 4] = [
+    ('P', BinfmtFlags::PRESERVE_ARGV0),
+    ('O', BinfmtFlags::OPEN_BINARY),
+    ('C', BinfmtFlags::CREDENTIALS),
+    ('F', BinfmtFlags::FIX_BINARY),
+];
+
+impl BinfmtHandlers {
+    /// Read the handlers in `/proc/sys/fs/binfmt_misc`, sorted by name.
+    ///
+    /// The kernel tries the most recently registered handler first, which can't be recovered
+    /// from the files.
+    pub fn from_system() -> io::Result<Self> {
+        BinfmtHandlers::from_dir(Path::new(ROOT))
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        let enabled = parse_enabled(&util::read_value::<String>(dir.join("status"))?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut inner = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name == "status" || name == "register" || !entry.file_type()?.is_file() {
+                continue;
+            }
+            let contents = match fs::read_to_string(entry.path()) {
+                Ok(contents) => contents,
+                // The handler was removed while we were reading.
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let handler = BinfmtHandler::from_str(name, &contents).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", entry.path().display(), e),
+                )
+            })?;
+            inner.push(handler);
+        }
+        inner.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(BinfmtHandlers { enabled, inner })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, BinfmtHandler> {
+        self.inner.iter()
+    }
+
+    /// The number of handlers.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether no handlers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The handler registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&BinfmtHandler> {
+        self.iter().find(|handler| handler.name == name)
+    }
+}
+
+impl IntoIterator for BinfmtHandlers {
+    type IntoIter = std::vec::IntoIter<BinfmtHandler>;
+    type Item = BinfmtHandler;
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a BinfmtHandlers {
+    type IntoIter = std::slice::Iter<'a, BinfmtHandler>;
+    type Item = &'a BinfmtHandler;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl BinfmtHandler {
+    /// Whether the handler recognises a binary called `file_name` that starts with `header`.
+    ///
+    /// `header` should be the first 128 bytes of the file, or all of it if it's shorter, as the
+    /// kernel only looks that far. Doesn't check whether the handler is enabled.
+    pub fn matches(&self, file_name: &str, header: &[u8]) -> bool {
+        match self.matcher {
+            BinfmtMatch::Magic {
+                offset,
+                ref magic,
+                ref mask,
+            } => {
+                let start = offset as usize;
+                let bytes = match header.get(start..start + magic.len()) {
+                    Some(bytes) => bytes,
+                    None => return false,
+                };
+                bytes
+                    .iter()
+                    .zip(magic)
+                    .enumerate()
+                    .all(|(idx, (byte, magic))| {
+                        let mask = mask
+                            .as_ref()
+                            .and_then(|mask| mask.get(idx))
+                            .unwrap_or(&0xff);
+                        (byte ^ magic) & mask == 0
+                    })
+            }
+            BinfmtMatch::Extension(ref extension) => file_name
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| ext == extension),
+        }
+    }
+
+    fn from_str(name: String, input: &str) -> Result<BinfmtHandler, Error> {
+        let mut lines = input.lines();
+        let enabled = parse_enabled(err_msg!(lines.next(), "status")?)?;
+        let mut interpreter = None;
+        let mut flags = BinfmtFlags::empty();
+        let mut offset = 0;
+        let mut magic = None;
+        let mut mask = None;
+        let mut extension = None;
+        for line in lines {
+            let (key, value) = match line.split_once([' ', ':']) {
+                Some((key, value)) => (key, value.trim_start_matches([' ', ':'])),
+                None => (line, ""),
+            };
+            match key {
+                "interpreter" => interpreter = Some(PathBuf::from(value)),
+                "flags" => {
+                    for letter in value.trim_end().chars() {
+                        // Letters from later kernels are dropped.
+                        if let Some(&(_, flag)) = FLAG_LETTERS.iter().find(|(l, _)| *l == letter) {
+                            flags |= flag;
+                        }
+                    }
+                }
+                "offset" => offset = err_msg!(util::parse_u64(value), "offset")?.1,
+                "magic" => magic = Some(err_msg!(parse_hex(value), "magic")?),
+                "mask" => mask = Some(err_msg!(parse_hex(value), "mask")?),
+                "extension" => {
+                    let value = value.strip_prefix('.').unwrap_or(value);
+                    extension = Some(value.to_owned());
+                }
+                _ => (),
+            }
+        }
+        let matcher = match (magic, extension) {
+            (Some(magic), _) => {
+                if mask
+                    .as_ref()
+                    .is_some_and(|mask: &Vec<u8>| mask.len() != magic.len())
+                {
+                    return Err("mask and magic differ in length".into());
+                }
+                BinfmtMatch::Magic {
+                    offset,
+                    magic,
+                    mask,
+                }
+            }
+            (None, Some(extension)) => BinfmtMatch::Extension(extension),
+            (None, None) => return Err("missing \"magic\" or \"extension\"".into()),
+        };
+        Ok(BinfmtHandler {
+            name,
+            enabled,
+            interpreter: err_msg!(interpreter, "missing \"interpreter\"")?,
+            flags,
+            matcher,
+        })
+    }
+}
+
+impl fmt::Display for BinfmtFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(letter, flag) in FLAG_LETTERS.iter() {
+            if self.contains(flag) {
+                write!(f, "{}", letter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_enabled(input: &str) -> Result<bool, Error> {
+    match input.trim_end() {
+        "enabled" => Ok(true),
+        "disabled" => Ok(false),
+        _ => Err("expected \"enabled\" or \"disabled\"".into()),
+    }
+}
+
+/// Parses a run of hex digit pairs, as printed by the kernel's `bin2hex`.
+fn parse_hex(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end();
+    if input.is_empty() || !input.len().is_multiple_of(2) || !input.is_ascii() {
+        return None;
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&input[idx..idx + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinfmtFlags, BinfmtHandlers, BinfmtMatch};
+    use crate::{util::TempDir, ReadOutcome};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn proc_sys_fs_binfmt_misc() {
+        let dir = TempDir::new("binfmt_misc");
+        fs::write(dir.join("status"), "enabled\n").unwrap();
+        fs::write(dir.join("register"), "").unwrap();
+        fs::write(
+            dir.join("qemu-aarch64"),
+            "\
+enabled
+interpreter /usr/bin/qemu-aarch64-static
+flags: OCF
+offset 0
+magic 7f454c460201010000000000000000000200b700
+mask ffffffffffffff00fffffffffffffffffeffffff
+",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("wine"),
+            "disabled\ninterpreter /usr/bin/wine\nflags: \nextension .exe\n",
+        )
+        .unwrap();
+        let handlers = BinfmtHandlers::from_dir(&dir).unwrap();
+        assert!(handlers.enabled);
+        assert_eq!(handlers.len(), 2);
+        let qemu = handlers.get("qemu-aarch64").unwrap();
+        assert!(qemu.enabled);
+        assert_eq!(qemu.interpreter, Path::new("/usr/bin/qemu-aarch64-static"));
+        assert_eq!(
+            qemu.flags,
+            BinfmtFlags::OPEN_BINARY | BinfmtFlags::CREDENTIALS | BinfmtFlags::FIX_BINARY
+        );
+        assert_eq!(qemu.flags.to_string(), "OCF");
+        match qemu.matcher {
+            BinfmtMatch::Magic {
+                offset, ref mask, ..
+            } => {
+                assert_eq!(offset, 0);
+                assert_eq!(mask.as_ref().unwrap()[7], 0);
+            }
+            ref other => panic!("{:?}", other),
+        }
+        // A static aarch64 executable, with an OS/ABI byte the mask ignores.
+        let mut header = b"\x7fELF\x02\x01\x01\x03\0\0\0\0\0\0\0\0\x02\0\xb7\0".to_vec();
+        assert!(qemu.matches("hello", &header));
+        header[18] = 0x3e;
+        assert!(!qemu.matches("hello", &header));
+        assert!(!qemu.matches("hello", &header[..4]));
+
+        let wine = handlers.get("wine").unwrap();
+        assert!(!wine.enabled);
+        assert_eq!(wine.flags, BinfmtFlags::empty());
+        assert_eq!(wine.matcher, BinfmtMatch::Extension("exe".into()));
+        assert!(wine.matches("setup.exe", b"MZ"));
+        assert!(!wine.matches("exe", b"MZ"));
+
+        fs::write(dir.join("wine"), "enabled\ninterpreter /usr/bin/wine\n").unwrap();
+        assert!(BinfmtHandlers::from_dir(&dir).is_err());
+
+        // Not mounted in most containers.
+        match ReadOutcome::from(BinfmtHandlers::from_system()) {
+            ReadOutcome::Ok(_) | ReadOutcome::NotFound | ReadOutcome::PermissionDenied => (),
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Bindings to `/proc/sys/fs`.
+use std::io;
+use std::path::Path;
+
+use crate::util;
+
+const ROOT: &str = "/proc/sys/fs";
+
+/// Per-user limits on epoll and inotify, from `/proc/sys/fs/epoll` and `/proc/sys/fs/inotify`.
+///
+/// When a user runs out of inotify watches or instances, file watchers fail with `ENOSPC` or
+/// `EMFILE`, which is rarely reported helpfully.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WatchLimits {
+    /// The maximum number of file descriptors a user can register across all their epoll
+    /// instances, from `epoll/max_user_watches`.
+    pub epoll_max_user_watches: u64,
+    /// The maximum number of inotify watches per user, from `inotify/max_user_watches`.
+    pub inotify_max_user_watches: u64,
+    /// The maximum number of inotify instances per user, from `inotify/max_user_instances`.
+    pub inotify_max_user_instances: u64,
+    /// The maximum number of events queued on an inotify instance before events are dropped,
+    /// from `inotify/max_queued_events`.
+    pub inotify_max_queued_events: u64,
+}
+
+impl WatchLimits {
+    /// Read the limits from `/proc/sys/fs`.
+    pub fn from_system() -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<u64>(Path::new(ROOT).join(name));
+        Ok(WatchLimits {
+            epoll_max_user_watches: read("epoll/max_user_watches")?,
+            inotify_max_user_watches: read("inotify/max_user_watches")?,
+            inotify_max_user_instances: read("inotify/max_user_instances")?,
+            inotify_max_queued_events: read("inotify/max_queued_events")?,
+        })
+    }
+}
+
+/// Disk quota statistics, from `/proc/sys/fs/quota`.
+///
+/// A "dquot" is the in-memory copy of one user's or group's quota on one filesystem. Only
+/// exists if the kernel was built with `CONFIG_QUOTA`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct QuotaStats {
+    /// The number of quota lookups.
+    pub lookups: u64,
+    /// The number of times a dquot was released.
+    pub drops: u64,
+    /// The number of quota structures read from disk.
+    pub reads: u64,
+    /// The number of quota structures written to disk.
+    pub writes: u64,
+    /// The number of lookups answered from the dquot cache.
+    pub cache_hits: u64,
+    /// The number of dquots allocated.
+    pub allocated_dquots: u64,
+    /// The number of allocated dquots that are unused and can be reclaimed.
+    pub free_dquots: u64,
+    /// The number of times all dquots were written to disk, e.g. by `sync`.
+    pub syncs: u64,
+}
+
+impl QuotaStats {
+    /// Read the statistics from `/proc/sys/fs/quota`.
+    pub fn from_system() -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<u64>(Path::new(ROOT).join("quota").join(name));
+        Ok(QuotaStats {
+            lookups: read("lookups")?,
+            drops: read("drops")?,
+            reads: read("reads")?,
+            writes: read("writes")?,
+            cache_hits: read("cache_hits")?,
+            allocated_dquots: read("allocated_dquots")?,
+            free_dquots: read("free_dquots")?,
+            syncs: read("syncs")?,
+        })
+    }
+
+    /// The proportion of lookups answered from the cache, from 0 to 1.
+    pub fn cache_hit_ratio(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        (self.cache_hits as f64 / self.lookups as f64).min(1.0)
+    }
+}
+
+/// Set the maximum number of inotify watches per user, `inotify/max_user_watches`.
+///
+/// Each watch costs about 1kB of unswappable kernel memory on 64-bit machines.
+#[cfg(feature = "write")]
+pub fn set_inotify_max_user_watches(value: u32) -> io::Result<()> {
+    if value > i32::MAX as u32 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("inotify max_user_watches {} is out of range", value),
+        ));
+    }
+    util::write_value(Path::new(ROOT).join("inotify/max_user_watches"), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuotaStats, WatchLimits};
+    use crate::ReadOutcome;
+
+    #[test]
+    fn watch_limits() {
+        let limits = WatchLimits::from_system().unwrap();
+        assert!(limits.inotify_max_user_instances > 0);
+        assert!(limits.inotify_max_user_watches > 0);
+    }
+
+    #[test]
+    fn quota_stats() {
+        // Without `CONFIG_QUOTA` there is nothing to read.
+        if let ReadOutcome::Ok(stats) = ReadOutcome::from(QuotaStats::from_system()) {
+            assert!(stats.free_dquots <= stats.allocated_dquots);
+            assert!(stats.cache_hit_ratio() <= 1.0);
+        }
+    }
+}
@@ -0,0 +1,843 @@
+//! Bindings to `/proc/sys/kernel`.
+#[cfg(feature = "write")]
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use bitflags::bitflags;
+
+use crate::{
+    loadavg::LoadAvg,
+    process::{Capabilities, Process},
+    stat::{Stat, StatOptions},
+    util,
+};
+
+const ROOT: &str = "/proc/sys/kernel";
+
+/// The identity of the running kernel and host, like `uname -a`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KernelIdentity {
+    /// From `/proc/sys/kernel/hostname`.
+    pub hostname: String,
+    /// The NIS domain name, from `/proc/sys/kernel/domainname`. `None` if unset.
+    pub domainname: Option<String>,
+    /// The kernel name, from `/proc/sys/kernel/ostype` (always `Linux`).
+    pub ostype: String,
+    /// The kernel release, e.g. `6.1.0-13-amd64`, from `/proc/sys/kernel/osrelease`.
+    pub osrelease: String,
+    /// The kernel build version, e.g. `#1 SMP PREEMPT_DYNAMIC Debian 6.1.55-1 (2023-09-29)`,
+    /// from `/proc/sys/kernel/version`.
+    pub version: String,
+}
+
+impl KernelIdentity {
+    /// Read the identity files from `/proc/sys/kernel`.
+    pub fn from_system() -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<String>(Path::new(ROOT).join(name));
+        let domainname = read("domainname")?;
+        Ok(KernelIdentity {
+            hostname: read("hostname")?,
+            // The kernel reports an unset domain name as "(none)".
+            domainname: if domainname == "(none)" {
+                None
+            } else {
+                Some(domainname)
+            },
+            ostype: read("ostype")?,
+            osrelease: read("osrelease")?,
+            version: read("version")?,
+        })
+    }
+}
+
+bitflags! {
+    /// Which magic SysRq key functions are allowed, from `/proc/sys/kernel/sysrq`.
+    ///
+    /// This only restricts the keyboard. Writing to `/proc/sysrq-trigger` as root is always
+    /// allowed. A mask of exactly `ENABLE_ALL` enables every function; combined with other bits,
+    /// it enables nothing.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct SysRqMask: u64 {
+        /// Every function, when it's the only bit set.
+        const ENABLE_ALL = 0x1;
+        /// Changing the console log level (`0`-`9`).
+        const LOG_LEVEL = 0x2;
+        /// Keyboard control (`r`, `k`).
+        const KEYBOARD = 0x4;
+        /// Debugging dumps of processes and memory (`t`, `m`, `w`, ...).
+        const DUMP = 0x8;
+        /// Syncing filesystems (`s`).
+        const SYNC = 0x10;
+        /// Remounting filesystems read-only (`u`).
+        const REMOUNT_READ_ONLY = 0x20;
+        /// Signalling processes, including the OOM killer (`e`, `i`, `f`).
+        const SIGNAL = 0x40;
+        /// Rebooting and powering off (`b`, `o`).
+        const REBOOT = 0x80;
+        /// Changing the nice level of realtime tasks (`n`).
+        const NICE_REALTIME = 0x100;
+
+        // Unknown bits are kept.
+        const _ = !0;
+    }
+}
+
+/// The bits of `SysRqMask` that each enable a group of functions.
+const SYSRQ_FUNCTIONS: SysRqMask = SysRqMask::LOG_LEVEL
+    .union(SysRqMask::KEYBOARD)
+    .union(SysRqMask::DUMP)
+    .union(SysRqMask::SYNC)
+    .union(SysRqMask::REMOUNT_READ_ONLY)
+    .union(SysRqMask::SIGNAL)
+    .union(SysRqMask::REBOOT)
+    .union(SysRqMask::NICE_REALTIME);
+
+impl SysRqMask {
+    /// Read `/proc/sys/kernel/sysrq`.
+    pub fn from_system() -> io::Result<Self> {
+        let bits = util::read_value(Path::new(ROOT).join("sysrq"))?;
+        Ok(SysRqMask::from_bits_retain(bits))
+    }
+
+    /// Whether every function is enabled (the mask is exactly `ENABLE_ALL`).
+    pub fn all_enabled(self) -> bool {
+        self == SysRqMask::ENABLE_ALL
+    }
+
+    /// Whether all the functions in `functions` are enabled.
+    pub fn is_enabled(self, functions: SysRqMask) -> bool {
+        self.enabled().contains(functions)
+    }
+
+    /// The enabled functions, with `ENABLE_ALL` expanded into the functions it enables.
+    pub fn enabled(self) -> SysRqMask {
+        if self.all_enabled() {
+            SYSRQ_FUNCTIONS
+        } else {
+            self & SYSRQ_FUNCTIONS
+        }
+    }
+}
+
+/// Limits on the number of processes and threads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TaskLimits {
+    /// One more than the largest pid the kernel will allocate, from `/proc/sys/kernel/pid_max`.
+    pub pid_max: u64,
+    /// The maximum number of threads on the system, from `/proc/sys/kernel/threads-max`.
+    pub threads_max: u64,
+}
+
+impl TaskLimits {
+    /// Read the limits from `/proc/sys/kernel`.
+    pub fn from_system() -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<u64>(Path::new(ROOT).join(name));
+        Ok(TaskLimits {
+            pid_max: read("pid_max")?,
+            threads_max: read("threads-max")?,
+        })
+    }
+}
+
+/// The last pid allocated in the current process's pid namespace, from
+/// `/proc/sys/kernel/ns_last_pid`.
+///
+/// Unlike `LoadAvg::last_pid`, which is from the initial namespace, this is the pid as seen
+/// inside a container.
+pub fn ns_last_pid() -> io::Result<u32> {
+    util::read_value(Path::new(ROOT).join("ns_last_pid"))
+}
+
+/// Set the last pid allocated in the current pid namespace, so the next process created gets
+/// `pid + 1` if it's free. This is how checkpoint/restore tools recreate processes with their
+/// old pids, and requires `CAP_SYS_ADMIN` or `CAP_CHECKPOINT_RESTORE`.
+#[cfg(feature = "write")]
+pub fn set_ns_last_pid(pid: u32) -> io::Result<()> {
+    util::write_value(Path::new(ROOT).join("ns_last_pid"), pid)
+}
+
+/// The scheduler tunables in `/proc/sys/kernel/sched_*`.
+///
+/// Which files exist depends on the kernel version and configuration, so most fields are
+/// optional. In particular the CFS latency and granularity settings moved to debugfs in Linux
+/// 5.13, and are `None` after that.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SchedTunables {
+    /// The targeted preemption latency for cpu-bound tasks, from `sched_latency_ns`.
+    pub latency: Option<Duration>,
+    /// The minimum time slice for cpu-bound tasks, from `sched_min_granularity_ns`.
+    pub min_granularity: Option<Duration>,
+    /// How much longer a waking task must have waited to preempt the current one, from
+    /// `sched_wakeup_granularity_ns`.
+    pub wakeup_granularity: Option<Duration>,
+    /// How much of each `rt_period` realtime tasks may use, from `sched_rt_runtime_us`. `None`
+    /// if they're unlimited (`-1`), which can let a runaway realtime task lock up a cpu.
+    pub rt_runtime: Option<Duration>,
+    /// The period `rt_runtime` is measured over, from `sched_rt_period_us`.
+    pub rt_period: Duration,
+    /// The time slice of `SCHED_RR` tasks, from `sched_rr_timeslice_ms`.
+    pub rr_timeslice: Option<Duration>,
+    /// Whether tasks are grouped by session for fairness, from `sched_autogroup_enabled`.
+    /// `None` without `CONFIG_SCHED_AUTOGROUP`.
+    pub autogroup_enabled: Option<bool>,
+    /// How much cpu time a cgroup's bandwidth quota is handed out in, from
+    /// `sched_cfs_bandwidth_slice_us`.
+    pub cfs_bandwidth_slice: Option<Duration>,
+}
+
+impl SchedTunables {
+    /// Read the tunables from `/proc/sys/kernel`.
+    pub fn from_system() -> io::Result<Self> {
+        SchedTunables::from_dir(Path::new(ROOT))
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<i64>(dir.join(name));
+        let read_optional = |name: &str| match read(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        };
+        let nanos = |value: i64| Duration::from_nanos(value.max(0) as u64);
+        let micros = |value: i64| Duration::from_micros(value.max(0) as u64);
+        let rt_runtime = read("sched_rt_runtime_us")?;
+        Ok(SchedTunables {
+            latency: read_optional("sched_latency_ns")?.map(nanos),
+            min_granularity: read_optional("sched_min_granularity_ns")?.map(nanos),
+            wakeup_granularity: read_optional("sched_wakeup_granularity_ns")?.map(nanos),
+            rt_runtime: if rt_runtime < 0 {
+                None
+            } else {
+                Some(micros(rt_runtime))
+            },
+            rt_period: micros(read("sched_rt_period_us")?),
+            rr_timeslice: read_optional("sched_rr_timeslice_ms")?
+                .map(|ms| Duration::from_millis(ms.max(0) as u64)),
+            autogroup_enabled: read_optional("sched_autogroup_enabled")?.map(|value| value != 0),
+            cfs_bandwidth_slice: read_optional("sched_cfs_bandwidth_slice_us")?.map(micros),
+        })
+    }
+
+    /// The proportion of each cpu that realtime tasks may use, from 0 to 1.
+    pub fn rt_share(&self) -> f64 {
+        match self.rt_runtime {
+            Some(runtime) if !self.rt_period.is_zero() => {
+                (runtime.as_secs_f64() / self.rt_period.as_secs_f64()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Limit realtime tasks to `runtime` in each `sched_rt_period_us`, or lift the limit with `None`.
+///
+/// The kernel rejects a runtime longer than the period.
+#[cfg(feature = "write")]
+pub fn set_sched_rt_runtime(runtime: Option<Duration>) -> io::Result<()> {
+    let value = match runtime {
+        Some(runtime) => {
+            i64::try_from(runtime.as_micros()).map_err(|_| duration_out_of_range(runtime))?
+        }
+        None => -1,
+    };
+    util::write_value(Path::new(ROOT).join("sched_rt_runtime_us"), value)
+}
+
+/// Set the period realtime tasks' runtime is measured over. It must be at least 1 microsecond
+/// and fit in an `i32` of microseconds.
+#[cfg(feature = "write")]
+pub fn set_sched_rt_period(period: Duration) -> io::Result<()> {
+    let value = period.as_micros();
+    if value == 0 || value > i32::MAX as u128 {
+        return Err(duration_out_of_range(period));
+    }
+    util::write_value(Path::new(ROOT).join("sched_rt_period_us"), value)
+}
+
+/// Turn grouping tasks by session on or off.
+#[cfg(feature = "write")]
+pub fn set_sched_autogroup_enabled(enabled: bool) -> io::Result<()> {
+    util::write_value(
+        Path::new(ROOT).join("sched_autogroup_enabled"),
+        u8::from(enabled),
+    )
+}
+
+/// Set the targeted preemption latency. Only possible before Linux 5.13; later kernels fail
+/// with `NotFound`.
+#[cfg(feature = "write")]
+pub fn set_sched_latency(latency: Duration) -> io::Result<()> {
+    let value = u64::try_from(latency.as_nanos()).map_err(|_| duration_out_of_range(latency))?;
+    util::write_value(Path::new(ROOT).join("sched_latency_ns"), value)
+}
+
+#[cfg(feature = "write")]
+fn duration_out_of_range(duration: Duration) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{:?} is out of range for this tunable", duration),
+    )
+}
+
+/// Pids below this are reserved for daemons started at boot, so allocation restarts here after
+/// reaching `pid_max`.
+const RESERVED_PIDS: u64 = 300;
+
+/// How much of the pid space is in use, for capacity planning.
+///
+/// Every thread uses a pid, so running out of pids (or hitting `threads-max`) makes `fork` and
+/// `clone` fail with `EAGAIN`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PidUsage {
+    pub limits: TaskLimits,
+    /// The number of processes and threads that currently exist (from `/proc/loadavg`).
+    pub tasks: u64,
+    /// The most recently allocated pid (from `/proc/loadavg`).
+    pub last_pid: u32,
+    /// The number of processes and threads created since boot (from `/proc/stat`).
+    pub created: u64,
+}
+
+impl PidUsage {
+    /// Read `/proc/sys/kernel`, `/proc/loadavg` and `/proc/stat`.
+    pub fn from_system() -> io::Result<Self> {
+        let options = StatOptions::new()
+            .per_cpu(false)
+            .interrupts(false)
+            .softirqs(false);
+        let stat = Stat::parse_with(options)?;
+        Ok(PidUsage::new(
+            TaskLimits::from_system()?,
+            &stat,
+            &LoadAvg::from_system()?,
+        ))
+    }
+
+    /// Combine existing readings.
+    pub fn new(limits: TaskLimits, stat: &Stat, load: &LoadAvg) -> Self {
+        PidUsage {
+            limits,
+            tasks: load.total,
+            last_pid: load.last_pid,
+            created: stat.processes,
+        }
+    }
+
+    /// The proportion of pids in use, from 0 to 1.
+    pub fn pid_utilization(&self) -> f64 {
+        ratio(self.tasks, self.limits.pid_max)
+    }
+
+    /// The proportion of `threads-max` in use, from 0 to 1.
+    pub fn thread_utilization(&self) -> f64 {
+        ratio(self.tasks, self.limits.threads_max)
+    }
+
+    /// An estimate of the next pid to be allocated.
+    ///
+    /// Pids are allocated in increasing order, skipping those in use, so this is a lower bound.
+    pub fn next_pid(&self) -> u64 {
+        let next = u64::from(self.last_pid) + 1;
+        if next >= self.limits.pid_max {
+            RESERVED_PIDS
+        } else {
+            next
+        }
+    }
+
+    /// An estimate of how many times pid allocation has wrapped around since boot.
+    ///
+    /// Frequent wrapping means pids are reused quickly, so anything that identifies processes by
+    /// pid alone is more likely to get confused.
+    pub fn wraps(&self) -> u64 {
+        match self.limits.pid_max.checked_sub(RESERVED_PIDS) {
+            Some(cycle) if cycle > 0 => self.created.saturating_sub(RESERVED_PIDS) / cycle,
+            _ => 0,
+        }
+    }
+}
+
+fn ratio(used: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    (used as f64 / max as f64).min(1.0)
+}
+
+/// The settings that decide what profilers using `perf_event_open` may do.
+///
+/// Use `check` before starting to sample, to report which setting is in the way rather than
+/// the `EACCES` or `EINVAL` from the kernel.
+///
+/// ```no_run
+/// use linux_proc::sys::kernel::{PerfAccess, PerfSettings};
+///
+/// let perf = PerfSettings::from_system()?;
+/// if let Err(e) = perf.check_current(PerfAccess::SystemWide) {
+///     eprintln!("can't profile the whole system: {}", e);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PerfSettings {
+    /// How much unprivileged processes are restricted, from `perf_event_paranoid`: `-1` allows
+    /// everything, and each level up to `2` blocks more (see `PerfAccess`). Some distributions
+    /// add a level `3` (or more) blocking unprivileged use entirely, which upstream kernels treat
+    /// as `2`.
+    pub paranoid: i32,
+    /// The highest sampling frequency in Hz, from `perf_event_max_sample_rate`. The kernel
+    /// lowers this itself when sampling takes more than `perf_cpu_time_max_percent` of the cpu.
+    pub max_sample_rate: u32,
+    /// Whether kernel addresses are hidden, from `kptr_restrict`: `0` shows them (if
+    /// `paranoid` is at most 1), `1` only to processes with `CAP_SYSLOG`, and `2` to nobody.
+    pub kptr_restrict: u8,
+}
+
+/// Something a profiler wants to do, for `PerfSettings::check`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PerfAccess {
+    /// Sample the user space code of your own processes.
+    UserSpace,
+    /// Sample the kernel code run on behalf of your own processes.
+    Kernel,
+    /// Sample every process on some cpus, like `perf record -a` and `perf top`.
+    SystemWide,
+    /// Read the raw data of tracepoints.
+    RawTracepoints,
+    /// Resolve kernel addresses to symbols through `/proc/kallsyms`.
+    KernelSymbols,
+}
+
+impl PerfSettings {
+    /// Read the settings from `/proc/sys/kernel`.
+    pub fn from_system() -> io::Result<Self> {
+        PerfSettings::from_dir(Path::new(ROOT))
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        Ok(PerfSettings {
+            paranoid: util::read_value(dir.join("perf_event_paranoid"))?,
+            max_sample_rate: util::read_value(dir.join("perf_event_max_sample_rate"))?,
+            kptr_restrict: util::read_value(dir.join("kptr_restrict"))?,
+        })
+    }
+
+    /// Whether a process with the effective capabilities `capabilities` (`Status::cap_eff`) may
+    /// do `access`.
+    ///
+    /// `CAP_PERFMON`, or `CAP_SYS_ADMIN`, exempts a process from `paranoid`. `CAP_SYSLOG` is needed
+    /// to see kernel addresses when they're restricted.
+    ///
+    /// The error is `PermissionDenied`, saying which setting blocks it and what it would need to
+    /// be.
+    pub fn check(&self, access: PerfAccess, capabilities: Capabilities) -> io::Result<()> {
+        let (max, what) = match access {
+            PerfAccess::UserSpace => (2, "all profiling"),
+            PerfAccess::Kernel => (1, "kernel profiling"),
+            PerfAccess::SystemWide => (0, "system-wide profiling"),
+            PerfAccess::RawTracepoints => (-1, "raw tracepoint access"),
+            PerfAccess::KernelSymbols => {
+                return self.check_kernel_symbols(capabilities.contains(Capabilities::SYSLOG))
+            }
+        };
+        if capabilities.intersects(Capabilities::PERFMON | Capabilities::SYS_ADMIN) {
+            return Ok(());
+        }
+        if self.paranoid <= max {
+            return Ok(());
+        }
+        Err(blocked(format!(
+            "kernel.perf_event_paranoid is {}, which blocks {} without CAP_PERFMON; it must be at \
+             most {}",
+            self.paranoid, what, max
+        )))
+    }
+
+    fn check_kernel_symbols(&self, syslog: bool) -> io::Result<()> {
+        match self.kptr_restrict {
+            0 if self.paranoid <= 1 || syslog => Ok(()),
+            0 => Err(blocked(format!(
+                "kernel.perf_event_paranoid is {}, which hides kernel addresses from processes \
+                 without CAP_SYSLOG; it must be at most 1",
+                self.paranoid
+            ))),
+            1 if syslog => Ok(()),
+            1 => Err(blocked(
+                "kernel.kptr_restrict is 1, which hides kernel addresses from processes without \
+                 CAP_SYSLOG; it must be 0"
+                    .to_owned(),
+            )),
+            other => Err(blocked(format!(
+                "kernel.kptr_restrict is {}, which hides kernel addresses from everyone; it must \
+                 be 0 or 1",
+                other
+            ))),
+        }
+    }
+
+    /// `check`, for the current process.
+    pub fn check_current(&self, access: PerfAccess) -> io::Result<()> {
+        self.check(access, Process::current().status()?.cap_eff)
+    }
+
+    /// Whether sampling at `frequency` Hz is allowed. This applies to privileged processes too.
+    pub fn check_frequency(&self, frequency: u64) -> io::Result<()> {
+        if frequency <= u64::from(self.max_sample_rate) {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "kernel.perf_event_max_sample_rate is {}, below the requested {} Hz",
+                self.max_sample_rate, frequency
+            ),
+        ))
+    }
+}
+
+fn blocked(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, message)
+}
+
+/// How core dumps are named, from `/proc/sys/kernel/core_pattern`.
+///
+/// The pattern is either a file name template, relative to the crashing process's working
+/// directory unless it's absolute, or (when it starts with `|`) a helper program that the core
+/// dump is piped to, like `systemd-coredump` or `apport`. Either way it contains `%` specifiers
+/// that the kernel fills in from the crashing process, which `expand` previews.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CorePattern {
+    pattern: String,
+}
+
+/// The details of a crashing process, for `CorePattern::expand`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CrashInfo {
+    /// The pid as seen in the process's pid namespace (`%p`).
+    pub pid: u32,
+    /// The pid as seen in the initial pid namespace (`%P`).
+    pub global_pid: u32,
+    /// The id of the crashing thread, as seen in the process's pid namespace (`%i`).
+    pub tid: u32,
+    /// The id of the crashing thread, as seen in the initial pid namespace (`%I`).
+    pub global_tid: u32,
+    /// The real user id (`%u`).
+    pub uid: u32,
+    /// The real group id (`%g`).
+    pub gid: u32,
+    /// The signal that caused the dump (`%s`).
+    pub signal: u32,
+    /// The time of the dump, in seconds since the unix epoch (`%t`).
+    pub time: u64,
+    /// The hostname (`%h`).
+    pub hostname: String,
+    /// The thread name (`%e`).
+    pub comm: String,
+    /// The path of the executable (`%E` and `%f`).
+    pub exe: String,
+    /// The soft `RLIMIT_CORE` limit in bytes, `None` if unlimited (`%c`).
+    pub core_limit: Option<u64>,
+    /// The dump mode, as in `/proc/sys/fs/suid_dumpable` (`%d`).
+    pub dump_mode: u8,
+}
+
+impl CorePattern {
+    /// The kernel rejects patterns of this many bytes or more.
+    pub const MAX_LEN: usize = 128;
+
+    /// Read `/proc/sys/kernel/core_pattern`.
+    pub fn from_system() -> io::Result<Self> {
+        let pattern = util::read_value::<String>(Path::new(ROOT).join("core_pattern"))?;
+        Ok(CorePattern::new(pattern))
+    }
+
+    /// Wrap a pattern in the `core_pattern` syntax.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        CorePattern {
+            pattern: pattern.into(),
+        }
+    }
+
+    /// The pattern as written to `core_pattern`.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether core dumps are piped to a helper program.
+    pub fn is_pipe(&self) -> bool {
+        self.pattern.starts_with('|')
+    }
+
+    /// The path of the helper program, if core dumps are piped to one.
+    pub fn pipe_program(&self) -> Option<&str> {
+        let command = self.pattern.strip_prefix('|')?;
+        command.split(' ').find(|arg| !arg.is_empty())
+    }
+
+    /// What the kernel would produce for `crash`: the core file's path, or the helper's argument
+    /// list (program first) if core dumps are piped.
+    ///
+    /// Arguments are split at spaces in the pattern, so a specifier expanding to something with
+    /// spaces stays one argument. Unknown specifiers expand to nothing, as in the kernel. The
+    /// kernel appends `.<pid>` to file names without `%p` if `kernel.core_uses_pid` is set,
+    /// which this doesn't do.
+    pub fn expand(&self, crash: &CrashInfo) -> Vec<String> {
+        match self.pattern.strip_prefix('|') {
+            Some(command) => command
+                .split(' ')
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| expand_specifiers(arg, crash))
+                .collect(),
+            None => vec![expand_specifiers(&self.pattern, crash)],
+        }
+    }
+}
+
+/// Replace the `%` specifiers in `template` with the details of `crash`.
+fn expand_specifiers(template: &str, crash: &CrashInfo) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('p') => out.push_str(&crash.pid.to_string()),
+            Some('P') => out.push_str(&crash.global_pid.to_string()),
+            Some('i') => out.push_str(&crash.tid.to_string()),
+            Some('I') => out.push_str(&crash.global_tid.to_string()),
+            Some('u') => out.push_str(&crash.uid.to_string()),
+            Some('g') => out.push_str(&crash.gid.to_string()),
+            Some('d') => out.push_str(&crash.dump_mode.to_string()),
+            Some('s') => out.push_str(&crash.signal.to_string()),
+            Some('t') => out.push_str(&crash.time.to_string()),
+            // The kernel replaces slashes so these can't add directories.
+            Some('h') => out.push_str(&crash.hostname.replace('/', "!")),
+            Some('e') => out.push_str(&crash.comm.replace('/', "!")),
+            Some('E') => out.push_str(&crash.exe.replace('/', "!")),
+            Some('f') => out.push_str(crash.exe.rsplit('/').next().unwrap_or_default()),
+            Some('c') => match crash.core_limit {
+                Some(limit) => out.push_str(&limit.to_string()),
+                None => out.push_str(&u64::MAX.to_string()),
+            },
+            _ => (),
+        }
+    }
+    out
+}
+
+/// Set `/proc/sys/kernel/core_pattern`.
+///
+/// Patterns of `CorePattern::MAX_LEN` bytes or more fail with `InvalidInput`.
+#[cfg(feature = "write")]
+pub fn set_core_pattern(pattern: &CorePattern) -> io::Result<()> {
+    if pattern.as_str().len() >= CorePattern::MAX_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("core pattern {:?} is too long", pattern.as_str()),
+        ));
+    }
+    util::write_value(Path::new(ROOT).join("core_pattern"), pattern.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ns_last_pid, CorePattern, CrashInfo, KernelIdentity, PerfAccess, PerfSettings, PidUsage,
+        SchedTunables, SysRqMask, TaskLimits,
+    };
+    use crate::process::{Capabilities, Process};
+    use crate::util::TempDir;
+    use std::fs;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn kernel_identity() {
+        let identity = KernelIdentity::from_system().unwrap();
+        assert_eq!(identity.ostype, "Linux");
+        assert!(!identity.osrelease.is_empty());
+    }
+
+    #[test]
+    fn sched_tunables() {
+        let dir = TempDir::new("sched");
+        fs::write(dir.join("sched_rt_runtime_us"), "950000\n").unwrap();
+        fs::write(dir.join("sched_rt_period_us"), "1000000\n").unwrap();
+        fs::write(dir.join("sched_latency_ns"), "24000000\n").unwrap();
+        fs::write(dir.join("sched_autogroup_enabled"), "1\n").unwrap();
+        let tunables = SchedTunables::from_dir(&dir).unwrap();
+        assert_eq!(tunables.rt_runtime, Some(Duration::from_millis(950)));
+        assert_eq!(tunables.rt_share(), 0.95);
+        assert_eq!(tunables.latency, Some(Duration::from_millis(24)));
+        assert_eq!(tunables.autogroup_enabled, Some(true));
+        assert_eq!(tunables.rr_timeslice, None);
+        fs::write(dir.join("sched_rt_runtime_us"), "-1\n").unwrap();
+        let tunables = SchedTunables::from_dir(&dir).unwrap();
+        assert_eq!(tunables.rt_runtime, None);
+        assert_eq!(tunables.rt_share(), 1.0);
+
+        let tunables = SchedTunables::from_system().unwrap();
+        assert!(!tunables.rt_period.is_zero());
+    }
+
+    #[test]
+    fn perf_settings() {
+        let dir = TempDir::new("perf");
+        fs::write(dir.join("perf_event_paranoid"), "2\n").unwrap();
+        fs::write(dir.join("perf_event_max_sample_rate"), "50000\n").unwrap();
+        fs::write(dir.join("kptr_restrict"), "1\n").unwrap();
+        let perf = PerfSettings::from_dir(&dir).unwrap();
+        assert_eq!(
+            perf,
+            PerfSettings {
+                paranoid: 2,
+                max_sample_rate: 50000,
+                kptr_restrict: 1,
+            }
+        );
+        perf.check(PerfAccess::UserSpace, Capabilities::empty())
+            .unwrap();
+        let err = perf
+            .check(PerfAccess::Kernel, Capabilities::empty())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            err.to_string(),
+            "kernel.perf_event_paranoid is 2, which blocks kernel profiling without CAP_PERFMON; \
+             it must be at most 1"
+        );
+        perf.check(PerfAccess::SystemWide, Capabilities::PERFMON)
+            .unwrap();
+        assert!(perf
+            .check(PerfAccess::KernelSymbols, Capabilities::PERFMON)
+            .is_err());
+        perf.check(PerfAccess::KernelSymbols, Capabilities::SYSLOG)
+            .unwrap();
+        perf.check_frequency(4000).unwrap();
+        let err = perf.check_frequency(100_000).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // Debian's extra level blocks everything.
+        let perf = PerfSettings {
+            paranoid: 3,
+            ..perf
+        };
+        assert!(perf
+            .check(PerfAccess::UserSpace, Capabilities::empty())
+            .is_err());
+        let perf = PerfSettings {
+            paranoid: -1,
+            kptr_restrict: 0,
+            ..perf
+        };
+        perf.check(PerfAccess::RawTracepoints, Capabilities::empty())
+            .unwrap();
+        perf.check(PerfAccess::KernelSymbols, Capabilities::empty())
+            .unwrap();
+
+        let perf = PerfSettings::from_system().unwrap();
+        assert!(perf.max_sample_rate > 0);
+        // Whether this host allows it varies, but the answer must match our own capabilities.
+        let caps = Process::current().status().unwrap().cap_eff;
+        match perf.check_current(PerfAccess::UserSpace) {
+            Ok(()) => perf.check(PerfAccess::UserSpace, caps).unwrap(),
+            Err(err) => {
+                assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+                assert!(err.to_string().contains("perf_event_paranoid"), "{}", err);
+                assert!(perf.check(PerfAccess::UserSpace, caps).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn last_pid() {
+        // At least the test process itself has been allocated a pid.
+        assert!(ns_last_pid().unwrap() > 0);
+    }
+
+    #[test]
+    fn pid_usage() {
+        let usage = PidUsage {
+            limits: TaskLimits {
+                pid_max: 32768,
+                threads_max: 63445,
+            },
+            tasks: 8192,
+            last_pid: 32767,
+            created: 100_000,
+        };
+        assert_eq!(usage.pid_utilization(), 0.25);
+        assert_eq!(usage.next_pid(), 300);
+        assert_eq!(usage.wraps(), 3);
+        let usage = PidUsage {
+            last_pid: 1000,
+            ..usage
+        };
+        assert_eq!(usage.next_pid(), 1001);
+
+        let usage = PidUsage::from_system().unwrap();
+        assert!(usage.tasks > 0);
+        assert!(usage.pid_utilization() > 0.0);
+    }
+
+    #[test]
+    fn sysrq_mask() {
+        assert!(SysRqMask::empty().enabled().is_empty());
+        assert_eq!(SysRqMask::ENABLE_ALL.enabled().iter().count(), 8);
+        assert!(SysRqMask::ENABLE_ALL.is_enabled(SysRqMask::REBOOT | SysRqMask::DUMP));
+        // The Debian default: sync, remount read-only and reboot.
+        let mask = SysRqMask::from_bits_retain(176);
+        assert!(!mask.all_enabled());
+        assert_eq!(
+            mask.enabled(),
+            SysRqMask::SYNC | SysRqMask::REMOUNT_READ_ONLY | SysRqMask::REBOOT
+        );
+        assert!(!mask.is_enabled(SysRqMask::SIGNAL));
+        // With other bits set, `ENABLE_ALL` is just an unused bit.
+        let mask = SysRqMask::ENABLE_ALL | SysRqMask::SYNC | SysRqMask::from_bits_retain(0x1000);
+        assert_eq!(mask.enabled(), SysRqMask::SYNC);
+    }
+
+    #[test]
+    fn core_pattern() {
+        let crash = CrashInfo {
+            pid: 1234,
+            global_pid: 5678,
+            uid: 1000,
+            signal: 11,
+            time: 1700000000,
+            hostname: "host".to_owned(),
+            comm: "my app".to_owned(),
+            exe: "/usr/bin/my-app".to_owned(),
+            ..CrashInfo::default()
+        };
+        let file = CorePattern::new("/var/crash/core.%e.%p.%t%%%z");
+        assert!(!file.is_pipe());
+        assert_eq!(file.pipe_program(), None);
+        assert_eq!(
+            file.expand(&crash),
+            vec!["/var/crash/core.my app.1234.1700000000%"]
+        );
+
+        let pipe =
+            CorePattern::new("|/usr/lib/systemd/systemd-coredump %P %u %g %s %t %c %h %f %E");
+        assert!(pipe.is_pipe());
+        assert_eq!(
+            pipe.pipe_program(),
+            Some("/usr/lib/systemd/systemd-coredump")
+        );
+        let argv = pipe.expand(&crash);
+        assert_eq!(argv.len(), 10);
+        assert_eq!(argv[1], "5678");
+        assert_eq!(argv[6], "18446744073709551615");
+        assert_eq!(argv[8], "my-app");
+        assert_eq!(argv[9], "!usr!bin!my-app");
+
+        assert!(!CorePattern::from_system().unwrap().as_str().is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! Bindings to the kernel tunables in `/proc/sys`.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::util;
+
+pub mod abi;
+pub mod binfmt_misc;
+pub mod fs;
+pub mod kernel;
+pub mod net;
+pub mod user;
+#[cfg(feature = "write")]
+pub mod vm;
+
+/// A single kernel tunable in `/proc/sys`, for settings without a typed binding.
+///
+/// ```no_run
+/// use linux_proc::sys::Sysctl;
+///
+/// let swappiness: u32 = Sysctl::new("vm.swappiness")?.read()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sysctl {
+    path: PathBuf,
+}
+
+impl Sysctl {
+    /// The tunable called `name`, either as `sysctl(8)` writes it (`net.core.somaxconn`) or as a
+    /// path below `/proc/sys` (`net/core/somaxconn`).
+    ///
+    /// Use the path form for names containing dots, like `net/ipv4/conf/eth0.100/forwarding`.
+    /// Fails with `InvalidInput` if the name could point outside `/proc/sys`. Doesn't check the
+    /// tunable exists.
+    pub fn new(name: &str) -> io::Result<Sysctl> {
+        let separator = if name.contains('/') { '/' } else { '.' };
+        let mut path = PathBuf::from("/proc/sys");
+        for component in name.split(separator) {
+            if component.is_empty() || component == "." || component == ".." {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{:?} is not a sysctl name", name),
+                ));
+            }
+            path.push(component);
+        }
+        Ok(Sysctl { path })
+    }
+
+    /// The file backing the tunable.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the current value.
+    pub fn read<T: FromStr>(&self) -> io::Result<T> {
+        util::read_value(&self.path)
+    }
+
+    /// Set the value, like `sysctl -w`.
+    ///
+    /// The kernel checks the value itself: out-of-range values fail with `InvalidInput` and
+    /// missing privileges with `PermissionDenied`. The change doesn't survive a reboot.
+    #[cfg(feature = "write")]
+    pub fn write(&self, value: impl std::fmt::Display) -> io::Result<()> {
+        util::write_value(&self.path, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sysctl;
+    use std::{io, path::Path};
+
+    #[test]
+    fn sysctl() {
+        let sysctl = Sysctl::new("kernel.pid_max").unwrap();
+        assert_eq!(sysctl.path(), Path::new("/proc/sys/kernel/pid_max"));
+        assert!(sysctl.read::<u32>().unwrap() > 0);
+        assert_eq!(
+            Sysctl::new("net/ipv4/conf/eth0.100/forwarding")
+                .unwrap()
+                .path(),
+            Path::new("/proc/sys/net/ipv4/conf/eth0.100/forwarding")
+        );
+        for name in &["", "kernel..pid_max", "../../etc/passwd", "/kernel/pid_max"] {
+            let err = Sysctl::new(name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
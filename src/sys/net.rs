@@ -0,0 +1,196 @@
+//! Bindings to `/proc/sys/net`.
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+
+use crate::{util, Error};
+
+const ROOT: &str = "/proc/sys/net";
+
+/// The commonly tuned networking limits, from `/proc/sys/net/core` and `/proc/sys/net/ipv4`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NetTunables {
+    /// The maximum `listen` backlog, from `core/somaxconn`.
+    pub somaxconn: u64,
+    /// The maximum number of received packets queued per cpu before being dropped, from
+    /// `core/netdev_max_backlog`.
+    pub netdev_max_backlog: u64,
+    /// The maximum number of half-open connections per listening socket, from
+    /// `ipv4/tcp_max_syn_backlog`.
+    pub tcp_max_syn_backlog: u64,
+    /// The ports used for outgoing connections, from `ipv4/ip_local_port_range`.
+    pub ip_local_port_range: PortRange,
+    /// Whether sockets in `TIME_WAIT` can be reused for new outgoing connections, from
+    /// `ipv4/tcp_tw_reuse`.
+    pub tcp_tw_reuse: TcpTwReuse,
+}
+
+impl NetTunables {
+    /// Read the tunables from `/proc/sys/net`.
+    pub fn from_system() -> io::Result<Self> {
+        let path = |name: &str| Path::new(ROOT).join(name);
+        let read = |name: &str| util::read_value::<u64>(path(name));
+        Ok(NetTunables {
+            somaxconn: read("core/somaxconn")?,
+            netdev_max_backlog: read("core/netdev_max_backlog")?,
+            tcp_max_syn_backlog: read("ipv4/tcp_max_syn_backlog")?,
+            ip_local_port_range: ip_local_port_range()?,
+            tcp_tw_reuse: TcpTwReuse::from_value(read("ipv4/tcp_tw_reuse")?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        })
+    }
+}
+
+/// Read `ipv4/ip_local_port_range`, the ports used for outgoing connections.
+pub fn ip_local_port_range() -> io::Result<PortRange> {
+    let range = util::read_value::<String>(Path::new(ROOT).join("ipv4/ip_local_port_range"))?;
+    PortRange::from_str(&range).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Set `core/somaxconn`. At most `i32::MAX`.
+#[cfg(feature = "write")]
+pub fn set_somaxconn(value: u32) -> io::Result<()> {
+    if i32::try_from(value).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("somaxconn {} is out of range", value),
+        ));
+    }
+    util::write_value(Path::new(ROOT).join("core/somaxconn"), value)
+}
+
+/// Set `ipv4/ip_local_port_range`.
+///
+/// The range must be non-empty and can't include port 0. The kernel documentation recommends
+/// `low` and `high` have different parity (one even and one odd).
+#[cfg(feature = "write")]
+pub fn set_ip_local_port_range(range: PortRange) -> io::Result<()> {
+    if range.low == 0 || range.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}-{} is not a valid port range", range.low, range.high),
+        ));
+    }
+    util::write_value(
+        Path::new(ROOT).join("ipv4/ip_local_port_range"),
+        format!("{} {}", range.low, range.high),
+    )
+}
+
+/// Set `ipv4/tcp_tw_reuse`.
+#[cfg(feature = "write")]
+pub fn set_tcp_tw_reuse(value: TcpTwReuse) -> io::Result<()> {
+    util::write_value(Path::new(ROOT).join("ipv4/tcp_tw_reuse"), value.to_value())
+}
+
+/// An inclusive range of ports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PortRange {
+    pub low: u16,
+    pub high: u16,
+}
+
+impl PortRange {
+    /// The number of ports in the range.
+    pub fn len(&self) -> u32 {
+        (u32::from(self.high) + 1).saturating_sub(u32::from(self.low))
+    }
+
+    /// Whether the range contains no ports.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `port` is in the range.
+    pub fn contains(&self, port: u16) -> bool {
+        self.low <= port && port <= self.high
+    }
+
+    fn from_str(input: &str) -> Result<PortRange, Error> {
+        let port = |input| -> Result<(&str, u16), Error> {
+            let (input, port) = util::parse_u64(input).ok_or("port")?;
+            let port = u16::try_from(port).map_err(|_| "port out of range")?;
+            Ok((input, port))
+        };
+        let (input, low) = port(input)?;
+        let (_input, high) = port(input)?;
+        Ok(PortRange { low, high })
+    }
+}
+
+/// The `tcp_tw_reuse` setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TcpTwReuse {
+    Disabled,
+    Enabled,
+    /// Only for loopback traffic (since 4.19, and the default).
+    LoopbackOnly,
+}
+
+impl TcpTwReuse {
+    fn from_value(value: u64) -> Result<TcpTwReuse, Error> {
+        match value {
+            0 => Ok(TcpTwReuse::Disabled),
+            1 => Ok(TcpTwReuse::Enabled),
+            2 => Ok(TcpTwReuse::LoopbackOnly),
+            other => Err(format!("unknown tcp_tw_reuse value {}", other).into()),
+        }
+    }
+
+    #[cfg(feature = "write")]
+    fn to_value(self) -> u64 {
+        match self {
+            TcpTwReuse::Disabled => 0,
+            TcpTwReuse::Enabled => 1,
+            TcpTwReuse::LoopbackOnly => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetTunables, PortRange};
+
+    #[test]
+    fn port_range() {
+        let range = PortRange::from_str("32768\t60999\n").unwrap();
+        assert_eq!(
+            range,
+            PortRange {
+                low: 32768,
+                high: 60999
+            }
+        );
+        assert_eq!(range.len(), 28232);
+        assert!(range.contains(32768) && !range.contains(61000));
+        assert!(PortRange::from_str("1024 65536").is_err());
+    }
+
+    #[test]
+    fn net_tunables() {
+        let tunables = NetTunables::from_system().unwrap();
+        assert!(tunables.somaxconn > 0);
+        assert!(!tunables.ip_local_port_range.is_empty());
+        assert_eq!(
+            super::ip_local_port_range().unwrap(),
+            tunables.ip_local_port_range
+        );
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn rejects_invalid_tunables() {
+        use std::io;
+
+        let err = super::set_somaxconn(u32::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = super::set_ip_local_port_range(PortRange { low: 0, high: 100 }).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = super::set_ip_local_port_range(PortRange {
+            low: 2000,
+            high: 1000,
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,99 @@
+//! Bindings to `/proc/sys/user`, the limits on namespaces and watches per user.
+use std::io;
+use std::path::Path;
+
+use crate::util;
+
+const ROOT: &str = "/proc/sys/user";
+
+/// The limits on how many namespaces (and inotify and fanotify objects) each user can create,
+/// from `/proc/sys/user`.
+///
+/// The limits belong to the user namespace of the process reading them, and apply to each user
+/// in it and its descendants. Container runtimes need `max_user_namespaces` above zero for
+/// rootless containers. The files were added in Linux 4.9; those added later are `None` on
+/// older kernels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UserNamespaceLimits {
+    pub max_user_namespaces: u64,
+    pub max_pid_namespaces: u64,
+    pub max_net_namespaces: u64,
+    pub max_mnt_namespaces: u64,
+    pub max_ipc_namespaces: u64,
+    pub max_uts_namespaces: u64,
+    pub max_cgroup_namespaces: u64,
+    /// Added in Linux 5.6.
+    pub max_time_namespaces: Option<u64>,
+    /// Added in Linux 4.11. In the initial namespace this is the same setting as
+    /// `fs.inotify.max_user_instances`.
+    pub max_inotify_instances: Option<u64>,
+    /// Added in Linux 4.11.
+    pub max_inotify_watches: Option<u64>,
+    /// Added in Linux 5.13.
+    pub max_fanotify_groups: Option<u64>,
+    /// Added in Linux 5.13.
+    pub max_fanotify_marks: Option<u64>,
+}
+
+impl UserNamespaceLimits {
+    /// Read the limits from `/proc/sys/user`.
+    pub fn from_system() -> io::Result<Self> {
+        UserNamespaceLimits::from_dir(Path::new(ROOT))
+    }
+
+    fn from_dir(dir: &Path) -> io::Result<Self> {
+        let read = |name: &str| util::read_value::<u64>(dir.join(name));
+        let read_optional = |name: &str| match read(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        };
+        Ok(UserNamespaceLimits {
+            max_user_namespaces: read("max_user_namespaces")?,
+            max_pid_namespaces: read("max_pid_namespaces")?,
+            max_net_namespaces: read("max_net_namespaces")?,
+            max_mnt_namespaces: read("max_mnt_namespaces")?,
+            max_ipc_namespaces: read("max_ipc_namespaces")?,
+            max_uts_namespaces: read("max_uts_namespaces")?,
+            max_cgroup_namespaces: read("max_cgroup_namespaces")?,
+            max_time_namespaces: read_optional("max_time_namespaces")?,
+            max_inotify_instances: read_optional("max_inotify_instances")?,
+            max_inotify_watches: read_optional("max_inotify_watches")?,
+            max_fanotify_groups: read_optional("max_fanotify_groups")?,
+            max_fanotify_marks: read_optional("max_fanotify_marks")?,
+        })
+    }
+
+    /// Whether unprivileged users can create user namespaces at all, as far as this limit goes.
+    ///
+    /// Some distributions add their own switch, like Debian's
+    /// `kernel.unprivileged_userns_clone`, which this doesn't check.
+    pub fn user_namespaces_allowed(&self) -> bool {
+        self.max_user_namespaces > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UserNamespaceLimits;
+    use crate::util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn proc_sys_user() {
+        let dir = TempDir::new("user");
+        for name in &["user", "pid", "net", "mnt", "ipc", "uts", "cgroup"] {
+            fs::write(dir.join(format!("max_{}_namespaces", name)), "63239\n").unwrap();
+        }
+        fs::write(dir.join("max_user_namespaces"), "0\n").unwrap();
+        fs::write(dir.join("max_time_namespaces"), "100\n").unwrap();
+        let limits = UserNamespaceLimits::from_dir(&dir).unwrap();
+        assert_eq!(limits.max_pid_namespaces, 63239);
+        assert_eq!(limits.max_time_namespaces, Some(100));
+        assert_eq!(limits.max_fanotify_marks, None);
+        assert!(!limits.user_namespaces_allowed());
+
+        let limits = UserNamespaceLimits::from_system().unwrap();
+        assert!(limits.max_pid_namespaces > 0);
+    }
+}
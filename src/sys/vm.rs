@@ -0,0 +1,61 @@
+//! Bindings to `/proc/sys/vm`.
+//!
+//! Only available with the `write` feature.
+use std::io;
+
+use crate::util;
+
+/// Which caches `drop_caches` frees.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DropCachesMode {
+    /// Clean pages of the page cache.
+    PageCache,
+    /// Unused dentries and inodes (the slab objects caching directory lookups and file
+    /// metadata).
+    DentriesAndInodes,
+    /// Both of the above.
+    Both,
+}
+
+impl DropCachesMode {
+    fn value(self) -> u8 {
+        match self {
+            DropCachesMode::PageCache => 1,
+            DropCachesMode::DentriesAndInodes => 2,
+            DropCachesMode::Both => 3,
+        }
+    }
+}
+
+/// Write dirty data to disk with `sync(2)`, then free clean caches, like
+/// `sync; echo 3 > /proc/sys/vm/drop_caches`.
+///
+/// Requires root. This is meant for benchmarking cold-cache performance: it doesn't free memory
+/// that the kernel wouldn't reclaim by itself, and every process on the machine is slower
+/// afterwards while the caches refill. Running it in production to "free memory" is almost
+/// always a mistake.
+pub fn drop_caches(mode: DropCachesMode) -> io::Result<()> {
+    // Safety: sync has no preconditions.
+    unsafe { libc::sync() };
+    drop_caches_without_sync(mode)
+}
+
+/// Free clean caches without syncing first.
+///
+/// Dirty pages can't be dropped, so without a sync some of the cache may survive. Use this when
+/// the caller has already synced, or only cares about the dentry and inode caches.
+pub fn drop_caches_without_sync(mode: DropCachesMode) -> io::Result<()> {
+    util::write_value("/proc/sys/vm/drop_caches", mode.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DropCachesMode;
+
+    #[test]
+    fn drop_caches_mode() {
+        assert_eq!(DropCachesMode::PageCache.value(), 1);
+        assert_eq!(DropCachesMode::DentriesAndInodes.value(), 2);
+        assert_eq!(DropCachesMode::Both.value(), 3);
+    }
+}
@@ -0,0 +1,48 @@
+//! Triggering magic SysRq functions through `/proc/sysrq-trigger`.
+//!
+//! Only available with the `write` feature. These commands take effect immediately: they can
+//! kill processes, remount filesystems read-only, or crash or reboot the machine without syncing.
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+/// Confirmation that the caller knows what a SysRq command does.
+///
+/// There is deliberately no shorter way to construct this, so every call to `trigger` is
+/// obvious when reading the code.
+#[derive(Debug, Copy, Clone)]
+pub struct Confirm(());
+
+impl Confirm {
+    /// I understand the command runs immediately and may crash, hang or reboot the machine.
+    pub fn i_understand_this_may_crash_or_reboot_the_machine() -> Confirm {
+        Confirm(())
+    }
+}
+
+/// Run the SysRq command `command`, e.g. `'s'` to sync filesystems.
+///
+/// Requires root (or `CAP_SYS_ADMIN`). Unlike the keyboard, this isn't restricted by
+/// `sys::kernel::SysRqMask`.
+pub fn trigger(command: char, _confirm: Confirm) -> io::Result<()> {
+    if !command.is_ascii_alphanumeric() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} is not a sysrq command", command),
+        ));
+    }
+    let mut file = OpenOptions::new().write(true).open("/proc/sysrq-trigger")?;
+    file.write_all(&[command as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trigger, Confirm};
+    use std::io;
+
+    #[test]
+    fn rejects_non_commands() {
+        let confirm = Confirm::i_understand_this_may_crash_or_reboot_the_machine();
+        let err = trigger('\n', confirm).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,55 @@
+//! The standard "host facts", gathered from several files in `/proc`.
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    stat::{Stat, StatOptions},
+    sys::kernel::KernelIdentity,
+    uptime::Uptime,
+};
+
+/// Identity and uptime information about the host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SystemInfo {
+    /// Hostname and kernel version.
+    pub kernel: KernelIdentity,
+    /// How long the system has been up for.
+    pub uptime: Duration,
+    /// When the system booted.
+    pub boot_time: SystemTime,
+}
+
+impl SystemInfo {
+    /// Gather the information from `/proc/sys/kernel`, `/proc/uptime` and `/proc/stat`.
+    pub fn from_system() -> io::Result<Self> {
+        // We only need `btime`.
+        let options = StatOptions::new()
+            .per_cpu(false)
+            .interrupts(false)
+            .softirqs(false);
+        let stat = Stat::parse_with(options)?;
+        Ok(SystemInfo {
+            kernel: KernelIdentity::from_system()?,
+            uptime: Uptime::from_system()?.up,
+            boot_time: UNIX_EPOCH + Duration::from_secs(stat.boot_time),
+        })
+    }
+}
+
+/// Gather identity and uptime information about the host.
+///
+/// Shorthand for `SystemInfo::from_system()`.
+pub fn system_info() -> io::Result<SystemInfo> {
+    SystemInfo::from_system()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    #[test]
+    fn system_info() {
+        let info = super::system_info().unwrap();
+        assert!(info.boot_time < SystemTime::now());
+    }
+}
@@ -0,0 +1,254 @@
+//! A corpus of real `/proc` files captured from several kernel versions and architectures.
+//!
+//! Parsers that work on this crate's output, or that read `/proc` files themselves, can use
+//! the corpus to check they cope with the differences between kernels. It is available in this
+//! crate's tests, and to other crates with the `testdata` feature.
+//!
+//! Some files the crate parses aren't in the corpus: `kallsyms` and `slabinfo` are too large
+//! or need root, `latency_stats` needs `CONFIG_LATENCYTOP`, a cgroup's `memory.stat` isn't
+//! under `/proc`, and ext4's `mb_groups`, `net/nf_conntrack`, `net/bonding`, `net/vlan`,
+//! `net/xfrm_stat` and `net/rpc/nfs{,d}` depend on the hardware, modules and services of the
+//! machine rather than the kernel version.
+//!
+//! ```
+//! use linux_proc::{fuzz, testdata};
+//!
+//! let failures = testdata::check("/proc/loadavg", fuzz::parse_loadavg);
+//! assert!(failures.is_empty(), "{:?}", failures);
+//! ```
+use std::io;
+
+/// The files captured from one machine.
+#[derive(Debug, Clone, Copy)]
+pub struct Capture {
+    /// The kernel version, e.g. `"5.10"`.
+    pub kernel: &'static str,
+    /// The architecture, as in `uname -m`.
+    pub arch: &'static str,
+    files: &'static [(&'static str, &'static str)],
+}
+
+impl Capture {
+    /// The captured files as `(path, contents)` pairs, where the path is where the file was
+    /// read from, e.g. `"/proc/self/status"`.
+    pub fn files(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.files.iter().copied()
+    }
+
+    /// The contents of the file captured from `path`, if there is one.
+    pub fn file(&self, path: &str) -> Option<&'static str> {
+        self.files
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, contents)| *contents)
+    }
+}
+
+macro_rules! capture {
+    ($kernel:expr, $arch:expr, [$($path:expr),* $(,)?]) => {
+        Capture {
+            kernel: $kernel,
+            arch: $arch,
+            files: &[$(
+                ($path, include_str!(concat!("../testdata/", $kernel, "-", $arch, $path))),
+            )*],
+        }
+    };
+}
+
+macro_rules! captures {
+    ($(($kernel:expr, $arch:expr, [$($extra:expr),* $(,)?])),* $(,)?) => {
+        &[$(
+            capture!($kernel, $arch, [
+                "/proc/stat",
+                "/proc/diskstats",
+                "/proc/loadavg",
+                "/proc/uptime",
+                "/proc/swaps",
+                "/proc/meminfo",
+                "/proc/schedstat",
+                "/proc/cgroups",
+                "/proc/execdomains",
+                "/proc/net/dev",
+                "/proc/net/tcp",
+                "/proc/net/udp",
+                "/proc/net/raw",
+                "/proc/net/icmp",
+                "/proc/net/igmp",
+                "/proc/net/igmp6",
+                "/proc/net/packet",
+                "/proc/bus/input/devices",
+                "/proc/self/stat",
+                "/proc/self/status",
+                "/proc/self/mountinfo",
+                "/proc/self/mountstats",
+                "/proc/self/io",
+                "/proc/self/limits",
+                "/proc/self/sched",
+                "/proc/self/maps",
+                "/proc/self/cgroup",
+                "/proc/self/uid_map",
+                $($extra,)*
+            ]),
+        )*]
+    };
+}
+
+// Beyond the files every machine has, the x86 ones have MTRRs, a PCI bus and NUMA, and
+// pressure stall information only exists from 4.20. The 6.18 machine has no RTC driver.
+static CAPTURES: &[Capture] = captures![
+    (
+        "4.4",
+        "x86_64",
+        [
+            "/proc/mtrr",
+            "/proc/bus/pci/devices",
+            "/proc/driver/rtc",
+            "/proc/self/numa_maps",
+        ]
+    ),
+    ("4.19", "aarch64", []),
+    (
+        "5.10",
+        "x86_64",
+        [
+            "/proc/mtrr",
+            "/proc/bus/pci/devices",
+            "/proc/driver/rtc",
+            "/proc/self/numa_maps",
+            "/proc/pressure/cpu",
+            "/proc/pressure/memory",
+            "/proc/pressure/io",
+        ]
+    ),
+    (
+        "6.18",
+        "x86_64",
+        [
+            "/proc/mtrr",
+            "/proc/bus/pci/devices",
+            "/proc/self/numa_maps",
+            "/proc/pressure/cpu",
+            "/proc/pressure/memory",
+            "/proc/pressure/io",
+        ]
+    ),
+];
+
+/// Every capture in the corpus, oldest kernel first.
+pub fn captures() -> &'static [Capture] {
+    CAPTURES
+}
+
+/// A captured file that a parser rejected.
+#[derive(Debug)]
+pub struct Failure {
+    pub kernel: &'static str,
+    pub arch: &'static str,
+    pub path: &'static str,
+    pub error: io::Error,
+}
+
+/// Run `parser` over the file captured from `path` in every capture that has one, returning
+/// the captures it failed on.
+pub fn check<T>(path: &str, parser: impl Fn(&str) -> io::Result<T>) -> Vec<Failure> {
+    captures()
+        .iter()
+        .filter_map(|capture| {
+            let (path, contents) = capture.files.iter().find(|(p, _)| *p == path)?;
+            parser(contents).err().map(|error| Failure {
+                kernel: capture.kernel,
+                arch: capture.arch,
+                path,
+                error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{captures, check, Failure};
+    use crate::fuzz;
+
+    /// Checks every capture of the given path with the matching parser.
+    type Checker = fn(&str) -> Vec<Failure>;
+
+    const PARSERS: &[(&str, Checker)] = &[
+        ("/proc/stat", |p| check(p, fuzz::parse_stat)),
+        ("/proc/diskstats", |p| check(p, fuzz::parse_diskstats)),
+        ("/proc/loadavg", |p| check(p, fuzz::parse_loadavg)),
+        ("/proc/uptime", |p| check(p, fuzz::parse_uptime)),
+        ("/proc/swaps", |p| check(p, fuzz::parse_swaps)),
+        ("/proc/meminfo", |p| check(p, fuzz::parse_meminfo)),
+        ("/proc/schedstat", |p| check(p, fuzz::parse_schedstat)),
+        ("/proc/cgroups", |p| check(p, fuzz::parse_cgroups)),
+        ("/proc/execdomains", |p| check(p, fuzz::parse_execdomains)),
+        ("/proc/mtrr", |p| check(p, fuzz::parse_mtrr)),
+        ("/proc/net/dev", |p| check(p, fuzz::parse_net_dev)),
+        ("/proc/net/tcp", |p| check(p, fuzz::parse_tcp)),
+        ("/proc/net/udp", |p| check(p, fuzz::parse_udp)),
+        ("/proc/net/raw", |p| check(p, fuzz::parse_raw)),
+        ("/proc/net/icmp", |p| check(p, fuzz::parse_icmp)),
+        ("/proc/net/igmp", |p| check(p, fuzz::parse_igmp)),
+        ("/proc/net/igmp6", |p| check(p, fuzz::parse_igmp6)),
+        ("/proc/net/packet", |p| check(p, fuzz::parse_packet)),
+        ("/proc/bus/input/devices", |p| {
+            check(p, fuzz::parse_input_devices)
+        }),
+        ("/proc/bus/pci/devices", |p| {
+            check(p, fuzz::parse_pci_devices)
+        }),
+        ("/proc/driver/rtc", |p| check(p, fuzz::parse_rtc)),
+        ("/proc/pressure/cpu", |p| check(p, fuzz::parse_pressure)),
+        ("/proc/pressure/memory", |p| check(p, fuzz::parse_pressure)),
+        ("/proc/pressure/io", |p| check(p, fuzz::parse_pressure)),
+        ("/proc/self/stat", |p| check(p, fuzz::parse_process_stat)),
+        ("/proc/self/status", |p| {
+            check(p, fuzz::parse_process_status)
+        }),
+        ("/proc/self/mountinfo", |p| check(p, fuzz::parse_mounts)),
+        ("/proc/self/mountstats", |p| {
+            check(p, fuzz::parse_mountstats)
+        }),
+        ("/proc/self/io", |p| check(p, fuzz::parse_process_io)),
+        ("/proc/self/limits", |p| check(p, fuzz::parse_limits)),
+        ("/proc/self/sched", |p| check(p, fuzz::parse_process_sched)),
+        ("/proc/self/maps", |p| check(p, fuzz::parse_maps)),
+        ("/proc/self/numa_maps", |p| check(p, fuzz::parse_numa_maps)),
+        ("/proc/self/cgroup", |p| {
+            check(p, fuzz::parse_process_cgroup)
+        }),
+        ("/proc/self/uid_map", |p| check(p, fuzz::parse_id_map)),
+    ];
+
+    #[test]
+    fn every_capture_parses() {
+        let failures: Vec<Failure> = PARSERS.iter().flat_map(|(path, run)| run(path)).collect();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+
+    #[test]
+    fn every_file_has_a_parser() {
+        for capture in captures() {
+            for (path, _) in capture.files() {
+                assert!(
+                    PARSERS.iter().any(|(p, _)| *p == path),
+                    "no parser for {}",
+                    path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_captures() {
+        for capture in captures() {
+            for (_, contents) in capture.files() {
+                for (idx, _) in contents.match_indices('\n') {
+                    fuzz::parse_all(&contents.as_bytes()[..idx]);
+                }
+            }
+        }
+    }
+}
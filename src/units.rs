@@ -0,0 +1,243 @@
+//! Newtypes for the units `/proc` counts in.
+//!
+//! Many files print bare numbers whose unit depends on the file: cpu times are in clock ticks,
+//! disk I/O in 512-byte sectors, and memory in pages or kB. Wrapping them stops a sector count
+//! being mistaken for a byte count; convert them with the methods on each type.
+use std::{
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use crate::{util, ByteSize};
+
+macro_rules! counter {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+        pub struct $name(pub u64);
+
+        impl $name {
+            /// The raw count.
+            pub fn get(self) -> u64 {
+                self.0
+            }
+
+            /// `self - other`, or zero if `other` is larger (e.g. a counter was reset).
+            pub fn saturating_sub(self, other: Self) -> Self {
+                $name(self.0.saturating_sub(other.0))
+            }
+
+            /// `self + other`, or `u64::MAX` on overflow.
+            pub fn saturating_add(self, other: Self) -> Self {
+                $name(self.0.saturating_add(other.0))
+            }
+
+            /// `self + other`, or `None` on overflow.
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map($name)
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, other: $name) -> $name {
+                $name(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 - other.0)
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(count: u64) -> $name {
+                $name(count)
+            }
+        }
+    };
+}
+
+counter! {
+    /// A time in clock ticks (`USER_HZ`, see `process::clock_ticks_per_sec`), as in `/proc/stat`.
+    Jiffies
+}
+
+impl Jiffies {
+    /// The time as a `Duration`, given the number of clock ticks per second. `None` if
+    /// `ticks_per_sec` is 0.
+    pub fn to_duration(self, ticks_per_sec: u64) -> Option<Duration> {
+        util::ticks_to_duration(self.0, ticks_per_sec)
+    }
+}
+
+counter! {
+    /// A number of 512-byte sectors, as in `/proc/diskstats`.
+    ///
+    /// The kernel always counts in 512-byte units here, whatever the device's real sector size.
+    Sectors
+}
+
+impl Sectors {
+    /// The size of a sector in bytes.
+    pub const SIZE: u64 = 512;
+
+    /// The size in bytes, saturating at `u64::MAX`.
+    pub fn bytes(self) -> ByteSize {
+        ByteSize(self.0.saturating_mul(Self::SIZE))
+    }
+}
+
+counter! {
+    /// A number of memory pages, as in `/proc/[pid]/stat`.
+    Pages
+}
+
+impl Pages {
+    /// The size in bytes, given the page size (see `process::page_size`), saturating at
+    /// `u64::MAX`.
+    pub fn bytes(self, page_size: u64) -> ByteSize {
+        ByteSize(self.0.saturating_mul(page_size))
+    }
+}
+
+counter! {
+    /// A size in kB (1024 bytes), the unit of files like `/proc/meminfo` and `/proc/swaps`.
+    Kibibytes
+}
+
+impl Kibibytes {
+    /// The size in bytes, saturating at `u64::MAX`.
+    pub fn bytes(self) -> ByteSize {
+        ByteSize::from_kib(self.0)
+    }
+}
+
+impl From<Kibibytes> for ByteSize {
+    fn from(kib: Kibibytes) -> ByteSize {
+        kib.bytes()
+    }
+}
+
+/// The unit a proportion is expressed in.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum RatioUnit {
+    /// From 0 to 1.
+    #[default]
+    Fraction,
+    /// From 0 to 100.
+    Percent,
+    /// From 0 to 1000.
+    Permille,
+}
+
+impl RatioUnit {
+    /// What a fraction is multiplied by to express it in this unit.
+    pub fn factor(self) -> f64 {
+        match self {
+            RatioUnit::Fraction => 1.0,
+            RatioUnit::Percent => 100.0,
+            RatioUnit::Permille => 1000.0,
+        }
+    }
+
+    /// `fraction` in thousandths of this unit, rounded to the nearest integer, for exporters that
+    /// avoid floats. So 0.25 is 250 milli-fractions, 25000 milli-percent or 250000
+    /// milli-permille. Negative or NaN fractions give 0.
+    pub fn milli(self, fraction: f64) -> u64 {
+        (fraction * self.factor() * 1000.0).round() as u64
+    }
+}
+
+/// How the usage helpers (like `CpuUsage::busy_in` and `DiskStatDelta::utilization_in`)
+/// express a proportion: the unit, and optionally how many decimal places to round to.
+///
+/// The default is an unrounded fraction, which is what the plain helpers return.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RatioFormat {
+    unit: RatioUnit,
+    decimals: Option<u32>,
+}
+
+impl RatioFormat {
+    /// Unrounded fractions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unrounded percentages.
+    pub fn percent() -> Self {
+        RatioFormat::new().unit(RatioUnit::Percent)
+    }
+
+    /// The unit to express proportions in.
+    pub fn unit(mut self, unit: RatioUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// The number of decimal places to round to, or `None` not to round.
+    pub fn decimals(mut self, decimals: Option<u32>) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Express `fraction` (from 0 to 1) in this format.
+    pub fn apply(self, fraction: f64) -> f64 {
+        let value = fraction * self.unit.factor();
+        match self.decimals {
+            Some(decimals) => {
+                let scale = 10f64.powi(decimals.min(i32::MAX as u32) as i32);
+                (value * scale).round() / scale
+            }
+            None => value,
+        }
+    }
+
+    /// `fraction` in thousandths of this format's unit, ignoring `decimals` (see
+    /// `RatioUnit::milli`).
+    pub fn milli(self, fraction: f64) -> u64 {
+        self.unit.milli(fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Jiffies, Kibibytes, Pages, RatioFormat, RatioUnit, Sectors};
+    use crate::ByteSize;
+    use std::time::Duration;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            Jiffies(250).to_duration(100),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(Jiffies(250).to_duration(0), None);
+        assert_eq!(Sectors(8).bytes(), ByteSize(4096));
+        assert_eq!(Sectors(u64::MAX).bytes(), ByteSize(u64::MAX));
+        assert_eq!(Pages(3).bytes(4096), ByteSize(12288));
+        assert_eq!(ByteSize::from(Kibibytes(2)), ByteSize(2048));
+        assert_eq!(Sectors(1).saturating_sub(Sectors(2)), Sectors(0));
+        assert_eq!(Jiffies(1) + Jiffies(2), Jiffies(3));
+    }
+
+    #[test]
+    fn ratio_format() {
+        assert_eq!(RatioFormat::new().apply(0.123456), 0.123456);
+        assert_eq!(RatioFormat::percent().apply(0.5), 50.0);
+        let format = RatioFormat::percent().decimals(Some(1));
+        assert_eq!(format.apply(0.123456), 12.3);
+        assert_eq!(format.milli(0.123456), 12346);
+        let format = RatioFormat::new()
+            .unit(RatioUnit::Permille)
+            .decimals(Some(0));
+        assert_eq!(format.apply(0.12345), 123.0);
+        assert_eq!(RatioUnit::Fraction.milli(0.25), 250);
+        assert_eq!(RatioUnit::Percent.milli(-0.5), 0);
+        assert_eq!(RatioUnit::Permille.milli(f64::NAN), 0);
+    }
+}
@@ -1,10 +1,13 @@
 //! Bindings to `/proc/uptime`.
+use std::fmt;
 use std::fs::File;
-use std::io;
+use std::io::{self, Read};
 use std::time::Duration;
 
-use crate::{util, Error};
+use crate::{render::Seconds, util, Error};
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Uptime {
     /// The time the system has been up for.
     pub up: Duration,
@@ -13,6 +16,28 @@ pub struct Uptime {
     pub idle: Duration,
 }
 
+impl fmt::Display for Uptime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.up.as_secs();
+        let days = secs / 86400;
+        f.write_str("up ")?;
+        match days {
+            0 => (),
+            1 => f.write_str("1 day, ")?,
+            days => write!(f, "{} days, ", days)?,
+        }
+        write!(
+            f,
+            "{:02}:{:02}:{:02} ({}), idle {}",
+            secs % 86400 / 3600,
+            secs % 3600 / 60,
+            secs % 60,
+            Seconds(self.up),
+            Seconds(self.idle)
+        )
+    }
+}
+
 impl Uptime {
     const PATH: &'static str = "/proc/uptime";
     /// Parse the contents of `/proc/uptime`.
@@ -20,22 +45,54 @@ impl Uptime {
         Uptime::from_reader(File::open(Self::PATH)?)
     }
 
+    /// Re-read `/proc/uptime` into `self`.
+    ///
+    /// The file is read into a buffer on the stack, so this doesn't allocate.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        let mut file = File::open(Self::PATH)?;
+        let mut buf = [0u8; 128];
+        let mut len = 0;
+        loop {
+            if len == buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "/proc/uptime is too long",
+                ));
+            }
+            match file.read(&mut buf[len..])? {
+                0 => break,
+                read => len += read,
+            }
+        }
+        let input = std::str::from_utf8(&buf[..len])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        *self =
+            Uptime::from_str(input).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(())
+    }
+
     pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
         let mut reader = util::LineParser::new(reader);
         let uptime = reader.parse_line(Self::from_str)?;
         Ok(uptime)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> Result<Self, Error> {
         let (input, up_secs) = util::parse_u64(input).ok_or("expected number")?;
         let input = util::expect_bytes(".", input).ok_or("expected \".\"")?;
-        let (input, up_nanos) = util::parse_nanos(input).ok_or("expected number")?;
+        let (input, up_centis) = util::parse_fraction(input, 100).ok_or("expected number")?;
         let (input, idle_secs) = util::parse_u64(input).ok_or("expected number")?;
         let input = util::expect_bytes(".", input).ok_or("expected \".\"")?;
-        let (_input, idle_nanos) = util::parse_nanos(input).ok_or("expected number")?;
+        let (_input, idle_centis) = util::parse_fraction(input, 100).ok_or("expected number")?;
+        let duration = |secs, centis: u64| {
+            Duration::from_secs(secs)
+                .checked_add(Duration::from_millis(centis * 10))
+                .ok_or("duration overflows")
+        };
         Ok(Uptime {
-            up: Duration::new(up_secs, up_nanos),
-            idle: Duration::new(idle_secs, idle_nanos),
+            up: duration(up_secs, up_centis)?,
+            idle: duration(idle_secs, idle_centis)?,
         })
     }
 }
@@ -44,12 +101,23 @@ impl Uptime {
 mod tests {
     use super::Uptime;
     use std::io;
+    use std::time::Duration;
 
     #[test]
     fn proc_uptime() {
         let raw = "\
             1640919.14 2328903.47
 ";
-        let _stat = Uptime::from_reader(io::Cursor::new(raw)).unwrap();
+        let uptime = Uptime::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(uptime.up, Duration::from_millis(1_640_919_140));
+        assert_eq!(uptime.idle, Duration::from_millis(2_328_903_470));
+    }
+
+    #[test]
+    fn refresh() {
+        let mut uptime = Uptime::from_system().unwrap();
+        let before = uptime.up;
+        uptime.refresh().unwrap();
+        assert!(uptime.up >= before);
     }
 }
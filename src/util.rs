@@ -1,64 +1,90 @@
-use crate::Error;
-use std::{self, io}; // todo use `!`.
-
-/// A helper to facilitate paring line by line while reusing a string buffer.
-pub struct LineParser<R> {
-    reader: io::BufReader<R>,
-    buffer: String,
-}
-
-impl<R> LineParser<R>
-where
-    R: io::Read,
-{
-    pub fn new(reader: R) -> LineParser<R> {
-        LineParser {
-            reader: io::BufReader::new(reader),
-            buffer: String::with_capacity(100),
-        }
-    }
+pub use crate::parse::{LineParser, TableParser, TableRow};
+use crate::{ByteSize, Error};
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{self, fs, io}; // todo use `!`.
 
-    /// If the parse fails, the line is available for trying different parsers.
-    pub fn parse_line<F, E, Val>(&mut self, parser: F) -> io::Result<Val>
-    where
-        F: Fn(&str) -> Result<Val, E>,
-        E: std::error::Error + Send + Sync + 'static,
-    {
-        // Only fetch next line if we consumed the previous
-        if self.buffer.is_empty() {
-            let read = io::BufRead::read_line(&mut self.reader, &mut self.buffer)?;
-            if read == 0 {
-                return Err(io::ErrorKind::UnexpectedEof.into());
-            }
-        }
-        let parsed = parser(&self.buffer)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, Box::new(e)))?;
-        // we've succeeded so clear the buffer.
-        self.buffer.clear();
-        Ok(parsed)
-    }
+/// Read a file containing a single value, like most files in `/proc/sys`.
+pub fn read_value<T: FromStr>(path: impl AsRef<Path>) -> io::Result<T> {
+    let path = path.as_ref();
+    fs::read_to_string(path)?.trim().parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cannot parse contents of {}", path.display()),
+        )
+    })
+}
+
+/// Write a single value to a file in `/proc`, like `echo value > path`.
+///
+/// The file must already exist. Errors say which file was being written, since the kernel's own
+/// errors (`EACCES`, `EINVAL`) don't.
+#[cfg(feature = "write")]
+pub fn write_value(path: impl AsRef<Path>, value: impl std::fmt::Display) -> io::Result<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let value = value.to_string();
+    let context = |e: io::Error| match e.kind() {
+        io::ErrorKind::PermissionDenied => io::Error::new(
+            e.kind(),
+            format!(
+                "permission denied writing {} (usually requires root)",
+                path.display()
+            ),
+        ),
+        io::ErrorKind::InvalidInput => io::Error::new(
+            e.kind(),
+            format!("the kernel rejected {:?} for {}", value, path.display()),
+        ),
+        _ => e,
+    };
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(context)?;
+    // The kernel parses each `write` call separately, so the value must go in one call.
+    file.write_all(value.as_bytes()).map_err(context)
 }
 
+/// The error for a `/proc` file that doesn't exist on the architecture we were compiled for.
+#[cfg_attr(any(target_arch = "x86", target_arch = "x86_64"), allow(dead_code))]
+pub fn unsupported_arch(path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} is not available on {}", path, std::env::consts::ARCH),
+    )
+}
+
+/// Parses an unsigned decimal integer, consuming any space before it.
+///
+/// This works on bytes rather than `char`s since digits are always ASCII. Numbers too big for a
+/// `u64` fail to parse rather than wrapping.
 pub fn parse_u64(input: &str) -> Option<(&str, u64)> {
     let input = consume_space(input);
-    let mut chars = input.chars();
-    let (mut next_idx, mut acc) = match chars.next() {
-        Some(ch) => match ch.to_digit(10) {
-            Some(val) => (ch.len_utf8(), val as u64),
-            None => return None,
-        },
-        None => return None,
-    };
-    for ch in chars {
-        match ch.to_digit(10) {
-            Some(val) => {
-                acc = acc * 10 + val as u64;
-                next_idx += ch.len_utf8();
-            }
-            None => break,
+    let bytes = input.as_bytes();
+    let mut acc = 0u64;
+    let mut idx = 0;
+    while let Some(&byte) = bytes.get(idx) {
+        let digit = byte.wrapping_sub(b'0');
+        if digit > 9 {
+            break;
         }
+        acc = acc.checked_mul(10)?.checked_add(u64::from(digit))?;
+        idx += 1;
+    }
+    if idx == 0 {
+        return None;
     }
-    Some((&input[next_idx..], acc))
+    Some((&input[idx..], acc))
+}
+
+/// Narrows a parsed number to a `u32`, failing with "`name` out of range" rather than wrapping.
+pub fn to_u32(value: u64, name: &str) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| Error::from(format!("{} out of range", name)))
 }
 
 #[test]
@@ -71,15 +97,254 @@ fn test_parse_u64() {
     assert_eq!(parse_u64(" 12"), Some(("", 12)));
     assert_eq!(parse_u64("a 12"), None);
     assert_eq!(parse_u64(" 12a"), Some(("a", 12)));
+    assert_eq!(parse_u64("\t12\n"), Some(("\n", 12)));
+    assert_eq!(parse_u64("١٢"), None);
+    assert_eq!(parse_u64("18446744073709551615"), Some(("", u64::MAX)));
+    assert_eq!(parse_u64("18446744073709551616"), None);
+    assert_eq!(parse_u64("99999999999999999999999"), None);
 }
 
-pub fn consume_space(input: &str) -> &str {
-    for (idx, ch) in input.char_indices() {
-        if !ch.is_whitespace() && ch != '\n' && ch != '\r' {
-            return &input[idx..];
+/// Parses a signed decimal integer, consuming any space before it.
+pub fn parse_i64(input: &str) -> Option<(&str, i64)> {
+    let input = consume_space(input);
+    match input.strip_prefix('-') {
+        Some(rest) => {
+            // don't allow space between the sign and the number.
+            if rest.starts_with(|ch: char| ch.is_ascii_whitespace()) {
+                return None;
+            }
+            let (rest, value) = parse_u64(rest)?;
+            Some((rest, 0i64.checked_sub_unsigned(value)?))
+        }
+        None => {
+            let (rest, value) = parse_u64(input)?;
+            Some((rest, i64::try_from(value).ok()?))
         }
     }
-    return &input[input.len()..];
+}
+
+#[test]
+fn test_parse_i64() {
+    assert_eq!(parse_i64(""), None);
+    assert_eq!(parse_i64("-"), None);
+    assert_eq!(parse_i64("- 1"), None);
+    assert_eq!(parse_i64(" -1 "), Some((" ", -1)));
+    assert_eq!(parse_i64("12"), Some(("", 12)));
+    assert_eq!(parse_i64("-9223372036854775808"), Some(("", i64::MIN)));
+    assert_eq!(parse_i64("9223372036854775808"), None);
+}
+
+/// Parses a decimal number like `-12.34`, consuming any space before it.
+///
+/// The decimal separator is always `.` whatever the locale. Exponents, `inf` and `nan` are
+/// rejected since the kernel never prints them, so seeing one means the input isn't what we
+/// expect.
+pub fn parse_f64(input: &str) -> Option<(&str, f64)> {
+    let input = consume_space(input);
+    let bytes = input.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+    let mut idx = usize::from(bytes.first() == Some(&b'-'));
+    let whole = digits(idx);
+    if whole == 0 {
+        return None;
+    }
+    idx += whole;
+    if bytes.get(idx) == Some(&b'.') {
+        let fraction = digits(idx + 1);
+        if fraction > 0 {
+            idx += 1 + fraction;
+        }
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(idx) {
+        return None;
+    }
+    let value = input[..idx].parse().ok()?;
+    Some((&input[idx..], value))
+}
+
+#[test]
+fn test_parse_f64() {
+    assert_eq!(parse_f64(""), None);
+    assert_eq!(parse_f64("-"), None);
+    assert_eq!(parse_f64(".5"), None);
+    assert_eq!(parse_f64(" 0.20 "), Some((" ", 0.2)));
+    assert_eq!(parse_f64("-1.5"), Some(("", -1.5)));
+    assert_eq!(parse_f64("12"), Some(("", 12.0)));
+    assert_eq!(parse_f64("12."), Some((".", 12.0)));
+    assert_eq!(parse_f64("1,5"), Some((",5", 1.0)));
+    assert_eq!(parse_f64("1e5"), None);
+    assert_eq!(parse_f64("1.5E-3"), None);
+    assert_eq!(parse_f64("inf"), None);
+    assert_eq!(parse_f64("nan"), None);
+}
+
+/// Parses an unsigned hexadecimal integer (without a `0x` prefix), consuming any space before
+/// it.
+pub fn parse_hex_u64(input: &str) -> Option<(&str, u64)> {
+    let input = consume_space(input);
+    let bytes = input.as_bytes();
+    let mut acc = 0u64;
+    let mut idx = 0;
+    while let Some(digit) = bytes.get(idx).and_then(|&byte| hex_digit(byte)) {
+        acc = acc.checked_mul(16)?.checked_add(u64::from(digit))?;
+        idx += 1;
+    }
+    if idx == 0 {
+        return None;
+    }
+    Some((&input[idx..], acc))
+}
+
+#[test]
+fn test_parse_hex_u64() {
+    assert_eq!(parse_hex_u64(""), None);
+    assert_eq!(parse_hex_u64("x1"), None);
+    assert_eq!(parse_hex_u64(" 0035:"), Some((":", 0x35)));
+    assert_eq!(parse_hex_u64("deadBEEF"), Some(("", 0xdead_beef)));
+    assert_eq!(parse_hex_u64("ffffffffffffffff"), Some(("", u64::MAX)));
+    assert_eq!(parse_hex_u64("10000000000000000"), None);
+}
+
+/// Parses exactly `2 * N` hex digits into `N` bytes, in the order they are written, consuming
+/// any space before them.
+///
+/// Fails if there are fewer digits, but doesn't check what follows them, so fixed-width fields
+/// can be split out of longer runs of digits.
+pub fn parse_hex_bytes<const N: usize>(input: &str) -> Option<(&str, [u8; N])> {
+    let input = consume_space(input);
+    let digits = input.as_bytes().get(..2 * N)?;
+    let mut out = [0u8; N];
+    for (byte, pair) in out.iter_mut().zip(digits.chunks_exact(2)) {
+        *byte = hex_digit(pair[0])? << 4 | hex_digit(pair[1])?;
+    }
+    Some((&input[2 * N..], out))
+}
+
+#[test]
+fn test_parse_hex_bytes() {
+    assert_eq!(parse_hex_bytes::<2>("abc"), None);
+    assert_eq!(parse_hex_bytes::<2>("ab-d"), None);
+    assert_eq!(parse_hex_bytes::<2>(" 0A0b12"), Some(("12", [0x0a, 0x0b])));
+    assert_eq!(parse_hex_bytes::<0>("zz"), Some(("zz", [])));
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Parses an IPv4 address printed as 8 hex digits, like `0100007F` for `127.0.0.1` in
+/// `/proc/net/tcp`.
+///
+/// The kernel prints the address as a native-endian `u32` holding the network-order bytes, so
+/// the digits are reversed on little-endian machines.
+pub fn parse_hex_ipv4(input: &str) -> Option<(&str, Ipv4Addr)> {
+    let (input, word) = parse_hex_bytes::<4>(input)?;
+    Some((input, Ipv4Addr::from(kernel_word(word))))
+}
+
+/// Parses an IPv6 address printed as 32 hex digits, as four native-endian 32-bit words like
+/// `parse_hex_ipv4`.
+pub fn parse_hex_ipv6(input: &str) -> Option<(&str, Ipv6Addr)> {
+    let (input, words) = parse_hex_bytes::<16>(input)?;
+    let mut octets = [0u8; 16];
+    for (out, word) in octets.chunks_exact_mut(4).zip(words.chunks_exact(4)) {
+        out.copy_from_slice(&kernel_word([word[0], word[1], word[2], word[3]]));
+    }
+    Some((input, Ipv6Addr::from(octets)))
+}
+
+/// Convert the digits of a 32-bit word printed by the kernel back to the bytes in memory.
+fn kernel_word(digits: [u8; 4]) -> [u8; 4] {
+    u32::from_be_bytes(digits).to_ne_bytes()
+}
+
+#[test]
+fn test_parse_hex_ip() {
+    if cfg!(target_endian = "little") {
+        assert_eq!(
+            parse_hex_ipv4("0100007F:0035"),
+            Some((":0035", Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_hex_ipv6("00000000000000000000000001000000"),
+            Some(("", Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_hex_ipv6("B80D0120000000000000000001000000"),
+            Some(("", "2001:db8::1".parse().unwrap()))
+        );
+    } else {
+        assert_eq!(parse_hex_ipv4("7F000001"), Some(("", Ipv4Addr::LOCALHOST)));
+    }
+    assert_eq!(parse_hex_ipv4("7F0001"), None);
+}
+
+/// Splits a `Name:   value` line, as found in `/proc/meminfo` and `/proc/[pid]/status`, at the
+/// first colon, trimming the space around the value.
+pub fn parse_key_value(input: &str) -> Option<(&str, &str)> {
+    let (key, value) = input.split_once(':')?;
+    Some((key, value.trim_matches(|ch: char| ch.is_ascii_whitespace())))
+}
+
+#[test]
+fn test_parse_key_value() {
+    assert_eq!(parse_key_value("MemTotal"), None);
+    assert_eq!(
+        parse_key_value("MemTotal:       16318044 kB\n"),
+        Some(("MemTotal", "16318044 kB"))
+    );
+    assert_eq!(parse_key_value("Name:\ta: b\n"), Some(("Name", "a: b")));
+    assert_eq!(parse_key_value("Empty:\n"), Some(("Empty", "")));
+}
+
+/// Parses a size like `1304 kB`, consuming any space before it.
+///
+/// The unit is optional, and without it the size is in bytes. Any other unit is rejected.
+pub fn parse_size(input: &str) -> Option<(&str, ByteSize)> {
+    let (input, value) = parse_u64(input)?;
+    let (rest, unit) = match parse_token(input) {
+        Some((rest, unit)) => (rest, unit),
+        None => return Some((input, ByteSize(value))),
+    };
+    match unit {
+        "kB" => Some((rest, ByteSize::from_kib(value))),
+        "B" => Some((rest, ByteSize(value))),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size(""), None);
+    assert_eq!(parse_size("kB"), None);
+    assert_eq!(parse_size("    1304 kB"), Some(("", ByteSize(1304 * 1024))));
+    assert_eq!(parse_size("12 kB\n"), Some(("\n", ByteSize(12 * 1024))));
+    assert_eq!(parse_size("12"), Some(("", ByteSize(12))));
+    assert_eq!(parse_size("12 B"), Some(("", ByteSize(12))));
+    assert_eq!(parse_size("12 MB"), None);
+}
+
+/// Skips leading whitespace.
+///
+/// Only ASCII whitespace is recognised, which is all the kernel emits. Any non-ASCII byte ends
+/// the whitespace, so the returned slice always starts on a `char` boundary.
+pub fn consume_space(input: &str) -> &str {
+    let idx = input
+        .as_bytes()
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(input.len());
+    &input[idx..]
 }
 
 #[test]
@@ -97,13 +362,11 @@ pub fn parse_token(input: &str) -> Option<(&str, &str)> {
     if token.is_empty() {
         return None;
     }
-    let mut end = 0;
-    for (idx, ch) in token.char_indices() {
-        if ch.is_whitespace() {
-            break;
-        }
-        end = idx + ch.len_utf8();
-    }
+    let end = token
+        .as_bytes()
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace())
+        .unwrap_or(token.len());
     Some((&token[end..], &token[..end]))
 }
 
@@ -115,6 +378,7 @@ fn test_parse_token() {
     assert_eq!(parse_token("token"), Some(("", "token")));
     assert_eq!(parse_token(" token"), Some(("", "token")));
     assert_eq!(parse_token(" token "), Some((" ", "token")));
+    assert_eq!(parse_token("\tnämé\t"), Some(("\t", "nämé")));
 }
 
 // todo should be ! not Error.
@@ -124,11 +388,7 @@ pub fn parse_dummy(_input: &str) -> Result<(), Error> {
 
 pub fn expect_bytes<'a>(expected: &str, input: &'a str) -> Option<&'a str> {
     let input = consume_space(input);
-    if input.starts_with(expected) {
-        Some(&input[expected.len()..])
-    } else {
-        None
-    }
+    input.strip_prefix(expected)
 }
 
 #[test]
@@ -139,39 +399,233 @@ fn test_expect_bytes() {
     assert_eq!(expect_bytes("a", "b"), None);
 }
 
-/// Parses numbers after a decimal point, where the first column is 1_000_000_000.
-pub fn parse_nanos(input: &str) -> Option<(&str, u32)> {
+/// Parses the digits after a decimal point as a fixed-point fraction over `denominator`, which
+/// must be a power of 10.
+///
+/// For example with a denominator of 100, `"14"` is 14 (0.14) and `"5"` is 50 (0.5). Digits
+/// beyond the precision of the denominator are consumed but ignored.
+pub fn parse_fraction(input: &str, denominator: u64) -> Option<(&str, u64)> {
     let input = consume_space(input);
-    let mut chars = input.chars();
-    let (mut next_idx, mut acc) = match chars.next() {
-        Some(ch) => match ch.to_digit(10) {
-            Some(val) => (ch.len_utf8(), (val as u32) * 100_000_000),
-            None => return None,
-        },
-        None => return None,
-    };
-    let mut multer = 10_000_000u32;
-    for ch in chars {
-        match ch.to_digit(10) {
-            Some(val) => {
-                acc += (val as u32) * multer;
-                next_idx += ch.len_utf8();
-                multer /= 10;
+    let bytes = input.as_bytes();
+    let mut acc = 0u64;
+    let mut scale = denominator;
+    let mut idx = 0;
+    while let Some(&byte) = bytes.get(idx) {
+        let digit = byte.wrapping_sub(b'0');
+        if digit > 9 {
+            break;
+        }
+        if scale > 1 {
+            scale /= 10;
+            acc += u64::from(digit) * scale;
+        }
+        idx += 1;
+    }
+    if idx == 0 {
+        return None;
+    }
+    Some((&input[idx..], acc))
+}
+
+#[test]
+fn test_parse_fraction() {
+    assert_eq!(parse_fraction("", 100), None);
+    assert_eq!(parse_fraction(".12", 100), None);
+    assert_eq!(parse_fraction("14 ", 100), Some((" ", 14)));
+    assert_eq!(parse_fraction(" 5", 100), Some(("", 50)));
+    assert_eq!(parse_fraction("05", 100), Some(("", 5)));
+    assert_eq!(parse_fraction("149", 100), Some(("", 14)));
+    assert_eq!(parse_fraction("12", 1_000_000_000), Some(("", 120_000_000)));
+    assert_eq!(
+        parse_fraction("1234567890", 1_000_000_000),
+        Some(("", 123_456_789))
+    );
+}
+
+/// Parses a list of cpus in the kernel's list format, e.g. `0-3,8,10-11`.
+pub fn parse_cpu_list(input: &str) -> Option<Vec<u32>> {
+    let input = input.trim();
+    let mut cpus = Vec::new();
+    if input.is_empty() {
+        return Some(cpus);
+    }
+    for part in input.split(',') {
+        let (first, last): (u32, u32) = match part.find('-') {
+            Some(idx) => (part[..idx].parse().ok()?, part[idx + 1..].parse().ok()?),
+            None => {
+                let cpu = part.parse().ok()?;
+                (cpu, cpu)
             }
-            None => break,
+        };
+        if first > last {
+            return None;
         }
-        if multer == 1 {
-            panic!("too many numbers");
+        cpus.extend(first..=last);
+    }
+    Some(cpus)
+}
+
+#[test]
+fn test_parse_cpu_list() {
+    assert_eq!(
+        parse_cpu_list("0-3,8,10-11\n"),
+        Some(vec![0, 1, 2, 3, 8, 10, 11])
+    );
+    assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+    assert_eq!(parse_cpu_list("\n"), Some(vec![]));
+    assert_eq!(parse_cpu_list("3-1"), None);
+    assert_eq!(parse_cpu_list("0-"), None);
+}
+
+/// Converts a count of clock ticks into a `Duration`, without losing precision.
+///
+/// `None` if `ticks_per_sec` is 0.
+pub fn ticks_to_duration(ticks: u64, ticks_per_sec: u64) -> Option<Duration> {
+    if ticks_per_sec == 0 {
+        return None;
+    }
+    let secs = ticks / ticks_per_sec;
+    // The remainder is below `ticks_per_sec`, so this is below a billion, but the product can
+    // overflow a u64 for very high rates.
+    let nanos = u128::from(ticks % ticks_per_sec) * 1_000_000_000 / u128::from(ticks_per_sec);
+    Some(Duration::new(secs, nanos as u32))
+}
+
+#[test]
+fn test_ticks_to_duration() {
+    assert_eq!(ticks_to_duration(0, 100), Some(Duration::from_secs(0)));
+    assert_eq!(
+        ticks_to_duration(97004, 100),
+        Some(Duration::from_millis(970_040))
+    );
+    assert_eq!(
+        ticks_to_duration(1, 3),
+        Some(Duration::from_nanos(333_333_333))
+    );
+    assert_eq!(
+        ticks_to_duration(u64::MAX, 100),
+        Some(Duration::new(u64::MAX / 100, 150_000_000))
+    );
+    assert_eq!(
+        ticks_to_duration(u64::MAX - 1, u64::MAX),
+        Some(Duration::from_nanos(999_999_999))
+    );
+    assert_eq!(ticks_to_duration(5, 0), None);
+}
+
+/// The `/dev` path of the block device with number `major:minor`, like the device numbers in
+/// `/proc/[pid]/mountinfo`, `/proc/[pid]/maps` and `io.stat`.
+///
+/// This asks sysfs (`/sys/dev/block/major:minor/uevent`) first, and falls back to
+/// `/proc/partitions` where sysfs isn't mounted. Returns `None` for numbers that aren't a block
+/// device, like the anonymous devices of `tmpfs` and `overlay` mounts (major 0).
+pub fn device_name(major: u32, minor: u32) -> io::Result<Option<PathBuf>> {
+    device_name_in(
+        Path::new("/sys/dev/block"),
+        Path::new("/proc/partitions"),
+        major,
+        minor,
+    )
+}
+
+fn device_name_in(
+    sys_dev_block: &Path,
+    partitions: &Path,
+    major: u32,
+    minor: u32,
+) -> io::Result<Option<PathBuf>> {
+    let dev = Path::new("/dev");
+    match fs::read_to_string(sys_dev_block.join(format!("{}:{}/uevent", major, minor))) {
+        Ok(uevent) => {
+            if let Some(name) = uevent
+                .lines()
+                .find_map(|line| line.strip_prefix("DEVNAME="))
+            {
+                return Ok(Some(dev.join(name)));
+            }
         }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e),
     }
-    Some((&input[next_idx..], acc))
+    let partitions = match fs::read_to_string(partitions) {
+        Ok(partitions) => partitions,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    // After the header, each line is `major minor #blocks name`.
+    for line in partitions.lines().skip(1) {
+        let parsed = parse_u64(line).and_then(|(line, line_major)| {
+            let (line, line_minor) = parse_u64(line)?;
+            let (line, _) = parse_u64(line)?;
+            let (_, name) = parse_token(line)?;
+            Some((line_major, line_minor, name))
+        });
+        if let Some((line_major, line_minor, name)) = parsed {
+            if (line_major, line_minor) == (u64::from(major), u64::from(minor)) {
+                return Ok(Some(dev.join(name)));
+            }
+        }
+    }
+    Ok(None)
 }
 
 #[test]
-fn test_parse_nanos() {
-    assert_eq!(parse_nanos(""), None);
-    assert_eq!(parse_nanos("1"), Some(("", 100_000_000)));
-    assert_eq!(parse_nanos(" 12"), Some(("", 120_000_000)));
-    assert_eq!(parse_nanos("012"), Some(("", 12_000_000)));
-    assert_eq!(parse_nanos(".12"), None);
+fn test_device_name() {
+    let root = TempDir::new("devices");
+    let sys_dev_block = root.join("block");
+    let partitions = root.join("partitions");
+    fs::create_dir_all(sys_dev_block.join("259:1")).unwrap();
+    fs::write(
+        sys_dev_block.join("259:1/uevent"),
+        "MAJOR=259\nMINOR=1\nDEVNAME=nvme0n1p1\nDEVTYPE=partition\n",
+    )
+    .unwrap();
+    fs::write(
+        &partitions,
+        "major minor  #blocks  name\n\n   8        0  488386584 sda\n   8        1     524288 sda1\n",
+    )
+    .unwrap();
+    let name = |major, minor| device_name_in(&sys_dev_block, &partitions, major, minor).unwrap();
+    assert_eq!(name(259, 1), Some(PathBuf::from("/dev/nvme0n1p1")));
+    assert_eq!(name(8, 1), Some(PathBuf::from("/dev/sda1")));
+    assert_eq!(name(0, 52), None);
+
+    let mounts = crate::mounts::Mounts::from_system().unwrap();
+    let root = mounts.get("/").unwrap();
+    if root.major != 0 {
+        assert!(device_name(root.major, root.minor).unwrap().is_some());
+    }
+}
+
+/// A scratch directory for a test, removed when dropped so a failing assertion doesn't leave it
+/// behind.
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Create an empty directory named after `name` and this process, replacing any left over
+    /// from an earlier run.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("linux_proc_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
 }
@@ -0,0 +1,147 @@
+//! Bindings to the ZFS kstats in `/proc/spl/kstat/zfs`.
+//!
+//! Memory used by the ZFS ARC doesn't show up as cache in `/proc/meminfo`, so monitoring tools
+//! need to read it from here. The files only exist when the ZFS module is loaded: convert the
+//! result with `ReadOutcome::from` to treat that case as `ReadOutcome::NotFound`.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use crate::{util, Error};
+
+/// ARC (adaptive replacement cache) statistics, from `/proc/spl/kstat/zfs/arcstats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArcStats {
+    /// Total cache hits.
+    pub hits: u64,
+    /// Total cache misses.
+    pub misses: u64,
+    /// Current size of the ARC in bytes.
+    pub size: u64,
+    /// Target size of the ARC in bytes.
+    pub target_size: u64,
+    /// Minimum size of the ARC in bytes.
+    pub min_size: u64,
+    /// Maximum size of the ARC in bytes.
+    pub max_size: u64,
+    /// Every value in the file, by name. The set of statistics varies between ZFS versions.
+    pub values: HashMap<String, i64>,
+}
+
+impl ArcStats {
+    const PATH: &'static str = "/proc/spl/kstat/zfs/arcstats";
+    /// Parse the contents of `/proc/spl/kstat/zfs/arcstats`.
+    pub fn from_system() -> io::Result<Self> {
+        ArcStats::from_reader(File::open(Self::PATH)?)
+    }
+
+    /// Parse `/proc/spl/kstat/zfs/arcstats`-formatted data from `reader`.
+    pub fn from_reader(reader: impl io::Read) -> io::Result<Self> {
+        let values = parse_kstat(reader)?;
+        let get = |name: &str| match values.get(name) {
+            Some(&value) => Ok(value.max(0) as u64),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("missing arcstat {}", name),
+            )),
+        };
+        Ok(ArcStats {
+            hits: get("hits")?,
+            misses: get("misses")?,
+            size: get("size")?,
+            target_size: get("c")?,
+            min_size: get("c_min")?,
+            max_size: get("c_max")?,
+            values,
+        })
+    }
+
+    /// The proportion of lookups served from the cache, from 0 to 1.
+    pub fn hit_ratio(&self) -> f64 {
+        // Summed as floats, since the counters can be large enough to overflow together.
+        let total = self.hits as f64 + self.misses as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.hits as f64 / total
+    }
+}
+
+macro_rules! err_msg {
+    ($inner:expr, $msg:expr) => {
+        $inner.ok_or_else(|| Error::from($msg))
+    };
+}
+
+/// Parse a named-value kstat: a header line, a column names line, then `name type data` rows.
+fn parse_kstat(reader: impl io::Read) -> io::Result<HashMap<String, i64>> {
+    let mut reader = util::LineParser::new(reader);
+    // kstat header (id, type, flags, count, size, creation and snapshot times).
+    reader.parse_line(util::parse_dummy)?;
+    // column names
+    reader.parse_line(util::parse_dummy)?;
+    let mut values = HashMap::new();
+    loop {
+        match reader.parse_line(parse_kstat_row) {
+            Ok(Some((name, value))) => {
+                values.insert(name, value);
+            }
+            Ok(None) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(values)
+}
+
+/// Parse a `name type data` row. Rows with non-integer types are skipped.
+fn parse_kstat_row(input: &str) -> Result<Option<(String, i64)>, Error> {
+    let (input, name) = err_msg!(util::parse_token(input), "name")?;
+    let (input, kind) = err_msg!(util::parse_u64(input), "type")?;
+    let value = match kind {
+        // KSTAT_DATA_INT32, KSTAT_DATA_INT64
+        1 | 3 => err_msg!(util::parse_i64(input), "value")?.1,
+        // KSTAT_DATA_UINT32, KSTAT_DATA_UINT64
+        2 | 4 => {
+            let (_, value) = err_msg!(util::parse_u64(input), "value")?;
+            value.min(i64::MAX as u64) as i64
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some((name.to_owned(), value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcStats;
+    use std::io;
+
+    #[test]
+    fn proc_spl_kstat_zfs_arcstats() {
+        let raw = "\
+13 1 0x01 123 33456 6021894629 1163218735736
+name                            type data
+hits                            4    1055768
+misses                          4    223590
+demand_data_hits                4    567829
+c                               4    4117318656
+c_min                           4    520888448
+c_max                           4    8334215168
+size                            4    3891233792
+memory_available_bytes          3    -41234567
+";
+        let stats = ArcStats::from_reader(io::Cursor::new(raw)).unwrap();
+        assert_eq!(stats.hits, 1055768);
+        assert_eq!(stats.size, 3891233792);
+        assert_eq!(stats.target_size, 4117318656);
+        assert_eq!(stats.values["demand_data_hits"], 567829);
+        assert_eq!(stats.values["memory_available_bytes"], -41234567);
+        assert!((stats.hit_ratio() - 0.825).abs() < 0.001);
+        let stats = ArcStats {
+            hits: u64::MAX,
+            misses: u64::MAX,
+            ..stats
+        };
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+}